@@ -0,0 +1,38 @@
+use ethers::providers::{HttpClientError, ProviderError};
+use std::fmt::{Debug, Display, Formatter};
+
+#[derive(Debug)]
+pub enum Error {
+    InvalidConfig(String),
+    /// The error returned by the blockchain node, like a reverted `eth_call`.
+    /// Other endpoints are supposed to return the same error, so it's returned directly.
+    JsonRpcError(HttpClientError),
+    /// All endpoints fail, with the url and error of each endpoint.
+    AllEndpointsFailed(Vec<(String, HttpClientError)>),
+    /// Not enough endpoints return the same result.
+    QuorumNotReached {
+        quorum: usize,
+        max_votes: usize,
+    },
+    SerdeJson(serde_json::Error),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "ChainProviderError: {:?}", self)
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Error::SerdeJson(e)
+    }
+}
+
+impl From<Error> for ProviderError {
+    fn from(e: Error) -> Self {
+        ProviderError::JsonRpcClientError(Box::new(e))
+    }
+}
@@ -0,0 +1,314 @@
+#[macro_use]
+extern crate tracing;
+
+mod error;
+
+pub use error::Error;
+
+use async_trait::async_trait;
+use ethers::providers::{Http, HttpClientError, JsonRpcClient, Provider};
+use ethers::types::{Address, Log, H256, U256, U64};
+use futures::future::join_all;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use std::fmt::Debug;
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// How long a failed endpoint is skipped before it's tried again.
+const UNHEALTHY_DURATION: Duration = Duration::from_secs(30);
+
+const GET_LOGS_METHOD: &str = "eth_getLogs";
+
+#[derive(Debug)]
+struct Endpoint {
+    url: String,
+    client: Http,
+    /// The last time when a request to this endpoint fails.
+    /// `None` means the last request succeeds.
+    failed_at: Mutex<Option<Instant>>,
+}
+
+impl Endpoint {
+    fn is_healthy(&self) -> bool {
+        match *self.failed_at.lock().expect("lock poisoned") {
+            None => true,
+            Some(failed_at) => failed_at.elapsed() >= UNHEALTHY_DURATION,
+        }
+    }
+
+    fn set_healthy(&self, healthy: bool) {
+        let mut failed_at = self.failed_at.lock().expect("lock poisoned");
+        if healthy {
+            *failed_at = None;
+        } else if failed_at.map_or(true, |t| t.elapsed() >= UNHEALTHY_DURATION) {
+            // Keep the first failure time if it's still unhealthy.
+            *failed_at = Some(Instant::now());
+        }
+    }
+
+    async fn request(&self, method: &str, params: &Value) -> Result<Value, HttpClientError> {
+        let result = self.client.request(method, params).await;
+        match &result {
+            Ok(_) => self.set_healthy(true),
+            // The node is reachable, so the endpoint is still healthy.
+            Err(HttpClientError::JsonRpcError(_)) => self.set_healthy(true),
+            Err(e) => {
+                warn!(
+                    "blockchain rpc fails: url={} method={} e={:?}",
+                    self.url, method, e
+                );
+                self.set_healthy(false);
+            }
+        }
+        result
+    }
+}
+
+/// A `JsonRpcClient` over multiple HTTP endpoints of the same blockchain.
+///
+/// A request is sent to the first healthy endpoint in the configured order, and fails over to the
+/// next one if the endpoint is unreachable. If `quorum` is larger than 1, `eth_getLogs` is sent to
+/// all endpoints, and the logs are returned only if `quorum` endpoints return the same logs.
+#[derive(Debug)]
+pub struct MultiHttp {
+    endpoints: Vec<Endpoint>,
+    quorum: usize,
+}
+
+impl MultiHttp {
+    pub fn new(urls: &[String], quorum: usize) -> Result<Self, Error> {
+        if urls.is_empty() {
+            return Err(Error::InvalidConfig("no blockchain rpc endpoint".into()));
+        }
+        if quorum == 0 || quorum > urls.len() {
+            return Err(Error::InvalidConfig(format!(
+                "invalid quorum {} for {} endpoints",
+                quorum,
+                urls.len()
+            )));
+        }
+        let mut endpoints = Vec::with_capacity(urls.len());
+        for url in urls {
+            endpoints.push(Endpoint {
+                url: url.clone(),
+                client: Http::from_str(url).map_err(|e| {
+                    Error::InvalidConfig(format!("invalid endpoint {}: {:?}", url, e))
+                })?,
+                failed_at: Mutex::new(None),
+            });
+        }
+        Ok(Self { endpoints, quorum })
+    }
+
+    /// Return the endpoints with the healthy ones first.
+    /// The unhealthy ones are still tried if all healthy ones fail.
+    fn ordered_endpoints(&self) -> Vec<&Endpoint> {
+        let (mut healthy, unhealthy): (Vec<_>, Vec<_>) =
+            self.endpoints.iter().partition(|e| e.is_healthy());
+        healthy.extend(unhealthy);
+        healthy
+    }
+
+    async fn request_with_failover(&self, method: &str, params: &Value) -> Result<Value, Error> {
+        let mut errors = Vec::new();
+        for endpoint in self.ordered_endpoints() {
+            match endpoint.request(method, params).await {
+                Ok(result) => return Ok(result),
+                Err(e @ HttpClientError::JsonRpcError(_)) => return Err(Error::JsonRpcError(e)),
+                Err(e) => errors.push((endpoint.url.clone(), e)),
+            }
+        }
+        Err(Error::AllEndpointsFailed(errors))
+    }
+
+    async fn request_with_quorum(&self, method: &str, params: &Value) -> Result<Value, Error> {
+        let results = join_all(self.endpoints.iter().map(|e| e.request(method, params))).await;
+        let mut logs_list = Vec::new();
+        let mut errors = Vec::new();
+        for (endpoint, result) in self.endpoints.iter().zip(results) {
+            match result {
+                Ok(logs) => logs_list.push(logs),
+                Err(e) => errors.push((endpoint.url.clone(), e)),
+            }
+        }
+        pick_by_quorum(logs_list, self.quorum).map_err(|e| quorum_error(e, errors))
+    }
+}
+
+#[async_trait]
+impl JsonRpcClient for MultiHttp {
+    type Error = Error;
+
+    async fn request<T, R>(&self, method: &str, params: T) -> Result<R, Self::Error>
+    where
+        T: Debug + Serialize + Send + Sync,
+        R: DeserializeOwned,
+    {
+        let params = serde_json::to_value(params)?;
+        let result = if self.quorum > 1 && method == GET_LOGS_METHOD {
+            self.request_with_quorum(method, &params).await?
+        } else {
+            self.request_with_failover(method, &params).await?
+        };
+        Ok(serde_json::from_value(result)?)
+    }
+}
+
+/// Return the `eth_getLogs` result returned by at least `quorum` endpoints.
+///
+/// Only the fields decided by the chain are compared, because different clients may
+/// return different optional fields.
+fn pick_by_quorum(logs_list: Vec<Value>, quorum: usize) -> Result<Value, Error> {
+    let mut votes: Vec<(Vec<LogKey>, Value, usize)> = Vec::new();
+    for value in logs_list {
+        let key = match serde_json::from_value::<Vec<Log>>(value.clone()) {
+            Ok(logs) => logs.iter().map(LogKey::from).collect(),
+            Err(e) => {
+                warn!("invalid eth_getLogs result: e={:?}", e);
+                continue;
+            }
+        };
+        match votes.iter_mut().find(|(k, _, _)| *k == key) {
+            Some((_, _, count)) => *count += 1,
+            None => votes.push((key, value, 1)),
+        }
+    }
+    let max_votes = votes.iter().map(|(_, _, count)| *count).max().unwrap_or(0);
+    votes
+        .into_iter()
+        .find(|(_, _, count)| *count >= quorum)
+        .map(|(_, value, _)| value)
+        .ok_or(Error::QuorumNotReached { quorum, max_votes })
+}
+
+/// Return the error of endpoints if the quorum is not reached because of them, so that the
+/// caller can handle the provider error, e.g. too many logs in a page.
+///
+/// The first JSON-RPC error is returned, which is the provider error if all endpoints agree on
+/// it. Otherwise, the endpoints are unreachable and all their errors are returned.
+fn quorum_error(e: Error, mut errors: Vec<(String, HttpClientError)>) -> Error {
+    if errors.is_empty() {
+        return e;
+    }
+
+    match errors
+        .iter()
+        .position(|(_, e)| matches!(e, HttpClientError::JsonRpcError(_)))
+    {
+        Some(index) => Error::JsonRpcError(errors.swap_remove(index).1),
+        None => Error::AllEndpointsFailed(errors),
+    }
+}
+
+#[derive(PartialEq, Eq)]
+struct LogKey {
+    address: Address,
+    topics: Vec<H256>,
+    data: Vec<u8>,
+    block_hash: Option<H256>,
+    block_number: Option<U64>,
+    transaction_hash: Option<H256>,
+    log_index: Option<U256>,
+}
+
+impl From<&Log> for LogKey {
+    fn from(log: &Log) -> Self {
+        Self {
+            address: log.address,
+            topics: log.topics.clone(),
+            data: log.data.to_vec(),
+            block_hash: log.block_hash,
+            block_number: log.block_number,
+            transaction_hash: log.transaction_hash,
+            log_index: log.log_index,
+        }
+    }
+}
+
+/// Create a provider with `urls`. See `MultiHttp` for the details.
+pub fn make_provider(urls: &[String], quorum: usize) -> Result<Provider<MultiHttp>, Error> {
+    Ok(Provider::new(MultiHttp::new(urls, quorum)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{pick_by_quorum, quorum_error, Error};
+    use ethers::providers::{HttpClientError, JsonRpcError};
+    use serde_json::{json, Value};
+
+    fn json_rpc_error(message: &str) -> HttpClientError {
+        HttpClientError::JsonRpcError(JsonRpcError {
+            code: -32005,
+            message: message.into(),
+            data: None,
+        })
+    }
+
+    fn logs(block_number: u64) -> Value {
+        json!([{
+            "address": "0x0000000000000000000000000000000000000001",
+            "topics": [],
+            "data": "0x",
+            "blockHash": format!("0x{:064x}", block_number),
+            "blockNumber": format!("0x{:x}", block_number),
+            "transactionHash": format!("0x{:064x}", block_number),
+            "transactionIndex": "0x0",
+            "logIndex": "0x0",
+        }])
+    }
+
+    #[test]
+    fn test_quorum_reached() {
+        let mut with_extra_field = logs(1);
+        with_extra_field[0]["removed"] = json!(false);
+        let result = pick_by_quorum(vec![logs(2), logs(1), with_extra_field], 2).unwrap();
+        assert_eq!(result, logs(1));
+    }
+
+    #[test]
+    fn test_quorum_not_reached() {
+        let result = pick_by_quorum(vec![logs(1), logs(2), json!("invalid")], 2);
+        assert!(matches!(
+            result,
+            Err(Error::QuorumNotReached {
+                quorum: 2,
+                max_votes: 1
+            })
+        ));
+    }
+
+    #[test]
+    fn test_quorum_error() {
+        let not_reached = || Error::QuorumNotReached {
+            quorum: 2,
+            max_votes: 1,
+        };
+
+        // no endpoint fails
+        assert!(matches!(
+            quorum_error(not_reached(), vec![]),
+            Error::QuorumNotReached { .. }
+        ));
+
+        // the provider error is returned
+        let errors = vec![
+            (
+                "a".to_string(),
+                json_rpc_error("query returned more than 10000 results"),
+            ),
+            (
+                "b".to_string(),
+                json_rpc_error("query returned more than 10000 results"),
+            ),
+        ];
+        match quorum_error(not_reached(), errors) {
+            Error::JsonRpcError(HttpClientError::JsonRpcError(e)) => {
+                assert_eq!(e.message, "query returned more than 10000 results")
+            }
+            e => panic!("unexpected error {:?}", e),
+        }
+    }
+}
@@ -0,0 +1,411 @@
+use crate::filter::LogQuery;
+use ethers::types::{Address, BlockNumber, Bytes, Log, H256, U256, U64};
+use ethers::utils::keccak256;
+use futures::channel::mpsc::{unbounded, UnboundedReceiver, UnboundedSender};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// The number of reported blocks kept by a log filter to detect chain reorg.
+const FILTER_HISTORY: usize = 256;
+
+/// The handler of `eth_call` to a contract function.
+/// It's called with the calldata and returns the ABI-encoded output, or the revert reason.
+pub type CallHandler = Box<dyn Fn(&[u8]) -> Result<Vec<u8>, String> + Send + Sync>;
+
+/// A log to put in a mined block.
+#[derive(Clone, Debug, Default)]
+pub struct MockLog {
+    pub address: Address,
+    pub topics: Vec<H256>,
+    pub data: Vec<u8>,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct MockBlock {
+    pub number: u64,
+    pub hash: H256,
+    pub parent_hash: H256,
+    pub logs: Vec<MockLog>,
+    pub transactions: Vec<H256>,
+}
+
+impl MockBlock {
+    pub fn rpc_logs(&self, query: &LogQuery, removed: bool) -> Vec<Log> {
+        let mut logs = Vec::new();
+        // Each log is emitted by a separate transaction.
+        for (index, log) in self.logs.iter().enumerate() {
+            if query.matches(log) {
+                logs.push(Log {
+                    address: log.address,
+                    topics: log.topics.clone(),
+                    data: Bytes::from(log.data.clone()),
+                    block_hash: Some(self.hash),
+                    block_number: Some(U64::from(self.number)),
+                    transaction_hash: Some(H256::from(keccak256(
+                        [self.hash.as_bytes(), &index.to_be_bytes()].concat(),
+                    ))),
+                    transaction_index: Some(U64::from(index)),
+                    log_index: Some(U256::from(index)),
+                    removed: Some(removed),
+                    ..Default::default()
+                });
+            }
+        }
+        logs
+    }
+}
+
+pub(crate) struct LogFilter {
+    pub query: LogQuery,
+    /// The next block whose logs are not returned.
+    pub next_block_number: u64,
+    /// The blocks whose logs are returned, to detect reverted logs.
+    pub reported_blocks: Vec<(u64, H256)>,
+}
+
+/// An `eth_subscribe` subscription, which is dropped once its receiver is dropped.
+pub(crate) enum Subscription {
+    Logs(LogQuery, UnboundedSender<Log>),
+    NewHeads(UnboundedSender<MockBlock>),
+}
+
+pub(crate) struct ChainState {
+    pub chain_id: u64,
+    /// All blocks by hash, including the reverted ones.
+    pub blocks: HashMap<H256, MockBlock>,
+    /// The block hashes of the canonical chain, indexed by block number.
+    pub canonical: Vec<H256>,
+    /// Increased for each reorg, so the blocks on the new branch get new hashes.
+    pub fork_id: u64,
+    /// The blocks returned as `null`, like a node that has not synced them.
+    pub missing_blocks: HashSet<u64>,
+    pub safe_depth: Option<u64>,
+    pub finalized_depth: Option<u64>,
+    /// `eth_getLogs` fails if it returns more logs than this.
+    pub max_logs_per_query: Option<usize>,
+    /// The errors returned by the next `eth_getLogs` calls.
+    pub get_logs_errors: VecDeque<String>,
+    /// The block ranges of the `eth_getLogs` calls that have been served.
+    pub get_logs_ranges: Vec<(u64, u64)>,
+
+    pub filters: HashMap<U256, LogFilter>,
+    pub next_filter_id: u64,
+
+    pub subscriptions: Vec<Subscription>,
+    /// The number of `eth_subscribe` calls that have been served.
+    pub subscription_requests: usize,
+
+    pub call_handlers: HashMap<(Address, [u8; 4]), CallHandler>,
+    /// The raw transactions in the order of submission.
+    pub transactions: Vec<(H256, Bytes)>,
+    pub pending_transactions: Vec<H256>,
+    /// The location of the mined transactions, `(block_hash, tx_index)`.
+    pub mined_transactions: HashMap<H256, (H256, u64)>,
+    /// If set, a block is mined once a transaction is submitted.
+    pub auto_mine: bool,
+}
+
+impl ChainState {
+    pub fn latest_block_number(&self) -> u64 {
+        self.canonical.len() as u64 - 1
+    }
+
+    pub fn canonical_block(&self, block_number: u64) -> Option<&MockBlock> {
+        self.canonical
+            .get(block_number as usize)
+            .and_then(|hash| self.blocks.get(hash))
+    }
+
+    /// Return the block number of a block tag or number.
+    pub fn resolve_block_number(&self, block_number: BlockNumber) -> Result<u64, String> {
+        let latest = self.latest_block_number();
+        let tagged = |depth: Option<u64>, tag: &str| {
+            depth
+                .map(|depth| latest.saturating_sub(depth))
+                .ok_or_else(|| format!("block tag {} is not supported", tag))
+        };
+        match block_number {
+            BlockNumber::Latest | BlockNumber::Pending => Ok(latest),
+            BlockNumber::Earliest => Ok(0),
+            BlockNumber::Safe => tagged(self.safe_depth, "safe"),
+            BlockNumber::Finalized => tagged(self.finalized_depth, "finalized"),
+            BlockNumber::Number(n) => Ok(n.as_u64()),
+        }
+    }
+
+    fn mine_block(&mut self, logs: Vec<MockLog>) -> u64 {
+        let number = self.canonical.len() as u64;
+        let parent_hash = self.canonical.last().cloned().unwrap_or_default();
+        let hash = H256::from(keccak256(
+            [
+                parent_hash.as_bytes(),
+                &number.to_be_bytes(),
+                &self.fork_id.to_be_bytes(),
+            ]
+            .concat(),
+        ));
+        let transactions = std::mem::take(&mut self.pending_transactions);
+        for (index, tx_hash) in transactions.iter().enumerate() {
+            self.mined_transactions
+                .insert(*tx_hash, (hash, index as u64));
+        }
+        let block = MockBlock {
+            number,
+            hash,
+            parent_hash,
+            logs,
+            transactions,
+        };
+        self.notify_subscriptions(&block, false);
+        self.blocks.insert(hash, block);
+        self.canonical.push(hash);
+        number
+    }
+
+    /// Notify the subscriptions of a new canonical block, or a reverted one if `removed`.
+    /// The subscriptions whose receivers are dropped are removed.
+    fn notify_subscriptions(&mut self, block: &MockBlock, removed: bool) {
+        self.subscriptions
+            .retain(|subscription| match subscription {
+                Subscription::Logs(query, sender) => block
+                    .rpc_logs(query, removed)
+                    .into_iter()
+                    .try_for_each(|log| sender.unbounded_send(log))
+                    .is_ok(),
+                // Like geth, reverted blocks are not notified as new heads.
+                Subscription::NewHeads(sender) if removed => !sender.is_closed(),
+                Subscription::NewHeads(sender) => sender.unbounded_send(block.clone()).is_ok(),
+            });
+    }
+
+    pub fn submit_transaction(&mut self, raw: Bytes) -> H256 {
+        let hash = H256::from(keccak256(raw.as_ref()));
+        if !self.transactions.iter().any(|(h, _)| *h == hash) {
+            self.transactions.push((hash, raw));
+            self.pending_transactions.push(hash);
+            if self.auto_mine {
+                self.mine_block(vec![]);
+            }
+        }
+        hash
+    }
+
+    /// Return the changes of a log filter since the last call, with the reverted logs first.
+    pub fn filter_changes(&mut self, id: U256) -> Option<Vec<Log>> {
+        let latest_block_number = self.latest_block_number();
+        let mut filter = self.filters.remove(&id)?;
+
+        let mut removed_logs = Vec::new();
+        while let Some((block_number, block_hash)) = filter.reported_blocks.last().cloned() {
+            if self.canonical.get(block_number as usize) == Some(&block_hash) {
+                break;
+            }
+            let mut logs = self.blocks[&block_hash].rpc_logs(&filter.query, true);
+            logs.append(&mut removed_logs);
+            removed_logs = logs;
+            filter.reported_blocks.pop();
+            filter.next_block_number = block_number;
+        }
+
+        let mut logs = removed_logs;
+        let to_block = self
+            .resolve_block_number(filter.query.to_block)
+            .unwrap_or(latest_block_number)
+            .min(latest_block_number);
+        while filter.next_block_number <= to_block {
+            let block = self
+                .canonical_block(filter.next_block_number)
+                .expect("in range");
+            logs.append(&mut block.rpc_logs(&filter.query, false));
+            filter.reported_blocks.push((block.number, block.hash));
+            filter.next_block_number += 1;
+        }
+        if filter.reported_blocks.len() > FILTER_HISTORY {
+            let extra = filter.reported_blocks.len() - FILTER_HISTORY;
+            filter.reported_blocks.drain(..extra);
+        }
+
+        self.filters.insert(id, filter);
+        Some(logs)
+    }
+}
+
+/// An in-memory blockchain served with the JSON-RPC subset used by the node.
+///
+/// Blocks are only mined when requested, so tests can script new blocks, chain reorg and
+/// missing blocks step by step.
+#[derive(Clone)]
+pub struct MockChain {
+    state: Arc<Mutex<ChainState>>,
+}
+
+impl MockChain {
+    pub fn new(chain_id: u64) -> Self {
+        let mut state = ChainState {
+            chain_id,
+            blocks: HashMap::new(),
+            canonical: Vec::new(),
+            fork_id: 0,
+            missing_blocks: HashSet::new(),
+            safe_depth: None,
+            finalized_depth: None,
+            max_logs_per_query: None,
+            get_logs_errors: VecDeque::new(),
+            get_logs_ranges: Vec::new(),
+            filters: HashMap::new(),
+            next_filter_id: 1,
+            subscriptions: Vec::new(),
+            subscription_requests: 0,
+            call_handlers: HashMap::new(),
+            transactions: Vec::new(),
+            pending_transactions: Vec::new(),
+            mined_transactions: HashMap::new(),
+            auto_mine: true,
+        };
+        // The genesis block.
+        state.mine_block(vec![]);
+        Self {
+            state: Arc::new(Mutex::new(state)),
+        }
+    }
+
+    pub(crate) fn state(&self) -> MutexGuard<'_, ChainState> {
+        self.state.lock().expect("lock poisoned")
+    }
+
+    /// Mine a block with `logs` and the pending transactions. Return the block number.
+    pub fn mine_block(&self, logs: Vec<MockLog>) -> u64 {
+        self.state().mine_block(logs)
+    }
+
+    /// Mine `n` empty blocks. Return the latest block number.
+    pub fn mine_blocks(&self, n: u64) -> u64 {
+        let mut state = self.state();
+        for _ in 0..n {
+            state.mine_block(vec![]);
+        }
+        state.latest_block_number()
+    }
+
+    /// Revert the latest `depth` blocks and mine a new branch with a block for each item in
+    /// `new_blocks`. The transactions in the reverted blocks become pending again.
+    pub fn reorg(&self, depth: u64, new_blocks: Vec<Vec<MockLog>>) -> u64 {
+        let mut state = self.state();
+        assert!(
+            depth <= state.latest_block_number(),
+            "cannot revert the genesis block"
+        );
+        state.fork_id += 1;
+        let new_len = state.canonical.len() - depth as usize;
+        let mut reverted_transactions = Vec::new();
+        for hash in state.canonical.split_off(new_len) {
+            let block = state.blocks[&hash].clone();
+            state.notify_subscriptions(&block, true);
+            reverted_transactions.extend(block.transactions);
+        }
+        for tx_hash in &reverted_transactions {
+            state.mined_transactions.remove(tx_hash);
+        }
+        reverted_transactions.append(&mut state.pending_transactions);
+        state.pending_transactions = reverted_transactions;
+        for logs in new_blocks {
+            state.mine_block(logs);
+        }
+        state.latest_block_number()
+    }
+
+    pub fn latest_block_number(&self) -> u64 {
+        self.state().latest_block_number()
+    }
+
+    pub fn block_hash(&self, block_number: u64) -> Option<H256> {
+        self.state().canonical.get(block_number as usize).cloned()
+    }
+
+    /// Make `eth_getBlockByNumber` return `null` for the block, like a lagging node.
+    pub fn set_block_missing(&self, block_number: u64, missing: bool) {
+        let mut state = self.state();
+        if missing {
+            state.missing_blocks.insert(block_number);
+        } else {
+            state.missing_blocks.remove(&block_number);
+        }
+    }
+
+    /// Support the `safe` block tag with the block `depth` blocks before the latest one.
+    /// `None` means the tag is not supported.
+    pub fn set_safe_depth(&self, depth: Option<u64>) {
+        self.state().safe_depth = depth;
+    }
+
+    /// Support the `finalized` block tag with the block `depth` blocks before the latest one.
+    /// `None` means the tag is not supported.
+    pub fn set_finalized_depth(&self, depth: Option<u64>) {
+        self.state().finalized_depth = depth;
+    }
+
+    /// Make `eth_getLogs` fail if it returns more logs than `max_logs`, like public providers.
+    pub fn set_max_logs_per_query(&self, max_logs: Option<usize>) {
+        self.state().max_logs_per_query = max_logs;
+    }
+
+    /// Make the next `eth_getLogs` call fail with `message`, like a rate limited provider.
+    pub fn push_get_logs_error(&self, message: impl Into<String>) {
+        self.state().get_logs_errors.push_back(message.into());
+    }
+
+    /// Return the block ranges of the `eth_getLogs` calls that have been served.
+    pub fn get_logs_ranges(&self) -> Vec<(u64, u64)> {
+        self.state().get_logs_ranges.clone()
+    }
+
+    /// Subscribe to the logs matching `query` in new blocks. The logs in reverted blocks are
+    /// notified again as removed.
+    pub(crate) fn subscribe_logs(&self, query: LogQuery) -> UnboundedReceiver<Log> {
+        let (sender, receiver) = unbounded();
+        let mut state = self.state();
+        state.subscription_requests += 1;
+        state.subscriptions.push(Subscription::Logs(query, sender));
+        receiver
+    }
+
+    pub(crate) fn subscribe_new_heads(&self) -> UnboundedReceiver<MockBlock> {
+        let (sender, receiver) = unbounded();
+        let mut state = self.state();
+        state.subscription_requests += 1;
+        state.subscriptions.push(Subscription::NewHeads(sender));
+        receiver
+    }
+
+    /// Return the number of `eth_subscribe` calls that have been served, including the
+    /// subscriptions that have been dropped.
+    pub fn subscription_requests(&self) -> usize {
+        self.state().subscription_requests
+    }
+
+    /// Handle `eth_call` to the contract function at `address` with the function `selector`.
+    pub fn set_call_handler(
+        &self,
+        address: Address,
+        selector: [u8; 4],
+        handler: impl Fn(&[u8]) -> Result<Vec<u8>, String> + Send + Sync + 'static,
+    ) {
+        self.state()
+            .call_handlers
+            .insert((address, selector), Box::new(handler));
+    }
+
+    /// If set, submitted transactions are mined in a new block immediately.
+    pub fn set_auto_mine(&self, auto_mine: bool) {
+        self.state().auto_mine = auto_mine;
+    }
+
+    /// Return all the submitted raw transactions.
+    pub fn submitted_transactions(&self) -> Vec<Bytes> {
+        self.state()
+            .transactions
+            .iter()
+            .map(|(_, raw)| raw.clone())
+            .collect()
+    }
+}
@@ -0,0 +1,105 @@
+use crate::chain::MockLog;
+use ethers::types::{Address, BlockNumber, H256};
+use serde_json::Value;
+use std::str::FromStr;
+
+/// The log filter options of `eth_getLogs` and `eth_newFilter`.
+#[derive(Clone, Debug)]
+pub(crate) struct LogQuery {
+    pub from_block: BlockNumber,
+    pub to_block: BlockNumber,
+    pub block_hash: Option<H256>,
+    /// Empty means any address.
+    pub addresses: Vec<Address>,
+    /// `None` means any topic at the position.
+    pub topics: Vec<Option<Vec<H256>>>,
+}
+
+impl LogQuery {
+    pub fn parse(value: &Value) -> Result<Self, String> {
+        let from_block = match value.get("fromBlock") {
+            Some(Value::String(s)) => parse_block_number(s)?,
+            _ => BlockNumber::Latest,
+        };
+        let to_block = match value.get("toBlock") {
+            Some(Value::String(s)) => parse_block_number(s)?,
+            _ => BlockNumber::Latest,
+        };
+        let block_hash = match value.get("blockHash") {
+            Some(Value::String(s)) => Some(parse_h256(s)?),
+            _ => None,
+        };
+        let addresses = match value.get("address") {
+            None | Some(Value::Null) => vec![],
+            Some(Value::String(s)) => vec![parse_address(s)?],
+            Some(Value::Array(list)) => list
+                .iter()
+                .map(|v| v.as_str().ok_or("invalid address").and_then(parse_address))
+                .collect::<Result<_, _>>()?,
+            Some(v) => return Err(format!("invalid address {}", v)),
+        };
+        let topics = match value.get("topics") {
+            None | Some(Value::Null) => vec![],
+            Some(Value::Array(list)) => list
+                .iter()
+                .map(|topic| match topic {
+                    Value::Null => Ok(None),
+                    Value::String(s) => Ok(Some(vec![parse_h256(s)?])),
+                    Value::Array(options) => options
+                        .iter()
+                        .map(|v| v.as_str().ok_or("invalid topic").and_then(parse_h256))
+                        .collect::<Result<_, _>>()
+                        .map(Some),
+                    v => Err(format!("invalid topic {}", v)),
+                })
+                .collect::<Result<_, _>>()?,
+            Some(v) => return Err(format!("invalid topics {}", v)),
+        };
+        Ok(Self {
+            from_block,
+            to_block,
+            block_hash,
+            addresses,
+            topics,
+        })
+    }
+
+    pub fn matches(&self, log: &MockLog) -> bool {
+        if !self.addresses.is_empty() && !self.addresses.contains(&log.address) {
+            return false;
+        }
+        self.topics
+            .iter()
+            .enumerate()
+            .all(|(i, options)| match options {
+                None => true,
+                Some(options) => log.topics.get(i).map_or(false, |t| options.contains(t)),
+            })
+    }
+}
+
+pub(crate) fn parse_block_number(s: &str) -> Result<BlockNumber, String> {
+    Ok(match s {
+        "latest" => BlockNumber::Latest,
+        "earliest" => BlockNumber::Earliest,
+        "pending" => BlockNumber::Pending,
+        "safe" => BlockNumber::Safe,
+        "finalized" => BlockNumber::Finalized,
+        _ => BlockNumber::Number(parse_u64(s)?.into()),
+    })
+}
+
+pub(crate) fn parse_u64(s: &str) -> Result<u64, String> {
+    let hex = s
+        .strip_prefix("0x")
+        .ok_or_else(|| format!("invalid quantity {}", s))?;
+    u64::from_str_radix(hex, 16).map_err(|e| format!("invalid quantity {}: {:?}", s, e))
+}
+
+pub(crate) fn parse_h256(s: &str) -> Result<H256, String> {
+    H256::from_str(s).map_err(|e| format!("invalid hash {}: {:?}", s, e))
+}
+
+pub(crate) fn parse_address(s: &str) -> Result<Address, String> {
+    Address::from_str(s).map_err(|e| format!("invalid address {}: {:?}", s, e))
+}
@@ -0,0 +1,213 @@
+//! An in-process EVM chain with the JSON-RPC subset used by the node, for integration tests.
+//!
+//! ```ignore
+//! let chain = MockChain::new(CHAIN_ID);
+//! let server = MockChainServer::start(chain.clone()).await?;
+//! let provider = Provider::new(Ws::connect(server.ws_url()).await?);
+//! chain.mine_block(vec![submission_log(flow_address, 0, 0, 256, nodes)]);
+//! chain.reorg(1, vec![vec![]]);
+//! ```
+
+#[macro_use]
+extern crate tracing;
+
+mod chain;
+mod filter;
+mod server;
+mod submission;
+
+pub use chain::{CallHandler, MockChain, MockLog};
+pub use server::MockChainServer;
+pub use submission::submission_log;
+
+#[cfg(test)]
+mod tests {
+    use crate::{submission_log, MockChain, MockChainServer, MockLog};
+    use contract_interface::SubmissionFilter;
+    use ethers::abi::RawLog;
+    use ethers::contract::EthEvent;
+    use ethers::core::k256::SecretKey;
+    use ethers::middleware::SignerMiddleware;
+    use ethers::providers::{FilterKind, Http, Middleware, Provider, Ws};
+    use ethers::signers::{LocalWallet, Signer};
+    use ethers::types::{Address, BlockNumber, Filter, Log, TransactionRequest, H256, U256};
+    use futures::StreamExt;
+    use std::time::Duration;
+
+    const CHAIN_ID: u64 = 1234;
+
+    async fn start() -> (MockChain, MockChainServer, Provider<Http>) {
+        let chain = MockChain::new(CHAIN_ID);
+        let server = MockChainServer::start(chain.clone()).await.unwrap();
+        let provider = Provider::<Http>::try_from(server.url())
+            .unwrap()
+            .interval(Duration::from_millis(10));
+        (chain, server, provider)
+    }
+
+    fn log(address: Address, topic: u64) -> MockLog {
+        MockLog {
+            address,
+            topics: vec![H256::from_low_u64_be(topic)],
+            data: vec![],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_blocks() {
+        let (chain, server, provider) = start().await;
+        chain.mine_blocks(10);
+        assert_eq!(provider.get_chainid().await.unwrap(), U256::from(CHAIN_ID));
+        assert_eq!(provider.get_block_number().await.unwrap().as_u64(), 10);
+
+        let block = provider.get_block(5).await.unwrap().unwrap();
+        assert_eq!(block.hash, chain.block_hash(5));
+        assert_eq!(block.parent_hash, chain.block_hash(4).unwrap());
+
+        chain.set_block_missing(5, true);
+        assert!(provider.get_block(5).await.unwrap().is_none());
+        chain.set_block_missing(5, false);
+        assert!(provider.get_block(5).await.unwrap().is_some());
+
+        assert!(provider.get_block(BlockNumber::Finalized).await.is_err());
+        chain.set_finalized_depth(Some(3));
+        let finalized = provider.get_block(BlockNumber::Finalized).await.unwrap();
+        assert_eq!(finalized.unwrap().number.unwrap().as_u64(), 7);
+
+        let old_hash = chain.block_hash(9);
+        chain.reorg(2, vec![vec![], vec![], vec![]]);
+        assert_eq!(chain.latest_block_number(), 11);
+        assert_ne!(chain.block_hash(9), old_hash);
+        server.stop();
+    }
+
+    #[tokio::test]
+    async fn test_logs() {
+        let (chain, server, provider) = start().await;
+        let address = Address::from_low_u64_be(1);
+        let other = Address::from_low_u64_be(2);
+        chain.mine_block(vec![log(address, 1), log(other, 1)]);
+        chain.mine_block(vec![log(address, 2)]);
+
+        let filter = Filter::new().address(address).from_block(0);
+        let logs = provider.get_logs(&filter).await.unwrap();
+        assert_eq!(logs.len(), 2);
+        let logs = provider
+            .get_logs(&filter.clone().topic0(H256::from_low_u64_be(2)))
+            .await
+            .unwrap();
+        assert_eq!(logs.len(), 1);
+        assert_eq!(logs[0].block_number.unwrap().as_u64(), 2);
+
+        let id = provider
+            .new_filter(FilterKind::Logs(&filter))
+            .await
+            .unwrap();
+        let changes: Vec<Log> = provider.get_filter_changes(id).await.unwrap();
+        assert_eq!(changes.len(), 2);
+
+        // The logs in the reverted blocks are returned as removed.
+        chain.reorg(1, vec![vec![log(address, 3)], vec![]]);
+        let changes: Vec<Log> = provider.get_filter_changes(id).await.unwrap();
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0].removed, Some(true));
+        assert_eq!(changes[0].topics[0], H256::from_low_u64_be(2));
+        assert_eq!(changes[1].removed, Some(false));
+        assert_eq!(changes[1].topics[0], H256::from_low_u64_be(3));
+        assert_eq!(changes[1].block_hash, chain.block_hash(2));
+        server.stop();
+    }
+
+    #[tokio::test]
+    async fn test_call_and_transaction() {
+        let (chain, server, provider) = start().await;
+        let contract = Address::from_low_u64_be(1);
+        chain.set_call_handler(contract, [1, 2, 3, 4], |data| {
+            if data.len() == 4 {
+                Ok(vec![7; 32])
+            } else {
+                Err("unexpected calldata".into())
+            }
+        });
+        let call = TransactionRequest::new()
+            .to(contract)
+            .data(vec![1, 2, 3, 4]);
+        let output = provider.call(&call.clone().into(), None).await.unwrap();
+        assert_eq!(output.to_vec(), vec![7; 32]);
+        let call = call.data(vec![1, 2, 3, 4, 5]);
+        assert!(provider.call(&call.into(), None).await.is_err());
+
+        let secret_key = SecretKey::from_be_bytes(&[1; 32]).unwrap();
+        let wallet = LocalWallet::from(secret_key).with_chain_id(CHAIN_ID);
+        let client = SignerMiddleware::new(provider, wallet);
+        let tx = TransactionRequest::new().to(contract).data(vec![5]);
+        let receipt = client
+            .send_transaction(tx, None)
+            .await
+            .unwrap()
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(receipt.block_number.unwrap().as_u64(), 1);
+        assert_eq!(chain.submitted_transactions().len(), 1);
+        server.stop();
+    }
+
+    #[tokio::test]
+    async fn test_subscriptions() {
+        let (chain, mut server, _) = start().await;
+        let address = Address::from_low_u64_be(1);
+        let provider = Provider::new(Ws::connect(server.ws_url()).await.unwrap());
+        let filter = Filter::new().address(address);
+        let mut logs = provider.subscribe_logs(&filter).await.unwrap();
+        let mut blocks = provider.subscribe_blocks().await.unwrap();
+        assert_eq!(chain.subscription_requests(), 2);
+
+        chain.mine_block(vec![log(address, 1), log(Address::zero(), 1)]);
+        let block = blocks.next().await.unwrap();
+        assert_eq!(block.number.unwrap().as_u64(), 1);
+        assert_eq!(block.hash, chain.block_hash(1));
+        let log1 = logs.next().await.unwrap();
+        assert_eq!(log1.address, address);
+        assert_eq!(log1.removed, Some(false));
+
+        // The logs in the reverted blocks are notified as removed before the new ones.
+        chain.reorg(1, vec![vec![log(address, 2)]]);
+        let removed = logs.next().await.unwrap();
+        assert_eq!(removed.removed, Some(true));
+        assert_eq!(removed.block_hash, log1.block_hash);
+        let log2 = logs.next().await.unwrap();
+        assert_eq!(log2.removed, Some(false));
+        assert_eq!(log2.topics[0], H256::from_low_u64_be(2));
+        assert_eq!(log2.block_hash, chain.block_hash(1));
+        assert_eq!(blocks.next().await.unwrap().hash, chain.block_hash(1));
+
+        // Subscriptions end once the server is stopped, and can be created again after restart.
+        server.stop_ws();
+        assert!(logs.next().await.is_none());
+        server.restart_ws().await.unwrap();
+        let provider = Provider::new(Ws::connect(server.ws_url()).await.unwrap());
+        let mut logs = provider.subscribe_logs(&filter).await.unwrap();
+        assert_eq!(chain.subscription_requests(), 3);
+        chain.mine_block(vec![log(address, 3)]);
+        let log3 = logs.next().await.unwrap();
+        assert_eq!(log3.block_number.unwrap().as_u64(), 2);
+        server.stop();
+    }
+
+    #[test]
+    fn test_submission_log() {
+        let flow = Address::from_low_u64_be(1);
+        let log = submission_log(flow, 3, 1024, 256, vec![([1; 32], 8)]);
+        assert_eq!(log.address, flow);
+        let event = SubmissionFilter::decode_log(&RawLog {
+            topics: log.topics,
+            data: log.data,
+        })
+        .unwrap();
+        assert_eq!(event.submission_index.as_u64(), 3);
+        assert_eq!(event.start_pos.as_u64(), 1024);
+        assert_eq!(event.submission.0.as_u64(), 256);
+        assert_eq!(event.submission.2, vec![([1; 32], U256::from(8))]);
+    }
+}
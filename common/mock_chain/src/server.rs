@@ -0,0 +1,404 @@
+use crate::chain::{LogFilter, MockBlock, MockChain};
+use crate::filter::{parse_address, parse_block_number, parse_h256, LogQuery};
+use ethers::types::{
+    Block, BlockNumber, Bytes, Log, Transaction, TransactionReceipt, H256, U256, U64,
+};
+use ethers::utils::hex;
+use futures::stream::{BoxStream, StreamExt};
+use jsonrpsee::core::traits::IdProvider;
+use jsonrpsee::core::Error;
+use jsonrpsee::http_server::{HttpServerBuilder, HttpServerHandle};
+use jsonrpsee::types::error::{CallError, ErrorCode, ErrorObject};
+use jsonrpsee::types::{Params, SubscriptionId};
+use jsonrpsee::ws_server::{WsServerBuilder, WsServerHandle};
+use jsonrpsee::RpcModule;
+use serde_json::Value;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// The error code of `eth_call` when the call reverts.
+const EXECUTION_REVERTED_CODE: i32 = 3;
+const SERVER_ERROR_CODE: i32 = -32000;
+
+const GAS_PRICE: u64 = 1_000_000_000;
+const GAS_LIMIT: u64 = 10_000_000;
+
+/// The attempts to bind the WebSocket address again after the server is stopped.
+const WS_RESTART_ATTEMPTS: usize = 50;
+const WS_RESTART_WAIT: Duration = Duration::from_millis(100);
+
+/// Subscription ids are hex quantities, which are expected by the ethers clients.
+#[derive(Debug, Default)]
+struct HexIdProvider {
+    last_id: AtomicU64,
+}
+
+impl IdProvider for HexIdProvider {
+    fn next_id(&self) -> SubscriptionId<'static> {
+        let id = self.last_id.fetch_add(1, Ordering::Relaxed) + 1;
+        SubscriptionId::Str(format!("{:#x}", id).into())
+    }
+}
+
+/// The HTTP and WebSocket JSON-RPC servers of a `MockChain` on random local ports.
+///
+/// Only the WebSocket server supports `eth_subscribe`. It can be stopped and started again
+/// to drop the subscriptions, like a node restart.
+pub struct MockChainServer {
+    chain: MockChain,
+    url: String,
+    handle: HttpServerHandle,
+    ws_addr: SocketAddr,
+    ws_handle: Option<WsServerHandle>,
+}
+
+impl MockChainServer {
+    pub async fn start(chain: MockChain) -> Result<Self, Error> {
+        let server = HttpServerBuilder::default().build("127.0.0.1:0").await?;
+        let url = format!("http://{}", server.local_addr()?);
+        let handle = server.start(rpc_module(chain.clone())?)?;
+
+        let ws_server = ws_server_builder().build("127.0.0.1:0").await?;
+        let ws_addr = ws_server.local_addr()?;
+        let ws_handle = ws_server.start(rpc_module(chain.clone())?)?;
+        debug!("Mock chain started at {} and ws://{}", url, ws_addr);
+
+        Ok(Self {
+            chain,
+            url,
+            handle,
+            ws_addr,
+            ws_handle: Some(ws_handle),
+        })
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn ws_url(&self) -> String {
+        format!("ws://{}", self.ws_addr)
+    }
+
+    /// Stop the WebSocket server, which closes all the connections and their subscriptions.
+    pub fn stop_ws(&mut self) {
+        if let Some(ws_handle) = self.ws_handle.take() {
+            if let Err(e) = ws_handle.stop() {
+                warn!("Failed to stop mock chain ws server: {:?}", e);
+            }
+        }
+    }
+
+    /// Start the stopped WebSocket server again at the same address.
+    pub async fn restart_ws(&mut self) -> Result<(), Error> {
+        if self.ws_handle.is_some() {
+            return Ok(());
+        }
+        // The address may not be released right after the server is stopped.
+        let mut attempts = 0;
+        let ws_server = loop {
+            match ws_server_builder().build(self.ws_addr).await {
+                Ok(ws_server) => break ws_server,
+                Err(e) if attempts < WS_RESTART_ATTEMPTS => {
+                    debug!("Failed to restart mock chain ws server: {:?}", e);
+                    attempts += 1;
+                    tokio::time::sleep(WS_RESTART_WAIT).await;
+                }
+                Err(e) => return Err(e),
+            }
+        };
+        self.ws_handle = Some(ws_server.start(rpc_module(self.chain.clone())?)?);
+        Ok(())
+    }
+
+    pub fn stop(mut self) {
+        self.stop_ws();
+        if let Err(e) = self.handle.stop() {
+            warn!("Failed to stop mock chain server: {:?}", e);
+        }
+    }
+}
+
+fn ws_server_builder() -> WsServerBuilder {
+    WsServerBuilder::default().set_id_provider(HexIdProvider::default())
+}
+
+fn rpc_error(code: i32, msg: impl AsRef<str>) -> Error {
+    Error::Call(CallError::Custom(ErrorObject::owned(
+        code,
+        msg.as_ref(),
+        None::<()>,
+    )))
+}
+
+fn invalid_params(msg: impl AsRef<str>) -> Error {
+    rpc_error(ErrorCode::InvalidParams.code(), msg)
+}
+
+fn server_error(msg: impl AsRef<str>) -> Error {
+    rpc_error(SERVER_ERROR_CODE, msg)
+}
+
+fn param(params: &Params, index: usize) -> Result<Value, Error> {
+    let mut list: Vec<Value> = params.parse()?;
+    if index < list.len() {
+        Ok(list.swap_remove(index))
+    } else {
+        Err(invalid_params(format!("missing param {}", index)))
+    }
+}
+
+fn param_str(params: &Params, index: usize) -> Result<String, Error> {
+    match param(params, index)? {
+        Value::String(s) => Ok(s),
+        v => Err(invalid_params(format!("invalid param {}: {}", index, v))),
+    }
+}
+
+/// Create the notification stream of an `eth_subscribe` call, which supports `logs` and
+/// `newHeads` subscriptions.
+fn subscribe(params: &Params, chain: &MockChain) -> Result<BoxStream<'static, Value>, String> {
+    let mut list: Vec<Value> = params
+        .parse()
+        .map_err(|e| format!("invalid params: {:?}", e))?;
+    if list.is_empty() {
+        return Err("missing subscription kind".into());
+    }
+    let kind = list.remove(0);
+    match kind.as_str() {
+        Some("logs") => {
+            let query = LogQuery::parse(&list.into_iter().next().unwrap_or_default())?;
+            Ok(chain
+                .subscribe_logs(query)
+                .map(|log| serde_json::to_value(log).expect("serializable log"))
+                .boxed())
+        }
+        Some("newHeads") => Ok(chain
+            .subscribe_new_heads()
+            .map(|block| serde_json::to_value(rpc_block(&block)).expect("serializable block"))
+            .boxed()),
+        _ => Err(format!("unsupported subscription {}", kind)),
+    }
+}
+
+fn rpc_block(block: &MockBlock) -> Block<H256> {
+    Block {
+        hash: Some(block.hash),
+        parent_hash: block.parent_hash,
+        number: Some(U64::from(block.number)),
+        timestamp: U256::from(block.number),
+        gas_limit: U256::from(GAS_LIMIT),
+        transactions: block.transactions.clone(),
+        ..Default::default()
+    }
+}
+
+fn rpc_module(chain: MockChain) -> Result<RpcModule<MockChain>, Error> {
+    let mut module = RpcModule::new(chain);
+
+    module.register_method("eth_chainId", |_, chain| {
+        Ok(U64::from(chain.state().chain_id))
+    })?;
+    module.register_method("net_version", |_, chain| {
+        Ok(chain.state().chain_id.to_string())
+    })?;
+    module.register_method("eth_blockNumber", |_, chain| {
+        Ok(U64::from(chain.state().latest_block_number()))
+    })?;
+    module.register_method("eth_gasPrice", |_, _| Ok(U256::from(GAS_PRICE)))?;
+    module.register_method("eth_estimateGas", |_, _| Ok(U256::from(GAS_LIMIT)))?;
+    module.register_method("eth_getTransactionCount", |_, chain| {
+        Ok(U256::from(chain.state().transactions.len()))
+    })?;
+
+    module.register_method("eth_getBlockByNumber", |params, chain| {
+        let block_number = parse_block_number(&param_str(&params, 0)?).map_err(invalid_params)?;
+        let state = chain.state();
+        let block_number = state
+            .resolve_block_number(block_number)
+            .map_err(server_error)?;
+        if state.missing_blocks.contains(&block_number) {
+            return Ok(None);
+        }
+        Ok(state.canonical_block(block_number).map(rpc_block))
+    })?;
+    module.register_method("eth_getBlockByHash", |params, chain| {
+        let hash = parse_h256(&param_str(&params, 0)?).map_err(invalid_params)?;
+        let state = chain.state();
+        Ok(state
+            .blocks
+            .get(&hash)
+            .filter(|block| !state.missing_blocks.contains(&block.number))
+            .map(rpc_block))
+    })?;
+
+    module.register_method("eth_getLogs", |params, chain| {
+        let query = LogQuery::parse(&param(&params, 0)?).map_err(invalid_params)?;
+        let mut state = chain.state();
+        if let Some(message) = state.get_logs_errors.pop_front() {
+            return Err(server_error(message));
+        }
+        if let Some(block_hash) = query.block_hash {
+            return match state.blocks.get(&block_hash) {
+                Some(block) => Ok(block.rpc_logs(&query, false)),
+                None => Err(server_error("unknown block")),
+            };
+        }
+        let from_block = state
+            .resolve_block_number(query.from_block)
+            .map_err(server_error)?;
+        let to_block = state
+            .resolve_block_number(query.to_block)
+            .map_err(server_error)?
+            .min(state.latest_block_number());
+        state.get_logs_ranges.push((from_block, to_block));
+        let mut logs = Vec::new();
+        for block_number in from_block..=to_block {
+            let block = state.canonical_block(block_number).expect("in range");
+            logs.append(&mut block.rpc_logs(&query, false));
+        }
+        match state.max_logs_per_query {
+            Some(max_logs) if logs.len() > max_logs => Err(server_error(format!(
+                "query returned more than {} results",
+                max_logs
+            ))),
+            _ => Ok(logs),
+        }
+    })?;
+    module.register_method("eth_newFilter", |params, chain| {
+        let query = LogQuery::parse(&param(&params, 0)?).map_err(invalid_params)?;
+        let mut state = chain.state();
+        let next_block_number = match query.from_block {
+            // Only the logs in the new blocks are returned.
+            BlockNumber::Latest | BlockNumber::Pending => state.latest_block_number() + 1,
+            from_block => state
+                .resolve_block_number(from_block)
+                .map_err(server_error)?,
+        };
+        let id = U256::from(state.next_filter_id);
+        state.next_filter_id += 1;
+        state.filters.insert(
+            id,
+            LogFilter {
+                query,
+                next_block_number,
+                reported_blocks: vec![],
+            },
+        );
+        Ok(id)
+    })?;
+    module.register_method("eth_getFilterChanges", |params, chain| {
+        let id: U256 = serde_json::from_value(param(&params, 0)?)
+            .map_err(|e| invalid_params(format!("invalid filter id: {:?}", e)))?;
+        chain
+            .state()
+            .filter_changes(id)
+            .ok_or_else(|| server_error("filter not found"))
+    })?;
+    module.register_method("eth_uninstallFilter", |params, chain| {
+        let id: U256 = serde_json::from_value(param(&params, 0)?)
+            .map_err(|e| invalid_params(format!("invalid filter id: {:?}", e)))?;
+        Ok(chain.state().filters.remove(&id).is_some())
+    })?;
+
+    module.register_method("eth_call", |params, chain| {
+        let call = param(&params, 0)?;
+        let to = call
+            .get("to")
+            .and_then(Value::as_str)
+            .ok_or_else(|| invalid_params("missing to"))
+            .and_then(|s| parse_address(s).map_err(invalid_params))?;
+        let data: Bytes = call
+            .get("data")
+            .or_else(|| call.get("input"))
+            .map(|v| serde_json::from_value(v.clone()))
+            .transpose()
+            .map_err(|e| invalid_params(format!("invalid data: {:?}", e)))?
+            .unwrap_or_default();
+        if data.len() < 4 {
+            return Err(invalid_params("missing function selector"));
+        }
+        let mut selector = [0u8; 4];
+        selector.copy_from_slice(&data[..4]);
+        let state = chain.state();
+        let handler = state.call_handlers.get(&(to, selector)).ok_or_else(|| {
+            server_error(format!(
+                "no call handler: to={:?} selector=0x{}",
+                to,
+                hex::encode(selector)
+            ))
+        })?;
+        handler(data.as_ref()).map(Bytes::from).map_err(|reason| {
+            rpc_error(
+                EXECUTION_REVERTED_CODE,
+                format!("execution reverted: {}", reason),
+            )
+        })
+    })?;
+    module.register_method("eth_sendRawTransaction", |params, chain| {
+        let raw: Bytes = serde_json::from_value(param(&params, 0)?)
+            .map_err(|e| invalid_params(format!("invalid transaction: {:?}", e)))?;
+        Ok(chain.state().submit_transaction(raw))
+    })?;
+    module.register_method("eth_getTransactionByHash", |params, chain| {
+        let hash = parse_h256(&param_str(&params, 0)?).map_err(invalid_params)?;
+        let state = chain.state();
+        if !state.transactions.iter().any(|(h, _)| *h == hash) {
+            return Ok(None);
+        }
+        let mut tx = Transaction {
+            hash,
+            ..Default::default()
+        };
+        if let Some((block_hash, index)) = state.mined_transactions.get(&hash) {
+            tx.block_hash = Some(*block_hash);
+            tx.block_number = Some(U64::from(state.blocks[block_hash].number));
+            tx.transaction_index = Some(U64::from(*index));
+        }
+        Ok(Some(tx))
+    })?;
+    module.register_method("eth_getTransactionReceipt", |params, chain| {
+        let hash = parse_h256(&param_str(&params, 0)?).map_err(invalid_params)?;
+        let state = chain.state();
+        Ok(state
+            .mined_transactions
+            .get(&hash)
+            .map(|(block_hash, index)| TransactionReceipt {
+                transaction_hash: hash,
+                transaction_index: U64::from(*index),
+                block_hash: Some(*block_hash),
+                block_number: Some(U64::from(state.blocks[block_hash].number)),
+                cumulative_gas_used: U256::from(GAS_LIMIT),
+                gas_used: Some(U256::from(GAS_LIMIT)),
+                status: Some(U64::from(1)),
+                logs: Vec::<Log>::new(),
+                ..Default::default()
+            }))
+    })?;
+
+    module.register_subscription(
+        "eth_subscribe",
+        "eth_subscription",
+        "eth_unsubscribe",
+        |params, mut sink, chain| {
+            let stream = match subscribe(&params, &chain) {
+                Ok(stream) => stream,
+                Err(e) => {
+                    return sink.reject(ErrorObject::owned(
+                        ErrorCode::InvalidParams.code(),
+                        e,
+                        None::<()>,
+                    ))
+                }
+            };
+            sink.accept()?;
+            tokio::spawn(async move {
+                let _ = sink.pipe_from_stream(stream).await;
+            });
+            Ok(())
+        },
+    )?;
+
+    Ok(module)
+}
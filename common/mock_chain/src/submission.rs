@@ -0,0 +1,78 @@
+use crate::chain::MockLog;
+use contract_interface::IONIANFLOW_ABI;
+use ethers::abi::{encode, ParamType, Token};
+use ethers::types::{Address, H256, U256};
+use ethers::utils::keccak256;
+
+/// Build the `Submission` log of the Flow contract at `flow_address`.
+///
+/// `nodes` are the `(root, height)` pairs of the submission merkle tree. The event inputs that
+/// are not used by the node are left as zero values.
+pub fn submission_log(
+    flow_address: Address,
+    submission_index: u64,
+    start_pos: u64,
+    length: u64,
+    nodes: Vec<([u8; 32], u64)>,
+) -> MockLog {
+    let event = IONIANFLOW_ABI
+        .event("Submission")
+        .expect("Submission event in Flow ABI");
+
+    let mut topics = vec![event.signature()];
+    let mut data_tokens = Vec::new();
+    for input in &event.inputs {
+        let token = match (input.name.as_str(), &input.kind) {
+            ("submissionIndex", _) => Token::Uint(U256::from(submission_index)),
+            ("startPos", _) => Token::Uint(U256::from(start_pos)),
+            ("submission", ParamType::Tuple(fields)) => {
+                let mut tokens: Vec<Token> = fields.iter().map(default_token).collect();
+                tokens[0] = Token::Uint(U256::from(length));
+                tokens[2] = Token::Array(
+                    nodes
+                        .iter()
+                        .map(|(root, height)| {
+                            Token::Tuple(vec![
+                                Token::FixedBytes(root.to_vec()),
+                                Token::Uint(U256::from(*height)),
+                            ])
+                        })
+                        .collect(),
+                );
+                Token::Tuple(tokens)
+            }
+            (_, kind) => default_token(kind),
+        };
+        if input.indexed {
+            let encoded = encode(&[token]);
+            if encoded.len() == 32 {
+                topics.push(H256::from_slice(&encoded));
+            } else {
+                topics.push(H256::from(keccak256(&encoded)));
+            }
+        } else {
+            data_tokens.push(token);
+        }
+    }
+
+    MockLog {
+        address: flow_address,
+        topics,
+        data: encode(&data_tokens),
+    }
+}
+
+fn default_token(kind: &ParamType) -> Token {
+    match kind {
+        ParamType::Address => Token::Address(Address::zero()),
+        ParamType::Bytes => Token::Bytes(vec![]),
+        ParamType::Int(_) => Token::Int(U256::zero()),
+        ParamType::Uint(_) => Token::Uint(U256::zero()),
+        ParamType::Bool => Token::Bool(false),
+        ParamType::String => Token::String(String::new()),
+        ParamType::Array(_) => Token::Array(vec![]),
+        ParamType::FixedBytes(size) => Token::FixedBytes(vec![0; *size]),
+        ParamType::FixedArray(kind, size) => Token::FixedArray(vec![default_token(kind); *size]),
+        ParamType::Tuple(kinds) => Token::Tuple(kinds.iter().map(default_token).collect()),
+    }
+}
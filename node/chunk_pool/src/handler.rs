@@ -1,10 +1,12 @@
 use super::mem_pool::MemoryChunkPool;
 use crate::mem_pool::FileID;
 use anyhow::Result;
+use log_entry_sync::LogSyncEvent;
 use network::NetworkMessage;
 use shared_types::ChunkArray;
 use std::sync::Arc;
 use storage_async::Store;
+use tokio::sync::broadcast::{error::RecvError, Receiver};
 use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
 
 /// Handle the cached file when uploaded completely and verified from blockchain.
@@ -92,3 +94,20 @@ impl ChunkPoolHandler {
         }
     }
 }
+
+/// Monitors the reverted transactions, so that the uploading files could be
+/// uploaded again after log entries retrieved from blockchain again.
+pub async fn monitor_reorg(mem_pool: Arc<MemoryChunkPool>, mut receiver: Receiver<LogSyncEvent>) {
+    info!("Worker started to monitor reverted transactions");
+
+    loop {
+        match receiver.recv().await {
+            Ok(LogSyncEvent::ReorgDetected { .. }) => {}
+            Ok(LogSyncEvent::Reverted { tx_seq }) => mem_pool.on_tx_reverted(tx_seq).await,
+            Err(RecvError::Closed) => return,
+            Err(RecvError::Lagged(lagged)) => {
+                error!(%lagged, "Failed to receive reverted tx (Lagged)");
+            }
+        }
+    }
+}
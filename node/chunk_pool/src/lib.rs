@@ -4,7 +4,7 @@ extern crate tracing;
 mod handler;
 mod mem_pool;
 
-pub use handler::ChunkPoolHandler;
+pub use handler::{monitor_reorg, ChunkPoolHandler};
 pub use mem_pool::{FileID, MemoryChunkPool, SegmentInfo};
 
 use std::sync::Arc;
@@ -42,6 +42,18 @@ impl MemoryCachedFile {
         self.id.tx_id = tx.id();
     }
 
+    /// Clears the transaction info if reverted, so that memory cached segments will
+    /// not be written into database until log entry retrieved again.
+    fn revert_tx(&mut self, tx_seq: u64) -> bool {
+        if self.total_chunks == 0 || self.id.tx_id.seq < tx_seq {
+            return false;
+        }
+
+        self.total_chunks = 0;
+        self.id.tx_id = Default::default();
+        true
+    }
+
     fn update_expiration_time(&mut self, timeout: Duration) {
         self.expired_at = Instant::now().add(timeout);
     }
@@ -86,6 +98,15 @@ impl ChunkPoolCache {
         Some(file)
     }
 
+    /// Reverts the transaction info of cached files, and returns the number of reverted files.
+    pub fn revert_files(&mut self, tx_seq: u64) -> usize {
+        self.files
+            .iter_mut()
+            .map(|(_, file)| file.revert_tx(tx_seq))
+            .filter(|reverted| *reverted)
+            .count()
+    }
+
     /// Remove files that no new segment uploaded for a long time.
     ///
     /// Note, when log sync delayed, files may be also garbage collected if the
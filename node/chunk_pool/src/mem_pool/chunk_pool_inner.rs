@@ -246,6 +246,18 @@ impl MemoryChunkPool {
         Ok(true)
     }
 
+    /// Updates the cached files and uploading files when transactions reverted
+    /// from `tx_seq`, so that users could upload them again.
+    pub async fn on_tx_reverted(&self, tx_seq: u64) {
+        let mut inner = self.inner.lock().await;
+        let cached = inner.segment_cache.revert_files(tx_seq);
+        let writing = inner.write_control.remove_reverted_files(tx_seq);
+        debug!(
+            "Files reverted in chunk pool, tx_seq={}, cached={}, writing={}",
+            tx_seq, cached, writing
+        );
+    }
+
     pub(crate) async fn remove_cached_file(&self, root: &DataRoot) -> Option<MemoryCachedFile> {
         self.inner.lock().await.segment_cache.remove_file(root)
     }
@@ -56,6 +56,10 @@ impl CtrlWindow {
         Ok(())
     }
 
+    fn has_writing(&self) -> bool {
+        self.slots.values().any(|s| *s == SlotStatus::Writing)
+    }
+
     fn rollback_writing(&mut self, index: usize) {
         let slot_status = self.slots.remove(&index);
         assert_eq!(slot_status, Some(SlotStatus::Writing));
@@ -124,6 +128,15 @@ impl ChunkPoolWriteCtrl {
         self.files.remove(root)
     }
 
+    /// Removes the files whose transactions are reverted. Files in writing are kept,
+    /// and will be removed when failed to write into store.
+    pub fn remove_reverted_files(&mut self, tx_seq: u64) -> usize {
+        let num_files = self.files.len();
+        self.files
+            .retain(|_, file| file.id.tx_id.seq < tx_seq || file.window.has_writing());
+        num_files - self.files.len()
+    }
+
     pub fn write_segment(
         &mut self,
         id: FileID,
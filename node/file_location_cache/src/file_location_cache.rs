@@ -1,15 +1,87 @@
 use crate::Config;
-use network::types::SignedAnnounceFile;
-use network::PeerId;
+use network::types::{SignedAnnounceFile, SignedAnnounceFiles, SignedAnnounceShardConfig};
+use network::{Multiaddr, PeerId, PubsubMessage};
 use parking_lot::Mutex;
 use priority_queue::PriorityQueue;
 use rand::seq::IteratorRandom;
 use shared_types::{timestamp_now, TxID};
 use std::cmp::Reverse;
 use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Announcement that signed by a peer, and only the latest one of each peer is cached.
+trait Announcement: Clone {
+    fn peer_id(&self) -> PeerId;
+    fn timestamp(&self) -> u32;
+}
+
+/// Announcement of a file, which is either announced alone or in a batch of files.
+#[derive(Debug, Clone)]
+pub enum FileAnnouncement {
+    Single(SignedAnnounceFile),
+    Batch(Arc<SignedAnnounceFiles>),
+}
+
+impl FileAnnouncement {
+    pub fn peer_id(&self) -> PeerId {
+        match self {
+            FileAnnouncement::Single(msg) => msg.peer_id.clone().into(),
+            FileAnnouncement::Batch(msg) => msg.peer_id.clone().into(),
+        }
+    }
+
+    pub fn at(&self) -> Multiaddr {
+        match self {
+            FileAnnouncement::Single(msg) => msg.at.clone().into(),
+            FileAnnouncement::Batch(msg) => msg.at.clone().into(),
+        }
+    }
+
+    pub fn timestamp(&self) -> u32 {
+        match self {
+            FileAnnouncement::Single(msg) => msg.timestamp,
+            FileAnnouncement::Batch(msg) => msg.timestamp,
+        }
+    }
+
+    /// Converts into a pubsub message to re-publish with the specified `resend_timestamp`.
+    pub fn into_pubsub_message(self, resend_timestamp: u32) -> PubsubMessage {
+        match self {
+            FileAnnouncement::Single(mut msg) => {
+                msg.resend_timestamp = resend_timestamp;
+                PubsubMessage::AnnounceFile(msg)
+            }
+            FileAnnouncement::Batch(msg) => {
+                let mut msg = msg.as_ref().clone();
+                msg.resend_timestamp = resend_timestamp;
+                PubsubMessage::AnnounceFiles(msg)
+            }
+        }
+    }
+}
+
+impl Announcement for FileAnnouncement {
+    fn peer_id(&self) -> PeerId {
+        FileAnnouncement::peer_id(self)
+    }
+
+    fn timestamp(&self) -> u32 {
+        FileAnnouncement::timestamp(self)
+    }
+}
+
+impl Announcement for SignedAnnounceShardConfig {
+    fn peer_id(&self) -> PeerId {
+        self.peer_id.clone().into()
+    }
+
+    fn timestamp(&self) -> u32 {
+        self.timestamp
+    }
+}
 
 /// Caches limited announcements of specified file from different peers.
-struct AnnouncementCache {
+struct AnnouncementCache<T> {
     /// Maximum number of announcements in cache.
     capacity: usize,
 
@@ -20,14 +92,14 @@ struct AnnouncementCache {
 
     /// All cached announcements that mapped from peer id to announcement.
     /// Note, only cache the latest announcement for each peer.
-    items: HashMap<PeerId, SignedAnnounceFile>,
+    items: HashMap<PeerId, T>,
 
     /// All announcements are prioritized by timestamp.
     /// The top element is the oldest announcement.
     priorities: PriorityQueue<PeerId, Reverse<u32>>,
 }
 
-impl AnnouncementCache {
+impl<T: Announcement> AnnouncementCache<T> {
     fn new(capacity: usize, timeout_secs: u32) -> Self {
         assert!(capacity > 0);
 
@@ -46,7 +118,7 @@ impl AnnouncementCache {
     }
 
     /// Removes the oldest announcement if any.
-    fn pop(&mut self) -> Option<SignedAnnounceFile> {
+    fn pop(&mut self) -> Option<T> {
         let (peer_id, _) = self.priorities.pop()?;
         self.items.remove(&peer_id)
     }
@@ -73,21 +145,21 @@ impl AnnouncementCache {
     }
 
     /// Insert the specified `announcement` into cache.
-    fn insert(&mut self, announcement: SignedAnnounceFile) {
+    fn insert(&mut self, announcement: T) {
         self.garbage_collect();
 
-        let peer_id = announcement.peer_id.clone().into();
+        let peer_id = announcement.peer_id();
 
         if let Some(existing) = self.items.get(&peer_id) {
             // ignore older announcement
-            if announcement.timestamp <= existing.timestamp {
+            if announcement.timestamp() <= existing.timestamp() {
                 return;
             }
         }
 
         // insert or update
         self.priorities
-            .push(peer_id, Reverse(announcement.timestamp));
+            .push(peer_id, Reverse(announcement.timestamp()));
         self.items.insert(peer_id, announcement);
 
         // remove oldest one if capacity exceeded
@@ -97,7 +169,7 @@ impl AnnouncementCache {
     }
 
     /// Randomly pick an announcement if any.
-    fn random(&mut self) -> (Option<SignedAnnounceFile>, usize) {
+    fn random(&mut self) -> (Option<T>, usize) {
         let collected = self.garbage_collect();
 
         // all announcements garbage collected
@@ -115,7 +187,7 @@ impl AnnouncementCache {
     }
 
     /// Returns all announcements.
-    fn all(&mut self) -> (Vec<SignedAnnounceFile>, usize) {
+    fn all(&mut self) -> (Vec<T>, usize) {
         let collected = self.garbage_collect();
         let result = self.items.iter().map(|(_, item)| item.clone()).collect();
         (result, collected)
@@ -131,7 +203,7 @@ struct FileCache {
     total_announcements: usize,
 
     /// All cached files that mapped from `tx_id` to `AnnouncementCache`.
-    files: HashMap<TxID, AnnouncementCache>,
+    files: HashMap<TxID, AnnouncementCache<FileAnnouncement>>,
 
     /// All files are prioritized by timestamp.
     /// The top element is the `AnnouncementCache` that has the oldest announcement.
@@ -148,10 +220,8 @@ impl FileCache {
         }
     }
 
-    /// Insert the specified `announcement` into cache.
-    fn insert(&mut self, announcement: SignedAnnounceFile) {
-        let tx_id = announcement.tx_id;
-
+    /// Insert the specified `announcement` of file `tx_id` into cache.
+    fn insert(&mut self, tx_id: TxID, announcement: FileAnnouncement) {
         let item = self.files.entry(tx_id).or_insert_with(|| {
             AnnouncementCache::new(
                 self.config.max_entries_per_file,
@@ -176,7 +246,7 @@ impl FileCache {
     }
 
     /// Removes the oldest file announcement.
-    fn pop(&mut self) -> Option<SignedAnnounceFile> {
+    fn pop(&mut self) -> Option<FileAnnouncement> {
         let (&tx_id, _) = self.priorities.peek()?;
         let item = self.files.get_mut(&tx_id)?;
 
@@ -191,7 +261,7 @@ impl FileCache {
     }
 
     /// Randomly pick a announcement of specified file by `tx_id`.
-    fn random(&mut self, tx_id: TxID) -> Option<SignedAnnounceFile> {
+    fn random(&mut self, tx_id: TxID) -> Option<FileAnnouncement> {
         let item = self.files.get_mut(&tx_id)?;
         let (result, collected) = item.random();
         self.update_after_gc(tx_id, collected);
@@ -224,7 +294,7 @@ impl FileCache {
     }
 
     /// Returns all the announcements of specified file by `tx_id`.
-    fn all(&mut self, tx_id: TxID) -> Option<Vec<SignedAnnounceFile>> {
+    fn all(&mut self, tx_id: TxID) -> Option<Vec<FileAnnouncement>> {
         let item = self.files.get_mut(&tx_id)?;
         let (result, collected) = item.all();
         self.update_after_gc(tx_id, collected);
@@ -234,26 +304,64 @@ impl FileCache {
 
 pub struct FileLocationCache {
     cache: Mutex<FileCache>,
+
+    /// The latest shard config announcements of peers.
+    shard_configs: Mutex<AnnouncementCache<SignedAnnounceShardConfig>>,
 }
 
 impl Default for FileLocationCache {
     fn default() -> Self {
+        let config = Config::default();
+        let shard_configs =
+            AnnouncementCache::new(config.max_shard_configs, config.entry_expiration_time_secs);
+
         FileLocationCache {
-            cache: Mutex::new(FileCache::new(Default::default())),
+            cache: Mutex::new(FileCache::new(config)),
+            shard_configs: Mutex::new(shard_configs),
         }
     }
 }
 
 impl FileLocationCache {
     pub fn insert(&self, announcement: SignedAnnounceFile) {
-        self.cache.lock().insert(announcement);
+        let tx_id = announcement.tx_id;
+        self.cache
+            .lock()
+            .insert(tx_id, FileAnnouncement::Single(announcement));
+    }
+
+    /// Inserts an announcement of a batch of files, which is cached for each file.
+    pub fn insert_batch(&self, announcement: SignedAnnounceFiles) {
+        let announcement = Arc::new(announcement);
+        let mut cache = self.cache.lock();
+
+        for tx_id in announcement.tx_ids.iter() {
+            cache.insert(*tx_id, FileAnnouncement::Batch(announcement.clone()));
+        }
     }
 
-    pub fn get_one(&self, tx_id: TxID) -> Option<SignedAnnounceFile> {
+    pub fn get_one(&self, tx_id: TxID) -> Option<FileAnnouncement> {
         self.cache.lock().random(tx_id)
     }
 
-    pub fn get_all(&self, tx_id: TxID) -> Vec<SignedAnnounceFile> {
+    pub fn get_all(&self, tx_id: TxID) -> Vec<FileAnnouncement> {
         self.cache.lock().all(tx_id).unwrap_or_default()
     }
+
+    pub fn insert_shard_config(&self, announcement: SignedAnnounceShardConfig) {
+        self.shard_configs.lock().insert(announcement);
+    }
+
+    /// Returns the shard config announcements of peers that store all the flow entries
+    /// in range `[start_index, end_index)`.
+    pub fn get_shard_configs(
+        &self,
+        start_index: u64,
+        end_index: u64,
+    ) -> Vec<SignedAnnounceShardConfig> {
+        let (all, _) = self.shard_configs.lock().all();
+        all.into_iter()
+            .filter(|announcement| announcement.config.covers(start_index, end_index))
+            .collect()
+    }
 }
@@ -1,11 +1,12 @@
 mod file_location_cache;
 
-pub use crate::file_location_cache::FileLocationCache;
+pub use crate::file_location_cache::{FileAnnouncement, FileLocationCache};
 
 pub struct Config {
     pub max_entries_total: usize,
     pub max_entries_per_file: usize,
     pub entry_expiration_time_secs: u32,
+    pub max_shard_configs: usize,
 }
 
 impl Default for Config {
@@ -14,6 +15,7 @@ impl Default for Config {
             max_entries_total: 4096,
             max_entries_per_file: 4,
             entry_expiration_time_secs: 3600,
+            max_shard_configs: 1024,
         }
     }
 }
@@ -5,6 +5,8 @@ mod sync_manager;
 
 pub use rpc_proxy::ContractAddress;
 pub use sync_manager::{
-    config::{CacheConfig, LogSyncConfig},
-    LogSyncEvent, LogSyncManager,
+    config::{CacheConfig, ConfirmationStrategy, LogSyncConfig},
+    file_log_source::LogRecord,
+    status::{LogRecoveryStatus, LogSyncStatus},
+    LogSyncEvent, LogSyncManager, LogSyncRequest, LogSyncResponse, LogSyncSender,
 };
@@ -1,15 +1,15 @@
 use crate::rpc_proxy::{ContractAddress, EvmRpcProxy, SubEvent, SubFilter};
 use async_trait::async_trait;
-use ethers::prelude::{Bytes, Middleware, Provider, Ws};
-use ethers::types::TransactionRequest;
-use jsonrpsee::core::client::Subscription;
+use ethers::prelude::{Bytes, Filter, Middleware, Provider, Ws, H256};
+use ethers::types::{TransactionRequest, ValueOrArray};
+use futures::stream::BoxStream;
+use futures::StreamExt;
 
 pub struct EthClient {
     client: Provider<Ws>,
 }
 
 impl EthClient {
-    #[allow(unused)]
     pub async fn new(url: &str) -> anyhow::Result<EthClient> {
         let client = Provider::new(Ws::connect(url).await?);
         Ok(Self { client })
@@ -23,7 +23,34 @@ impl EvmRpcProxy for EthClient {
         Ok(self.client.call(&request.into(), None).await?)
     }
 
-    async fn sub_events(&self, _filter: SubFilter) -> Subscription<SubEvent> {
-        todo!()
+    async fn sub_events(&self, filter: SubFilter) -> anyhow::Result<BoxStream<'_, SubEvent>> {
+        let mut log_filter = Filter::new();
+        if let Some(to) = filter.to {
+            log_filter = log_filter.address(ValueOrArray::Value(to));
+        }
+        for (i, topic) in filter.topics.into_iter().enumerate() {
+            log_filter = match i {
+                0 => log_filter.topic0(topic),
+                1 => log_filter.topic1(topic),
+                2 => log_filter.topic2(topic),
+                3 => log_filter.topic3(topic),
+                _ => anyhow::bail!("too many topics in filter"),
+            };
+        }
+        let stream = self.client.subscribe_logs(&log_filter).await?;
+        Ok(stream.boxed())
+    }
+
+    async fn sub_blocks(&self) -> anyhow::Result<BoxStream<'_, (u64, H256)>> {
+        let stream = self.client.subscribe_blocks().await?;
+        Ok(stream
+            .filter_map(|block| async move {
+                match (block.number, block.hash) {
+                    (Some(number), Some(hash)) => Some((number.as_u64(), hash)),
+                    // Pending blocks are not used for sync progress.
+                    _ => None,
+                }
+            })
+            .boxed())
     }
 }
@@ -1,38 +1,33 @@
 use anyhow::Result;
 use async_trait::async_trait;
 use ethereum_types::{H160, H256};
-use ethers::prelude::Bytes;
-use jsonrpsee::core::client::Subscription;
+use ethers::prelude::{Bytes, Log};
+use futures::stream::BoxStream;
 
 // TODO: Define accounts/filter/events as associated types?
-// TODO: Define an abstraction suitable for other chains.
 #[async_trait]
 pub trait EvmRpcProxy {
     async fn call(&self, to: ContractAddress, data: Bytes) -> Result<Bytes>;
 
-    async fn sub_events(&self, filter: SubFilter) -> Subscription<SubEvent>;
+    /// Subscribe to the event logs matching `filter`.
+    /// The stream ends if the subscription is dropped by the remote side.
+    async fn sub_events(&self, filter: SubFilter) -> Result<BoxStream<'_, SubEvent>>;
+
+    /// Subscribe to new block heads and return `(block_number, block_hash)`.
+    async fn sub_blocks(&self) -> Result<BoxStream<'_, (u64, H256)>>;
 }
 
 pub type ContractAddress = H160;
 
 pub type Topic = H256;
 
-#[allow(unused)]
 pub struct SubFilter {
-    to: Option<ContractAddress>,
-    topics: Vec<Topic>,
-}
-
-#[allow(unused)]
-pub struct SubEvent {
-    /// Address
-    pub address: ContractAddress,
-
-    /// Topics
+    pub to: Option<ContractAddress>,
     pub topics: Vec<Topic>,
-
-    /// Data
-    pub data: Bytes,
 }
 
+/// The logs returned by subscriptions keep the block info and the `removed` flag,
+/// which are needed to handle chain reorgs.
+pub type SubEvent = Log;
+
 pub(crate) mod eth;
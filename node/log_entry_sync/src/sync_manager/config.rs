@@ -1,45 +1,130 @@
 use crate::rpc_proxy::ContractAddress;
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
 
 pub struct LogSyncConfig {
-    pub rpc_endpoint_url: String,
+    /// The blockchain endpoints in the order of priority.
+    /// Requests fail over to the next endpoint if the previous ones are unavailable.
+    pub rpc_endpoint_urls: Vec<String>,
+    /// If larger than 1, the logs are accepted only if this number of endpoints return the
+    /// same logs.
+    pub rpc_quorum: usize,
+    /// The WebSocket endpoint used to subscribe to new logs.
+    /// If it's `None`, new logs are polled with `rpc_endpoint_urls`.
+    pub ws_endpoint_url: Option<String>,
     pub contract_address: ContractAddress,
     pub cache_config: CacheConfig,
 
     /// The block number where we start to sync data.
     /// This is usually the block number when Ionian contract is deployed.
     pub start_block_number: u64,
-    /// The number of blocks needed for confirmation on the blockchain.
-    /// This is used to rollback to a stable height if reorg happens during node restart.
-    /// TODO(zz): Some blockchains have better confirmation/finalization mechanisms.
+    /// The number of blocks built on a block before it is final, which is used by the `Depth`
+    /// and hybrid confirmation strategies.
     pub confirmation_block_count: u64,
+    /// How to decide if a block is final.
+    pub confirmation_strategy: ConfirmationStrategy,
     /// Maximum number of event logs to poll at a time.
     pub log_page_size: u64,
+    /// If set, the submissions are replayed from this file instead of the blockchain.
+    /// Each line is a JSON `LogRecord`.
+    pub submission_file: Option<PathBuf>,
 }
 
 #[derive(Clone)]
 pub struct CacheConfig {
-    /// The data with a size larger than this will not be cached.
+    /// The data with a size larger than this will not be cached in memory.
     /// This is reasonable because uploading
     pub max_data_size: usize,
+    /// The directory to save the reverted data larger than `max_data_size`.
+    /// It's cleared when the node starts. If it's `None`, large data are not cached.
+    pub dir: Option<PathBuf>,
+    /// The maximum total size of the data saved in `dir`.
+    pub max_disk_data_size: usize,
     pub tx_seq_ttl: usize,
 }
 
 impl LogSyncConfig {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
-        rpc_endpoint_url: String,
+        rpc_endpoint_urls: Vec<String>,
+        rpc_quorum: usize,
+        ws_endpoint_url: Option<String>,
         contract_address: ContractAddress,
         start_block_number: u64,
         confirmation_block_count: u64,
+        confirmation_strategy: ConfirmationStrategy,
         cache_config: CacheConfig,
         log_page_size: u64,
+        submission_file: Option<PathBuf>,
     ) -> Self {
         Self {
-            rpc_endpoint_url,
+            rpc_endpoint_urls,
+            rpc_quorum,
+            ws_endpoint_url,
             contract_address,
             cache_config,
             start_block_number,
             confirmation_block_count,
+            confirmation_strategy,
             log_page_size,
+            submission_file,
         }
     }
 }
+
+/// The strategy to decide which blocks are final, so their logs can be processed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConfirmationStrategy {
+    /// A block is final after `confirmation_block_count` blocks are built on it.
+    Depth,
+    /// A block is final if it is not after the `safe` block.
+    Safe,
+    /// A block is final if it is not after the `finalized` block.
+    Finalized,
+    /// A block is final if it is final with either `Safe` or `Depth`.
+    /// `Depth` is used alone if the blockchain does not support the `safe` tag.
+    HybridSafe,
+    /// A block is final if it is final with either `Finalized` or `Depth`.
+    /// `Depth` is used alone if the blockchain does not support the `finalized` tag.
+    HybridFinalized,
+}
+
+impl ConfirmationStrategy {
+    pub fn uses_depth(&self) -> bool {
+        matches!(
+            self,
+            ConfirmationStrategy::Depth
+                | ConfirmationStrategy::HybridSafe
+                | ConfirmationStrategy::HybridFinalized
+        )
+    }
+}
+
+impl FromStr for ConfirmationStrategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "depth" => Ok(ConfirmationStrategy::Depth),
+            "safe" => Ok(ConfirmationStrategy::Safe),
+            "finalized" => Ok(ConfirmationStrategy::Finalized),
+            "hybrid_safe" => Ok(ConfirmationStrategy::HybridSafe),
+            "hybrid_finalized" => Ok(ConfirmationStrategy::HybridFinalized),
+            _ => Err(format!("unknown confirmation strategy: {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for ConfirmationStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            ConfirmationStrategy::Depth => "depth",
+            ConfirmationStrategy::Safe => "safe",
+            ConfirmationStrategy::Finalized => "finalized",
+            ConfirmationStrategy::HybridSafe => "hybrid_safe",
+            ConfirmationStrategy::HybridFinalized => "hybrid_finalized",
+        };
+        write!(f, "{}", s)
+    }
+}
@@ -1,58 +1,315 @@
 use crate::sync_manager::config::CacheConfig;
-use shared_types::DataRoot;
+use anyhow::{bail, Result};
+use jsonrpsee::tracing::{debug, warn};
+use shared_types::{ChunkArray, DataRoot, CHUNK_SIZE};
 use std::cmp;
 use std::collections::HashMap;
+use std::fs;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+/// The extension of the files saved in the cache directory. Only these files are removed when
+/// the cache is created, so a misconfigured directory does not lose other files.
+const CACHE_FILE_EXTENSION: &str = "revert";
+
+enum CachedLocation {
+    Memory(Vec<ChunkArray>),
+    /// The data are saved in a file in the cache directory.
+    Disk(PathBuf),
+}
 
 struct CachedData {
     /// Used for garbage collection.
     last_seen_tx_seq: u64,
-    /// Complete data for a given DataRoot.
-    data: Vec<u8>,
+    /// The total size of the cached chunks.
+    size: usize,
+    /// Sorted, non-overlapping chunks. The `start_index` of each `ChunkArray` is the chunk
+    /// offset within the data.
+    location: CachedLocation,
 }
 
+/// Cache the data of reverted transactions, so the data can be restored if the same data root
+/// is submitted again after chain reorg.
+///
+/// The data larger than `max_data_size` are saved in `dir` if it's set.
 pub struct DataCache {
     root_to_data: HashMap<DataRoot, CachedData>,
+    /// The total size of the data saved on disk.
+    disk_size: usize,
     config: CacheConfig,
 }
 
 impl DataCache {
     pub fn new(config: CacheConfig) -> Self {
+        if let Some(dir) = &config.dir {
+            if let Err(e) = fs::create_dir_all(dir) {
+                warn!("failed to create data cache dir: dir={:?} e={:?}", dir, e);
+            }
+            // The cached data from the last run cannot be matched to any transaction.
+            if let Err(e) = clear_cache_files(dir) {
+                warn!("failed to clear data cache dir: dir={:?} e={:?}", dir, e);
+            }
+        }
         Self {
             root_to_data: HashMap::new(),
+            disk_size: 0,
             config,
         }
     }
 
-    pub fn add_data(&mut self, root: DataRoot, tx_seq: u64, data: Vec<u8>) -> bool {
-        if data.len() > self.config.max_data_size {
-            // large data are not cached.
-            return false;
+    /// Cache the available chunks of a data root. The chunks are merged with the ones cached
+    /// for the same root before.
+    ///
+    /// Return `false` if the data are too large to cache.
+    pub fn add_data(&mut self, root: DataRoot, tx_seq: u64, chunks: Vec<ChunkArray>) -> bool {
+        let (last_seen_tx_seq, mut cached_chunks) = match self.remove(&root) {
+            Some((last_seen_tx_seq, cached_chunks)) => {
+                (cmp::max(last_seen_tx_seq, tx_seq), cached_chunks)
+            }
+            None => (tx_seq, Vec::new()),
+        };
+        cached_chunks.extend(chunks);
+        let chunks = merge_chunks(cached_chunks);
+        let size = chunks.iter().map(|c| c.data.len()).sum();
+        if size == 0 {
+            return true;
         }
-        // TODO: insert partial data and merge here.
-        self.root_to_data
-            .entry(root)
-            .and_modify(|cached| {
-                cached.last_seen_tx_seq = cmp::max(tx_seq, cached.last_seen_tx_seq)
-            })
-            .or_insert(CachedData {
-                last_seen_tx_seq: tx_seq,
-                data,
-            });
+
+        let location = if size <= self.config.max_data_size {
+            CachedLocation::Memory(chunks)
+        } else {
+            match self.save_to_disk(&root, &chunks, size) {
+                Ok(path) => {
+                    self.disk_size += size;
+                    CachedLocation::Disk(path)
+                }
+                Err(e) => {
+                    debug!("reverted data not saved to disk: root={:?} e={:?}", root, e);
+                    return false;
+                }
+            }
+        };
+        self.root_to_data.insert(
+            root,
+            CachedData {
+                last_seen_tx_seq,
+                size,
+                location,
+            },
+        );
         true
     }
 
-    /// Remove and return the data of a given `DataRoot`.
-    /// If two completed reverted transactions have the same root and both appear later,
+    /// Remove and return the data chunks of a given `DataRoot`.
+    /// If two reverted transactions have the same root and both appear later,
     /// the second one will have its data copied in `put_tx`.
-    pub fn pop_data(&mut self, root: &DataRoot) -> Option<Vec<u8>> {
-        self.root_to_data.remove(root).map(|e| e.data)
+    pub fn pop_data(&mut self, root: &DataRoot) -> Option<Vec<ChunkArray>> {
+        self.remove(root).map(|(_, chunks)| chunks)
     }
 
     /// Remove timeout data entries according to TTL.
     pub fn garbage_collect(&mut self, latest_tx_seq: u64) {
+        let tx_seq_ttl = self.config.tx_seq_ttl as u64;
         // We won't keep too many data, so it's okay to just iterate here.
-        self.root_to_data.retain(|_, cached| {
-            cached.last_seen_tx_seq + self.config.tx_seq_ttl as u64 >= latest_tx_seq
-        })
+        let expired: Vec<DataRoot> = self
+            .root_to_data
+            .iter()
+            .filter(|(_, cached)| cached.last_seen_tx_seq + tx_seq_ttl < latest_tx_seq)
+            .map(|(root, _)| *root)
+            .collect();
+        for root in expired {
+            if let Some(cached) = self.root_to_data.remove(&root) {
+                self.delete_from_disk(cached);
+            }
+        }
+    }
+
+    fn remove(&mut self, root: &DataRoot) -> Option<(u64, Vec<ChunkArray>)> {
+        let cached = self.root_to_data.remove(root)?;
+        let last_seen_tx_seq = cached.last_seen_tx_seq;
+        let chunks = match &cached.location {
+            CachedLocation::Memory(chunks) => Ok(chunks.clone()),
+            CachedLocation::Disk(path) => load_from_disk(path),
+        };
+        self.delete_from_disk(cached);
+        match chunks {
+            Ok(chunks) => Some((last_seen_tx_seq, chunks)),
+            Err(e) => {
+                warn!("failed to load cached data: root={:?} e={:?}", root, e);
+                None
+            }
+        }
+    }
+
+    fn save_to_disk(&self, root: &DataRoot, chunks: &[ChunkArray], size: usize) -> Result<PathBuf> {
+        let dir = match &self.config.dir {
+            Some(dir) => dir,
+            None => bail!("no cache dir"),
+        };
+        if self.disk_size + size > self.config.max_disk_data_size {
+            bail!("disk cache is full: used={} size={}", self.disk_size, size);
+        }
+        let path = dir.join(format!("{:x}.{}", root, CACHE_FILE_EXTENSION));
+        let mut writer = BufWriter::new(fs::File::create(&path)?);
+        for chunk_array in chunks {
+            writer.write_all(&chunk_array.start_index.to_be_bytes())?;
+            writer.write_all(&(chunk_array.data.len() as u64).to_be_bytes())?;
+            writer.write_all(&chunk_array.data)?;
+        }
+        writer.flush()?;
+        Ok(path)
+    }
+
+    fn delete_from_disk(&mut self, cached: CachedData) {
+        if let CachedLocation::Disk(path) = cached.location {
+            self.disk_size -= cached.size;
+            if let Err(e) = fs::remove_file(&path) {
+                warn!("failed to remove cached data: path={:?} e={:?}", path, e);
+            }
+        }
+    }
+}
+
+/// Remove the files saved by the cache in `dir`, and keep the other files.
+fn clear_cache_files(dir: &Path) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_file()
+            && path.extension().and_then(|ext| ext.to_str()) == Some(CACHE_FILE_EXTENSION)
+        {
+            fs::remove_file(&path)?;
+        }
+    }
+    Ok(())
+}
+
+fn load_from_disk(path: &Path) -> Result<Vec<ChunkArray>> {
+    let mut reader = BufReader::new(fs::File::open(path)?);
+    let mut chunks = Vec::new();
+    let mut header = [0u8; 16];
+    loop {
+        match reader.read_exact(&mut header) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e.into()),
+        }
+        let start_index = u64::from_be_bytes(header[..8].try_into().expect("length match"));
+        let len = u64::from_be_bytes(header[8..].try_into().expect("length match"));
+        let mut data = vec![0u8; len as usize];
+        reader.read_exact(&mut data)?;
+        chunks.push(ChunkArray { data, start_index });
+    }
+    Ok(chunks)
+}
+
+/// Sort the chunks and merge the overlapping or adjacent ones.
+fn merge_chunks(mut chunks: Vec<ChunkArray>) -> Vec<ChunkArray> {
+    chunks.sort_by_key(|c| c.start_index);
+    let mut merged: Vec<ChunkArray> = Vec::with_capacity(chunks.len());
+    for chunk_array in chunks {
+        if chunk_array.data.is_empty() {
+            continue;
+        }
+        if let Some(last) = merged.last_mut() {
+            let last_end = last.start_index + (last.data.len() / CHUNK_SIZE) as u64;
+            if chunk_array.start_index <= last_end {
+                let end = chunk_array.start_index + (chunk_array.data.len() / CHUNK_SIZE) as u64;
+                if end > last_end {
+                    let offset = (last_end - chunk_array.start_index) as usize * CHUNK_SIZE;
+                    last.data.extend_from_slice(&chunk_array.data[offset..]);
+                }
+                continue;
+            }
+        }
+        merged.push(chunk_array);
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{merge_chunks, DataCache};
+    use crate::sync_manager::config::CacheConfig;
+    use shared_types::{ChunkArray, DataRoot, CHUNK_SIZE};
+
+    fn chunks(start_index: u64, end_index: u64) -> ChunkArray {
+        let mut data = vec![0u8; (end_index - start_index) as usize * CHUNK_SIZE];
+        for i in start_index..end_index {
+            data[(i - start_index) as usize * CHUNK_SIZE] = i as u8;
+        }
+        ChunkArray { data, start_index }
+    }
+
+    fn cache_config(dir: Option<std::path::PathBuf>) -> CacheConfig {
+        CacheConfig {
+            max_data_size: 4 * CHUNK_SIZE,
+            dir,
+            max_disk_data_size: 16 * CHUNK_SIZE,
+            tx_seq_ttl: 10,
+        }
+    }
+
+    #[test]
+    fn test_merge_chunks() {
+        assert_eq!(
+            merge_chunks(vec![chunks(6, 8), chunks(0, 2), chunks(1, 4), chunks(4, 5)]),
+            vec![chunks(0, 5), chunks(6, 8)]
+        );
+    }
+
+    #[test]
+    fn test_partial_data() {
+        let mut cache = DataCache::new(cache_config(None));
+        let root = DataRoot::from_low_u64_be(1);
+        assert!(cache.add_data(root, 0, vec![chunks(0, 1)]));
+        assert!(cache.add_data(root, 1, vec![chunks(2, 3)]));
+        assert!(cache.add_data(root, 2, vec![chunks(1, 2)]));
+        assert_eq!(cache.pop_data(&root), Some(vec![chunks(0, 3)]));
+        assert_eq!(cache.pop_data(&root), None);
+
+        // Large data are dropped without a cache dir.
+        assert!(!cache.add_data(root, 3, vec![chunks(0, 5)]));
+    }
+
+    #[test]
+    fn test_disk_data() {
+        let dir = std::env::temp_dir().join(format!("data_cache_test_{}", std::process::id()));
+        let mut cache = DataCache::new(cache_config(Some(dir.clone())));
+        let root = DataRoot::from_low_u64_be(1);
+        assert!(cache.add_data(root, 0, vec![chunks(0, 3), chunks(5, 8)]));
+        assert!(cache.add_data(root, 1, vec![chunks(3, 5)]));
+        assert_eq!(cache.disk_size, 8 * CHUNK_SIZE);
+        // The disk cache is full.
+        assert!(!cache.add_data(DataRoot::from_low_u64_be(2), 1, vec![chunks(0, 10)]));
+
+        assert_eq!(cache.pop_data(&root), Some(vec![chunks(0, 8)]));
+        assert_eq!(cache.disk_size, 0);
+
+        assert!(cache.add_data(root, 1, vec![chunks(0, 8)]));
+        cache.garbage_collect(11);
+        assert_eq!(cache.disk_size, 8 * CHUNK_SIZE);
+        cache.garbage_collect(12);
+        assert_eq!(cache.disk_size, 0);
+        assert_eq!(cache.pop_data(&root), None);
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 0);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_clear_only_cache_files() {
+        let dir = std::env::temp_dir().join(format!("data_cache_clear_{}", std::process::id()));
+        let mut cache = DataCache::new(cache_config(Some(dir.clone())));
+        assert!(cache.add_data(DataRoot::from_low_u64_be(1), 0, vec![chunks(0, 8)]));
+        std::fs::write(dir.join("other"), b"other").unwrap();
+        drop(cache);
+
+        // The cached data of the last run are removed, and other files are kept.
+        let _cache = DataCache::new(cache_config(Some(dir.clone())));
+        let files = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect::<Vec<_>>();
+        assert_eq!(files, vec!["other"]);
+        std::fs::remove_dir_all(dir).unwrap();
     }
 }
@@ -0,0 +1,349 @@
+use crate::sync_manager::log_source::{LogFetchProgress, LogSource};
+use crate::sync_manager::RETRY_WAIT_MS;
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use ethereum_types::H256;
+use jsonrpsee::tracing::{debug, error, warn};
+use serde::{Deserialize, Serialize};
+use shared_types::Transaction;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use task_executor::TaskExecutor;
+use tokio::sync::mpsc::{Receiver, Sender, UnboundedReceiver, UnboundedSender};
+
+/// The capacity of the channel to send recovered submissions.
+const RECOVER_CHANNEL_CAPACITY: usize = 1000;
+
+/// The submissions in a block, as a line of JSON in a submission file.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogRecord {
+    pub block_number: u64,
+    pub block_hash: H256,
+    pub transactions: Vec<Transaction>,
+}
+
+/// A `LogSource` that replays the submissions recorded in a file.
+///
+/// Each line of the file is a `LogRecord`, in ascending order of block number. The blocks
+/// without submissions can be skipped. The file is read again periodically, so new records can
+/// be appended. If the recorded blocks are rewritten with other hashes, the transactions in them
+/// are reverted like chain reorg.
+pub struct FileLogSource {
+    path: PathBuf,
+    poll_interval: Duration,
+}
+
+impl FileLogSource {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            poll_interval: Duration::from_millis(RETRY_WAIT_MS),
+        }
+    }
+
+    async fn send_record(record: LogRecord, tx: &Sender<LogFetchProgress>) -> Result<()> {
+        for transaction in record.transactions {
+            tx.send(LogFetchProgress::Transaction((transaction, None)))
+                .await?;
+        }
+        tx.send(LogFetchProgress::SyncedBlock((
+            record.block_number,
+            record.block_hash,
+        )))
+        .await?;
+        Ok(())
+    }
+
+    async fn watch_loop(
+        path: &Path,
+        poll_interval: Duration,
+        start_block_number: u64,
+        watch_tx: &UnboundedSender<LogFetchProgress>,
+    ) -> Result<()> {
+        // The sent blocks as `(block_number, block_hash, first_tx_seq)`, which are checked to
+        // detect rewritten records.
+        let mut sent_blocks: Vec<(u64, H256, Option<u64>)> = Vec::new();
+        let mut next_block_number = start_block_number;
+        // Stop if the receiver is dropped, e.g. for a resync.
+        while !watch_tx.is_closed() {
+            let records = match read_records(path) {
+                Ok(records) => records,
+                Err(e) => {
+                    error!("read submission file error: e={:?}", e);
+                    tokio::time::sleep(poll_interval).await;
+                    continue;
+                }
+            };
+
+            // A sent block is reverted if its record is replaced or removed. The blocks after the
+            // last record are kept, because the file may be being rewritten.
+            let last_block_number = records.last().map(|r| r.block_number);
+            let reverted_index = sent_blocks.iter().position(|(number, hash, _)| {
+                match records.iter().find(|r| r.block_number == *number) {
+                    Some(r) => r.block_hash != *hash,
+                    None => last_block_number.map_or(false, |last| last > *number),
+                }
+            });
+            if let Some(index) = reverted_index {
+                warn!(
+                    "submission records rewritten from block {}",
+                    sent_blocks[index].0
+                );
+                if let Some(tx_seq) = sent_blocks[index..].iter().find_map(|b| b.2) {
+                    watch_tx.send(LogFetchProgress::Reverted(tx_seq))?;
+                }
+                next_block_number = sent_blocks[index].0;
+                sent_blocks.truncate(index);
+            }
+
+            for record in records
+                .into_iter()
+                .filter(|r| r.block_number >= next_block_number)
+            {
+                debug!("replay submission records: block={}", record.block_number);
+                sent_blocks.push((
+                    record.block_number,
+                    record.block_hash,
+                    record.transactions.first().map(|tx| tx.seq),
+                ));
+                next_block_number = record.block_number + 1;
+                for transaction in record.transactions {
+                    watch_tx.send(LogFetchProgress::Transaction((transaction, None)))?;
+                }
+                watch_tx.send(LogFetchProgress::SyncedBlock((
+                    record.block_number,
+                    record.block_hash,
+                )))?;
+            }
+            tokio::time::sleep(poll_interval).await;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl LogSource for FileLogSource {
+    async fn latest_block_number(&self) -> Result<u64> {
+        Ok(read_records(&self.path)?
+            .last()
+            .map_or(0, |r| r.block_number))
+    }
+
+    async fn block_hash(&self, block_number: u64) -> Result<Option<H256>> {
+        Ok(read_records(&self.path)?
+            .into_iter()
+            .find(|r| r.block_number == block_number)
+            .map(|r| r.block_hash))
+    }
+
+    /// The recorded submissions are final.
+    async fn finalized_block_number(&self, latest_block_number: u64) -> Result<u64> {
+        Ok(latest_block_number)
+    }
+
+    fn start_recover(
+        &self,
+        start_block_number: u64,
+        end_block_number: u64,
+        executor: &TaskExecutor,
+    ) -> Receiver<LogFetchProgress> {
+        let (recover_tx, recover_rx) = tokio::sync::mpsc::channel(RECOVER_CHANNEL_CAPACITY);
+        let path = self.path.clone();
+        executor.spawn(
+            async move {
+                let records = match read_records(&path) {
+                    Ok(records) => records,
+                    Err(e) => {
+                        error!("read submission file error: e={:?}", e);
+                        return;
+                    }
+                };
+                for record in records.into_iter().filter(|r| {
+                    r.block_number >= start_block_number && r.block_number <= end_block_number
+                }) {
+                    if let Err(e) = Self::send_record(record, &recover_tx).await {
+                        error!("send error: e={:?}", e);
+                        return;
+                    }
+                }
+            },
+            "log recover",
+        );
+        recover_rx
+    }
+
+    fn start_watch(
+        &self,
+        start_block_number: u64,
+        executor: &TaskExecutor,
+    ) -> UnboundedReceiver<LogFetchProgress> {
+        let (watch_tx, watch_rx) = tokio::sync::mpsc::unbounded_channel();
+        let path = self.path.clone();
+        let poll_interval = self.poll_interval;
+        executor.spawn(
+            async move {
+                if let Err(e) =
+                    Self::watch_loop(&path, poll_interval, start_block_number, &watch_tx).await
+                {
+                    error!("submission file watch stops: e={:?}", e);
+                }
+            },
+            "log watch",
+        );
+        watch_rx
+    }
+}
+
+fn read_records(path: &Path) -> Result<Vec<LogRecord>> {
+    let content = std::fs::read_to_string(path)?;
+    let mut records: Vec<LogRecord> = Vec::new();
+    for (i, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record: LogRecord = serde_json::from_str(line)
+            .map_err(|e| anyhow::anyhow!("invalid record at line {}: {:?}", i + 1, e))?;
+        if let Some(last) = records.last() {
+            if record.block_number <= last.block_number {
+                bail!("records out of order at line {}", i + 1);
+            }
+        }
+        records.push(record);
+    }
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FileLogSource, LogRecord};
+    use crate::sync_manager::log_source::{LogFetchProgress, LogSource};
+    use ethereum_types::H256;
+    use shared_types::Transaction;
+    use std::path::{Path, PathBuf};
+    use std::time::Duration;
+    use task_executor::test_utils::TestRuntime;
+    use tokio::sync::mpsc::UnboundedReceiver;
+
+    fn record(block_number: u64, fork: u64, tx_seqs: &[u64]) -> LogRecord {
+        LogRecord {
+            block_number,
+            block_hash: H256::from_low_u64_be((fork << 32) + block_number),
+            transactions: tx_seqs
+                .iter()
+                .map(|seq| Transaction {
+                    stream_ids: vec![],
+                    data: vec![],
+                    data_merkle_root: H256::from_low_u64_be(*seq),
+                    merkle_nodes: vec![(1, H256::from_low_u64_be(*seq))],
+                    start_entry_index: *seq,
+                    size: 256,
+                    seq: *seq,
+                })
+                .collect(),
+        }
+    }
+
+    fn write_records(path: &Path, records: &[LogRecord]) {
+        let lines: Vec<String> = records
+            .iter()
+            .map(|r| serde_json::to_string(r).unwrap())
+            .collect();
+        // Replace the file at once, so it's not read when it's half written.
+        let tmp_path = path.with_extension("tmp");
+        std::fs::write(&tmp_path, lines.join("\n")).unwrap();
+        std::fs::rename(tmp_path, path).unwrap();
+    }
+
+    fn temp_file(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("{}_{}", name, std::process::id()))
+    }
+
+    async fn next_event(rx: &mut UnboundedReceiver<LogFetchProgress>) -> LogFetchProgress {
+        tokio::time::timeout(Duration::from_secs(10), rx.recv())
+            .await
+            .expect("wait log sync event")
+            .expect("channel closed")
+    }
+
+    #[tokio::test]
+    async fn test_recover() {
+        let path = temp_file("file_log_source_recover");
+        write_records(
+            &path,
+            &[
+                record(1, 0, &[0]),
+                record(3, 0, &[1, 2]),
+                record(4, 0, &[3]),
+            ],
+        );
+        let source = FileLogSource::new(path.clone());
+        assert_eq!(source.latest_block_number().await.unwrap(), 4);
+        assert_eq!(
+            source.block_hash(3).await.unwrap(),
+            Some(record(3, 0, &[]).block_hash)
+        );
+        assert_eq!(source.block_hash(2).await.unwrap(), None);
+
+        let runtime = TestRuntime::default();
+        let mut rx = source.start_recover(0, 3, &runtime.task_executor);
+        let mut events = vec![];
+        while let Some(event) = rx.recv().await {
+            events.push(event);
+        }
+        assert_eq!(events.len(), 5);
+        assert!(matches!(&events[0], LogFetchProgress::Transaction((tx, _)) if tx.seq == 0));
+        assert!(matches!(&events[1], LogFetchProgress::SyncedBlock((1, _))));
+        assert!(matches!(&events[3], LogFetchProgress::Transaction((tx, _)) if tx.seq == 2));
+        assert!(matches!(&events[4], LogFetchProgress::SyncedBlock((3, _))));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_watch_rewritten_records() {
+        let path = temp_file("file_log_source_watch");
+        write_records(&path, &[record(1, 0, &[0]), record(2, 0, &[1])]);
+        let source = FileLogSource::new(path.clone());
+        let runtime = TestRuntime::default();
+        let mut rx = source.start_watch(2, &runtime.task_executor);
+        assert!(matches!(
+            next_event(&mut rx).await,
+            LogFetchProgress::Transaction((tx, _)) if tx.seq == 1
+        ));
+        assert!(matches!(
+            next_event(&mut rx).await,
+            LogFetchProgress::SyncedBlock((2, _))
+        ));
+
+        // Append a block, and then rewrite it.
+        write_records(
+            &path,
+            &[record(1, 0, &[0]), record(2, 0, &[1]), record(3, 0, &[2])],
+        );
+        assert!(matches!(
+            next_event(&mut rx).await,
+            LogFetchProgress::Transaction((tx, _)) if tx.seq == 2
+        ));
+        assert!(matches!(
+            next_event(&mut rx).await,
+            LogFetchProgress::SyncedBlock((3, _))
+        ));
+        write_records(
+            &path,
+            &[record(1, 0, &[0]), record(2, 0, &[1]), record(4, 1, &[2])],
+        );
+        assert!(matches!(
+            next_event(&mut rx).await,
+            LogFetchProgress::Reverted(2)
+        ));
+        assert!(matches!(
+            next_event(&mut rx).await,
+            LogFetchProgress::Transaction((tx, _)) if tx.seq == 2
+        ));
+        assert!(matches!(
+            next_event(&mut rx).await,
+            LogFetchProgress::SyncedBlock((4, _))
+        ));
+        std::fs::remove_file(path).unwrap();
+    }
+}
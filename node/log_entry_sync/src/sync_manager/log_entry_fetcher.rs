@@ -1,110 +1,511 @@
-use crate::rpc_proxy::ContractAddress;
+use crate::rpc_proxy::eth::EthClient;
+use crate::rpc_proxy::{ContractAddress, EvmRpcProxy, SubFilter};
+use crate::sync_manager::config::ConfirmationStrategy;
+use crate::sync_manager::log_source::{LogFetchProgress, LogSource};
 use crate::sync_manager::{repeat_run_and_log, RETRY_WAIT_MS};
 use anyhow::{anyhow, bail, Result};
 use append_merkle::{Algorithm, Sha3Algorithm};
+use async_trait::async_trait;
+use chain_provider::MultiHttp;
 use contract_interface::{IonianFlow, SubmissionFilter};
 use ethers::abi::RawLog;
-use ethers::prelude::{BlockNumber, EthLogDecode, Http, Log, Middleware, Provider, U256};
-use ethers::providers::FilterKind;
+use ethers::prelude::{
+    Block, BlockNumber, EthEvent, EthLogDecode, Filter, Log, Middleware, Provider, U256,
+};
+use ethers::providers::{FilterKind, ProviderError};
 use ethers::types::H256;
 use futures::StreamExt;
-use jsonrpsee::tracing::{debug, error, info};
+use jsonrpsee::tracing::{debug, error, info, warn};
 use shared_types::{DataRoot, Transaction};
+use std::cmp;
 use std::collections::{BTreeMap, VecDeque};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use task_executor::TaskExecutor;
-use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
+use tokio::sync::mpsc::{Receiver, Sender, UnboundedReceiver, UnboundedSender};
+
+/// How long to poll logs before trying to subscribe again after a subscription is dropped.
+const RESUBSCRIBE_INTERVAL: Duration = Duration::from_secs(60);
+/// The maximum number of recovered logs waiting to be processed.
+const RECOVER_CHANNEL_CAPACITY: usize = 1000;
+/// The reduced log page size is doubled after this number of successful pages.
+const PAGE_SIZE_GROW_THRESHOLD: usize = 10;
+/// The error messages of providers that limit the number of logs or the block range of
+/// `eth_getLogs`. They are specific enough not to match rate limit or quota errors.
+const TOO_MANY_RESULTS_PATTERNS: [&str; 5] = [
+    "query returned more than",
+    "block range is too large",
+    "exceed maximum block range",
+    "log response size exceeded",
+    "too many logs",
+];
 
 pub struct LogEntryFetcher {
     contract_address: ContractAddress,
-    provider: Arc<Provider<Http>>,
+    provider: Arc<Provider<MultiHttp>>,
+    /// If set, new logs are watched with WebSocket subscriptions instead of polling.
+    ws_url: Option<String>,
+    /// `RESUBSCRIBE_INTERVAL` unless it's shortened in tests.
+    resubscribe_interval: Duration,
     log_page_size: u64,
+    /// If set, the logs are fetched again with `eth_getLogs` once they are final, so they are
+    /// cross-checked between endpoints.
+    cross_check: bool,
 
-    confirmation_delay: u64,
+    finality_checker: FinalityChecker,
 }
 
 impl LogEntryFetcher {
     pub async fn new(
-        url: &str,
+        urls: &[String],
+        quorum: usize,
+        ws_url: Option<String>,
         contract_address: ContractAddress,
         log_page_size: u64,
+        confirmation_strategy: ConfirmationStrategy,
         confirmation_delay: u64,
     ) -> Result<Self> {
-        let provider = Arc::new(Provider::try_from(url)?);
+        let provider = Arc::new(chain_provider::make_provider(urls, quorum)?);
+        let finality_checker = FinalityChecker {
+            provider: provider.clone(),
+            strategy: confirmation_strategy,
+            confirmation_delay,
+        };
         // TODO: `error` types are removed from the ABI json file.
         Ok(Self {
             contract_address,
             provider,
+            ws_url,
+            resubscribe_interval: RESUBSCRIBE_INTERVAL,
             log_page_size,
-            confirmation_delay,
+            cross_check: quorum > 1,
+            finality_checker,
         })
     }
 
-    pub fn start_recover(
+    /// Send the transactions in a page of logs, and then send the last block of the page as the
+    /// progress, so the page will not be fetched again after restart.
+    ///
+    /// Within the page, the progress of a block is sent once after all its logs are sent.
+    async fn send_recovered_page(
+        provider: &Provider<MultiHttp>,
+        logs: Vec<Log>,
+        to_block: u64,
+        recover_tx: &Sender<LogFetchProgress>,
+    ) -> Result<()> {
+        let mut last_block: Option<(u64, H256)> = None;
+        for log in logs {
+            if let (Some(number), Some(hash)) = (log.block_number, log.block_hash) {
+                let number = number.as_u64();
+                match last_block {
+                    Some(block) if block.0 >= number => {}
+                    Some(block) => {
+                        recover_tx
+                            .send(LogFetchProgress::SyncedBlock(block))
+                            .await?;
+                        last_block = Some((number, hash));
+                    }
+                    None => last_block = Some((number, hash)),
+                }
+            }
+            match SubmissionFilter::decode_log(&RawLog {
+                topics: log.topics,
+                data: log.data.to_vec(),
+            }) {
+                Ok(event) => {
+                    recover_tx
+                        .send(submission_event_to_transaction(event))
+                        .await?;
+                }
+                Err(e) => {
+                    error!("log decode error: e={:?}", e);
+                }
+            }
+        }
+        match provider.get_block(to_block).await {
+            Ok(Some(Block {
+                hash: Some(hash), ..
+            })) => {
+                recover_tx
+                    .send(LogFetchProgress::SyncedBlock((to_block, hash)))
+                    .await?;
+            }
+            // The rest of the progress will be saved with the next page.
+            r => {
+                warn!("page end block unavailable: block={} r={:?}", to_block, r);
+                if let Some(block) = last_block {
+                    recover_tx
+                        .send(LogFetchProgress::SyncedBlock(block))
+                        .await?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Watch new logs with `eth_subscribe` until the subscription is dropped.
+    /// Logs from `progress` to the latest block are fetched with `eth_getLogs` after the
+    /// subscription is created, so no log will be skipped.
+    async fn subscription_loop(
+        ws_url: &str,
+        contract_address: ContractAddress,
+        provider: &Provider<MultiHttp>,
+        filter: &Filter,
+        progress: u64,
+        finality_checker: &FinalityChecker,
+        watch_tx: &UnboundedSender<LogFetchProgress>,
+        log_confirmation_queue: &mut LogConfirmationQueue,
+    ) -> Result<()> {
+        let client = EthClient::new(ws_url).await?;
+        let mut log_stream = client
+            .sub_events(SubFilter {
+                to: Some(contract_address),
+                topics: vec![SubmissionFilter::signature()],
+            })
+            .await?;
+        let mut block_stream = client.sub_blocks().await?;
+
+        let reconciled_block = Self::reconcile(
+            provider,
+            filter,
+            progress,
+            finality_checker,
+            watch_tx,
+            log_confirmation_queue,
+        )
+        .await?;
+        info!(
+            "log sync subscription starts after block number {}",
+            reconciled_block
+        );
+
+        loop {
+            tokio::select! {
+                maybe_log = log_stream.next() => {
+                    let log = maybe_log.ok_or_else(|| anyhow!("log subscription closed"))?;
+                    let block_number = log
+                        .block_number
+                        .ok_or_else(|| anyhow!("block number missing"))?
+                        .as_u64();
+                    // These logs have been fetched in `reconcile`.
+                    if block_number <= reconciled_block && !log.removed.unwrap_or(false) {
+                        continue;
+                    }
+                    if let Some(reverted) = log_confirmation_queue.push(vec![log])? {
+                        watch_tx.send(LogFetchProgress::Reverted(reverted))?;
+                    }
+                }
+                maybe_block = block_stream.next() => {
+                    let latest_block =
+                        maybe_block.ok_or_else(|| anyhow!("block subscription closed"))?;
+                    if latest_block.0 <= reconciled_block {
+                        continue;
+                    }
+                    Self::confirm_and_send(
+                        latest_block.0,
+                        finality_checker,
+                        watch_tx,
+                        log_confirmation_queue,
+                    )
+                    .await?;
+                }
+            }
+        }
+    }
+
+    /// Poll new logs with a log filter. If `resubscribe_interval` is set, this returns after
+    /// the interval so the caller can try to subscribe again.
+    ///
+    /// Return the first block whose logs are not confirmed yet.
+    async fn polling_loop(
+        provider: &Provider<MultiHttp>,
+        filter: &Filter,
+        mut progress: u64,
+        resubscribe_interval: Option<Duration>,
+        finality_checker: &FinalityChecker,
+        watch_tx: &UnboundedSender<LogFetchProgress>,
+        log_confirmation_queue: &mut LogConfirmationQueue,
+    ) -> u64 {
+        let since = Instant::now();
+        loop {
+            if watch_tx.is_closed() {
+                return progress;
+            }
+            let reconciled_block = match Self::reconcile(
+                provider,
+                filter,
+                progress,
+                finality_checker,
+                watch_tx,
+                log_confirmation_queue,
+            )
+            .await
+            {
+                Ok(block_number) => block_number,
+                Err(e) => {
+                    error!("log sync reconcile error: e={:?}", e);
+                    progress = log_confirmation_queue.clear_unconfirmed(progress);
+                    tokio::time::sleep(Duration::from_millis(RETRY_WAIT_MS)).await;
+                    continue;
+                }
+            };
+            let watch_filter = filter.clone().from_block(reconciled_block + 1);
+            let filter_id =
+                repeat_run_and_log(|| provider.new_filter(FilterKind::Logs(&watch_filter))).await;
+
+            loop {
+                if watch_tx.is_closed() {
+                    return progress;
+                }
+                if matches!(resubscribe_interval, Some(interval) if since.elapsed() >= interval) {
+                    return log_confirmation_queue.clear_unconfirmed(progress);
+                }
+                match Self::watch_loop(
+                    provider,
+                    filter_id,
+                    finality_checker,
+                    watch_tx,
+                    log_confirmation_queue,
+                )
+                .await
+                {
+                    Err(e) => {
+                        error!("log sync watch error: e={:?}", e);
+                        break;
+                    }
+                    Ok(Some(p)) => {
+                        info!("log sync to block number {:?}", p);
+                    }
+                    Ok(None) => {
+                        error!("log sync gets entries without progress?")
+                    }
+                }
+                tokio::time::sleep(Duration::from_millis(RETRY_WAIT_MS)).await;
+            }
+            progress = log_confirmation_queue.clear_unconfirmed(progress);
+        }
+    }
+
+    async fn watch_loop(
+        provider: &Provider<MultiHttp>,
+        filter_id: U256,
+        finality_checker: &FinalityChecker,
+        watch_tx: &UnboundedSender<LogFetchProgress>,
+        log_confirmation_queue: &mut LogConfirmationQueue,
+    ) -> Result<Option<u64>> {
+        let latest_block = provider
+            .get_block(BlockNumber::Latest)
+            .await?
+            .ok_or_else(|| anyhow!("None for latest block"))?;
+        let logs: Vec<Log> = provider.get_filter_changes(filter_id).await?;
+        if let Some(reverted) = log_confirmation_queue.push(logs)? {
+            watch_tx.send(LogFetchProgress::Reverted(reverted))?;
+        }
+        match latest_block.number {
+            Some(number) => {
+                Self::confirm_and_send(
+                    number.as_u64(),
+                    finality_checker,
+                    watch_tx,
+                    log_confirmation_queue,
+                )
+                .await?;
+                Ok(Some(number.as_u64()))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Fetch the logs from `from_block` to the latest block with `eth_getLogs`.
+    /// This is used to fill the gap before a subscription or a log filter is created.
+    ///
+    /// Return the latest block number.
+    async fn reconcile(
+        provider: &Provider<MultiHttp>,
+        filter: &Filter,
+        from_block: u64,
+        finality_checker: &FinalityChecker,
+        watch_tx: &UnboundedSender<LogFetchProgress>,
+        log_confirmation_queue: &mut LogConfirmationQueue,
+    ) -> Result<u64> {
+        let latest_block = provider
+            .get_block(BlockNumber::Latest)
+            .await?
+            .ok_or_else(|| anyhow!("None for latest block"))?;
+        let latest_number = latest_block
+            .number
+            .ok_or_else(|| anyhow!("latest block number missing"))?
+            .as_u64();
+        if from_block <= latest_number {
+            let logs = provider
+                .get_logs(
+                    &filter
+                        .clone()
+                        .from_block(from_block)
+                        .to_block(latest_number),
+                )
+                .await?;
+            debug!(
+                "log sync reconciles {} logs from {} to {}",
+                logs.len(),
+                from_block,
+                latest_number
+            );
+            if let Some(reverted) = log_confirmation_queue.push(logs)? {
+                watch_tx.send(LogFetchProgress::Reverted(reverted))?;
+            }
+        }
+        Self::confirm_and_send(
+            latest_number,
+            finality_checker,
+            watch_tx,
+            log_confirmation_queue,
+        )
+        .await?;
+        Ok(latest_number)
+    }
+
+    /// Send the logs in the final blocks, and then send the latest final block as the progress
+    /// if it changes.
+    async fn confirm_and_send(
+        latest_block_number: u64,
+        finality_checker: &FinalityChecker,
+        watch_tx: &UnboundedSender<LogFetchProgress>,
+        log_confirmation_queue: &mut LogConfirmationQueue,
+    ) -> Result<()> {
+        let finalized_block_number = finality_checker
+            .finalized_block_number(latest_block_number)
+            .await?;
+        let previous_finalized = log_confirmation_queue.finalized_block_number;
+        let cross_checked_logs = match &log_confirmation_queue.cross_check_filter {
+            Some(filter)
+                if previous_finalized
+                    .map_or(true, |previous| finalized_block_number > previous) =>
+            {
+                let from_block = previous_finalized.map_or(0, |previous| previous + 1);
+                Some(
+                    finality_checker
+                        .provider
+                        .get_logs(
+                            &filter
+                                .clone()
+                                .from_block(from_block)
+                                .to_block(finalized_block_number),
+                        )
+                        .await?,
+                )
+            }
+            _ => None,
+        };
+        let queued_logs = log_confirmation_queue.confirm_logs(finalized_block_number);
+        for log in cross_checked_logs.unwrap_or(queued_logs) {
+            assert!(!log.removed.unwrap_or(false));
+            // TODO(zz): Log parse error means logs might be lost here.
+            let tx = SubmissionFilter::decode_log(&RawLog {
+                topics: log.topics,
+                data: log.data.to_vec(),
+            })?;
+            watch_tx.send(submission_event_to_transaction(tx))?;
+        }
+        if previous_finalized.map_or(true, |previous| finalized_block_number > previous) {
+            let block_hash = finality_checker
+                .provider
+                .get_block(finalized_block_number)
+                .await?
+                .and_then(|b| b.hash)
+                .ok_or_else(|| anyhow!("final block hash missing"))?;
+            watch_tx.send(LogFetchProgress::SyncedBlock((
+                finalized_block_number,
+                block_hash,
+            )))?;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl LogSource for LogEntryFetcher {
+    async fn latest_block_number(&self) -> Result<u64> {
+        Ok(self.provider.get_block_number().await?.as_u64())
+    }
+
+    async fn block_hash(&self, block_number: u64) -> Result<Option<H256>> {
+        Ok(self
+            .provider
+            .get_block(block_number)
+            .await?
+            .and_then(|b| b.hash))
+    }
+
+    async fn finalized_block_number(&self, latest_block_number: u64) -> Result<u64> {
+        self.finality_checker
+            .finalized_block_number(latest_block_number)
+            .await
+    }
+
+    /// Fetch the logs from `start_block_number` to `end_block_number` page by page.
+    ///
+    /// The channel is bounded, so the fetching pauses if the logs are not processed in time.
+    /// If a page has too many logs for the provider, the page size is reduced and it grows back
+    /// after some successful pages.
+    fn start_recover(
         &self,
         start_block_number: u64,
         end_block_number: u64,
         executor: &TaskExecutor,
-    ) -> UnboundedReceiver<LogFetchProgress> {
+    ) -> Receiver<LogFetchProgress> {
         let provider = self.provider.clone();
-        let (recover_tx, recover_rx) = tokio::sync::mpsc::unbounded_channel();
+        let (recover_tx, recover_rx) = tokio::sync::mpsc::channel(RECOVER_CHANNEL_CAPACITY);
         let contract = IonianFlow::new(self.contract_address, provider.clone());
-        let log_page_size = self.log_page_size;
+        let max_page_size = cmp::max(self.log_page_size, 1);
 
         executor.spawn(
             async move {
-                let mut progress = start_block_number;
-                let mut filter = contract
-                    .submission_filter()
-                    .from_block(progress)
-                    .to_block(end_block_number)
-                    .filter;
-                let mut stream = provider.get_logs_paginated(&filter, log_page_size);
+                let filter = contract.submission_filter().filter;
+                let mut page_size = max_page_size;
+                let mut successful_pages = 0;
+                let mut from_block = start_block_number;
                 debug!(
                     "start_recover starts, start={} end={}",
                     start_block_number, end_block_number
                 );
-                while let Some(maybe_log) = stream.next().await {
-                    match maybe_log {
-                        Ok(log) => {
-                            let sync_progress =
-                                if log.block_hash.is_some() && log.block_number.is_some() {
-                                    let synced_block = LogFetchProgress::SyncedBlock((
-                                        log.block_number.unwrap().as_u64(),
-                                        log.block_hash.unwrap(),
-                                    ));
-                                    progress = log.block_number.unwrap().as_u64();
-                                    Some(synced_block)
-                                } else {
-                                    None
-                                };
-
-                            match SubmissionFilter::decode_log(&RawLog {
-                                topics: log.topics,
-                                data: log.data.to_vec(),
-                            }) {
-                                Ok(event) => {
-                                    if let Err(e) = recover_tx
-                                        .send(submission_event_to_transaction(event))
-                                        .and_then(|_| match sync_progress {
-                                            Some(b) => recover_tx.send(b),
-                                            None => Ok(()),
-                                        })
-                                    {
-                                        error!("send error: e={:?}", e);
-                                    }
-                                }
-                                Err(e) => {
-                                    error!("log decode error: e={:?}", e);
-                                }
-                            }
+                // Stop if the receiver is dropped, e.g. for a resync.
+                while from_block <= end_block_number && !recover_tx.is_closed() {
+                    let to_block =
+                        cmp::min(from_block.saturating_add(page_size - 1), end_block_number);
+                    let page_filter = filter.clone().from_block(from_block).to_block(to_block);
+                    let logs = match provider.get_logs(&page_filter).await {
+                        Ok(logs) => logs,
+                        Err(e) if page_size > 1 && is_too_many_results(&e) => {
+                            page_size = cmp::max(page_size / 2, 1);
+                            successful_pages = 0;
+                            warn!(
+                                "too many logs in a page, reduce log page size to {}: e={:?}",
+                                page_size, e
+                            );
+                            continue;
                         }
                         Err(e) => {
                             error!("log query error: e={:?}", e);
-                            filter = filter.from_block(progress);
-                            stream = provider.get_logs_paginated(&filter, log_page_size);
                             tokio::time::sleep(Duration::from_millis(RETRY_WAIT_MS)).await;
+                            continue;
+                        }
+                    };
+                    if let Err(e) =
+                        Self::send_recovered_page(provider.as_ref(), logs, to_block, &recover_tx)
+                            .await
+                    {
+                        error!("send error: e={:?}", e);
+                        break;
+                    }
+
+                    from_block = to_block + 1;
+                    if page_size < max_page_size {
+                        successful_pages += 1;
+                        if successful_pages >= PAGE_SIZE_GROW_THRESHOLD {
+                            page_size = cmp::min(page_size * 2, max_page_size);
+                            successful_pages = 0;
+                            debug!("increase log page size to {}", page_size);
                         }
                     }
                 }
@@ -114,101 +515,108 @@ impl LogEntryFetcher {
         recover_rx
     }
 
-    pub fn start_watch(
+    fn start_watch(
         &self,
         start_block_number: u64,
         executor: &TaskExecutor,
     ) -> UnboundedReceiver<LogFetchProgress> {
         let (watch_tx, watch_rx) = tokio::sync::mpsc::unbounded_channel();
         let contract = IonianFlow::new(self.contract_address, self.provider.clone());
+        let contract_address = self.contract_address;
         let provider = self.provider.clone();
-        let mut log_confirmation_queue = LogConfirmationQueue::new(self.confirmation_delay);
+        let ws_url = self.ws_url.clone();
+        let resubscribe_interval = self.resubscribe_interval;
+        let finality_checker = self.finality_checker.clone();
+        let cross_check = self.cross_check;
+        let mut log_confirmation_queue = LogConfirmationQueue::new(start_block_number);
         executor.spawn(
             async move {
-                let mut filter = contract
-                    .submission_filter()
-                    .from_block(start_block_number)
-                    .filter;
-                debug!("start_watch starts, start={}", start_block_number);
-                let mut filter_id =
-                    repeat_run_and_log(|| provider.new_filter(FilterKind::Logs(&filter))).await;
+                let filter = contract.submission_filter().filter;
+                log_confirmation_queue.cross_check_filter = cross_check.then(|| filter.clone());
+                debug!(
+                    "start_watch starts, start={} ws_url={:?}",
+                    start_block_number, ws_url
+                );
+                // The first block whose logs are not confirmed yet.
                 let mut progress = start_block_number;
 
-                loop {
-                    match Self::watch_loop(
+                // Stop if the receiver is dropped, e.g. for a resync.
+                while !watch_tx.is_closed() {
+                    if let Some(ws_url) = &ws_url {
+                        if let Err(e) = Self::subscription_loop(
+                            ws_url,
+                            contract_address,
+                            provider.as_ref(),
+                            &filter,
+                            progress,
+                            &finality_checker,
+                            &watch_tx,
+                            &mut log_confirmation_queue,
+                        )
+                        .await
+                        {
+                            warn!(
+                                "log sync subscription dropped, fall back to polling: e={:?}",
+                                e
+                            );
+                        }
+                        progress = log_confirmation_queue.clear_unconfirmed(progress);
+                    }
+
+                    progress = Self::polling_loop(
                         provider.as_ref(),
-                        filter_id,
+                        &filter,
+                        progress,
+                        ws_url.is_some().then(|| resubscribe_interval),
+                        &finality_checker,
                         &watch_tx,
                         &mut log_confirmation_queue,
                     )
-                    .await
-                    {
-                        Err(e) => {
-                            error!("log sync watch error: e={:?}", e);
-                            filter = filter.from_block(progress);
-                            filter_id = repeat_run_and_log(|| {
-                                provider.new_filter(FilterKind::Logs(&filter))
-                            })
-                            .await;
-                        }
-                        Ok(Some(p)) => {
-                            progress = p;
-                            info!("log sync to block number {:?}", progress);
-                        }
-                        Ok(None) => {
-                            error!(
-                                "log sync gets entries without progress? old_progress={}",
-                                progress
-                            )
-                        }
-                    }
-                    tokio::time::sleep(Duration::from_millis(RETRY_WAIT_MS)).await;
+                    .await;
                 }
+                debug!("start_watch stops, progress={}", progress);
             },
             "log watch",
         );
         watch_rx
     }
+}
 
-    async fn watch_loop(
-        provider: &Provider<Http>,
-        filter_id: U256,
-        watch_tx: &UnboundedSender<LogFetchProgress>,
-        log_confirmation_queue: &mut LogConfirmationQueue,
-    ) -> Result<Option<u64>> {
-        let latest_block = provider
-            .get_block(BlockNumber::Latest)
-            .await?
-            .ok_or_else(|| anyhow!("None for latest block"))?;
-        let logs: Vec<Log> = provider.get_filter_changes(filter_id).await?;
-        if let Some(reverted) = log_confirmation_queue.push(logs)? {
-            watch_tx.send(LogFetchProgress::Reverted(reverted))?;
-        }
-        for log in log_confirmation_queue.confirm_logs(latest_block.number.unwrap().as_u64()) {
-            assert!(!log.removed.unwrap_or(false));
-            // TODO(zz): Log parse error means logs might be lost here.
-            let tx = SubmissionFilter::decode_log(&RawLog {
-                topics: log.topics,
-                data: log.data.to_vec(),
-            })?;
-            watch_tx.send(submission_event_to_transaction(tx))?;
-        }
-        let progress = if latest_block.hash.is_some() && latest_block.number.is_some() {
-            Some((
-                latest_block.number.unwrap().as_u64(),
-                latest_block.hash.unwrap(),
-            ))
-        } else {
-            None
+/// Decide which blocks are final according to the `ConfirmationStrategy`.
+/// Only the logs in final blocks are processed.
+#[derive(Clone)]
+pub struct FinalityChecker {
+    provider: Arc<Provider<MultiHttp>>,
+    strategy: ConfirmationStrategy,
+    confirmation_delay: u64,
+}
+
+impl FinalityChecker {
+    /// Return the largest final block number which is not larger than `latest_block_number`.
+    pub async fn finalized_block_number(&self, latest_block_number: u64) -> Result<u64> {
+        let by_depth = latest_block_number.saturating_sub(self.confirmation_delay);
+        let tag = match self.strategy {
+            ConfirmationStrategy::Depth => return Ok(by_depth),
+            ConfirmationStrategy::Safe | ConfirmationStrategy::HybridSafe => BlockNumber::Safe,
+            ConfirmationStrategy::Finalized | ConfirmationStrategy::HybridFinalized => {
+                BlockNumber::Finalized
+            }
+        };
+        let by_tag = match self.provider.get_block(tag).await {
+            Ok(Some(b)) => b.number.map(|n| cmp::min(n.as_u64(), latest_block_number)),
+            Ok(None) => None,
+            Err(e) if self.strategy.uses_depth() => {
+                debug!("get {:?} block fails, use depth instead: e={:?}", tag, e);
+                None
+            }
+            Err(e) => return Err(e.into()),
         };
-        if let Some(p) = &progress {
-            watch_tx.send(LogFetchProgress::SyncedBlock(*p))?;
+        match by_tag {
+            Some(n) if self.strategy.uses_depth() => Ok(cmp::max(n, by_depth)),
+            Some(n) => Ok(n),
+            None if self.strategy.uses_depth() => Ok(by_depth),
+            None => bail!("{:?} block not available", tag),
         }
-        Ok(progress.map(|p| p.0))
-    }
-
-    pub fn provider(&self) -> &Provider<Http> {
-        self.provider.as_ref()
     }
 }
 
@@ -217,16 +625,22 @@ struct LogConfirmationQueue {
     /// The key is the block number and the value is the set of needed logs in that block.
     queue: VecDeque<(u64, Vec<Log>)>,
 
-    latest_block_number: u64,
-    confirmation_delay: u64,
+    /// If set, the logs in final blocks are fetched again with this filter instead of using the
+    /// queued ones.
+    cross_check_filter: Option<Filter>,
+
+    /// The largest final block number passed to `confirm_logs`.
+    /// The logs before `start_block_number` are handled by recovery, so they are regarded as
+    /// confirmed.
+    finalized_block_number: Option<u64>,
 }
 
 impl LogConfirmationQueue {
-    fn new(confirmation_delay: u64) -> Self {
+    fn new(start_block_number: u64) -> Self {
         Self {
             queue: VecDeque::new(),
-            latest_block_number: 0,
-            confirmation_delay,
+            cross_check_filter: None,
+            finalized_block_number: start_block_number.checked_sub(1),
         }
     }
     /// Push a set of new logs.
@@ -275,26 +689,29 @@ impl LogConfirmationQueue {
         }
 
         // Add new logs to the queue.
-        for (block_number, new_logs) in block_logs {
-            if block_number <= self.queue.back().map(|e| e.0).unwrap_or(0) {
-                bail!("reverted without being notified");
+        for (block_number, mut new_logs) in block_logs {
+            match self.queue.back_mut() {
+                // Logs of the same block may be pushed separately by subscriptions.
+                Some((last_block_number, last_logs)) if *last_block_number == block_number => {
+                    last_logs.append(&mut new_logs);
+                }
+                Some((last_block_number, _)) if *last_block_number > block_number => {
+                    bail!("reverted without being notified");
+                }
+                _ => self.queue.push_back((block_number, new_logs)),
             }
-            self.queue.push_back((block_number, new_logs));
         }
 
         Ok(revert_to)
     }
 
-    /// Pass in the latest block number and return the confirmed logs.
-    fn confirm_logs(&mut self, latest_block_number: u64) -> Vec<Log> {
-        self.latest_block_number = latest_block_number;
+    /// Pass in the largest final block number and return the confirmed logs.
+    fn confirm_logs(&mut self, finalized_block_number: u64) -> Vec<Log> {
+        self.finalized_block_number =
+            cmp::max(self.finalized_block_number, Some(finalized_block_number));
         let mut confirmed_logs = Vec::new();
         while let Some((block_number, _)) = self.queue.front() {
-            if *block_number
-                > self
-                    .latest_block_number
-                    .wrapping_sub(self.confirmation_delay)
-            {
+            if *block_number > finalized_block_number {
                 break;
             }
             let (_, mut logs) = self.queue.pop_front().unwrap();
@@ -302,31 +719,47 @@ impl LogConfirmationQueue {
         }
         confirmed_logs
     }
+
+    /// Drop all the unconfirmed logs so they can be fetched again.
+    ///
+    /// Return the first block number whose logs are not confirmed, which is not less than
+    /// `progress`.
+    fn clear_unconfirmed(&mut self, progress: u64) -> u64 {
+        self.queue.clear();
+        match self.finalized_block_number {
+            Some(finalized) => cmp::max(progress, finalized + 1),
+            // No block is confirmed yet.
+            None => progress,
+        }
+    }
 }
 
-#[derive(Debug)]
-pub enum LogFetchProgress {
-    SyncedBlock((u64, H256)),
-    Transaction(Transaction),
-    Reverted(u64),
+fn is_too_many_results(e: &ProviderError) -> bool {
+    let message = format!("{:?}", e).to_lowercase();
+    TOO_MANY_RESULTS_PATTERNS
+        .iter()
+        .any(|pattern| message.contains(pattern))
 }
 
 fn submission_event_to_transaction(e: SubmissionFilter) -> LogFetchProgress {
-    LogFetchProgress::Transaction(Transaction {
-        stream_ids: vec![],
-        data: vec![],
-        data_merkle_root: nodes_to_root(&e.submission.2),
-        merkle_nodes: e
-            .submission
-            .2
-            .iter()
-            // the submission height is the height of the root node starting from height 0.
-            .map(|(root, height)| (height.as_usize() + 1, root.into()))
-            .collect(),
-        start_entry_index: e.start_pos.as_u64(),
-        size: e.submission.0.as_u64(),
-        seq: e.submission_index.as_u64(),
-    })
+    LogFetchProgress::Transaction((
+        Transaction {
+            stream_ids: vec![],
+            data: vec![],
+            data_merkle_root: nodes_to_root(&e.submission.2),
+            merkle_nodes: e
+                .submission
+                .2
+                .iter()
+                // the submission height is the height of the root node starting from height 0.
+                .map(|(root, height)| (height.as_usize() + 1, root.into()))
+                .collect(),
+            start_entry_index: e.start_pos.as_u64(),
+            size: e.submission.0.as_u64(),
+            seq: e.submission_index.as_u64(),
+        },
+        Some(e.sender),
+    ))
 }
 
 fn nodes_to_root(node_list: &Vec<([u8; 32], U256)>) -> DataRoot {
@@ -336,3 +769,365 @@ fn nodes_to_root(node_list: &Vec<([u8; 32], U256)>) -> DataRoot {
     }
     root
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{FinalityChecker, LogEntryFetcher};
+    use crate::sync_manager::config::ConfirmationStrategy;
+    use crate::sync_manager::log_source::{LogFetchProgress, LogSource};
+    use crate::sync_manager::RETRY_WAIT_MS;
+    use ethers::types::Address;
+    use mock_chain::{submission_log, MockChain, MockChainServer, MockLog};
+    use std::sync::Arc;
+    use std::time::Duration;
+    use task_executor::test_utils::TestRuntime;
+    use tokio::sync::mpsc::UnboundedReceiver;
+
+    const CONFIRMATION_DELAY: u64 = 3;
+
+    fn submission(flow_address: Address, seq: u64, root: u8) -> MockLog {
+        submission_log(flow_address, seq, seq * 256, 256, vec![([root; 32], 8)])
+    }
+
+    async fn new_fetcher(
+        server: &MockChainServer,
+        ws_url: Option<String>,
+        flow_address: Address,
+    ) -> LogEntryFetcher {
+        LogEntryFetcher::new(
+            &[server.url().to_string()],
+            1,
+            ws_url,
+            flow_address,
+            1000,
+            ConfirmationStrategy::Depth,
+            CONFIRMATION_DELAY,
+        )
+        .await
+        .unwrap()
+    }
+
+    /// Return the next event that is not `SyncedBlock`.
+    async fn next_event(rx: &mut UnboundedReceiver<LogFetchProgress>) -> LogFetchProgress {
+        loop {
+            let event = tokio::time::timeout(Duration::from_secs(10), rx.recv())
+                .await
+                .expect("wait log sync event")
+                .expect("channel closed");
+            if !matches!(event, LogFetchProgress::SyncedBlock(_)) {
+                return event;
+            }
+        }
+    }
+
+    /// Return `None` if the final block cannot be decided.
+    async fn finalized_block_number(
+        server: &MockChainServer,
+        strategy: ConfirmationStrategy,
+        latest_block_number: u64,
+    ) -> Option<u64> {
+        let provider = chain_provider::make_provider(&[server.url().to_string()], 1).unwrap();
+        let finality_checker = FinalityChecker {
+            provider: Arc::new(provider),
+            strategy,
+            confirmation_delay: CONFIRMATION_DELAY,
+        };
+        finality_checker
+            .finalized_block_number(latest_block_number)
+            .await
+            .ok()
+    }
+
+    async fn wait_for_subscriptions(chain: &MockChain, count: usize) {
+        tokio::time::timeout(Duration::from_secs(10), async {
+            while chain.subscription_requests() < count {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+        })
+        .await
+        .expect("wait for subscriptions");
+    }
+
+    fn tx_seq_and_root(event: LogFetchProgress) -> (u64, u8) {
+        match event {
+            LogFetchProgress::Transaction((tx, _)) => (tx.seq, tx.data_merkle_root.as_bytes()[0]),
+            e => panic!("unexpected event {:?}", e),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_recover() {
+        let chain = MockChain::new(1);
+        let server = MockChainServer::start(chain.clone()).await.unwrap();
+        let flow_address = Address::from_low_u64_be(1);
+        chain.mine_block(vec![submission(flow_address, 0, 1)]);
+        chain.mine_blocks(1);
+        chain.mine_block(vec![
+            submission(flow_address, 1, 2),
+            submission(Address::from_low_u64_be(2), 100, 2),
+            submission(flow_address, 2, 3),
+        ]);
+
+        let runtime = TestRuntime::default();
+        let fetcher = new_fetcher(&server, None, flow_address).await;
+        let mut rx = fetcher.start_recover(0, 3, &runtime.task_executor);
+        let mut events = vec![];
+        while let Some(event) = rx.recv().await {
+            events.push(event);
+        }
+
+        // The progress is sent once for each block.
+        assert_eq!(events.len(), 5);
+        let block_1 = chain.block_hash(1).unwrap();
+        let block_3 = chain.block_hash(3).unwrap();
+        assert!(matches!(&events[0], LogFetchProgress::Transaction((tx, _)) if tx.seq == 0));
+        assert!(matches!(&events[1], LogFetchProgress::SyncedBlock((1, h)) if *h == block_1));
+        assert!(matches!(&events[2], LogFetchProgress::Transaction((tx, _)) if tx.seq == 1));
+        assert!(matches!(&events[3], LogFetchProgress::Transaction((tx, _)) if tx.seq == 2));
+        // The end of the page.
+        assert!(matches!(&events[4], LogFetchProgress::SyncedBlock((3, h)) if *h == block_3));
+
+        // The last block with logs is the progress if the page end block is unavailable.
+        chain.set_block_missing(3, true);
+        let mut rx = fetcher.start_recover(0, 3, &runtime.task_executor);
+        let mut events = vec![];
+        while let Some(event) = rx.recv().await {
+            events.push(event);
+        }
+        assert_eq!(events.len(), 5);
+        assert!(matches!(&events[4], LogFetchProgress::SyncedBlock((3, h)) if *h == block_3));
+        server.stop();
+    }
+
+    #[tokio::test]
+    async fn test_recover_with_log_limit() {
+        let chain = MockChain::new(1);
+        let server = MockChainServer::start(chain.clone()).await.unwrap();
+        let flow_address = Address::from_low_u64_be(1);
+        for seq in 0..20 {
+            chain.mine_block(vec![submission(flow_address, seq, 1)]);
+        }
+        chain.set_max_logs_per_query(Some(1));
+
+        let runtime = TestRuntime::default();
+        let fetcher = new_fetcher(&server, None, flow_address).await;
+        let mut rx = fetcher.start_recover(0, 20, &runtime.task_executor);
+        let mut tx_seqs = vec![];
+        let mut last_synced_block = None;
+        while let Some(event) = rx.recv().await {
+            match event {
+                LogFetchProgress::Transaction((tx, _)) => tx_seqs.push(tx.seq),
+                LogFetchProgress::SyncedBlock((number, _)) => last_synced_block = Some(number),
+                e => panic!("unexpected event {:?}", e),
+            }
+        }
+        assert_eq!(tx_seqs, (0..20).collect::<Vec<_>>());
+        assert_eq!(last_synced_block, Some(20));
+        server.stop();
+    }
+
+    #[tokio::test]
+    async fn test_recover_with_rate_limit() {
+        let chain = MockChain::new(1);
+        let server = MockChainServer::start(chain.clone()).await.unwrap();
+        let flow_address = Address::from_low_u64_be(1);
+        for seq in 0..5 {
+            chain.mine_block(vec![submission(flow_address, seq, 1)]);
+        }
+        chain.push_get_logs_error("rate limit exceeded");
+        chain.push_get_logs_error("daily request count exceeded, request rate limited");
+
+        let runtime = TestRuntime::default();
+        let fetcher = new_fetcher(&server, None, flow_address).await;
+        let mut rx = fetcher.start_recover(0, 5, &runtime.task_executor);
+        let mut tx_seqs = vec![];
+        while let Some(event) = rx.recv().await {
+            if let LogFetchProgress::Transaction((tx, _)) = event {
+                tx_seqs.push(tx.seq);
+            }
+        }
+        assert_eq!(tx_seqs, (0..5).collect::<Vec<_>>());
+        // The page size is not reduced for rate limit errors.
+        assert_eq!(chain.get_logs_ranges(), vec![(0, 5)]);
+        server.stop();
+    }
+
+    #[tokio::test]
+    async fn test_watch_with_reorg() {
+        let chain = MockChain::new(1);
+        let server = MockChainServer::start(chain.clone()).await.unwrap();
+        let flow_address = Address::from_low_u64_be(1);
+
+        let runtime = TestRuntime::default();
+        let fetcher = new_fetcher(&server, None, flow_address).await;
+        let mut rx = fetcher.start_watch(1, &runtime.task_executor);
+
+        chain.mine_block(vec![submission(flow_address, 0, 1)]);
+        chain.mine_blocks(CONFIRMATION_DELAY);
+        assert_eq!(tx_seq_and_root(next_event(&mut rx).await), (0, 1));
+
+        // Wait until the unconfirmed log is fetched, and then revert it.
+        chain.mine_block(vec![submission(flow_address, 1, 2)]);
+        tokio::time::sleep(Duration::from_millis(RETRY_WAIT_MS * 3)).await;
+        chain.reorg(1, vec![vec![], vec![submission(flow_address, 1, 3)]]);
+        assert!(matches!(
+            next_event(&mut rx).await,
+            LogFetchProgress::Reverted(1)
+        ));
+
+        chain.mine_blocks(CONFIRMATION_DELAY);
+        assert_eq!(tx_seq_and_root(next_event(&mut rx).await), (1, 3));
+        server.stop();
+    }
+
+    #[tokio::test]
+    async fn test_watch_with_resubscribe() {
+        let chain = MockChain::new(1);
+        let mut server = MockChainServer::start(chain.clone()).await.unwrap();
+        let flow_address = Address::from_low_u64_be(1);
+
+        let runtime = TestRuntime::default();
+        let mut fetcher = new_fetcher(&server, Some(server.ws_url()), flow_address).await;
+        fetcher.resubscribe_interval = Duration::from_millis(RETRY_WAIT_MS * 4);
+        let mut rx = fetcher.start_watch(1, &runtime.task_executor);
+        // Both logs and new blocks are subscribed.
+        wait_for_subscriptions(&chain, 2).await;
+
+        chain.mine_block(vec![submission(flow_address, 0, 1)]);
+        chain.mine_blocks(CONFIRMATION_DELAY);
+        assert_eq!(tx_seq_and_root(next_event(&mut rx).await), (0, 1));
+
+        // Fall back to polling once the subscriptions are dropped.
+        server.stop_ws();
+        chain.mine_block(vec![submission(flow_address, 1, 2)]);
+        chain.mine_blocks(CONFIRMATION_DELAY);
+        assert_eq!(tx_seq_and_root(next_event(&mut rx).await), (1, 2));
+
+        // Subscribe again once the server is back, without skipping the logs in between.
+        chain.mine_block(vec![submission(flow_address, 2, 3)]);
+        server.restart_ws().await.unwrap();
+        wait_for_subscriptions(&chain, 4).await;
+        chain.mine_blocks(CONFIRMATION_DELAY);
+        assert_eq!(tx_seq_and_root(next_event(&mut rx).await), (2, 3));
+
+        chain.mine_block(vec![submission(flow_address, 3, 4)]);
+        chain.mine_blocks(CONFIRMATION_DELAY);
+        assert_eq!(tx_seq_and_root(next_event(&mut rx).await), (3, 4));
+        server.stop();
+    }
+
+    #[tokio::test]
+    async fn test_finality_checker() {
+        use ConfirmationStrategy::*;
+
+        let chain = MockChain::new(1);
+        let latest = chain.mine_blocks(20);
+        let server = MockChainServer::start(chain.clone()).await.unwrap();
+        let by_depth = latest - CONFIRMATION_DELAY;
+
+        // the tags are available
+        chain.set_safe_depth(Some(1));
+        chain.set_finalized_depth(Some(2));
+        for (strategy, expected) in [
+            (Depth, by_depth),
+            (Safe, latest - 1),
+            (Finalized, latest - 2),
+            (HybridSafe, latest - 1),
+            (HybridFinalized, latest - 2),
+        ] {
+            assert_eq!(
+                finalized_block_number(&server, strategy, latest).await,
+                Some(expected),
+                "{}",
+                strategy
+            );
+        }
+
+        // the depth is used if it's larger than the tag
+        chain.set_finalized_depth(Some(CONFIRMATION_DELAY + 2));
+        assert_eq!(
+            finalized_block_number(&server, Finalized, latest).await,
+            Some(latest - CONFIRMATION_DELAY - 2)
+        );
+        assert_eq!(
+            finalized_block_number(&server, HybridFinalized, latest).await,
+            Some(by_depth)
+        );
+
+        // the tagged blocks are missing
+        chain.set_finalized_depth(Some(2));
+        chain.set_block_missing(latest - 1, true);
+        chain.set_block_missing(latest - 2, true);
+        for (strategy, expected) in [
+            (Depth, Some(by_depth)),
+            (Safe, None),
+            (Finalized, None),
+            (HybridSafe, Some(by_depth)),
+            (HybridFinalized, Some(by_depth)),
+        ] {
+            assert_eq!(
+                finalized_block_number(&server, strategy, latest).await,
+                expected,
+                "{}",
+                strategy
+            );
+        }
+        chain.set_block_missing(latest - 1, false);
+        chain.set_block_missing(latest - 2, false);
+
+        // the tags are not supported
+        chain.set_safe_depth(None);
+        chain.set_finalized_depth(None);
+        for (strategy, expected) in [
+            (Depth, Some(by_depth)),
+            (Safe, None),
+            (Finalized, None),
+            (HybridSafe, Some(by_depth)),
+            (HybridFinalized, Some(by_depth)),
+        ] {
+            assert_eq!(
+                finalized_block_number(&server, strategy, latest).await,
+                expected,
+                "{}",
+                strategy
+            );
+        }
+        server.stop();
+    }
+
+    #[tokio::test]
+    async fn test_watch_with_finalized_tag() {
+        let chain = MockChain::new(1);
+        chain.set_finalized_depth(Some(CONFIRMATION_DELAY + 2));
+        let server = MockChainServer::start(chain.clone()).await.unwrap();
+        let flow_address = Address::from_low_u64_be(1);
+
+        let runtime = TestRuntime::default();
+        let fetcher = LogEntryFetcher::new(
+            &[server.url().to_string()],
+            1,
+            None,
+            flow_address,
+            1000,
+            ConfirmationStrategy::Finalized,
+            CONFIRMATION_DELAY,
+        )
+        .await
+        .unwrap();
+        let mut rx = fetcher.start_watch(1, &runtime.task_executor);
+
+        // deep enough, but not finalized yet
+        chain.mine_block(vec![submission(flow_address, 0, 1)]);
+        chain.mine_blocks(CONFIRMATION_DELAY);
+        let pending = tokio::time::timeout(
+            Duration::from_millis(RETRY_WAIT_MS * 3),
+            next_event(&mut rx),
+        )
+        .await;
+        assert!(pending.is_err());
+
+        chain.mine_blocks(2);
+        assert_eq!(tx_seq_and_root(next_event(&mut rx).await), (0, 1));
+        server.stop();
+    }
+}
@@ -0,0 +1,46 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use ethereum_types::{H160, H256};
+use shared_types::Transaction;
+use task_executor::TaskExecutor;
+use tokio::sync::mpsc::{Receiver, UnboundedReceiver};
+
+/// A source of the ordered transaction submissions to sync, such as the Flow contract logs on an
+/// EVM chain or a recorded submission file.
+#[async_trait]
+pub trait LogSource: Send + Sync {
+    async fn latest_block_number(&self) -> Result<u64>;
+
+    /// Return `None` if the block does not exist.
+    /// This is used to detect chain reorg during node restart.
+    async fn block_hash(&self, block_number: u64) -> Result<Option<H256>>;
+
+    /// Return the largest final block number which is not larger than `latest_block_number`.
+    async fn finalized_block_number(&self, latest_block_number: u64) -> Result<u64>;
+
+    /// Fetch the submissions in the blocks from `start_block_number` to `end_block_number`.
+    /// The channel is closed once all of them are sent.
+    fn start_recover(
+        &self,
+        start_block_number: u64,
+        end_block_number: u64,
+        executor: &TaskExecutor,
+    ) -> Receiver<LogFetchProgress>;
+
+    /// Watch the new submissions from `start_block_number`, including the reorg signals.
+    fn start_watch(
+        &self,
+        start_block_number: u64,
+        executor: &TaskExecutor,
+    ) -> UnboundedReceiver<LogFetchProgress>;
+}
+
+#[derive(Debug)]
+pub enum LogFetchProgress {
+    /// The logs up to this block have been sent.
+    SyncedBlock((u64, H256)),
+    /// A submitted transaction and its sender if known.
+    Transaction((Transaction, Option<H160>)),
+    /// The transactions from this tx seq are reverted.
+    Reverted(u64),
+}
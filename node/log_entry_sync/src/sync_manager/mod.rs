@@ -1,24 +1,29 @@
 use crate::sync_manager::config::LogSyncConfig;
 use crate::sync_manager::data_cache::DataCache;
-use crate::sync_manager::log_entry_fetcher::{LogEntryFetcher, LogFetchProgress};
+use crate::sync_manager::file_log_source::FileLogSource;
+use crate::sync_manager::log_entry_fetcher::LogEntryFetcher;
+use crate::sync_manager::log_source::{LogFetchProgress, LogSource};
+use crate::sync_manager::status::{LogRecoveryStatus, LogSyncStatus};
 use anyhow::{bail, Result};
-use ethers::prelude::Middleware;
+use ethereum_types::{H160, H256};
 use futures::FutureExt;
-use jsonrpsee::tracing::{debug, error, trace, warn};
-use shared_types::{ChunkArray, Transaction};
+use jsonrpsee::tracing::{debug, error, info, trace, warn};
+use shared_types::{Transaction, CHUNK_SIZE};
 use std::fmt::Debug;
 use std::future::Future;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use storage::log_store::Store;
 use task_executor::{ShutdownReason, TaskExecutor};
 use tokio::sync::broadcast;
-use tokio::sync::mpsc::UnboundedReceiver;
 use tokio::sync::RwLock;
 
 const RETRY_WAIT_MS: u64 = 500;
 const BROADCAST_CHANNEL_CAPACITY: usize = 16;
 
+pub type LogSyncSender = channel::Sender<(), LogSyncRequest, LogSyncResponse>;
+type LogSyncReceiver = channel::Receiver<(), LogSyncRequest, LogSyncResponse>;
+
 #[derive(Clone, Debug)]
 pub enum LogSyncEvent {
     /// Chain reorg detected without any operation yet.
@@ -27,9 +32,31 @@ pub enum LogSyncEvent {
     Reverted { tx_seq: u64 },
 }
 
+#[derive(Debug)]
+pub enum LogSyncRequest {
+    /// Revert the transactions submitted from `block_number` and sync them again.
+    ResyncFrom { block_number: u64 },
+}
+
+#[derive(Debug)]
+pub enum LogSyncResponse {
+    /// `result` is the first reverted tx seq.
+    ResyncFrom {
+        result: std::result::Result<u64, String>,
+    },
+}
+
+/// Where to restart log sync for `LogSyncRequest::ResyncFrom`.
+struct ResyncPoint {
+    /// The latest synced block to keep, or the start block if all synced blocks are reverted.
+    progress: (u64, H256),
+    next_tx_seq: u64,
+    start_block_number: u64,
+}
+
 pub struct LogSyncManager {
     config: LogSyncConfig,
-    log_fetcher: LogEntryFetcher,
+    log_source: Box<dyn LogSource>,
     store: Arc<RwLock<dyn Store>>,
     data_cache: DataCache,
 
@@ -37,21 +64,41 @@ pub struct LogSyncManager {
 
     /// To broadcast events to handle in advance.
     event_send: broadcast::Sender<LogSyncEvent>,
+
+    /// Shared with RPC to report the sync progress.
+    status: Arc<RwLock<LogSyncStatus>>,
 }
 
 impl LogSyncManager {
+    /// Spawn the log sync task.
+    ///
+    /// The returned receiver is subscribed before the task starts, so it will not miss the
+    /// events of the chain reorg handled during restart.
     pub async fn spawn(
         config: LogSyncConfig,
         executor: TaskExecutor,
         store: Arc<RwLock<dyn Store>>,
-    ) -> Result<broadcast::Sender<LogSyncEvent>> {
+    ) -> Result<(
+        broadcast::Sender<LogSyncEvent>,
+        broadcast::Receiver<LogSyncEvent>,
+        Arc<RwLock<LogSyncStatus>>,
+        LogSyncSender,
+    )> {
         let next_tx_seq = store.read().await.next_tx_seq()?;
+        let status = Arc::new(RwLock::new(LogSyncStatus {
+            confirmation_strategy: config.confirmation_strategy.to_string(),
+            confirmation_block_count: config.confirmation_block_count,
+            next_tx_seq,
+            ..Default::default()
+        }));
+        let status_cloned = status.clone();
 
         let executor_clone = executor.clone();
         let mut shutdown_sender = executor.shutdown_sender();
 
-        let (event_send, _) = broadcast::channel(BROADCAST_CHANNEL_CAPACITY);
+        let (event_send, event_recv) = broadcast::channel(BROADCAST_CHANNEL_CAPACITY);
         let event_send_cloned = event_send.clone();
+        let (request_send, mut request_recv) = channel::Channel::unbounded();
 
         // Spawn the task to sync log entries from the blockchain.
         executor.spawn(
@@ -62,95 +109,211 @@ impl LogSyncManager {
                         .expect("shutdown send error")
                 },
                 async move {
-                    let log_fetcher = LogEntryFetcher::new(
-                        &config.rpc_endpoint_url,
-                        config.contract_address,
-                        config.log_page_size,
-                        config.confirmation_block_count,
-                    )
-                    .await?;
+                    let log_source: Box<dyn LogSource> = match &config.submission_file {
+                        Some(path) => Box::new(FileLogSource::new(path.clone())),
+                        None => Box::new(
+                            LogEntryFetcher::new(
+                                &config.rpc_endpoint_urls,
+                                config.rpc_quorum,
+                                config.ws_endpoint_url.clone(),
+                                config.contract_address,
+                                config.log_page_size,
+                                config.confirmation_strategy,
+                                config.confirmation_block_count,
+                            )
+                            .await?,
+                        ),
+                    };
                     let data_cache = DataCache::new(config.cache_config.clone());
                     let mut log_sync_manager = Self {
                         config,
-                        log_fetcher,
+                        log_source,
                         next_tx_seq,
                         store,
                         data_cache,
                         event_send,
+                        status,
                     };
 
                     // Load previous progress from db and check if chain reorg happens after restart.
-                    // TODO(zz): Handle reorg instead of return.
-                    let start_block_number =
-                        match log_sync_manager.store.read().await.get_sync_progress()? {
-                            // No previous progress, so just use config.
-                            None => log_sync_manager.config.start_block_number,
-                            Some((block_number, block_hash)) => {
-                                match log_sync_manager
-                                    .log_fetcher
-                                    .provider()
-                                    .get_block(block_number)
-                                    .await
-                                {
-                                    Ok(Some(b)) => {
-                                        if b.hash == Some(block_hash) {
-                                            block_number
-                                        } else {
-                                            warn!(
-                                                "log sync progress check hash fails, \
-                                            block_number={:?} expect={:?} get={:?}",
-                                                block_number, block_hash, b.hash
-                                            );
-                                            // Assume the blocks before this are not reverted.
-                                            block_number.saturating_sub(
-                                                log_sync_manager.config.confirmation_block_count,
-                                            )
-                                        }
-                                    }
-                                    e => {
-                                        error!("log sync progress check rpc fails, e={:?}", e);
-                                        bail!("log sync start error");
-                                    }
-                                }
-                            }
-                        };
-                    let latest_block_number = log_sync_manager
-                        .log_fetcher
-                        .provider()
-                        .get_block_number()
+                    let mut start_block_number = log_sync_manager.handle_reorg_on_restart().await?;
+                    // Syncing is supposed to block forever unless a resync is requested.
+                    while let Some((resync_point, sender)) = log_sync_manager
+                        .sync_from(start_block_number, &executor_clone, &mut request_recv)
                         .await?
-                        .as_u64();
-
-                    // Start watching before recovery to ensure that no log is skipped.
-                    // TODO(zz): Rate limit to avoid OOM during recovery.
-                    let watch_rx = log_sync_manager
-                        .log_fetcher
-                        .start_watch(latest_block_number, &executor_clone);
-                    let recover_rx = log_sync_manager.log_fetcher.start_recover(
-                        start_block_number,
-                        // -1 so the recover and watch ranges do not overlap.
-                        latest_block_number.wrapping_sub(1),
-                        &executor_clone,
-                    );
-                    log_sync_manager.handle_data(recover_rx).await?;
-                    // Syncing `watch_rx` is supposed to block forever.
-                    log_sync_manager.handle_data(watch_rx).await?;
+                    {
+                        // The log fetching tasks have stopped as their channels are dropped.
+                        let tx_seq = resync_point.next_tx_seq;
+                        start_block_number = log_sync_manager.resync(resync_point).await?;
+                        let _ = sender.send(LogSyncResponse::ResyncFrom { result: Ok(tx_seq) });
+                    }
                     Ok(())
                 },
             )
             .map(|_| ()),
             "log_sync",
         );
-        Ok(event_send_cloned)
+        Ok((event_send_cloned, event_recv, status_cloned, request_send))
     }
 
-    async fn put_tx(&mut self, tx: Transaction) -> bool {
+    /// Sync the logs from `start_block_number` until the log source stops or a resync is
+    /// requested.
+    ///
+    /// Return the accepted resync request.
+    async fn sync_from(
+        &mut self,
+        start_block_number: u64,
+        executor: &TaskExecutor,
+        request_recv: &mut LogSyncReceiver,
+    ) -> Result<Option<(ResyncPoint, channel::ResponseSender<LogSyncResponse>)>> {
+        let latest_block_number = self.log_source.latest_block_number().await?;
+        // The watched progress is final, so this is only needed until the recovery finishes.
+        match self
+            .log_source
+            .finalized_block_number(latest_block_number)
+            .await
+        {
+            Ok(number) => self.status.write().await.finalized_block_number = Some(number),
+            Err(e) => warn!("final block unavailable: e={:?}", e),
+        }
+
+        // Start watching before recovery to ensure that no log is skipped.
+        let mut watch_rx = self.log_source.start_watch(latest_block_number, executor);
+        // -1 so the recover and watch ranges do not overlap.
+        if let Some(end_block_number) = latest_block_number.checked_sub(1) {
+            if let Some(resync) = self
+                .recover(start_block_number, end_block_number, executor, request_recv)
+                .await?
+            {
+                return Ok(Some(resync));
+            }
+        }
+        loop {
+            tokio::select! {
+                maybe_data = watch_rx.recv() => match maybe_data {
+                    Some(data) => {
+                        let finalized_block_number = match &data {
+                            LogFetchProgress::SyncedBlock((number, _)) => Some(*number),
+                            _ => None,
+                        };
+                        if !self.handle_data(data).await? {
+                            return Ok(None);
+                        }
+                        if finalized_block_number.is_some() {
+                            self.status.write().await.finalized_block_number =
+                                finalized_block_number;
+                        }
+                    }
+                    None => return Ok(None),
+                },
+                Some(msg) = request_recv.recv() => {
+                    if let Some(resync) = self.on_request(msg).await {
+                        return Ok(Some(resync));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Return the resync point and the response sender if a resync is accepted.
+    /// Otherwise, the response is sent here.
+    async fn on_request(
+        &mut self,
+        msg: channel::Message<(), LogSyncRequest, LogSyncResponse>,
+    ) -> Option<(ResyncPoint, channel::ResponseSender<LogSyncResponse>)> {
+        match msg {
+            channel::Message::Notification(()) => None,
+            channel::Message::Request(LogSyncRequest::ResyncFrom { block_number }, sender) => {
+                match self.find_resync_point(block_number).await {
+                    Ok(resync_point) => Some((resync_point, sender)),
+                    Err(e) => {
+                        let _ = sender.send(LogSyncResponse::ResyncFrom {
+                            result: Err(e.to_string()),
+                        });
+                        None
+                    }
+                }
+            }
+        }
+    }
+
+    /// Find the latest synced block before `block_number`, whose next tx seq is the first
+    /// transaction to revert.
+    async fn find_resync_point(&self, block_number: u64) -> Result<ResyncPoint> {
+        let progress = match self.store.read().await.get_sync_progress()? {
+            Some(progress) => progress,
+            None => bail!("no block is synced yet"),
+        };
+        if block_number > progress.0 {
+            bail!(
+                "block {} is not synced yet, synced_block_number={}",
+                block_number,
+                progress.0
+            );
+        }
+        if block_number <= self.config.start_block_number {
+            // Resync the start block, so its transactions are not skipped after restart.
+            let start_block_number = self.config.start_block_number;
+            let hash = match self.log_source.block_hash(start_block_number).await? {
+                Some(hash) => hash,
+                None => bail!("start block {} is not available", start_block_number),
+            };
+            return Ok(ResyncPoint {
+                progress: (start_block_number, hash),
+                next_tx_seq: 0,
+                start_block_number,
+            });
+        }
+        let synced_blocks = self.store.read().await.get_synced_blocks()?;
+        match synced_blocks
+            .into_iter()
+            .rev()
+            .find(|(number, _, _)| *number < block_number)
+        {
+            Some((number, hash, next_tx_seq)) => Ok(ResyncPoint {
+                progress: (number, hash),
+                next_tx_seq,
+                start_block_number: number + 1,
+            }),
+            None => bail!(
+                "the synced blocks before {} are not kept, resync from block {} instead",
+                block_number,
+                self.config.start_block_number
+            ),
+        }
+    }
+
+    /// Revert the transactions after the resync point.
+    ///
+    /// Return the block number to start syncing from.
+    async fn resync(&mut self, resync_point: ResyncPoint) -> Result<u64> {
+        info!(
+            "log sync resyncs from block {}, tx_seq={}",
+            resync_point.start_block_number, resync_point.next_tx_seq
+        );
+        if resync_point.next_tx_seq < self.next_tx_seq {
+            self.process_reverted(resync_point.next_tx_seq).await;
+        }
+        // The progress is saved so the node can resume the resync after restart.
+        let progress = resync_point.progress;
+        self.store
+            .write()
+            .await
+            .put_sync_progress(progress, resync_point.next_tx_seq)?;
+        let mut status = self.status.write().await;
+        status.next_tx_seq = self.next_tx_seq;
+        status.synced_block_number = Some(progress.0);
+        status.synced_block_hash = Some(progress.1);
+        Ok(resync_point.start_block_number)
+    }
+
+    async fn put_tx(&mut self, tx: Transaction, sender: Option<H160>) -> bool {
         // We call this after process chain reorg, so the sequence number should match.
         match tx.seq.cmp(&self.next_tx_seq) {
             std::cmp::Ordering::Less => true,
             std::cmp::Ordering::Equal => {
                 debug!("log entry sync get entry: {:?}", tx);
-                self.put_tx_inner(tx).await
+                self.put_tx_inner(tx, sender).await
             }
             std::cmp::Ordering::Greater => {
                 error!(
@@ -162,26 +325,82 @@ impl LogSyncManager {
         }
     }
 
+    /// Find the latest synced block which is still on the chain, and revert the transactions
+    /// submitted after it.
+    ///
+    /// Return the block number to start syncing from.
+    async fn handle_reorg_on_restart(&mut self) -> Result<u64> {
+        let progress = match self.store.read().await.get_sync_progress()? {
+            // No previous progress, so just use config.
+            None => return Ok(self.config.start_block_number),
+            Some(progress) => progress,
+        };
+        let mut synced_blocks = self.store.read().await.get_synced_blocks()?;
+        if synced_blocks.is_empty() {
+            // The progress is saved without the synced block list by earlier versions.
+            synced_blocks.push((progress.0, progress.1, self.next_tx_seq));
+        }
+
+        let log_source = self.log_source.as_ref();
+        let ancestor = find_common_ancestor(&synced_blocks, |block_number| async move {
+            Ok(repeat_run_and_log(|| log_source.block_hash(block_number)).await)
+        })
+        .await?;
+        let (start_block_number, start_block_hash, next_tx_seq) = match ancestor {
+            Some(ancestor) => ancestor,
+            None => {
+                // All the synced blocks are reverted, so we sync from the start.
+                warn!(
+                    "log sync finds no common ancestor, synced_blocks={}",
+                    synced_blocks.len()
+                );
+                if self.next_tx_seq != 0 {
+                    self.process_reverted(0).await;
+                }
+                return Ok(self.config.start_block_number);
+            }
+        };
+
+        if start_block_number != progress.0 {
+            info!(
+                "log sync reverts to block {} after restart, tx_seq={}",
+                start_block_number, next_tx_seq
+            );
+            if next_tx_seq < self.next_tx_seq {
+                self.process_reverted(next_tx_seq).await;
+            }
+            self.store
+                .write()
+                .await
+                .put_sync_progress((start_block_number, start_block_hash), next_tx_seq)?;
+        }
+        Ok(start_block_number)
+    }
+
     /// `tx_seq` is the first reverted tx seq.
     async fn process_reverted(&mut self, tx_seq: u64) {
         warn!("revert for chain reorg: seq={}", tx_seq);
         {
             let store = self.store.read().await;
             for seq in tx_seq..self.next_tx_seq {
-                if matches!(store.check_tx_completed(seq), Ok(true)) {
-                    if let Ok(Some(tx)) = store.get_tx_by_seq_number(seq) {
-                        // TODO(zz): Skip reading the rear padding data?
-                        if let Ok(Some(data)) =
-                            store.get_chunks_by_tx_and_index_range(seq, 0, tx.num_entries())
-                        {
-                            if !self
-                                .data_cache
-                                .add_data(tx.data_merkle_root, seq, data.data)
-                            {
-                                // TODO(zz): Data too large. Save to disk?
+                if let Ok(Some(tx)) = store.get_tx_by_seq_number(seq) {
+                    // TODO(zz): Skip reading the rear padding data?
+                    let chunks = if matches!(store.check_tx_completed(seq), Ok(true)) {
+                        store
+                            .get_chunks_by_tx_and_index_range(seq, 0, tx.num_entries())
+                            .map(|maybe_data| maybe_data.into_iter().collect())
+                    } else {
+                        // Keep the partial data, so they can be merged if the same data
+                        // are reverted again.
+                        store.get_available_chunks_by_tx(seq)
+                    };
+                    match chunks {
+                        Ok(chunks) => {
+                            if !self.data_cache.add_data(tx.data_merkle_root, seq, chunks) {
                                 warn!("large reverted data dropped for tx={:?}", tx);
                             }
                         }
+                        Err(e) => error!("read reverted data error: seq={} e={:?}", seq, e),
                     }
                 }
             }
@@ -195,74 +414,140 @@ impl LogSyncManager {
             return;
         }
         self.next_tx_seq = tx_seq;
+        self.status.write().await.next_tx_seq = tx_seq;
 
         let _ = self.event_send.send(LogSyncEvent::Reverted { tx_seq });
     }
 
-    async fn handle_data(&mut self, mut rx: UnboundedReceiver<LogFetchProgress>) -> Result<()> {
-        while let Some(data) = rx.recv().await {
-            trace!("handle_data: data={:?}", data);
-            match data {
-                LogFetchProgress::SyncedBlock(progress) => {
-                    match self
-                        .log_fetcher
-                        .provider()
-                        .get_block(
-                            progress
-                                .0
-                                .saturating_sub(self.config.confirmation_block_count),
-                        )
-                        .await
-                    {
-                        Ok(Some(b)) => {
-                            if let (Some(block_number), Some(block_hash)) = (b.number, b.hash) {
-                                self.store
-                                    .write()
-                                    .await
-                                    .put_sync_progress((block_number.as_u64(), block_hash))?;
-                            }
-                        }
-                        e => {
-                            error!("log put progress check rpc fails, e={:?}", e);
-                            bail!("log sync update progress error");
-                        }
+    /// Recover the logs in the blocks before the node starts, and report the progress in
+    /// `status`.
+    ///
+    /// The recovery stops early if a resync is requested, and the accepted request is returned.
+    async fn recover(
+        &mut self,
+        start_block_number: u64,
+        end_block_number: u64,
+        executor: &TaskExecutor,
+        request_recv: &mut LogSyncReceiver,
+    ) -> Result<Option<(ResyncPoint, channel::ResponseSender<LogSyncResponse>)>> {
+        let started_at = Instant::now();
+        self.status.write().await.recovery = Some(LogRecoveryStatus {
+            start_block_number,
+            end_block_number,
+            ..Default::default()
+        });
+        let mut recover_rx =
+            self.log_source
+                .start_recover(start_block_number, end_block_number, executor);
+        loop {
+            let data = tokio::select! {
+                maybe_data = recover_rx.recv() => match maybe_data {
+                    Some(data) => data,
+                    None => break,
+                },
+                Some(msg) = request_recv.recv() => {
+                    if let Some(resync) = self.on_request(msg).await {
+                        self.status.write().await.recovery = None;
+                        return Ok(Some(resync));
                     }
+                    continue;
                 }
-                LogFetchProgress::Transaction(tx) => {
-                    if !self.put_tx(tx).await {
-                        // Unexpected error.
-                        error!("log sync write error");
-                        break;
-                    }
+            };
+            let synced_block_number = match &data {
+                LogFetchProgress::SyncedBlock((number, _)) => Some(*number),
+                _ => None,
+            };
+            if !self.handle_data(data).await? {
+                break;
+            }
+            if let Some(number) = synced_block_number {
+                let mut status = self.status.write().await;
+                if let Some(recovery) = status.recovery.as_mut() {
+                    let recovered = number.saturating_sub(start_block_number) + 1;
+                    let remaining = end_block_number.saturating_sub(number);
+                    recovery.eta_secs = Some(
+                        (started_at.elapsed().as_secs_f64() * remaining as f64 / recovered as f64)
+                            as u64,
+                    );
                 }
-                LogFetchProgress::Reverted(reverted) => {
-                    self.process_reverted(reverted).await;
+            }
+        }
+        if let Some(recovery) = self.status.write().await.recovery.as_mut() {
+            recovery.finished = true;
+            recovery.eta_secs = Some(0);
+        }
+        info!(
+            "log recovery finished: start={} end={} elapsed={:?}",
+            start_block_number,
+            end_block_number,
+            started_at.elapsed()
+        );
+        Ok(None)
+    }
+
+    /// Return `false` if the sync cannot continue.
+    async fn handle_data(&mut self, data: LogFetchProgress) -> Result<bool> {
+        trace!("handle_data: data={:?}", data);
+        match data {
+            LogFetchProgress::SyncedBlock(progress) => {
+                self.store
+                    .write()
+                    .await
+                    .put_sync_progress(progress, self.next_tx_seq)?;
+                let mut status = self.status.write().await;
+                status.next_tx_seq = self.next_tx_seq;
+                status.synced_block_number = Some(progress.0);
+                status.synced_block_hash = Some(progress.1);
+            }
+            LogFetchProgress::Transaction((tx, sender)) => {
+                if !self.put_tx(tx, sender).await {
+                    // Unexpected error.
+                    error!("log sync write error");
+                    return Ok(false);
                 }
             }
+            LogFetchProgress::Reverted(reverted) => {
+                self.process_reverted(reverted).await;
+            }
         }
-        Ok(())
+        Ok(true)
     }
 
-    async fn put_tx_inner(&mut self, tx: Transaction) -> bool {
+    async fn put_tx_inner(&mut self, tx: Transaction, sender: Option<H160>) -> bool {
         if let Err(e) = self.store.write().await.put_tx(tx.clone()) {
             error!("put_tx error: e={:?}", e);
             false
         } else {
-            if let Some(data) = self.data_cache.pop_data(&tx.data_merkle_root) {
+            if let Some(sender) = sender {
+                if let Err(e) = self.store.write().await.put_tx_sender(tx.seq, sender) {
+                    error!("put_tx sender error: e={:?}", e);
+                    return false;
+                }
+            }
+            if let Some(chunks) = self.data_cache.pop_data(&tx.data_merkle_root) {
                 let mut store = self.store.write().await;
+                // Only finalize the tx if the cached data are complete.
+                let completed = matches!(
+                    chunks.as_slice(),
+                    [c] if c.start_index == 0 && c.data.len() / CHUNK_SIZE == tx.num_entries()
+                );
                 // We are holding a mutable reference of LogSyncManager, so no chain reorg is
                 // possible after put_tx.
-                if let Err(e) = store
-                    .put_chunks_with_tx_hash(
-                        tx.seq,
-                        tx.hash(),
-                        ChunkArray {
-                            data,
-                            start_index: 0,
-                        },
-                    )
-                    .and_then(|_| store.finalize_tx_with_hash(tx.seq, tx.hash()))
-                {
+                let result = chunks
+                    .into_iter()
+                    .try_for_each(|c| {
+                        store
+                            .put_chunks_with_tx_hash(tx.seq, tx.hash(), c)
+                            .map(|_| ())
+                    })
+                    .and_then(|_| {
+                        if completed {
+                            store.finalize_tx_with_hash(tx.seq, tx.hash()).map(|_| ())
+                        } else {
+                            Ok(())
+                        }
+                    });
+                if let Err(e) = result {
                     error!("put_tx data error: e={:?}", e);
                     return false;
                 }
@@ -291,6 +576,32 @@ where
     }
 }
 
+/// Walk back `synced_blocks` and return the latest one whose hash matches the chain.
+/// `synced_blocks` is in ascending order of block number.
+async fn find_common_ancestor<F, Fut>(
+    synced_blocks: &[(u64, H256, u64)],
+    get_block_hash: F,
+) -> Result<Option<(u64, H256, u64)>>
+where
+    F: Fn(u64) -> Fut,
+    Fut: Future<Output = Result<Option<H256>>>,
+{
+    for (block_number, block_hash, next_tx_seq) in synced_blocks.iter().rev() {
+        match get_block_hash(*block_number).await? {
+            Some(hash) if hash == *block_hash => {
+                return Ok(Some((*block_number, *block_hash, *next_tx_seq)))
+            }
+            hash => {
+                debug!(
+                    "synced block reverted: block_number={} expect={:?} get={:?}",
+                    block_number, block_hash, hash
+                );
+            }
+        }
+    }
+    Ok(None)
+}
+
 async fn repeat_run_and_log<R, E, F>(f: impl Fn() -> F) -> R
 where
     E: Debug,
@@ -306,4 +617,324 @@ where
 
 pub(crate) mod config;
 mod data_cache;
+pub(crate) mod file_log_source;
 mod log_entry_fetcher;
+pub(crate) mod log_source;
+pub(crate) mod status;
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        find_common_ancestor, LogSyncEvent, LogSyncManager, LogSyncRequest, LogSyncResponse,
+    };
+    use crate::sync_manager::config::{CacheConfig, ConfirmationStrategy, LogSyncConfig};
+    use crate::sync_manager::status::LogSyncStatus;
+    use ethereum_types::{H160, H256};
+    use mock_chain::{submission_log, MockChain, MockChainServer, MockLog};
+    use std::sync::Arc;
+    use std::time::Duration;
+    use storage::log_store::log_manager::LogConfig;
+    use storage::log_store::{LogStoreRead, LogStoreWrite, Store};
+    use storage::LogManager;
+    use task_executor::test_utils::TestRuntime;
+    use tokio::sync::{broadcast, RwLock};
+
+    const CONFIRMATION_BLOCK_COUNT: u64 = 3;
+
+    /// Save the progress of each block, with one tx submitted in each block.
+    fn sync_blocks(store: &LogManager, chain: &MockChain, from: u64, to: u64) {
+        for block_number in from..=to {
+            store
+                .put_sync_progress(
+                    (block_number, chain.block_hash(block_number).unwrap()),
+                    block_number + 1,
+                )
+                .unwrap();
+        }
+    }
+
+    async fn find_ancestor(store: &LogManager, chain: &MockChain) -> Option<(u64, H256, u64)> {
+        let synced_blocks = store.get_synced_blocks().unwrap();
+        find_common_ancestor(&synced_blocks, |n| async move { Ok(chain.block_hash(n)) })
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_no_reorg() {
+        let store = LogManager::memorydb(LogConfig::default()).unwrap();
+        let chain = MockChain::new(1);
+        chain.mine_blocks(9);
+        sync_blocks(&store, &chain, 0, 9);
+        chain.mine_blocks(5);
+
+        let ancestor = find_ancestor(&store, &chain).await;
+        assert_eq!(ancestor, Some((9, chain.block_hash(9).unwrap(), 10)));
+    }
+
+    #[tokio::test]
+    async fn test_reorg() {
+        let store = LogManager::memorydb(LogConfig::default()).unwrap();
+        let chain = MockChain::new(1);
+        chain.mine_blocks(9);
+        sync_blocks(&store, &chain, 0, 9);
+        // The new branch from block 6 is shorter than the reverted one.
+        chain.reorg(4, vec![vec![]; 2]);
+
+        let ancestor = find_ancestor(&store, &chain).await;
+        assert_eq!(ancestor, Some((5, chain.block_hash(5).unwrap(), 6)));
+
+        // Revert the progress and sync the new branch.
+        let (block_number, block_hash, next_tx_seq) = ancestor.unwrap();
+        store
+            .put_sync_progress((block_number, block_hash), next_tx_seq)
+            .unwrap();
+        sync_blocks(&store, &chain, 6, 7);
+        let synced_blocks = store.get_synced_blocks().unwrap();
+        assert_eq!(synced_blocks.len(), 8);
+        assert_eq!(synced_blocks[7], (7, chain.block_hash(7).unwrap(), 8));
+    }
+
+    #[tokio::test]
+    async fn test_reorg_all_synced_blocks() {
+        let store = LogManager::memorydb(LogConfig::default()).unwrap();
+        let chain = MockChain::new(1);
+        chain.mine_blocks(9);
+        sync_blocks(&store, &chain, 5, 9);
+        // The new branch from block 3.
+        chain.reorg(7, vec![vec![]; 10]);
+
+        assert_eq!(find_ancestor(&store, &chain).await, None);
+    }
+
+    fn submission(flow_address: H160, seq: u64, root: u8) -> MockLog {
+        submission_log(flow_address, seq, seq * 256, 256, vec![([root; 32], 8)])
+    }
+
+    fn log_sync_config(server: &MockChainServer, flow_address: H160) -> LogSyncConfig {
+        LogSyncConfig::new(
+            vec![server.url().to_string()],
+            1,
+            None,
+            flow_address,
+            0,
+            CONFIRMATION_BLOCK_COUNT,
+            ConfirmationStrategy::Depth,
+            CacheConfig {
+                max_data_size: 1024,
+                dir: None,
+                max_disk_data_size: 0,
+                tx_seq_ttl: 10,
+            },
+            1000,
+            None,
+        )
+    }
+
+    async fn wait_for_next_tx_seq(status: &RwLock<LogSyncStatus>, next_tx_seq: u64) {
+        tokio::time::timeout(Duration::from_secs(10), async {
+            while status.read().await.next_tx_seq != next_tx_seq {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+        })
+        .await
+        .expect("wait log sync progress");
+    }
+
+    async fn next_event(event_recv: &mut broadcast::Receiver<LogSyncEvent>) -> LogSyncEvent {
+        tokio::time::timeout(Duration::from_secs(10), event_recv.recv())
+            .await
+            .expect("wait log sync event")
+            .expect("event channel closed")
+    }
+
+    async fn tx_root(store: &RwLock<dyn Store>, seq: u64) -> Option<u8> {
+        store
+            .read()
+            .await
+            .get_tx_by_seq_number(seq)
+            .unwrap()
+            .map(|tx| tx.data_merkle_root.as_bytes()[0])
+    }
+
+    #[tokio::test]
+    async fn test_sync_from_chain() {
+        let chain = MockChain::new(1);
+        let server = MockChainServer::start(chain.clone()).await.unwrap();
+        let flow_address = H160::from_low_u64_be(1);
+
+        // The submissions before the node starts are recovered.
+        chain.mine_block(vec![submission(flow_address, 0, 1)]);
+        chain.mine_block(vec![
+            submission(flow_address, 1, 2),
+            submission(H160::from_low_u64_be(2), 100, 2),
+        ]);
+        chain.mine_blocks(CONFIRMATION_BLOCK_COUNT);
+
+        let runtime = TestRuntime::default();
+        let store: Arc<RwLock<dyn Store>> = Arc::new(RwLock::new(
+            LogManager::memorydb(LogConfig::default()).unwrap(),
+        ));
+        let (_, mut event_recv, status, request_send) = LogSyncManager::spawn(
+            log_sync_config(&server, flow_address),
+            runtime.task_executor.clone(),
+            store.clone(),
+        )
+        .await
+        .unwrap();
+        wait_for_next_tx_seq(&status, 2).await;
+        assert_eq!(tx_root(store.as_ref(), 0).await, Some(1));
+        assert_eq!(tx_root(store.as_ref(), 1).await, Some(2));
+        assert_eq!(
+            status.read().await.finalized_block_number,
+            Some(chain.latest_block_number() - CONFIRMATION_BLOCK_COUNT)
+        );
+
+        // The new submissions are watched once they are confirmed.
+        let block_number = chain.mine_block(vec![submission(flow_address, 2, 3)]);
+        chain.mine_blocks(CONFIRMATION_BLOCK_COUNT);
+        wait_for_next_tx_seq(&status, 3).await;
+        assert_eq!(tx_root(store.as_ref(), 2).await, Some(3));
+        let (synced_block_number, _) = store.read().await.get_sync_progress().unwrap().unwrap();
+        assert!(synced_block_number >= block_number);
+        assert!(status.read().await.finalized_block_number >= Some(block_number));
+
+        // The confirmed submissions reverted by a chain reorg are synced again.
+        chain.reorg(
+            chain.latest_block_number() - block_number + 1,
+            vec![vec![submission(flow_address, 2, 4)]],
+        );
+        chain.mine_blocks(CONFIRMATION_BLOCK_COUNT);
+        assert!(matches!(
+            next_event(&mut event_recv).await,
+            LogSyncEvent::ReorgDetected { tx_seq: 2 }
+        ));
+        assert!(matches!(
+            next_event(&mut event_recv).await,
+            LogSyncEvent::Reverted { tx_seq: 2 }
+        ));
+        wait_for_next_tx_seq(&status, 3).await;
+        assert_eq!(tx_root(store.as_ref(), 1).await, Some(2));
+        assert_eq!(tx_root(store.as_ref(), 2).await, Some(4));
+
+        // Resync on request.
+        let response = request_send
+            .request(LogSyncRequest::ResyncFrom { block_number })
+            .await
+            .unwrap();
+        assert!(matches!(
+            response,
+            LogSyncResponse::ResyncFrom { result: Ok(2) }
+        ));
+        assert!(matches!(
+            next_event(&mut event_recv).await,
+            LogSyncEvent::ReorgDetected { tx_seq: 2 }
+        ));
+        assert!(matches!(
+            next_event(&mut event_recv).await,
+            LogSyncEvent::Reverted { tx_seq: 2 }
+        ));
+        wait_for_next_tx_seq(&status, 3).await;
+        assert_eq!(tx_root(store.as_ref(), 2).await, Some(4));
+        server.stop();
+    }
+
+    #[tokio::test]
+    async fn test_resync_start_block_missing() {
+        let chain = MockChain::new(1);
+        let server = MockChainServer::start(chain.clone()).await.unwrap();
+        let flow_address = H160::from_low_u64_be(1);
+        chain.mine_block(vec![submission(flow_address, 0, 1)]);
+        chain.mine_blocks(CONFIRMATION_BLOCK_COUNT);
+
+        let runtime = TestRuntime::default();
+        let store: Arc<RwLock<dyn Store>> = Arc::new(RwLock::new(
+            LogManager::memorydb(LogConfig::default()).unwrap(),
+        ));
+        let (_, _, status, request_send) = LogSyncManager::spawn(
+            log_sync_config(&server, flow_address),
+            runtime.task_executor.clone(),
+            store.clone(),
+        )
+        .await
+        .unwrap();
+        wait_for_next_tx_seq(&status, 1).await;
+
+        // Nothing is reverted if the start block is not available.
+        chain.set_block_missing(0, true);
+        let response = request_send
+            .request(LogSyncRequest::ResyncFrom { block_number: 0 })
+            .await
+            .unwrap();
+        assert!(matches!(
+            response,
+            LogSyncResponse::ResyncFrom { result: Err(_) }
+        ));
+        assert_eq!(status.read().await.next_tx_seq, 1);
+        assert_eq!(tx_root(store.as_ref(), 0).await, Some(1));
+        let (_, synced_block_hash) = store.read().await.get_sync_progress().unwrap().unwrap();
+        assert_ne!(synced_block_hash, H256::zero());
+
+        chain.set_block_missing(0, false);
+        let response = request_send
+            .request(LogSyncRequest::ResyncFrom { block_number: 0 })
+            .await
+            .unwrap();
+        assert!(matches!(
+            response,
+            LogSyncResponse::ResyncFrom { result: Ok(0) }
+        ));
+        wait_for_next_tx_seq(&status, 1).await;
+        assert_eq!(tx_root(store.as_ref(), 0).await, Some(1));
+        server.stop();
+    }
+
+    #[tokio::test]
+    async fn test_reorg_on_restart() {
+        let chain = MockChain::new(1);
+        let server = MockChainServer::start(chain.clone()).await.unwrap();
+        let flow_address = H160::from_low_u64_be(1);
+        chain.mine_block(vec![submission(flow_address, 0, 1)]);
+        chain.mine_block(vec![submission(flow_address, 1, 2)]);
+        chain.mine_blocks(CONFIRMATION_BLOCK_COUNT);
+
+        let store: Arc<RwLock<dyn Store>> = Arc::new(RwLock::new(
+            LogManager::memorydb(LogConfig::default()).unwrap(),
+        ));
+        let runtime = TestRuntime::default();
+        let (_, _, status, _) = LogSyncManager::spawn(
+            log_sync_config(&server, flow_address),
+            runtime.task_executor.clone(),
+            store.clone(),
+        )
+        .await
+        .unwrap();
+        wait_for_next_tx_seq(&status, 2).await;
+        drop(runtime);
+
+        // The second submission is replaced while the node is stopped.
+        let depth = chain.latest_block_number() - 1;
+        let mut new_blocks = vec![vec![submission(flow_address, 1, 3)]];
+        new_blocks.resize(depth as usize, vec![]);
+        chain.reorg(depth, new_blocks);
+
+        let runtime = TestRuntime::default();
+        let (_, mut event_recv, status, _) = LogSyncManager::spawn(
+            log_sync_config(&server, flow_address),
+            runtime.task_executor.clone(),
+            store.clone(),
+        )
+        .await
+        .unwrap();
+        loop {
+            if let LogSyncEvent::Reverted { tx_seq } = next_event(&mut event_recv).await {
+                assert_eq!(tx_seq, 1);
+                break;
+            }
+        }
+        wait_for_next_tx_seq(&status, 2).await;
+        assert_eq!(tx_root(store.as_ref(), 0).await, Some(1));
+        assert_eq!(tx_root(store.as_ref(), 1).await, Some(3));
+        server.stop();
+    }
+}
@@ -0,0 +1,33 @@
+use ethers::types::H256;
+use serde::{Deserialize, Serialize};
+
+/// The progress of log sync, reported by the `admin_getLogSyncStatus` RPC.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogSyncStatus {
+    /// The strategy to decide which blocks are final.
+    pub confirmation_strategy: String,
+    /// The confirmation depth used by the `depth` and hybrid strategies.
+    pub confirmation_block_count: u64,
+    /// The next tx seq to put into the store.
+    pub next_tx_seq: u64,
+    /// The logs up to this block have been processed and the progress is saved.
+    /// After the logs before the node starts are recovered, this is the latest final block.
+    pub synced_block_number: Option<u64>,
+    pub synced_block_hash: Option<H256>,
+    /// The latest final block decided by the confirmation strategy.
+    /// The recovered blocks after it are not final and may still be reorganized.
+    pub finalized_block_number: Option<u64>,
+    /// The progress of recovering the logs before the node starts.
+    pub recovery: Option<LogRecoveryStatus>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogRecoveryStatus {
+    pub start_block_number: u64,
+    pub end_block_number: u64,
+    pub finished: bool,
+    /// The estimated remaining time in seconds, based on the speed so far.
+    pub eta_secs: Option<u64>,
+}
@@ -1,7 +1,7 @@
+use chain_provider::MultiHttp;
 use ethereum_types::{Address, H256};
 use ethers::core::k256::SecretKey;
 use ethers::middleware::SignerMiddleware;
-use ethers::providers::Http;
 use ethers::providers::Middleware;
 use ethers::providers::Provider;
 use ethers::signers::LocalWallet;
@@ -10,18 +10,19 @@ use ethers::signers::Signer;
 pub struct MinerConfig {
     pub(crate) miner_id: H256,
     pub(crate) miner_key: H256,
-    pub(crate) rpc_endpoint_url: String,
+    /// The blockchain endpoints in the order of priority.
+    pub(crate) rpc_endpoint_urls: Vec<String>,
     pub(crate) mine_address: Address,
     pub(crate) flow_address: Address,
 }
 
-pub type MineServiceMiddleware = SignerMiddleware<Provider<Http>, LocalWallet>;
+pub type MineServiceMiddleware = SignerMiddleware<Provider<MultiHttp>, LocalWallet>;
 
 impl MinerConfig {
     pub fn new(
         miner_id: Option<H256>,
         miner_key: Option<H256>,
-        rpc_endpoint_url: String,
+        rpc_endpoint_urls: Vec<String>,
         mine_address: Address,
         flow_address: Address,
     ) -> Option<MinerConfig> {
@@ -29,7 +30,7 @@ impl MinerConfig {
             (Some(miner_id), Some(miner_key)) => Some(MinerConfig {
                 miner_id,
                 miner_key,
-                rpc_endpoint_url,
+                rpc_endpoint_urls,
                 mine_address,
                 flow_address,
             }),
@@ -38,7 +39,8 @@ impl MinerConfig {
     }
 
     pub(crate) async fn make_provider(&self) -> Result<MineServiceMiddleware, String> {
-        let provider = Provider::<Http>::try_from(&self.rpc_endpoint_url)
+        // Quorum is only used to check the synced logs.
+        let provider = chain_provider::make_provider(&self.rpc_endpoint_urls, 1)
             .map_err(|e| format!("Can not parse blockchain endpoint: {:?}", e))?;
         let chain_id = provider
             .get_chainid()
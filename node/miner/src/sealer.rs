@@ -211,3 +211,123 @@ impl Sealer {
         Ok(true)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Sealer;
+    use crate::MinerConfig;
+    use contract_interface::ionian_flow::{
+        GetEpochRangeCall, MakeContextWithResultCall, MineContext, QueryContextAtPositionCall,
+    };
+    use contract_interface::EpochRangeWithContextDigest;
+    use ethereum_types::{Address, H256, U256};
+    use ethers::abi::{Token, Tokenizable};
+    use ethers::contract::EthCall;
+    use ionian_spec::{BYTES_PER_SEAL, SECTORS_PER_SEAL};
+    use mock_chain::{MockChain, MockChainServer};
+    use shared_types::ChunkArray;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use storage::log_store::log_manager::LogConfig;
+    use storage::log_store::{FlowRead, FlowWrite, LogStoreInner, Store};
+    use storage::LogManager;
+    use task_executor::test_utils::TestRuntime;
+    use tokio::sync::RwLock;
+
+    #[tokio::test]
+    async fn test_seal() {
+        let chain = MockChain::new(1);
+        let server = MockChainServer::start(chain.clone()).await.unwrap();
+        let flow_address = Address::from_low_u64_be(1);
+        let miner_id = H256::from_low_u64_be(1);
+
+        // The two seals are in different epochs.
+        let digests = [[1u8; 32], [2u8; 32]];
+        let flow_length = 2 * SECTORS_PER_SEAL as u64;
+        chain.set_call_handler(
+            flow_address,
+            MakeContextWithResultCall::selector(),
+            move |_| {
+                let context = MineContext {
+                    epoch: U256::from(2),
+                    digest: digests[1],
+                    flow_length: U256::from(flow_length),
+                    ..Default::default()
+                };
+                Ok(ethers::abi::encode(&[context.into_token()]))
+            },
+        );
+        chain.set_call_handler(flow_address, GetEpochRangeCall::selector(), |_| {
+            Ok(ethers::abi::encode(&[Token::Tuple(vec![
+                Token::Uint(U256::from(SECTORS_PER_SEAL)),
+                Token::Uint(U256::from(2 * SECTORS_PER_SEAL)),
+            ])]))
+        });
+        chain.set_call_handler(
+            flow_address,
+            QueryContextAtPositionCall::selector(),
+            move |_| {
+                let context = EpochRangeWithContextDigest {
+                    start: 0,
+                    end: SECTORS_PER_SEAL as u128,
+                    digest: digests[0],
+                };
+                Ok(ethers::abi::encode(&[context.into_token()]))
+            },
+        );
+
+        let data: Vec<u8> = (0..2 * BYTES_PER_SEAL).map(|i| i as u8).collect();
+        let mut store = LogManager::memorydb(LogConfig::default()).unwrap();
+        store
+            .flow_mut()
+            .append_entries(ChunkArray {
+                data: data.clone(),
+                start_index: 0,
+            })
+            .unwrap();
+        let store: Arc<RwLock<dyn Store>> = Arc::new(RwLock::new(store));
+
+        let config = MinerConfig::new(
+            Some(miner_id),
+            Some(H256::from_low_u64_be(1)),
+            vec![server.url().to_string()],
+            Address::from_low_u64_be(2),
+            flow_address,
+        )
+        .unwrap();
+        let provider = Arc::new(config.make_provider().await.unwrap());
+        let runtime = TestRuntime::default();
+        Sealer::spawn(
+            runtime.task_executor.clone(),
+            provider,
+            store.clone(),
+            &config,
+        );
+
+        let sealed = tokio::time::timeout(Duration::from_secs(20), async {
+            loop {
+                let sealed = store.read().await.flow().load_sealed_data(0).unwrap();
+                match sealed {
+                    Some(chunk) if chunk.avalibilities[..2].iter().all(|x| *x) => break chunk,
+                    _ => tokio::time::sleep(Duration::from_millis(100)).await,
+                }
+            }
+        })
+        .await
+        .expect("wait for sealing");
+
+        for (seal_index, digest) in digests.iter().enumerate() {
+            let mut expected =
+                data[seal_index * BYTES_PER_SEAL..(seal_index + 1) * BYTES_PER_SEAL].to_vec();
+            ionian_seal::seal(
+                &mut expected,
+                &miner_id,
+                &H256(*digest),
+                (seal_index * SECTORS_PER_SEAL) as u64,
+            );
+            assert_eq!(sealed.loaded_chunk[seal_index].to_vec(), expected);
+        }
+        assert!(!sealed.avalibilities[2]);
+        server.stop();
+    }
+}
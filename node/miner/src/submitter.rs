@@ -126,3 +126,144 @@ fn flow_proof_to_pora_merkle_proof(flow_proof: FlowRangeProof) -> Vec<[u8; 32]>
     // Exclude `item`, the nodes in the sealed data subtree, and `root`.
     full_proof[depth_in_sealed_data + 1..full_proof.len() - 1].to_vec()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{flow_proof_to_pora_merkle_proof, Submitter};
+    use crate::pora::AnswerWithoutProof;
+    use crate::MinerConfig;
+    use contract_interface::ionian_flow::QueryContextAtPositionCall;
+    use contract_interface::{EpochRangeWithContextDigest, PoraAnswer, IONIANMINE_ABI};
+    use ethereum_types::{Address, H256, U256};
+    use ethers::abi::Tokenizable;
+    use ethers::contract::EthCall;
+    use ethers::utils::rlp::Rlp;
+    use ionian_spec::{BYTES_PER_LOAD, BYTES_PER_SEAL, SECTORS_PER_SEAL};
+    use mock_chain::{MockChain, MockChainServer};
+    use shared_types::{ChunkArray, Transaction};
+    use std::sync::Arc;
+    use std::time::Duration;
+    use storage::log_store::log_manager::{
+        tx_subtree_root_list_padded, LogConfig, PORA_CHUNK_SIZE,
+    };
+    use storage::log_store::{LogStoreChunkWrite, LogStoreRead, LogStoreWrite, Store};
+    use storage::LogManager;
+    use task_executor::test_utils::TestRuntime;
+    use tokio::sync::{mpsc, RwLock};
+
+    #[tokio::test]
+    async fn test_submit_answer() {
+        let chain = MockChain::new(1);
+        let server = MockChainServer::start(chain.clone()).await.unwrap();
+        let flow_address = Address::from_low_u64_be(1);
+        let mine_address = Address::from_low_u64_be(2);
+        let sealed_context_digest = [5u8; 32];
+        chain.set_call_handler(
+            flow_address,
+            QueryContextAtPositionCall::selector(),
+            move |_| {
+                let context = EpochRangeWithContextDigest {
+                    start: 0,
+                    end: 2 * PORA_CHUNK_SIZE as u128,
+                    digest: sealed_context_digest,
+                };
+                Ok(ethers::abi::encode(&[context.into_token()]))
+            },
+        );
+
+        // A file of a full PoRA chunk after the first chunk.
+        let data = vec![1u8; BYTES_PER_LOAD];
+        let merkle_nodes = tx_subtree_root_list_padded(&data);
+        let tx = Transaction {
+            stream_ids: vec![],
+            data: vec![],
+            data_merkle_root: merkle_nodes[0].1,
+            merkle_nodes,
+            start_entry_index: PORA_CHUNK_SIZE as u64,
+            size: data.len() as u64,
+            seq: 0,
+        };
+        let mut store = LogManager::memorydb(LogConfig::default()).unwrap();
+        store.put_tx(tx).unwrap();
+        store
+            .put_chunks(
+                0,
+                ChunkArray {
+                    data,
+                    start_index: 0,
+                },
+            )
+            .unwrap();
+        let (flow_root, _) = store.get_context().unwrap();
+        let recall_position = PORA_CHUNK_SIZE as u64;
+        let flow_proof = store
+            .get_proof_at_root(&flow_root, recall_position, SECTORS_PER_SEAL as u64)
+            .unwrap();
+        let store: Arc<RwLock<dyn Store>> = Arc::new(RwLock::new(store));
+
+        let config = MinerConfig::new(
+            Some(H256::from_low_u64_be(1)),
+            Some(H256::from_low_u64_be(1)),
+            vec![server.url().to_string()],
+            mine_address,
+            flow_address,
+        )
+        .unwrap();
+        let provider = Arc::new(config.make_provider().await.unwrap());
+        let runtime = TestRuntime::default();
+        let (answer_send, answer_recv) = mpsc::unbounded_channel();
+        Submitter::spawn(
+            runtime.task_executor.clone(),
+            answer_recv,
+            provider,
+            store,
+            &config,
+        );
+
+        let nonce = H256::repeat_byte(2);
+        answer_send
+            .send(AnswerWithoutProof {
+                context_digest: H256::repeat_byte(3),
+                context_flow_root: flow_root,
+                nonce,
+                miner_id: H256::from_low_u64_be(1),
+                start_position: 0,
+                mining_length: 2 * PORA_CHUNK_SIZE as u64,
+                recall_position,
+                seal_offset: 0,
+                sealed_data: [4; BYTES_PER_SEAL],
+            })
+            .unwrap();
+
+        let raw_tx = tokio::time::timeout(Duration::from_secs(10), async {
+            loop {
+                if let Some(raw_tx) = chain.submitted_transactions().pop() {
+                    break raw_tx;
+                }
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+        })
+        .await
+        .expect("wait for answer submission");
+
+        // The answer is submitted in a legacy transaction to the mine contract.
+        let rlp = Rlp::new(raw_tx.as_ref());
+        let to: Vec<u8> = rlp.val_at(3).unwrap();
+        assert_eq!(to, mine_address.as_bytes());
+        let input: Vec<u8> = rlp.val_at(5).unwrap();
+        let submit = IONIANMINE_ABI.function("submit").unwrap();
+        assert_eq!(input[..4], submit.short_signature());
+        let mut tokens = submit.decode_input(&input[4..]).unwrap();
+        let answer = PoraAnswer::from_token(tokens.remove(0)).unwrap();
+
+        assert_eq!(answer.nonce, nonce.0);
+        assert_eq!(answer.recall_position, U256::from(recall_position));
+        assert_eq!(answer.sealed_context_digest, sealed_context_digest);
+        assert_eq!(
+            answer.merkle_proof,
+            flow_proof_to_pora_merkle_proof(flow_proof)
+        );
+        assert_eq!(chain.submitted_transactions().len(), 1);
+        server.stop();
+    }
+}
@@ -135,3 +135,79 @@ impl MineContextWatcher {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{MineContextWatcher, EMPTY_HASH};
+    use crate::MinerConfig;
+    use contract_interface::ionian_flow::{MakeContextWithResultCall, MineContext};
+    use contract_interface::ionian_mine::{LastMinedEpochCall, TargetQualityCall};
+    use ethereum_types::{Address, H256, U256};
+    use ethers::abi::{AbiEncode, Tokenizable};
+    use ethers::contract::EthCall;
+    use mock_chain::{MockChain, MockChainServer};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use task_executor::test_utils::TestRuntime;
+    use tokio::sync::broadcast;
+
+    #[tokio::test]
+    async fn test_watch_mine_context() {
+        let chain = MockChain::new(1);
+        let server = MockChainServer::start(chain.clone()).await.unwrap();
+        let flow_address = Address::from_low_u64_be(1);
+        let mine_address = Address::from_low_u64_be(2);
+
+        let context = Arc::new(Mutex::new(MineContext {
+            epoch: U256::from(1),
+            digest: [1; 32],
+            flow_length: U256::from(1024),
+            ..Default::default()
+        }));
+        let current_context = context.clone();
+        chain.set_call_handler(
+            flow_address,
+            MakeContextWithResultCall::selector(),
+            move |_| {
+                let context = current_context.lock().unwrap().clone();
+                Ok(ethers::abi::encode(&[context.into_token()]))
+            },
+        );
+        chain.set_call_handler(mine_address, LastMinedEpochCall::selector(), |_| {
+            Ok(U256::zero().encode())
+        });
+        chain.set_call_handler(mine_address, TargetQualityCall::selector(), |_| {
+            Ok(U256::from(100).encode())
+        });
+
+        let config = MinerConfig::new(
+            Some(H256::zero()),
+            Some(H256::from_low_u64_be(1)),
+            vec![server.url().to_string()],
+            mine_address,
+            flow_address,
+        )
+        .unwrap();
+        let provider = Arc::new(config.make_provider().await.unwrap());
+        let runtime = TestRuntime::default();
+        let (_msg_send, msg_recv) = broadcast::channel(1);
+        let mut context_recv =
+            MineContextWatcher::spawn(runtime.task_executor.clone(), msg_recv, provider, &config);
+
+        let report = tokio::time::timeout(Duration::from_secs(10), context_recv.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        let expected = context.lock().unwrap().clone();
+        assert_eq!(report, Some((expected, U256::from(100))));
+
+        // A context with the empty digest cannot be mined.
+        context.lock().unwrap().digest = EMPTY_HASH.0;
+        let report = tokio::time::timeout(Duration::from_secs(10), context_recv.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(report, None);
+        server.stop();
+    }
+}
@@ -24,6 +24,10 @@ pub struct GossipCache {
     find_file: Option<Duration>,
     /// Timeout for AnnounceFile.
     announce_file: Option<Duration>,
+    /// Timeout for AnnounceFiles.
+    announce_files: Option<Duration>,
+    /// Timeout for AnnounceShardConfig.
+    announce_shard_config: Option<Duration>,
 }
 
 #[derive(Default)]
@@ -35,6 +39,10 @@ pub struct GossipCacheBuilder {
     find_file: Option<Duration>,
     /// Timeout for AnnounceFile messages.
     announce_file: Option<Duration>,
+    /// Timeout for AnnounceFiles messages.
+    announce_files: Option<Duration>,
+    /// Timeout for AnnounceShardConfig messages.
+    announce_shard_config: Option<Duration>,
 }
 
 #[allow(dead_code)]
@@ -64,12 +72,26 @@ impl GossipCacheBuilder {
         self
     }
 
+    /// Timeout for AnnounceFiles messages.
+    pub fn announce_files_timeout(mut self, timeout: Duration) -> Self {
+        self.announce_files = Some(timeout);
+        self
+    }
+
+    /// Timeout for AnnounceShardConfig messages.
+    pub fn announce_shard_config_timeout(mut self, timeout: Duration) -> Self {
+        self.announce_shard_config = Some(timeout);
+        self
+    }
+
     pub fn build(self) -> GossipCache {
         let GossipCacheBuilder {
             default_timeout,
             example,
             find_file,
             announce_file,
+            announce_files,
+            announce_shard_config,
         } = self;
 
         GossipCache {
@@ -78,6 +100,8 @@ impl GossipCacheBuilder {
             example: example.or(default_timeout),
             find_file: find_file.or(default_timeout),
             announce_file: announce_file.or(default_timeout),
+            announce_files: announce_files.or(default_timeout),
+            announce_shard_config: announce_shard_config.or(default_timeout),
         }
     }
 }
@@ -95,6 +119,8 @@ impl GossipCache {
             GossipKind::Example => self.example,
             GossipKind::FindFile => self.find_file,
             GossipKind::AnnounceFile => self.announce_file,
+            GossipKind::AnnounceFiles => self.announce_files,
+            GossipKind::AnnounceShardConfig => self.announce_shard_config,
         };
 
         let expire_timeout = match expire_timeout {
@@ -172,8 +198,11 @@ mod tests {
         let mut cache = GossipCache::builder()
             .default_timeout(Duration::from_millis(300))
             .build();
-        let test_topic =
-            GossipTopic::new(GossipKind::Example, crate::types::GossipEncoding::SSZSnappy);
+        let test_topic = GossipTopic::new(
+            GossipKind::Example,
+            crate::types::GossipEncoding::SSZSnappy,
+            crate::types::DEFAULT_TOPIC_NAMESPACE,
+        );
         cache.insert(test_topic, vec![]);
         tokio::time::sleep(Duration::from_millis(300)).await;
         while cache.next().await.is_some() {}
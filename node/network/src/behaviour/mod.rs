@@ -2,15 +2,19 @@ use crate::behaviour::gossipsub_scoring_parameters::lighthouse_gossip_thresholds
 use crate::config::gossipsub_config;
 use crate::discovery::{Discovery, DiscoveryEvent, FIND_NODE_QUERY_CLOSEST_PEERS};
 use crate::peer_manager::{
-    config::Config as PeerManagerCfg, peerdb::score::PeerAction, peerdb::score::ReportSource,
+    config::Config as PeerManagerCfg,
+    peerdb::persisted::{PersistedPeers, MAX_PERSISTED_ENRS},
+    peerdb::score::PeerAction,
+    peerdb::score::ReportSource,
     ConnectionDirection, PeerManager, PeerManagerEvent,
 };
 use crate::rpc::methods::DataByHashRequest;
 use crate::rpc::methods::GetChunksRequest;
+use crate::rpc::methods::GetFileAvailabilityRequest;
 use crate::rpc::*;
 use crate::service::Context as ServiceContext;
 use crate::types::{GossipEncoding, GossipKind, GossipTopic, SnappyTransform};
-use crate::{error, metrics, Enr, NetworkGlobals, PubsubMessage, TopicHash};
+use crate::{error, metrics, Enr, EnrExt, NetworkGlobals, PubsubMessage, TopicHash};
 use futures::stream::StreamExt;
 use libp2p::gossipsub::error::PublishError;
 use libp2p::{
@@ -23,7 +27,9 @@ use libp2p::{
         MessageAuthenticity, MessageId,
     },
     identify::{Identify, IdentifyConfig, IdentifyEvent},
+    mdns::{Mdns, MdnsConfig, MdnsEvent},
     swarm::{
+        behaviour::toggle::Toggle,
         dial_opts::{DialOpts, PeerCondition},
         AddressScore, NetworkBehaviour, NetworkBehaviourAction as NBAction,
         NetworkBehaviourEventProcess, PollParameters,
@@ -32,9 +38,11 @@ use libp2p::{
 };
 use shared_types::ChunkArrayWithProof;
 use std::{
-    collections::VecDeque,
+    collections::{HashMap, HashSet, VecDeque},
+    path::PathBuf,
     sync::Arc,
     task::{Context, Poll},
+    time::Duration,
 };
 
 use self::gossip_cache::GossipCache;
@@ -47,6 +55,9 @@ pub const TARGET_SUBNET_PEERS: usize = 6;
 
 const MAX_IDENTIFY_ADDRESSES: usize = 10;
 
+/// The interval to persist the known peers to disk.
+const PERSIST_PEERS_INTERVAL: Duration = Duration::from_secs(300);
+
 /// Identifier of requests sent by a peer.
 pub type PeerRequestId = (ConnectionId, SubstreamId);
 
@@ -79,6 +90,8 @@ pub enum BehaviourEvent<AppReqId: ReqId> {
         id: AppReqId,
         /// The peer to which this request was sent.
         peer_id: PeerId,
+        /// The reason of the failure.
+        error: RPCError,
     },
     RequestReceived {
         /// The peer that sent the request.
@@ -117,6 +130,8 @@ pub enum BehaviourEvent<AppReqId: ReqId> {
 enum InternalBehaviourMessage {
     /// Dial a Peer.
     DialPeer(PeerId),
+    /// Dial a Peer persisted before restart with the known addresses.
+    DialPersistedPeer(PeerId, Vec<Multiaddr>),
     /// The socket has been updated.
     SocketUpdated(Multiaddr),
 }
@@ -142,6 +157,8 @@ pub struct Behaviour<AppReqId: ReqId> {
     // NOTE: The id protocol is used for initial interop. This will be removed by mainnet.
     /// Provides IP addresses and peer information.
     identify: Identify,
+    /// mDNS discovery of peers in the local network, if enabled.
+    mdns: Toggle<Mdns>,
     /// The peer manager that keeps track of peer's reputation and status.
     peer_manager: PeerManager,
 
@@ -165,6 +182,15 @@ pub struct Behaviour<AppReqId: ReqId> {
     update_gossipsub_scores: tokio::time::Interval,
     #[behaviour(ignore)]
     gossip_cache: GossipCache,
+    /// The namespace of gossipsub topics, which isolates the gossip of different networks.
+    #[behaviour(ignore)]
+    topic_namespace: String,
+    /// The directory to persist the known peers.
+    #[behaviour(ignore)]
+    network_dir: PathBuf,
+    /// The interval for persisting the known peers.
+    #[behaviour(ignore)]
+    persist_peers: tokio::time::Interval,
 }
 
 /// Implements the combined behaviour for the libp2p service.
@@ -194,6 +220,17 @@ impl<AppReqId: ReqId> Behaviour<AppReqId> {
         // start searching for peers
         discovery.discover_peers(FIND_NODE_QUERY_CLOSEST_PEERS);
 
+        // Build the mDNS sub-behaviour if enabled
+        let mdns = if config.enable_mdns {
+            let mdns = Mdns::new(MdnsConfig::default())
+                .await
+                .map_err(|e| format!("Could not construct mDNS: {:?}", e))?;
+            info!("mDNS discovery enabled");
+            Some(mdns)
+        } else {
+            None
+        };
+
         let filter = MaxCountSubscriptionFilter {
             filter: AllowAllSubscriptionFilter {},
             max_subscribed_topics: 200,
@@ -249,12 +286,18 @@ impl<AppReqId: ReqId> Behaviour<AppReqId> {
             .example_timeout(slot_duration) // TODO
             .build();
 
-        Ok(Behaviour {
+        let persist_peers = tokio::time::interval_at(
+            tokio::time::Instant::now() + PERSIST_PEERS_INTERVAL,
+            PERSIST_PEERS_INTERVAL,
+        );
+
+        let mut behaviour = Behaviour {
             // Sub-behaviours
             gossipsub,
             eth2_rpc: RPC::new(),
             discovery,
             identify: Identify::new(identify_config),
+            mdns: mdns.into(),
             // Auxiliary fields
             peer_manager: PeerManager::new(peer_manager_cfg, network_globals.clone()).await?,
             events: VecDeque::new(),
@@ -263,7 +306,79 @@ impl<AppReqId: ReqId> Behaviour<AppReqId> {
             waker: None,
             gossip_cache,
             update_gossipsub_scores,
-        })
+            topic_namespace: config.topic_namespace.clone(),
+            network_dir: config.network_dir.clone(),
+            persist_peers,
+        };
+
+        behaviour.restore_peers();
+
+        Ok(behaviour)
+    }
+
+    /// Restores the peers persisted before restart, which seeds the discovery and dialing.
+    fn restore_peers(&mut self) {
+        let PersistedPeers { peers, enrs } = PersistedPeers::load(&self.network_dir);
+
+        info!(
+            peers = peers.len(),
+            enrs = enrs.len(),
+            "Restoring persisted peers"
+        );
+
+        let banned = peers
+            .iter()
+            .filter(|peer| peer.banned)
+            .map(|peer| peer.peer_id)
+            .collect::<HashSet<_>>();
+
+        // add ENRs to the routing table of discovery
+        for enr in enrs
+            .into_iter()
+            .chain(peers.iter().filter_map(|peer| peer.enr.clone()))
+        {
+            if !banned.contains(&enr.peer_id()) {
+                self.discovery.add_enr(enr);
+            }
+        }
+
+        for peer in self.peer_manager.restore_peers(peers) {
+            let mut addresses = peer.listening_addresses;
+            if let Some(enr) = peer.enr.as_ref() {
+                addresses.extend(enr.multiaddr_tcp());
+            }
+
+            if addresses.is_empty() {
+                continue;
+            }
+
+            debug!(peer_id = %peer.peer_id, "Dialing persisted peer");
+            self.peer_manager.inject_dialing(&peer.peer_id, peer.enr);
+            self.internal_events
+                .push_back(InternalBehaviourMessage::DialPersistedPeer(
+                    peer.peer_id,
+                    addresses,
+                ));
+        }
+    }
+
+    /// Persists the known peers and discovered ENRs to disk.
+    pub fn persist_peers(&mut self) {
+        let peers = self.network_globals.peers.read().persisted_peers();
+
+        let mut node_ids = HashSet::new();
+        let enrs = self
+            .discovery
+            .cached_enrs()
+            .map(|(_, enr)| enr.clone())
+            .collect::<Vec<_>>()
+            .into_iter()
+            .chain(self.discovery.table_entries_enr())
+            .filter(|enr| node_ids.insert(enr.node_id()))
+            .take(MAX_PERSISTED_ENRS)
+            .collect();
+
+        PersistedPeers { peers, enrs }.save(&self.network_dir);
     }
 
     /* Public Accessible Functions to interact with the behaviour */
@@ -293,7 +408,7 @@ impl<AppReqId: ReqId> Behaviour<AppReqId> {
     /// Subscribes to a gossipsub topic kind, letting the network service determine the
     /// encoding and fork version.
     pub fn subscribe_kind(&mut self, kind: GossipKind) -> bool {
-        let gossip_topic = GossipTopic::new(kind, GossipEncoding::default());
+        let gossip_topic = GossipTopic::new(kind, GossipEncoding::default(), &self.topic_namespace);
 
         self.subscribe(gossip_topic)
     }
@@ -301,7 +416,7 @@ impl<AppReqId: ReqId> Behaviour<AppReqId> {
     /// Unsubscribes from a gossipsub topic kind, letting the network service determine the
     /// encoding and fork version.
     pub fn unsubscribe_kind(&mut self, kind: GossipKind) -> bool {
-        let gossip_topic = GossipTopic::new(kind, GossipEncoding::default());
+        let gossip_topic = GossipTopic::new(kind, GossipEncoding::default(), &self.topic_namespace);
         self.unsubscribe(gossip_topic)
     }
 
@@ -356,7 +471,7 @@ impl<AppReqId: ReqId> Behaviour<AppReqId> {
     /// Publishes a list of messages on the pubsub (gossipsub) behaviour, choosing the encoding.
     pub fn publish(&mut self, messages: Vec<PubsubMessage>) {
         for message in messages {
-            for topic in message.topics(GossipEncoding::default()) {
+            for topic in message.topics(GossipEncoding::default(), &self.topic_namespace) {
                 let message_data = message.encode(GossipEncoding::default());
                 if let Err(e) = self
                     .gossipsub
@@ -526,6 +641,9 @@ impl<AppReqId: ReqId> Behaviour<AppReqId> {
             Request::GetChunks { .. } => {
                 metrics::inc_counter_vec(&metrics::TOTAL_RPC_REQUESTS, &["get_chunks"])
             }
+            Request::GetFileAvailability { .. } => {
+                metrics::inc_counter_vec(&metrics::TOTAL_RPC_REQUESTS, &["get_file_availability"])
+            }
         }
         self.add_event(BehaviourEvent::RequestReceived {
             peer_id,
@@ -563,7 +681,7 @@ where
             } => {
                 // Note: We are keeping track here of the peer that sent us the message, not the
                 // peer that originally published the message.
-                match PubsubMessage::decode(&gs_msg.topic, &gs_msg.data) {
+                match PubsubMessage::decode(&gs_msg.topic, &gs_msg.data, &self.topic_namespace) {
                     Err(e) => {
                         debug!(topic = ?gs_msg.topic, error = ?e, "Could not decode gossipsub message");
                         //reject the message
@@ -588,7 +706,7 @@ where
                 }
             }
             GossipsubEvent::Subscribed { peer_id: _, topic } => {
-                if let Ok(topic) = GossipTopic::decode(topic.as_str()) {
+                if let Ok(topic) = GossipTopic::decode(topic.as_str(), &self.topic_namespace) {
                     // if let Some(subnet_id) = topic.subnet_id() {
                     //     self.network_globals
                     //         .peers
@@ -697,7 +815,7 @@ where
                         );
                         // inform failures of requests comming outside the behaviour
                         if let RequestId::Application(id) = id {
-                            self.add_event(BehaviourEvent::RPCFailed { peer_id, id });
+                            self.add_event(BehaviourEvent::RPCFailed { peer_id, id, error });
                         }
                     }
                 }
@@ -738,6 +856,11 @@ where
                     InboundRequest::GetChunks(req) => {
                         self.propagate_request(peer_request_id, peer_id, Request::GetChunks(req))
                     }
+                    InboundRequest::GetFileAvailability(req) => self.propagate_request(
+                        peer_request_id,
+                        peer_id,
+                        Request::GetFileAvailability(req),
+                    ),
                 }
             }
             Ok(RPCReceived::Response(id, resp)) => {
@@ -757,6 +880,9 @@ where
                     RPCResponse::Chunks(resp) => {
                         self.propagate_response(id, peer_id, Response::Chunks(resp))
                     }
+                    RPCResponse::FileAvailability(resp) => {
+                        self.propagate_response(id, peer_id, Response::FileAvailability(resp))
+                    }
                 }
             }
             Ok(RPCReceived::EndOfStream(id, termination)) => {
@@ -801,6 +927,32 @@ where
     }
 }
 
+// mDNS
+impl<AppReqId> NetworkBehaviourEventProcess<MdnsEvent> for Behaviour<AppReqId>
+where
+    AppReqId: ReqId,
+{
+    fn inject_event(&mut self, event: MdnsEvent) {
+        match event {
+            MdnsEvent::Discovered(list) => {
+                // the addresses are kept by mDNS to dial the peers
+                let results = list
+                    .map(|(peer_id, _)| (peer_id, None))
+                    .collect::<HashMap<_, _>>();
+
+                let to_dial_peers = self.peer_manager.peers_discovered(results);
+                for peer_id in to_dial_peers {
+                    debug!(%peer_id, "Dialing peer discovered by mDNS");
+                    self.peer_manager.inject_dialing(&peer_id, None);
+                    self.internal_events
+                        .push_back(InternalBehaviourMessage::DialPeer(peer_id));
+                }
+            }
+            MdnsEvent::Expired(_) => {}
+        }
+    }
+}
+
 // Identify
 impl<AppReqId> NetworkBehaviourEventProcess<IdentifyEvent> for Behaviour<AppReqId>
 where
@@ -856,6 +1008,17 @@ where
                         handler,
                     });
                 }
+                InternalBehaviourMessage::DialPersistedPeer(peer_id, addresses) => {
+                    let handler = self.new_handler();
+                    return Poll::Ready(NBAction::Dial {
+                        opts: DialOpts::peer_id(peer_id)
+                            .condition(PeerCondition::Disconnected)
+                            .addresses(addresses)
+                            .extend_addresses_through_behaviour()
+                            .build(),
+                        handler,
+                    });
+                }
                 InternalBehaviourMessage::SocketUpdated(address) => {
                     return Poll::Ready(NBAction::ReportObservedAddr {
                         address,
@@ -874,6 +1037,11 @@ where
             self.peer_manager.update_gossipsub_scores(&self.gossipsub);
         }
 
+        // persist the known peers periodically
+        while self.persist_peers.poll_tick(cx).is_ready() {
+            self.persist_peers();
+        }
+
         // poll the gossipsub cache to clear expired messages
         while let Poll::Ready(Some(result)) = self.gossip_cache.poll_next_unpin(cx) {
             match result {
@@ -936,6 +1104,13 @@ impl<AppReqId: ReqId> NetworkBehaviourEventProcess<PeerManagerEvent> for Behavio
     }
 }
 
+impl<AppReqId: ReqId> Drop for Behaviour<AppReqId> {
+    fn drop(&mut self) {
+        // persist peers on shutdown, so as to reconnect after restart
+        self.persist_peers();
+    }
+}
+
 /* Public API types */
 
 /// The type of RPC requests the Behaviour informs it has received and allows for sending.
@@ -951,6 +1126,8 @@ pub enum Request {
     DataByHash(DataByHashRequest),
     /// A GetChunks request.
     GetChunks(GetChunksRequest),
+    /// A GetFileAvailability request.
+    GetFileAvailability(GetFileAvailabilityRequest),
 }
 
 impl std::convert::From<Request> for OutboundRequest {
@@ -959,6 +1136,7 @@ impl std::convert::From<Request> for OutboundRequest {
             Request::Status(s) => OutboundRequest::Status(s),
             Request::DataByHash(r) => OutboundRequest::DataByHash(r),
             Request::GetChunks(r) => OutboundRequest::GetChunks(r),
+            Request::GetFileAvailability(r) => OutboundRequest::GetFileAvailability(r),
         }
     }
 }
@@ -977,6 +1155,8 @@ pub enum Response {
     DataByHash(Option<Box<IonianData>>),
     /// A response to a GET_CHUNKS request.
     Chunks(ChunkArrayWithProof),
+    /// A response to a GET_FILE_AVAILABILITY request.
+    FileAvailability(FileAvailability),
 }
 
 impl std::convert::From<Response> for RPCCodedResponse {
@@ -988,6 +1168,9 @@ impl std::convert::From<Response> for RPCCodedResponse {
                 None => RPCCodedResponse::StreamTermination(ResponseTermination::DataByHash),
             },
             Response::Chunks(c) => RPCCodedResponse::Success(RPCResponse::Chunks(c)),
+            Response::FileAvailability(a) => {
+                RPCCodedResponse::Success(RPCResponse::FileAvailability(a))
+            }
         }
     }
 }
@@ -1,4 +1,4 @@
-use crate::types::GossipKind;
+use crate::types::{GossipKind, DEFAULT_TOPIC_NAMESPACE};
 use crate::{Enr, PeerIdSerialized};
 use directory::{
     DEFAULT_BEACON_NODE_DIR, DEFAULT_HARDCODED_NETWORK, DEFAULT_NETWORK_DIR, DEFAULT_ROOT_DIR,
@@ -96,6 +96,9 @@ pub struct Config {
     /// Disables the discovery protocol from starting.
     pub disable_discovery: bool,
 
+    /// Enables mDNS to discover peers in the local network, next to or instead of discv5.
+    pub enable_mdns: bool,
+
     /// Attempt to construct external port mappings with UPnP.
     pub upnp_enabled: bool,
 
@@ -120,6 +123,10 @@ pub struct Config {
     /// List of extra topics to initially subscribe to as strings.
     pub topics: Vec<GossipKind>,
 
+    /// The namespace of gossipsub topics, so that different networks could share the same
+    /// discovery DHT without receiving gossip of each other.
+    pub topic_namespace: String,
+
     /// Whether metrics are enabled.
     pub metrics_enabled: bool,
 }
@@ -191,6 +198,7 @@ impl Default for Config {
             trusted_peers: vec![],
             client_version: ionian_version::version_with_platform(),
             disable_discovery: false,
+            enable_mdns: false,
             upnp_enabled: true,
             network_load: 3,
             private: false,
@@ -198,6 +206,7 @@ impl Default for Config {
             import_all_attestations: false,
             shutdown_after_sync: false,
             topics: Vec::new(),
+            topic_namespace: DEFAULT_TOPIC_NAMESPACE.to_string(),
             metrics_enabled: false,
         }
     }
@@ -100,6 +100,9 @@ pub enum RequestId {
 #[derive(Debug, Clone, Copy)]
 pub enum SyncId {
     SerialSync { tx_id: TxID },
+    ParallelSync { tx_id: TxID },
+    FileAvailability { tx_id: TxID },
+    DataByHash,
 }
 
 /// Types of messages that the network service can receive.
@@ -141,6 +144,16 @@ pub enum NetworkMessage {
     },
     /// Start dialing a new peer.
     DialPeer { address: Multiaddr, peer_id: PeerId },
+    /// Add a peer by its ENR to discovery and dial it, at the request of the operator.
+    AddPeer { enr: Box<Enr> },
+    /// Start dialing an address at the request of the operator.
+    DialAddress { address: Multiaddr },
+    /// Ban a peer at the request of the operator.
+    BanPeer { peer_id: PeerId },
+    /// Unban a peer at the request of the operator.
+    UnbanPeer { peer_id: PeerId },
+    /// Mark a peer as trusted at the request of the operator.
+    AddTrustedPeer { peer_id: PeerId },
     /// Notify that new file stored in db.
     AnnounceLocalFile { tx_id: TxID },
 }
@@ -6,6 +6,7 @@ use crate::{NetworkGlobals, PeerId};
 use discv5::Enr;
 use hashset_delay::HashSetDelay;
 use libp2p::identify::IdentifyInfo;
+use peerdb::persisted::PersistedPeer;
 use peerdb::{client::ClientKind, BanOperation, BanResult, ScoreUpdateResult};
 use smallvec::SmallVec;
 use std::{
@@ -173,6 +174,40 @@ impl PeerManager {
         self.handle_score_action(peer_id, action, reason);
     }
 
+    /// Bans a peer at the request of the operator, even if the peer is trusted.
+    pub fn ban_peer(&mut self, peer_id: &PeerId) {
+        // trusted peers are never downscored, so the trust is removed before banning
+        self.network_globals
+            .peers
+            .write()
+            .remove_trusted_peer(peer_id);
+
+        self.goodbye_peer(peer_id, GoodbyeReason::Banned, ReportSource::Admin);
+    }
+
+    /// Lifts the ban of a peer at the request of the operator.
+    pub fn unban_peer(&mut self, peer_id: &PeerId) {
+        let result = self.network_globals.peers.write().unban_peer(peer_id);
+
+        match result {
+            Some(action) => {
+                debug!(%peer_id, "Unbanning peer");
+                self.handle_score_action(peer_id, action, None);
+            }
+            None => debug!(%peer_id, "Ignoring to unban a peer that is not banned"),
+        }
+    }
+
+    /// Marks a peer as trusted at the request of the operator, and lifts its ban if banned.
+    pub fn add_trusted_peer(&mut self, peer_id: &PeerId) {
+        let result = self.network_globals.peers.write().add_trusted_peer(peer_id);
+        debug!(%peer_id, "Added trusted peer");
+
+        if let Some(action) = result {
+            self.handle_score_action(peer_id, action, None);
+        }
+    }
+
     /// Upon adjusting a Peer's score, there are times the peer manager must pass messages up to
     /// libp2p. This function handles the conditional logic associated with each score update
     /// result.
@@ -242,6 +277,41 @@ impl PeerManager {
         }
     }
 
+    /// Restores the peers persisted before restart, and bans the peers that were banned.
+    ///
+    /// Returns the healthy peers to dial, which are at most the target number of peers and
+    /// ordered by score.
+    pub fn restore_peers(&mut self, peers: Vec<PersistedPeer>) -> Vec<PersistedPeer> {
+        let mut to_dial_peers = Vec::new();
+
+        for peer in peers {
+            let peer_id = peer.peer_id;
+            let ban_operation = self
+                .network_globals
+                .peers
+                .write()
+                .restore_peer(peer.clone());
+
+            match ban_operation {
+                Some(ban_operation) => {
+                    debug!(%peer_id, "Restored banned peer");
+                    self.handle_ban_operation(&peer_id, ban_operation, None);
+                }
+                None if !peer.banned => to_dial_peers.push(peer),
+                None => {}
+            }
+        }
+
+        to_dial_peers.sort_unstable_by(|a, b| {
+            b.score
+                .partial_cmp(&a.score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        to_dial_peers.truncate(self.target_peers);
+
+        to_dial_peers
+    }
+
     /// Peers that have been returned by discovery requests that are suitable for dialing are
     /// returned here.
     ///
@@ -460,7 +530,10 @@ impl PeerManager {
                     Protocol::Goodbye => PeerAction::LowToleranceError,
                     Protocol::Status => PeerAction::LowToleranceError,
                     Protocol::DataByHash => PeerAction::MidToleranceError,
-                    Protocol::GetChunks => PeerAction::MidToleranceError,
+                    // Chunks requests are rejected when the bandwidth limits of the remote
+                    // peer exceeded, and sync will back off and retry later.
+                    Protocol::GetChunks => return,
+                    Protocol::GetFileAvailability => PeerAction::MidToleranceError,
                 },
             },
             RPCError::SSZDecodeError(_) => PeerAction::Fatal,
@@ -475,6 +548,7 @@ impl PeerManager {
                     Protocol::Status => PeerAction::LowToleranceError,
                     Protocol::DataByHash => return,
                     Protocol::GetChunks => return,
+                    Protocol::GetFileAvailability => return,
                 }
             }
             RPCError::StreamTimeout => match direction {
@@ -489,6 +563,7 @@ impl PeerManager {
                     Protocol::Status => return,
                     Protocol::DataByHash => PeerAction::MidToleranceError,
                     Protocol::GetChunks => PeerAction::MidToleranceError,
+                    Protocol::GetFileAvailability => PeerAction::MidToleranceError,
                 },
             },
             RPCError::NegotiationTimeout => PeerAction::LowToleranceError,
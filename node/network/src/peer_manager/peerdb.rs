@@ -4,6 +4,7 @@ use crate::{
     Enr, Gossipsub, PeerId,
 };
 use peer_info::{ConnectionDirection, PeerConnectionStatus, PeerInfo};
+use persisted::{PersistedPeer, MAX_PERSISTED_PEERS};
 use rand::seq::SliceRandom;
 use score::{PeerAction, ReportSource, Score, ScoreState};
 use std::cmp::Ordering;
@@ -14,6 +15,7 @@ use sync_status::SyncStatus;
 
 pub mod client;
 pub mod peer_info;
+pub mod persisted;
 pub mod score;
 pub mod sync_status;
 
@@ -331,6 +333,127 @@ impl PeerDB {
         }
     }
 
+    /// Returns the peers to persist across restarts, which includes the best healthy peers that
+    /// could be dialed and all the banned peers.
+    pub fn persisted_peers(&self) -> Vec<PersistedPeer> {
+        let to_persist = |peer_id: &PeerId, info: &PeerInfo, banned: bool| PersistedPeer {
+            peer_id: *peer_id,
+            listening_addresses: info.listening_addresses().clone(),
+            seen_addresses: info.seen_addresses().cloned().collect(),
+            enr: info.enr().cloned(),
+            score: info.score().score(),
+            banned,
+        };
+
+        let mut healthy = self
+            .peers
+            .iter()
+            .filter(|(_, info)| !info.is_trusted() && !info.is_banned())
+            .filter(|(_, info)| matches!(info.score_state(), ScoreState::Healthy))
+            .filter(|(_, info)| info.enr().is_some() || !info.listening_addresses().is_empty())
+            .collect::<Vec<_>>();
+        healthy.sort_unstable_by(|(_, a), (_, b)| {
+            b.score()
+                .score()
+                .partial_cmp(&a.score().score())
+                .unwrap_or(Ordering::Equal)
+        });
+
+        let banned = self
+            .peers
+            .iter()
+            .filter(|(_, info)| !info.is_trusted() && info.is_banned());
+
+        healthy
+            .into_iter()
+            .take(MAX_PERSISTED_PEERS)
+            .map(|(peer_id, info)| to_persist(peer_id, info, false))
+            .chain(banned.map(|(peer_id, info)| to_persist(peer_id, info, true)))
+            .collect()
+    }
+
+    /// Restores a peer persisted before restart as a disconnected peer, or a banned peer if it
+    /// was banned. Peers that already known, e.g. trusted peers, are ignored.
+    ///
+    /// Returns the `BanOperation` if the peer is banned.
+    #[must_use = "Banned peers need to be handled in libp2p"]
+    pub(super) fn restore_peer(&mut self, peer: PersistedPeer) -> Option<BanOperation> {
+        if self.peers.contains_key(&peer.peer_id) {
+            return None;
+        }
+
+        let info = PeerInfo::persisted_peer_info(
+            peer.score,
+            peer.listening_addresses,
+            peer.seen_addresses,
+            peer.enr,
+        );
+        self.peers.insert(peer.peer_id, info);
+        self.disconnected_peers += 1;
+
+        if peer.banned {
+            self.update_connection_state(&peer.peer_id, NewConnectionState::Banned)
+        } else {
+            None
+        }
+    }
+
+    /// Lifts the ban of a banned peer and resets its score.
+    ///
+    /// Returns `None` if the peer is unknown or not banned.
+    #[must_use = "Unbanned peers need to be reported to libp2p."]
+    pub(super) fn unban_peer(&mut self, peer_id: &PeerId) -> Option<ScoreUpdateResult> {
+        let info = self.peers.get_mut(peer_id)?;
+        if !info.is_banned() {
+            return None;
+        }
+
+        info.reset_score();
+        self.update_connection_state(peer_id, NewConnectionState::Unbanned);
+
+        let seen_ip_addresses = self
+            .peers
+            .get(peer_id)
+            .map(|info| {
+                info.seen_ip_addresses()
+                    .filter(|ip| !self.is_ip_banned(ip))
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+
+        Some(ScoreUpdateResult::Unbanned(seen_ip_addresses))
+    }
+
+    /// Marks a peer as trusted, so that it is never downscored or banned. The peer is inserted
+    /// if unknown, and unbanned if banned.
+    ///
+    /// Returns the `ScoreUpdateResult` if the peer has been unbanned.
+    #[must_use = "Unbanned peers need to be reported to libp2p."]
+    pub(super) fn add_trusted_peer(&mut self, peer_id: &PeerId) -> Option<ScoreUpdateResult> {
+        let result = self.unban_peer(peer_id);
+
+        match self.peers.get_mut(peer_id) {
+            Some(info) => info.set_trusted(true),
+            None => {
+                self.peers.insert(*peer_id, PeerInfo::trusted_peer_info());
+            }
+        }
+
+        result
+    }
+
+    /// Marks a trusted peer as untrusted with a default score. Returns false if the peer is
+    /// unknown or not trusted.
+    pub(super) fn remove_trusted_peer(&mut self, peer_id: &PeerId) -> bool {
+        match self.peers.get_mut(peer_id) {
+            Some(info) if info.is_trusted() => {
+                info.set_trusted(false);
+                true
+            }
+            _ => false,
+        }
+    }
+
     /// Allows the sync module to update sync status' of peers. Returns None, if the peer doesn't
     /// exist and returns Some(bool) representing if the sync state was modified.
     pub fn update_sync_status(
@@ -1927,4 +2050,37 @@ mod tests {
             Score::max_score().score()
         );
     }
+
+    #[test]
+    fn test_unban_and_trust_peer() {
+        let mut pdb = get_db();
+
+        let p = PeerId::random();
+        pdb.connect_ingoing(&p, "/ip4/0.0.0.0".parse().unwrap(), None);
+        assert!(pdb.unban_peer(&p).is_none());
+
+        let _ = pdb.report_peer(&p, PeerAction::Fatal, ReportSource::PeerManager, "");
+        pdb.inject_disconnect(&p);
+        assert!(pdb.peer_info(&p).unwrap().is_banned());
+
+        // unbanned with a reset score
+        assert!(matches!(
+            pdb.unban_peer(&p),
+            Some(ScoreUpdateResult::Unbanned(_))
+        ));
+        assert!(!pdb.peer_info(&p).unwrap().is_banned());
+        assert!(pdb.peer_info(&p).unwrap().is_disconnected());
+        assert_eq!(pdb.score(&p), 0.0);
+        assert_eq!(pdb.banned_peers_count.banned_peers(), 0);
+
+        // trusted peers are not banned
+        assert!(pdb.add_trusted_peer(&p).is_none());
+        let _ = pdb.report_peer(&p, PeerAction::Fatal, ReportSource::PeerManager, "");
+        assert!(!pdb.peer_info(&p).unwrap().is_banned());
+        assert_eq!(pdb.score(&p), Score::max_score().score());
+
+        assert!(pdb.remove_trusted_peer(&p));
+        assert!(!pdb.remove_trusted_peer(&p));
+        assert_eq!(pdb.score(&p), 0.0);
+    }
 }
@@ -71,6 +71,26 @@ impl PeerInfo {
         }
     }
 
+    /// Return a disconnected PeerInfo struct for a peer persisted before restart.
+    pub(super) fn persisted_peer_info(
+        score: f64,
+        listening_addresses: Vec<Multiaddr>,
+        seen_addresses: Vec<SocketAddr>,
+        enr: Option<Enr>,
+    ) -> Self {
+        let mut info = PeerInfo {
+            connection_status: Disconnected {
+                since: Instant::now(),
+            },
+            listening_addresses,
+            seen_addresses: seen_addresses.into_iter().collect(),
+            enr,
+            ..Default::default()
+        };
+        info.score.restore(score);
+        info
+    }
+
     /// Obtains the client of the peer.
     pub fn client(&self) -> &Client {
         &self.client
@@ -247,6 +267,24 @@ impl PeerInfo {
         }
     }
 
+    /// Marks the peer as trusted with the maximum score, or as untrusted with a default score.
+    // VISIBILITY: The peer manager is able to modify the trusted peers.
+    pub(super) fn set_trusted(&mut self, trusted: bool) {
+        self.is_trusted = trusted;
+        self.score = if trusted {
+            Score::max_score()
+        } else {
+            Score::default()
+        };
+    }
+
+    /// Resets a non-trusted peer's score, e.g. to lift the ban of the peer.
+    pub(super) fn reset_score(&mut self) {
+        if !self.is_trusted {
+            self.score = Score::default();
+        }
+    }
+
     /// Updates the gossipsub score with a new score. Optionally ignore the gossipsub score.
     pub(super) fn update_gossipsub_score(&mut self, new_score: f64, ignore: bool) {
         self.score.update_gossipsub_score(new_score, ignore);
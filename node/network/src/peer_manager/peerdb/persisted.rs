@@ -0,0 +1,274 @@
+//! Persists the known peers to disk, so that the node is able to reconnect to them and keep the
+//! bans after restarts instead of depending on the boot nodes again.
+
+use crate::{Enr, Multiaddr, PeerId};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::str::FromStr;
+
+/// The file under the network directory to persist peers.
+pub const PERSISTED_PEERS_FILENAME: &str = "peers.json";
+/// The file that peers are written to before it replaces `PERSISTED_PEERS_FILENAME`, so the
+/// persisted peers are not corrupted if the node stops in the middle of writing.
+const PERSISTED_PEERS_TEMP_FILENAME: &str = "peers.json.tmp";
+/// The maximum number of healthy peers to persist.
+pub const MAX_PERSISTED_PEERS: usize = 200;
+/// The maximum number of discovered ENRs to persist.
+pub const MAX_PERSISTED_ENRS: usize = 200;
+
+/// A known peer that is persisted across restarts.
+#[derive(Debug, Clone)]
+pub struct PersistedPeer {
+    pub peer_id: PeerId,
+    /// The listening addresses of the peer, which are used to dial the peer after restart.
+    pub listening_addresses: Vec<Multiaddr>,
+    /// The addresses seen in connections, which are used to ban IPs of banned peers.
+    pub seen_addresses: Vec<SocketAddr>,
+    pub enr: Option<Enr>,
+    pub score: f64,
+    pub banned: bool,
+}
+
+/// All the peers and ENRs persisted on disk.
+#[derive(Debug, Clone, Default)]
+pub struct PersistedPeers {
+    pub peers: Vec<PersistedPeer>,
+    /// ENRs of the discovery cache and routing table.
+    pub enrs: Vec<Enr>,
+}
+
+/// On-disk representation of `PersistedPeer`.
+#[derive(Serialize, Deserialize)]
+struct PeerRecord {
+    peer_id: String,
+    listening_addresses: Vec<String>,
+    seen_addresses: Vec<SocketAddr>,
+    enr: Option<String>,
+    score: f64,
+    banned: bool,
+}
+
+/// On-disk representation of `PersistedPeers`.
+#[derive(Default, Serialize, Deserialize)]
+struct PeersRecord {
+    peers: Vec<PeerRecord>,
+    enrs: Vec<String>,
+}
+
+impl From<&PersistedPeer> for PeerRecord {
+    fn from(peer: &PersistedPeer) -> Self {
+        PeerRecord {
+            peer_id: peer.peer_id.to_string(),
+            listening_addresses: peer
+                .listening_addresses
+                .iter()
+                .map(|addr| addr.to_string())
+                .collect(),
+            seen_addresses: peer.seen_addresses.clone(),
+            enr: peer.enr.as_ref().map(|enr| enr.to_base64()),
+            score: peer.score,
+            banned: peer.banned,
+        }
+    }
+}
+
+impl TryFrom<PeerRecord> for PersistedPeer {
+    type Error = String;
+
+    fn try_from(record: PeerRecord) -> Result<Self, Self::Error> {
+        let peer_id = PeerId::from_str(&record.peer_id)
+            .map_err(|e| format!("Invalid peer id {}: {:?}", record.peer_id, e))?;
+
+        // ignore the invalid addresses
+        let listening_addresses = record
+            .listening_addresses
+            .iter()
+            .filter_map(|addr| addr.parse::<Multiaddr>().ok())
+            .collect();
+
+        let enr = match record.enr {
+            Some(enr) => Some(Enr::from_str(&enr)?),
+            None => None,
+        };
+
+        if !record.score.is_finite() {
+            return Err(format!("Invalid score {}", record.score));
+        }
+
+        Ok(PersistedPeer {
+            peer_id,
+            listening_addresses,
+            seen_addresses: record.seen_addresses,
+            enr,
+            score: record.score,
+            banned: record.banned,
+        })
+    }
+}
+
+impl PersistedPeers {
+    /// Loads the persisted peers from the network directory. Invalid entries are ignored, and
+    /// nothing is loaded if the file does not exist or is corrupted.
+    pub fn load(dir: &Path) -> Self {
+        let path = dir.join(PERSISTED_PEERS_FILENAME);
+
+        let file = match File::open(&path) {
+            Ok(file) => file,
+            Err(_) => {
+                debug!(file = ?path, "No persisted peers found");
+                return Default::default();
+            }
+        };
+
+        let record: PeersRecord = match serde_json::from_reader(file) {
+            Ok(record) => record,
+            Err(e) => {
+                warn!(file = ?path, error = %e, "Failed to parse persisted peers");
+                return Default::default();
+            }
+        };
+
+        let peers = record
+            .peers
+            .into_iter()
+            .filter_map(|peer| match PersistedPeer::try_from(peer) {
+                Ok(peer) => Some(peer),
+                Err(e) => {
+                    debug!(error = %e, "Ignoring invalid persisted peer");
+                    None
+                }
+            })
+            .collect();
+
+        let enrs = record
+            .enrs
+            .iter()
+            .filter_map(|enr| Enr::from_str(enr).ok())
+            .collect();
+
+        PersistedPeers { peers, enrs }
+    }
+
+    /// Saves the peers to the network directory, overwriting the previously persisted peers.
+    pub fn save(&self, dir: &Path) {
+        let record = PeersRecord {
+            peers: self.peers.iter().map(PeerRecord::from).collect(),
+            enrs: self.enrs.iter().map(|enr| enr.to_base64()).collect(),
+        };
+
+        let path = dir.join(PERSISTED_PEERS_FILENAME);
+        let temp_path = dir.join(PERSISTED_PEERS_TEMP_FILENAME);
+        let _ = std::fs::create_dir_all(dir);
+
+        let write = || -> Result<(), String> {
+            let mut file = File::create(&temp_path).map_err(|e| e.to_string())?;
+            serde_json::to_writer(&mut file, &record).map_err(|e| e.to_string())?;
+            file.sync_all().map_err(|e| e.to_string())?;
+            std::fs::rename(&temp_path, &path).map_err(|e| e.to_string())
+        };
+
+        match write() {
+            Ok(_) => debug!(
+                peers = self.peers.len(),
+                enrs = self.enrs.len(),
+                "Persisted peers to disk"
+            ),
+            Err(e) => warn!(file = ?path, error = %e, "Failed to persist peers"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::discovery::{build_enr, CombinedKey, CombinedKeyExt};
+    use crate::NetworkConfig;
+    use libp2p::core::identity::Keypair;
+
+    #[test]
+    fn test_persisted_peers_save_and_load() {
+        let dir = tempfile::tempdir().unwrap();
+
+        // nothing persisted yet
+        assert!(PersistedPeers::load(dir.path()).peers.is_empty());
+
+        let keypair = Keypair::generate_secp256k1();
+        let enr_key = CombinedKey::from_libp2p(&keypair).unwrap();
+        let enr = build_enr(&enr_key, &NetworkConfig::default()).unwrap();
+
+        let peers = PersistedPeers {
+            peers: vec![
+                PersistedPeer {
+                    peer_id: keypair.public().to_peer_id(),
+                    listening_addresses: vec!["/ip4/127.0.0.1/tcp/10000".parse().unwrap()],
+                    seen_addresses: vec![],
+                    enr: Some(enr.clone()),
+                    score: 10.0,
+                    banned: false,
+                },
+                PersistedPeer {
+                    peer_id: PeerId::random(),
+                    listening_addresses: vec![],
+                    seen_addresses: vec!["10.0.0.1:10000".parse().unwrap()],
+                    enr: None,
+                    score: -80.0,
+                    banned: true,
+                },
+            ],
+            enrs: vec![enr.clone()],
+        };
+        peers.save(dir.path());
+
+        let loaded = PersistedPeers::load(dir.path());
+        assert_eq!(loaded.peers.len(), 2);
+        assert_eq!(loaded.peers[0].peer_id, peers.peers[0].peer_id);
+        assert_eq!(
+            loaded.peers[0].listening_addresses,
+            peers.peers[0].listening_addresses
+        );
+        assert_eq!(loaded.peers[0].enr, Some(enr.clone()));
+        assert_eq!(loaded.peers[1].peer_id, peers.peers[1].peer_id);
+        assert_eq!(
+            loaded.peers[1].seen_addresses,
+            peers.peers[1].seen_addresses
+        );
+        assert!(loaded.peers[1].banned);
+        assert_eq!(loaded.enrs, vec![enr]);
+    }
+
+    #[test]
+    fn test_persisted_peers_interrupted_save() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let peers = PersistedPeers {
+            peers: vec![PersistedPeer {
+                peer_id: PeerId::random(),
+                listening_addresses: vec![],
+                seen_addresses: vec!["10.0.0.1:10000".parse().unwrap()],
+                enr: None,
+                score: -80.0,
+                banned: true,
+            }],
+            enrs: vec![],
+        };
+        peers.save(dir.path());
+        assert!(!dir.path().join(PERSISTED_PEERS_TEMP_FILENAME).exists());
+
+        // a save interrupted before the rename does not affect the persisted peers
+        std::fs::write(
+            dir.path().join(PERSISTED_PEERS_TEMP_FILENAME),
+            "{\"peers\": [",
+        )
+        .unwrap();
+        let loaded = PersistedPeers::load(dir.path());
+        assert_eq!(loaded.peers.len(), 1);
+        assert!(loaded.peers[0].banned);
+
+        // the next save replaces the partial file
+        PersistedPeers::default().save(dir.path());
+        assert!(!dir.path().join(PERSISTED_PEERS_TEMP_FILENAME).exists());
+        assert!(PersistedPeers::load(dir.path()).peers.is_empty());
+    }
+}
@@ -73,6 +73,7 @@ pub enum ReportSource {
     Processor,
     SyncService,
     PeerManager,
+    Admin,
 }
 
 impl From<ReportSource> for &'static str {
@@ -83,6 +84,7 @@ impl From<ReportSource> for &'static str {
             ReportSource::Processor => "processor",
             ReportSource::SyncService => "sync",
             ReportSource::PeerManager => "peer_manager",
+            ReportSource::Admin => "admin",
         }
     }
 }
@@ -179,6 +181,11 @@ impl RealScore {
         }
     }
 
+    /// Restores the score persisted before restart.
+    pub fn restore(&mut self, score: f64) {
+        self.set_lighthouse_score(score.clamp(MIN_SCORE, MAX_SCORE));
+    }
+
     fn set_lighthouse_score(&mut self, new_score: f64) {
         self.lighthouse_score = new_score;
         self.update_state();
@@ -296,6 +303,7 @@ macro_rules! apply {
 apply!(apply_peer_action, peer_action: PeerAction);
 apply!(update);
 apply!(update_gossipsub_score, new_score: f64, ignore: bool);
+apply!(restore, score: f64);
 #[cfg(test)]
 apply!(test_add, score: f64);
 #[cfg(test)]
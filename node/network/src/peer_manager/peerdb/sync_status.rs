@@ -6,11 +6,11 @@ use serde::Serialize;
 #[derive(Clone, Debug, Serialize)]
 /// The current sync status of the peer.
 pub enum SyncStatus {
-    /// At the current state as our node or ahead of us.
+    /// At the same log sync height as our node.
     Synced { info: SyncInfo },
-    /// The peer has greater knowledge about the canonical chain than we do.
+    /// The peer has synced more log entries than we do.
     Advanced { info: SyncInfo },
-    /// Is behind our current head and not useful for block downloads.
+    /// Is behind our log sync height and not useful for the latest file downloads.
     Behind { info: SyncInfo },
     /// This peer is in an incompatible network.
     IrrelevantPeer,
@@ -21,8 +21,8 @@ pub enum SyncStatus {
 /// A relevant peer's sync information.
 #[derive(Clone, Debug, Serialize)]
 pub struct SyncInfo {
-    pub head_root: H256,
-    pub finalized_root: H256,
+    pub next_tx_seq: u64,
+    pub flow_root: H256,
 }
 
 impl std::cmp::PartialEq for SyncStatus {
@@ -52,6 +52,7 @@ impl Encoder<RPCCodedResponse> for SSZSnappyInboundCodec {
                 RPCResponse::Pong(res) => res.data.as_ssz_bytes(),
                 RPCResponse::DataByHash(res) => res.as_ssz_bytes(),
                 RPCResponse::Chunks(res) => res.as_ssz_bytes(),
+                RPCResponse::FileAvailability(res) => res.as_ssz_bytes(),
             },
             RPCCodedResponse::Error(_, err) => err.as_ssz_bytes(),
             RPCCodedResponse::StreamTermination(_) => {
@@ -160,6 +161,7 @@ impl Encoder<OutboundRequest> for SSZSnappyOutboundCodec {
             OutboundRequest::Ping(req) => req.as_ssz_bytes(),
             OutboundRequest::DataByHash(req) => req.hashes.as_ssz_bytes(),
             OutboundRequest::GetChunks(req) => req.as_ssz_bytes(),
+            OutboundRequest::GetFileAvailability(req) => req.as_ssz_bytes(),
         };
         // SSZ encoded bytes should be within `max_packet_size`
         if bytes.len() > self.max_packet_size {
@@ -349,6 +351,9 @@ fn handle_v1_request(
         Protocol::GetChunks => Ok(Some(InboundRequest::GetChunks(
             GetChunksRequest::from_ssz_bytes(decoded_buffer)?,
         ))),
+        Protocol::GetFileAvailability => Ok(Some(InboundRequest::GetFileAvailability(
+            GetFileAvailabilityRequest::from_ssz_bytes(decoded_buffer)?,
+        ))),
     }
 }
 
@@ -376,6 +381,9 @@ fn handle_v1_response(
         Protocol::GetChunks => Ok(Some(RPCResponse::Chunks(
             ChunkArrayWithProof::from_ssz_bytes(decoded_buffer)?,
         ))),
+        Protocol::GetFileAvailability => Ok(Some(RPCResponse::FileAvailability(
+            FileAvailability::from_ssz_bytes(decoded_buffer)?,
+        ))),
     }
 }
 
@@ -391,13 +399,40 @@ mod tests {
     use std::io::Write;
 
     fn status_message() -> StatusMessage {
-        StatusMessage { data: 1 }
+        StatusMessage {
+            network_id: 1,
+            flow_address: Default::default(),
+            next_tx_seq: 2,
+            flow_root: Default::default(),
+        }
     }
 
     fn ping_message() -> Ping {
         Ping { data: 1 }
     }
 
+    fn ionian_data() -> IonianData {
+        IonianData {
+            hash: Default::default(),
+            tx_id: Default::default(),
+            size: 4,
+            finalized: true,
+            data: VariableList::from(vec![1, 2, 3, 4]),
+        }
+    }
+
+    fn file_availability() -> FileAvailability {
+        let mut segments = BitList::with_capacity(3).unwrap();
+        segments.set(0, true).unwrap();
+        segments.set(2, true).unwrap();
+
+        FileAvailability {
+            tx_id: Default::default(),
+            finalized: false,
+            segments,
+        }
+    }
+
     /// Encodes the given protocol response as bytes.
     fn encode(
         protocol: Protocol,
@@ -487,13 +522,18 @@ mod tests {
             encode_then_decode(
                 Protocol::DataByHash,
                 Version::V1,
-                RPCCodedResponse::Success(RPCResponse::DataByHash(Box::new(IonianData {
-                    hash: Default::default()
-                }))),
+                RPCCodedResponse::Success(RPCResponse::DataByHash(Box::new(ionian_data()))),
+            ),
+            Ok(Some(RPCResponse::DataByHash(Box::new(ionian_data()))))
+        );
+
+        assert_eq!(
+            encode_then_decode(
+                Protocol::GetFileAvailability,
+                Version::V1,
+                RPCCodedResponse::Success(RPCResponse::FileAvailability(file_availability())),
             ),
-            Ok(Some(RPCResponse::DataByHash(Box::new(IonianData {
-                hash: Default::default()
-            }))))
+            Ok(Some(RPCResponse::FileAvailability(file_availability())))
         );
 
         // TODO(ionian-dev): add tests for outbound requests
@@ -559,8 +599,8 @@ mod tests {
 
         assert_eq!(stream_identifier.len(), 10);
 
-        // Status message is 84 bytes uncompressed. `max_compressed_len` is 32 + 84 + 84/6 = 130.
-        let status_message_bytes = StatusMessage { data: 1 }.as_ssz_bytes();
+        // Status message is 68 bytes uncompressed. `max_compressed_len` is 32 + 68 + 68/6 = 111.
+        let status_message_bytes = status_message().as_ssz_bytes();
 
         let mut uvi_codec: Uvi<usize> = Uvi::default();
         let mut dst = BytesMut::with_capacity(1024);
@@ -1,9 +1,10 @@
 //! Available RPC methods types and ids.
 
+use ethereum_types::Address;
 use regex::bytes::Regex;
 use ssz_derive::{Decode, Encode};
 use ssz_types::{
-    typenum::{U1024, U256},
+    typenum::{U1024, U1048576, U256},
     VariableList,
 };
 use std::ops::Deref;
@@ -21,16 +22,37 @@ pub const MAX_REQUEST_BLOCKS: u64 = 1024;
 pub type MaxErrorLen = U256;
 pub const MAX_ERROR_LEN: u64 = 256;
 
-/// Maximum length of data message.
+/// Maximum length of data message, e.g. the file data inlined in `IonianData`.
 pub type MaxDataLen = U256;
 pub const MAX_DATA_LEN: u64 = 256;
 
 // Maximum length of GetChunksResponse chunk data.
 pub const MAX_CHUNKS_LENGTH: usize = 10 * 1024 * 1024; // 10M
 
+/// Maximum number of segments in a file availability response.
+pub type MaxSegments = U1048576;
+pub const MAX_SEGMENTS: usize = 1024 * 1024;
+
+/// Number of chunks in a segment of file availability, so a file of `MAX_SEGMENTS` segments
+/// is 256G at most.
+pub const CHUNKS_PER_SEGMENT: usize = 1024;
+
+/// The latest transaction of a data root, which is the response of `DataByHash`.
 #[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
 pub struct IonianData {
+    /// The requested data root.
     pub hash: Hash256,
+
+    pub tx_id: TxID,
+
+    /// The file size in bytes.
+    pub size: u64,
+
+    /// Whether the file is finalized on the responding peer.
+    pub finalized: bool,
+
+    /// The file data if finalized and no larger than `MAX_DATA_LEN`, otherwise empty.
+    pub data: VariableList<u8, MaxDataLen>,
 }
 
 /// Wrapper over SSZ List to represent error message in rpc responses.
@@ -71,7 +93,17 @@ impl ToString for ErrorType {
 /// The STATUS request/response handshake message.
 #[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
 pub struct StatusMessage {
-    pub data: u64,
+    /// The network that the node belongs to, e.g. the chain id.
+    pub network_id: u64,
+
+    /// The address of the flow contract that the node syncs log entries from.
+    pub flow_address: Address,
+
+    /// The next transaction sequence number to sync from blockchain.
+    pub next_tx_seq: u64,
+
+    /// The merkle root of the flow that the node synced.
+    pub flow_root: Hash256,
 }
 
 /// The PING request/response message.
@@ -171,10 +203,13 @@ impl ssz::Decode for GoodbyeReason {
     }
 }
 
-/// Request a number of beacon block bodies from a peer.
+/// Request the latest transactions of a number of data roots from a peer.
+///
+/// The peer responds an `IonianData` for each data root that it knows, so unknown data
+/// roots are omitted.
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct DataByHashRequest {
-    /// The list of beacon block bodies being requested.
+    /// The list of data roots being requested.
     pub hashes: VariableList<Hash256, MaxRequestBlocks>,
 }
 
@@ -186,6 +221,41 @@ pub struct GetChunksRequest {
     pub index_end: u64,
 }
 
+/// Request the available segments of a file from a peer.
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct GetFileAvailabilityRequest {
+    pub tx_id: TxID,
+}
+
+/// The segments of a file that are available on a peer, each of which contains
+/// `CHUNKS_PER_SEGMENT` chunks except the last one.
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct FileAvailability {
+    pub tx_id: TxID,
+
+    /// Whether the file is finalized, in which case all segments are available.
+    pub finalized: bool,
+
+    pub segments: BitList<MaxSegments>,
+}
+
+impl FileAvailability {
+    /// Returns whether all the chunks in range `[index_start, index_end)` are available.
+    pub fn has_chunks(&self, index_start: u64, index_end: u64) -> bool {
+        if self.finalized {
+            return true;
+        }
+
+        if index_start >= index_end {
+            return false;
+        }
+
+        let first = index_start as usize / CHUNKS_PER_SEGMENT;
+        let last = (index_end as usize - 1) / CHUNKS_PER_SEGMENT;
+        (first..=last).all(|i| self.segments.get(i).unwrap_or(false))
+    }
+}
+
 /* RPC Handling and Grouping */
 // Collection of enums and structs used by the Codecs to encode/decode RPC messages
 
@@ -202,6 +272,9 @@ pub enum RPCResponse {
 
     /// A response to a GET_CHUNKS request.
     Chunks(ChunkArrayWithProof),
+
+    /// A response to a GET_FILE_AVAILABILITY request.
+    FileAvailability(FileAvailability),
 }
 
 /// Indicates which response is being terminated by a stream termination response.
@@ -271,6 +344,7 @@ impl RPCCodedResponse {
                 RPCResponse::Pong(_) => false,
                 RPCResponse::DataByHash(_) => true,
                 RPCResponse::Chunks(_) => false,
+                RPCResponse::FileAvailability(_) => false,
             },
             RPCCodedResponse::Error(_, _) => true,
             // Stream terminations are part of responses that have chunks
@@ -311,7 +385,11 @@ impl std::fmt::Display for RPCResponseErrorCode {
 
 impl std::fmt::Display for StatusMessage {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Status Message: Data: {:?}", self.data)
+        write!(
+            f,
+            "Status Message: Network: {}, Flow: {:?}, Next Tx Seq: {}, Flow Root: {:?}",
+            self.network_id, self.flow_address, self.next_tx_seq, self.flow_root
+        )
     }
 }
 
@@ -321,7 +399,11 @@ impl std::fmt::Display for RPCResponse {
             RPCResponse::Status(status) => write!(f, "{}", status),
             RPCResponse::Pong(ping) => write!(f, "Pong: {}", ping.data),
             RPCResponse::DataByHash(data) => {
-                write!(f, "DataByHash: Hash: {:?}", data.hash)
+                write!(
+                    f,
+                    "DataByHash: Hash: {:?}, Tx: {:?}, Finalized: {}",
+                    data.hash, data.tx_id, data.finalized
+                )
             }
             RPCResponse::Chunks(data) => {
                 write!(
@@ -330,6 +412,15 @@ impl std::fmt::Display for RPCResponse {
                     data.chunks.data.len()
                 )
             }
+            RPCResponse::FileAvailability(availability) => {
+                write!(
+                    f,
+                    "FileAvailability Response, tx: {:?}, finalized: {}, segments: {}",
+                    availability.tx_id,
+                    availability.finalized,
+                    availability.segments.num_set_bits()
+                )
+            }
         }
     }
 }
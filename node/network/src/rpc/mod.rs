@@ -12,7 +12,7 @@ use libp2p::swarm::{
     PollParameters, SubstreamProtocol,
 };
 use libp2p::PeerId;
-use rate_limiter::{RPCRateLimiter as RateLimiter, RPCRateLimiterBuilder, RateLimitedErr};
+use rate_limiter::{RPCRateLimiter as RateLimiter, RPCRateLimiterBuilder};
 use std::task::{Context, Poll};
 use std::time::Duration;
 
@@ -22,11 +22,13 @@ pub(crate) use protocol::{InboundRequest, RPCProtocol};
 
 pub use handler::SubstreamId;
 pub use methods::{
-    DataByHashRequest, GetChunksRequest, GoodbyeReason, IonianData, MaxRequestBlocks,
-    RPCResponseErrorCode, ResponseTermination, StatusMessage, MAX_REQUEST_BLOCKS,
+    DataByHashRequest, FileAvailability, GetChunksRequest, GetFileAvailabilityRequest,
+    GoodbyeReason, IonianData, MaxRequestBlocks, RPCResponseErrorCode, ResponseTermination,
+    StatusMessage, MAX_REQUEST_BLOCKS,
 };
 pub(crate) use outbound::OutboundRequest;
 pub use protocol::{max_rpc_size, Protocol, RPCError};
+pub use rate_limiter::{Limiter, Quota, RateLimitedErr};
 
 pub(crate) mod codec;
 mod handler;
@@ -119,6 +121,7 @@ impl<Id: ReqId> RPC<Id> {
             .one_every(Protocol::Goodbye, Duration::from_secs(10))
             .n_every(Protocol::DataByHash, 128, Duration::from_secs(10))
             .n_every(Protocol::GetChunks, 128, Duration::from_secs(10))
+            .n_every(Protocol::GetFileAvailability, 128, Duration::from_secs(10))
             .build()
             .expect("Configuration parameters are valid");
         RPC {
@@ -35,6 +35,7 @@ pub enum OutboundRequest {
     Ping(Ping),
     DataByHash(DataByHashRequest),
     GetChunks(GetChunksRequest),
+    GetFileAvailability(GetFileAvailabilityRequest),
 }
 
 impl UpgradeInfo for OutboundRequestContainer {
@@ -77,6 +78,11 @@ impl OutboundRequest {
                 Version::V1,
                 Encoding::SSZSnappy,
             )],
+            OutboundRequest::GetFileAvailability(_) => vec![ProtocolId::new(
+                Protocol::GetFileAvailability,
+                Version::V1,
+                Encoding::SSZSnappy,
+            )],
         }
     }
 
@@ -90,6 +96,7 @@ impl OutboundRequest {
             OutboundRequest::Ping(_) => 1,
             OutboundRequest::DataByHash(req) => req.hashes.len() as u64,
             OutboundRequest::GetChunks(_) => 1,
+            OutboundRequest::GetFileAvailability(_) => 1,
         }
     }
 
@@ -101,6 +108,7 @@ impl OutboundRequest {
             OutboundRequest::Ping(_) => Protocol::Ping,
             OutboundRequest::DataByHash(_) => Protocol::DataByHash,
             OutboundRequest::GetChunks(_) => Protocol::GetChunks,
+            OutboundRequest::GetFileAvailability(_) => Protocol::GetFileAvailability,
         }
     }
 
@@ -115,6 +123,7 @@ impl OutboundRequest {
             OutboundRequest::Goodbye(_) => unreachable!(),
             OutboundRequest::Ping(_) => unreachable!(),
             OutboundRequest::GetChunks(_) => unreachable!(),
+            OutboundRequest::GetFileAvailability(_) => unreachable!(),
         }
     }
 }
@@ -173,6 +182,9 @@ impl std::fmt::Display for OutboundRequest {
             OutboundRequest::GetChunks(req) => {
                 write!(f, "GetChunks: {:?}", req)
             }
+            OutboundRequest::GetFileAvailability(req) => {
+                write!(f, "GetFileAvailability: {:?}", req)
+            }
         }
     }
 }
@@ -1,7 +1,7 @@
 use super::methods::*;
 use crate::rpc::{
     codec::{base::BaseInboundCodec, ssz_snappy::SSZSnappyInboundCodec, InboundCodec},
-    methods::{MaxErrorLen, ResponseTermination, MAX_CHUNKS_LENGTH, MAX_ERROR_LEN},
+    methods::{MaxErrorLen, ResponseTermination, MAX_CHUNKS_LENGTH, MAX_DATA_LEN, MAX_ERROR_LEN},
     MaxRequestBlocks, MAX_REQUEST_BLOCKS,
 };
 use futures::future::BoxFuture;
@@ -34,6 +34,24 @@ lazy_static! {
         ])
         .as_ssz_bytes()
         .len();
+    pub static ref DATA_BY_HASH_RESPONSE_MIN: usize = IonianData {
+        hash: Hash256::zero(),
+        tx_id: Default::default(),
+        size: 0,
+        finalized: false,
+        data: VariableList::empty(),
+    }
+    .as_ssz_bytes()
+    .len();
+    pub static ref DATA_BY_HASH_RESPONSE_MAX: usize = IonianData {
+        hash: Hash256::zero(),
+        tx_id: Default::default(),
+        size: 0,
+        finalized: false,
+        data: VariableList::from(vec![0u8; MAX_DATA_LEN as usize]),
+    }
+    .as_ssz_bytes()
+    .len();
     pub static ref ERROR_TYPE_MIN: usize = VariableList::<u8, MaxErrorLen>::from(Vec::<u8>::new())
         .as_ssz_bytes()
         .len();
@@ -60,6 +78,20 @@ lazy_static! {
     }
     .as_ssz_bytes()
     .len();
+    pub static ref FILE_AVAILABILITY_RESPONSE_MIN: usize = FileAvailability {
+        tx_id: Default::default(),
+        finalized: false,
+        segments: BitList::with_capacity(0).expect("Valid capacity"),
+    }
+    .as_ssz_bytes()
+    .len();
+    pub static ref FILE_AVAILABILITY_RESPONSE_MAX: usize = FileAvailability {
+        tx_id: Default::default(),
+        finalized: false,
+        segments: BitList::with_capacity(MAX_SEGMENTS).expect("Valid capacity"),
+    }
+    .as_ssz_bytes()
+    .len();
 }
 
 // /// The maximum bytes that can be sent across the RPC pre-merge.
@@ -93,6 +125,9 @@ pub enum Protocol {
 
     /// The Chunk sync protocol.
     GetChunks,
+
+    /// The protocol to query the available segments of a file.
+    GetFileAvailability,
 }
 
 /// RPC Versions
@@ -116,6 +151,7 @@ impl std::fmt::Display for Protocol {
             Protocol::Ping => "ping",
             Protocol::DataByHash => "data_by_hash",
             Protocol::GetChunks => "get_chunks",
+            Protocol::GetFileAvailability => "get_file_availability",
         };
         f.write_str(repr)
     }
@@ -156,6 +192,11 @@ impl UpgradeInfo for RPCProtocol {
             ProtocolId::new(Protocol::Ping, Version::V1, Encoding::SSZSnappy),
             ProtocolId::new(Protocol::DataByHash, Version::V1, Encoding::SSZSnappy),
             ProtocolId::new(Protocol::GetChunks, Version::V1, Encoding::SSZSnappy),
+            ProtocolId::new(
+                Protocol::GetFileAvailability,
+                Version::V1,
+                Encoding::SSZSnappy,
+            ),
         ]
     }
 }
@@ -220,6 +261,10 @@ impl ProtocolId {
                 <GetChunksRequest as Encode>::ssz_fixed_len(),
                 <GetChunksRequest as Encode>::ssz_fixed_len(),
             ),
+            Protocol::GetFileAvailability => RpcLimits::new(
+                <GetFileAvailabilityRequest as Encode>::ssz_fixed_len(),
+                <GetFileAvailabilityRequest as Encode>::ssz_fixed_len(),
+            ),
         }
     }
 
@@ -237,13 +282,16 @@ impl ProtocolId {
                 <Ping as Encode>::ssz_fixed_len(),
             ),
 
-            // TODO(ionian-dev): not fixed size?
-            Protocol::DataByHash => RpcLimits::new(
-                <IonianData as Encode>::ssz_fixed_len(),
-                <IonianData as Encode>::ssz_fixed_len(),
-            ),
+            Protocol::DataByHash => {
+                RpcLimits::new(*DATA_BY_HASH_RESPONSE_MIN, *DATA_BY_HASH_RESPONSE_MAX)
+            }
 
             Protocol::GetChunks => RpcLimits::new(*CHUNKS_RESPONSE_MIN, *CHUNKS_RESPONSE_MAX),
+
+            Protocol::GetFileAvailability => RpcLimits::new(
+                *FILE_AVAILABILITY_RESPONSE_MIN,
+                *FILE_AVAILABILITY_RESPONSE_MAX,
+            ),
         }
     }
 }
@@ -326,6 +374,7 @@ pub enum InboundRequest {
     Ping(Ping),
     DataByHash(DataByHashRequest),
     GetChunks(GetChunksRequest),
+    GetFileAvailability(GetFileAvailabilityRequest),
 }
 
 impl UpgradeInfo for InboundRequest {
@@ -368,6 +417,11 @@ impl InboundRequest {
                 Version::V1,
                 Encoding::SSZSnappy,
             )],
+            InboundRequest::GetFileAvailability(_) => vec![ProtocolId::new(
+                Protocol::GetFileAvailability,
+                Version::V1,
+                Encoding::SSZSnappy,
+            )],
         }
     }
 
@@ -381,6 +435,7 @@ impl InboundRequest {
             InboundRequest::DataByHash(req) => req.hashes.len() as u64,
             InboundRequest::Ping(_) => 1,
             InboundRequest::GetChunks(_) => 1,
+            InboundRequest::GetFileAvailability(_) => 1,
         }
     }
 
@@ -392,6 +447,7 @@ impl InboundRequest {
             InboundRequest::Ping(_) => Protocol::Ping,
             InboundRequest::DataByHash(_) => Protocol::DataByHash,
             InboundRequest::GetChunks(_) => Protocol::GetChunks,
+            InboundRequest::GetFileAvailability(_) => Protocol::GetFileAvailability,
         }
     }
 
@@ -406,6 +462,7 @@ impl InboundRequest {
             InboundRequest::Goodbye(_) => unreachable!(),
             InboundRequest::Ping(_) => unreachable!(),
             InboundRequest::GetChunks(_) => unreachable!(),
+            InboundRequest::GetFileAvailability(_) => unreachable!(),
         }
     }
 }
@@ -526,6 +583,9 @@ impl std::fmt::Display for InboundRequest {
             InboundRequest::GetChunks(req) => {
                 write!(f, "Get Chunks: {:?}", req)
             }
+            InboundRequest::GetFileAvailability(req) => {
+                write!(f, "Get File Availability: {:?}", req)
+            }
         }
     }
 }
@@ -54,6 +54,16 @@ pub struct Quota {
     max_tokens: u64,
 }
 
+impl Quota {
+    /// Allow `n` tokens to be used every `time_period`.
+    pub fn n_every(n: u64, time_period: Duration) -> Self {
+        Quota {
+            replenish_all_every: time_period,
+            max_tokens: n,
+        }
+    }
+}
+
 /// Manages rate limiting of requests per peer, with differentiated rates per protocol.
 pub struct RPCRateLimiter {
     /// Interval to prune peers for which their timer ran out.
@@ -70,6 +80,8 @@ pub struct RPCRateLimiter {
     data_by_hash_rl: Limiter<PeerId>,
     /// GetChunks rate limiter.
     get_chunks_rl: Limiter<PeerId>,
+    /// GetFileAvailability rate limiter.
+    get_file_availability_rl: Limiter<PeerId>,
 }
 
 /// Error type for non conformant requests
@@ -93,6 +105,8 @@ pub struct RPCRateLimiterBuilder {
     data_by_hash_quota: Option<Quota>,
     /// Quota for the GetChunks protocol.
     get_chunks_quota: Option<Quota>,
+    /// Quota for the GetFileAvailability protocol.
+    get_file_availability_quota: Option<Quota>,
 }
 
 impl RPCRateLimiterBuilder {
@@ -110,6 +124,7 @@ impl RPCRateLimiterBuilder {
             Protocol::Goodbye => self.goodbye_quota = q,
             Protocol::DataByHash => self.data_by_hash_quota = q,
             Protocol::GetChunks => self.get_chunks_quota = q,
+            Protocol::GetFileAvailability => self.get_file_availability_quota = q,
         }
         self
     }
@@ -148,6 +163,9 @@ impl RPCRateLimiterBuilder {
         let get_chunks_quota = self
             .get_chunks_quota
             .ok_or("GetChunks quota not specified")?;
+        let get_file_availability_quota = self
+            .get_file_availability_quota
+            .ok_or("GetFileAvailability quota not specified")?;
 
         // create the rate limiters
         let ping_rl = Limiter::from_quota(ping_quota)?;
@@ -155,6 +173,7 @@ impl RPCRateLimiterBuilder {
         let goodbye_rl = Limiter::from_quota(goodbye_quota)?;
         let data_by_hash_rl = Limiter::from_quota(data_by_hash_quota)?;
         let get_chunks_rl = Limiter::from_quota(get_chunks_quota)?;
+        let get_file_availability_rl = Limiter::from_quota(get_file_availability_quota)?;
 
         // check for peers to prune every 30 seconds, starting in 30 seconds
         let prune_every = tokio::time::Duration::from_secs(30);
@@ -167,6 +186,7 @@ impl RPCRateLimiterBuilder {
             goodbye_rl,
             data_by_hash_rl,
             get_chunks_rl,
+            get_file_availability_rl,
             init_time: Instant::now(),
         })
     }
@@ -211,6 +231,7 @@ impl RPCRateLimiter {
             Protocol::Goodbye => &mut self.goodbye_rl,
             Protocol::DataByHash => &mut self.data_by_hash_rl,
             Protocol::GetChunks => &mut self.get_chunks_rl,
+            Protocol::GetFileAvailability => &mut self.get_file_availability_rl,
         };
         check(limiter)
     }
@@ -222,6 +243,7 @@ impl RPCRateLimiter {
         self.goodbye_rl.prune(time_since_start);
         self.data_by_hash_rl.prune(time_since_start);
         self.get_chunks_rl.prune(time_since_start);
+        self.get_file_availability_rl.prune(time_since_start);
     }
 }
 
@@ -5,7 +5,7 @@ use crate::multiaddr::Protocol;
 use crate::rpc::{GoodbyeReason, RPCResponseErrorCode, ReqId};
 use crate::types::{error, GossipKind};
 use crate::EnrExt;
-use crate::{NetworkConfig, NetworkGlobals, PeerAction, ReportSource};
+use crate::{Enr, NetworkConfig, NetworkGlobals, PeerAction, ReportSource};
 use futures::prelude::*;
 use libp2p::core::{
     identity::Keypair, multiaddr::Multiaddr, muxing::StreamMuxerBox, transport::Boxed,
@@ -291,6 +291,35 @@ impl<AppReqId: ReqId> Service<AppReqId> {
             .goodbye_peer(peer_id, reason, source);
     }
 
+    /// Ban a peer at the request of the operator.
+    pub fn ban_peer(&mut self, peer_id: &PeerId) {
+        self.swarm
+            .behaviour_mut()
+            .peer_manager_mut()
+            .ban_peer(peer_id);
+    }
+
+    /// Unban a peer at the request of the operator.
+    pub fn unban_peer(&mut self, peer_id: &PeerId) {
+        self.swarm
+            .behaviour_mut()
+            .peer_manager_mut()
+            .unban_peer(peer_id);
+    }
+
+    /// Add a trusted peer at the request of the operator.
+    pub fn add_trusted_peer(&mut self, peer_id: &PeerId) {
+        self.swarm
+            .behaviour_mut()
+            .peer_manager_mut()
+            .add_trusted_peer(peer_id);
+    }
+
+    /// Add an ENR to the routing table of discovery.
+    pub fn add_enr(&mut self, enr: Enr) {
+        self.swarm.behaviour_mut().add_enr(enr);
+    }
+
     /// Sends a response to a peer's request.
     pub fn send_response(&mut self, peer_id: PeerId, id: PeerRequestId, response: Response) {
         self.swarm
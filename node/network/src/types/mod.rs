@@ -6,5 +6,9 @@ mod topics;
 pub type Enr = discv5::enr::Enr<discv5::enr::CombinedKey>;
 
 pub use globals::NetworkGlobals;
-pub use pubsub::{AnnounceFile, FindFile, PubsubMessage, SignedAnnounceFile, SnappyTransform};
-pub use topics::{GossipEncoding, GossipKind, GossipTopic, CORE_TOPICS};
+pub use pubsub::{
+    AnnounceFile, AnnounceFiles, AnnounceShardConfig, FindFile, FlowRange, PubsubMessage,
+    ShardConfig, SignedAnnounceFile, SignedAnnounceFiles, SignedAnnounceShardConfig,
+    SnappyTransform, MAX_ANNOUNCE_FILES,
+};
+pub use topics::{GossipEncoding, GossipKind, GossipTopic, CORE_TOPICS, DEFAULT_TOPIC_NAMESPACE};
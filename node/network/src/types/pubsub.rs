@@ -1,5 +1,6 @@
 //! Handles the encoding and decoding of pubsub messages.
 
+use crate::rpc::methods::CHUNKS_PER_SEGMENT;
 use crate::types::{GossipEncoding, GossipKind, GossipTopic};
 use crate::{Keypair, PublicKey, SigningError, TopicHash};
 use libp2p::{
@@ -163,11 +164,169 @@ impl Deref for SignedAnnounceFile {
     }
 }
 
+/// The maximum number of files announced in a single `AnnounceFiles` message.
+pub const MAX_ANNOUNCE_FILES: usize = 256;
+
+/// Announces a batch of files stored by a node, so as to reduce the gossip messages
+/// when many files finalized in a short time, e.g. during bulk sync.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Encode, Decode)]
+pub struct AnnounceFiles {
+    pub tx_ids: Vec<TxID>,
+    pub peer_id: WrappedPeerId,
+    pub at: WrappedMultiaddr,
+    pub timestamp: u32,
+}
+
+impl AnnounceFiles {
+    pub fn into_signed(self, keypair: &Keypair) -> Result<SignedAnnounceFiles, SigningError> {
+        let raw = self.as_ssz_bytes();
+        let signature = keypair.sign(&raw)?;
+
+        Ok(SignedAnnounceFiles {
+            inner: self,
+            signature,
+            resend_timestamp: 0,
+        })
+    }
+
+    pub fn is_valid(&self) -> bool {
+        !self.tx_ids.is_empty() && self.tx_ids.len() <= MAX_ANNOUNCE_FILES
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Encode, Decode)]
+pub struct SignedAnnounceFiles {
+    pub inner: AnnounceFiles,
+    pub signature: Vec<u8>,
+    pub resend_timestamp: u32,
+}
+
+impl SignedAnnounceFiles {
+    pub fn verify_signature(&self, public_key: &PublicKey) -> bool {
+        let raw = self.inner.as_ssz_bytes();
+        public_key.verify(&raw, &self.signature)
+    }
+}
+
+impl Deref for SignedAnnounceFiles {
+    type Target = AnnounceFiles;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+/// A range of flow entries `[start, end)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Encode, Decode)]
+pub struct FlowRange {
+    pub start: u64,
+    pub end: u64,
+}
+
+/// The flow entries that a node stores long-term.
+///
+/// The flow is split into segments of `CHUNKS_PER_SEGMENT` entries, and a sharded node
+/// stores the segments where `segment_index % num_shard == shard_id`. Besides, the node
+/// also stores the specified flow ranges.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Encode, Decode)]
+pub struct ShardConfig {
+    pub shard_id: u64,
+    /// The number of shards, where 0 means no shard stored.
+    pub num_shard: u64,
+    pub flow_ranges: Vec<FlowRange>,
+}
+
+impl ShardConfig {
+    /// Returns true if nothing is declared to be stored.
+    pub fn is_empty(&self) -> bool {
+        self.num_shard == 0 && self.flow_ranges.is_empty()
+    }
+
+    pub fn is_valid(&self) -> bool {
+        (self.num_shard == 0 || self.shard_id < self.num_shard)
+            && self.flow_ranges.iter().all(|r| r.start < r.end)
+    }
+
+    /// Returns true if all the flow entries in range `[start, end)` are stored.
+    pub fn covers(&self, start: u64, end: u64) -> bool {
+        if start >= end || !self.is_valid() {
+            return false;
+        }
+
+        let segment_size = CHUNKS_PER_SEGMENT as u64;
+        let mut next = start;
+
+        while next < end {
+            if let Some(range) = self
+                .flow_ranges
+                .iter()
+                .find(|r| r.start <= next && next < r.end)
+            {
+                next = range.end;
+            } else if self.num_shard == 1 {
+                return true;
+            } else if self.num_shard > 0 && (next / segment_size) % self.num_shard == self.shard_id
+            {
+                next = (next / segment_size + 1) * segment_size;
+            } else {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Encode, Decode)]
+pub struct AnnounceShardConfig {
+    pub config: ShardConfig,
+    pub peer_id: WrappedPeerId,
+    pub at: WrappedMultiaddr,
+    pub timestamp: u32,
+}
+
+impl AnnounceShardConfig {
+    pub fn into_signed(self, keypair: &Keypair) -> Result<SignedAnnounceShardConfig, SigningError> {
+        let raw = self.as_ssz_bytes();
+        let signature = keypair.sign(&raw)?;
+
+        Ok(SignedAnnounceShardConfig {
+            inner: self,
+            signature,
+            resend_timestamp: 0,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Encode, Decode)]
+pub struct SignedAnnounceShardConfig {
+    pub inner: AnnounceShardConfig,
+    pub signature: Vec<u8>,
+    pub resend_timestamp: u32,
+}
+
+impl SignedAnnounceShardConfig {
+    pub fn verify_signature(&self, public_key: &PublicKey) -> bool {
+        let raw = self.inner.as_ssz_bytes();
+        public_key.verify(&raw, &self.signature)
+    }
+}
+
+impl Deref for SignedAnnounceShardConfig {
+    type Target = AnnounceShardConfig;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum PubsubMessage {
     ExampleMessage(u64),
     FindFile(FindFile),
     AnnounceFile(SignedAnnounceFile),
+    AnnounceFiles(SignedAnnounceFiles),
+    AnnounceShardConfig(SignedAnnounceShardConfig),
 }
 
 // Implements the `DataTransform` trait of gossipsub to employ snappy compression
@@ -232,9 +391,9 @@ impl DataTransform for SnappyTransform {
 
 impl PubsubMessage {
     /// Returns the topics that each pubsub message will be sent across, given a supported
-    /// gossipsub encoding and fork version.
-    pub fn topics(&self, encoding: GossipEncoding) -> Vec<GossipTopic> {
-        vec![GossipTopic::new(self.kind(), encoding)]
+    /// gossipsub encoding and network namespace.
+    pub fn topics(&self, encoding: GossipEncoding, namespace: &str) -> Vec<GossipTopic> {
+        vec![GossipTopic::new(self.kind(), encoding, namespace)]
     }
 
     /// Returns the kind of gossipsub topic associated with the message.
@@ -243,6 +402,8 @@ impl PubsubMessage {
             PubsubMessage::ExampleMessage(_) => GossipKind::Example,
             PubsubMessage::FindFile(_) => GossipKind::FindFile,
             PubsubMessage::AnnounceFile(_) => GossipKind::AnnounceFile,
+            PubsubMessage::AnnounceFiles(_) => GossipKind::AnnounceFiles,
+            PubsubMessage::AnnounceShardConfig(_) => GossipKind::AnnounceShardConfig,
         }
     }
 
@@ -250,8 +411,8 @@ impl PubsubMessage {
     /* Note: This is assuming we are not hashing topics. If we choose to hash topics, these will
      * need to be modified.
      */
-    pub fn decode(topic: &TopicHash, data: &[u8]) -> Result<Self, String> {
-        match GossipTopic::decode(topic.as_str()) {
+    pub fn decode(topic: &TopicHash, data: &[u8], namespace: &str) -> Result<Self, String> {
+        match GossipTopic::decode(topic.as_str(), namespace) {
             Err(_) => Err(format!("Unknown gossipsub topic: {:?}", topic)),
             Ok(gossip_topic) => {
                 // All topics are currently expected to be compressed and decompressed with snappy.
@@ -270,6 +431,14 @@ impl PubsubMessage {
                     GossipKind::AnnounceFile => Ok(PubsubMessage::AnnounceFile(
                         SignedAnnounceFile::from_ssz_bytes(data).map_err(|e| format!("{:?}", e))?,
                     )),
+                    GossipKind::AnnounceFiles => Ok(PubsubMessage::AnnounceFiles(
+                        SignedAnnounceFiles::from_ssz_bytes(data)
+                            .map_err(|e| format!("{:?}", e))?,
+                    )),
+                    GossipKind::AnnounceShardConfig => Ok(PubsubMessage::AnnounceShardConfig(
+                        SignedAnnounceShardConfig::from_ssz_bytes(data)
+                            .map_err(|e| format!("{:?}", e))?,
+                    )),
                 }
             }
         }
@@ -286,6 +455,8 @@ impl PubsubMessage {
             PubsubMessage::ExampleMessage(data) => data.as_ssz_bytes(),
             PubsubMessage::FindFile(data) => data.as_ssz_bytes(),
             PubsubMessage::AnnounceFile(data) => data.as_ssz_bytes(),
+            PubsubMessage::AnnounceFiles(data) => data.as_ssz_bytes(),
+            PubsubMessage::AnnounceShardConfig(data) => data.as_ssz_bytes(),
         }
     }
 }
@@ -302,6 +473,85 @@ impl std::fmt::Display for PubsubMessage {
             PubsubMessage::AnnounceFile(msg) => {
                 write!(f, "AnnounceFile message: {:?}", msg)
             }
+            PubsubMessage::AnnounceFiles(msg) => {
+                write!(f, "AnnounceFiles message: {:?}", msg)
+            }
+            PubsubMessage::AnnounceShardConfig(msg) => {
+                write!(f, "AnnounceShardConfig message: {:?}", msg)
+            }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flow_range(start: u64, end: u64) -> FlowRange {
+        FlowRange { start, end }
+    }
+
+    #[test]
+    fn test_shard_config_covers() {
+        let segment = CHUNKS_PER_SEGMENT as u64;
+
+        assert!(!ShardConfig::default().covers(0, 1));
+
+        let all = ShardConfig {
+            shard_id: 0,
+            num_shard: 1,
+            flow_ranges: vec![],
+        };
+        assert!(all.covers(0, 100 * segment));
+
+        // stores the segments 1, 3, 5, ... and the flow range [2, 3) in segments
+        let config = ShardConfig {
+            shard_id: 1,
+            num_shard: 2,
+            flow_ranges: vec![flow_range(2 * segment, 3 * segment)],
+        };
+        assert!(config.covers(segment, 4 * segment));
+        assert!(config.covers(segment + 1, segment + 2));
+        assert!(!config.covers(0, segment + 1));
+        assert!(!config.covers(3 * segment, 5 * segment));
+
+        // invalid shard id
+        let invalid = ShardConfig {
+            shard_id: 2,
+            num_shard: 2,
+            flow_ranges: vec![],
+        };
+        assert!(!invalid.covers(0, 1));
+    }
+
+    #[test]
+    fn test_announce_files_signature() {
+        let keypair = Keypair::generate_secp256k1();
+        let peer_id = keypair.public().to_peer_id();
+        let addr: Multiaddr = "/ip4/127.0.0.1/tcp/10000".parse().unwrap();
+
+        let tx_ids = (0..3)
+            .map(|seq| TxID {
+                seq,
+                hash: Default::default(),
+            })
+            .collect();
+        let msg = AnnounceFiles {
+            tx_ids,
+            peer_id: peer_id.into(),
+            at: addr.into(),
+            timestamp: 0,
+        };
+        assert!(msg.is_valid());
+
+        let mut signed = msg.into_signed(&keypair).unwrap();
+        assert!(signed.verify_signature(&keypair.public()));
+
+        // resend timestamp is not signed
+        signed.resend_timestamp = 1;
+        assert!(signed.verify_signature(&keypair.public()));
+
+        signed.inner.tx_ids.pop();
+        assert!(!signed.verify_signature(&keypair.public()));
+    }
+}
@@ -3,15 +3,24 @@ use serde_derive::{Deserialize, Serialize};
 use strum::AsRefStr;
 
 /// The gossipsub topic names.
-// These constants form a topic name of the form /TOPIC_PREFIX/TOPIC/ENCODING_POSTFIX
-// For example /eth2/beacon_block/ssz
-pub const TOPIC_PREFIX: &str = "eth2";
+// These constants form a topic name of the form /NAMESPACE/TOPIC/ENCODING_POSTFIX
+// For example /ionian/find_file/ssz_snappy
+//
+// The namespace isolates the gossip of different networks, e.g. testnet and mainnet.
+pub const DEFAULT_TOPIC_NAMESPACE: &str = "ionian";
 pub const SSZ_SNAPPY_ENCODING_POSTFIX: &str = "ssz_snappy";
 pub const EXAMPLE_TOPIC: &str = "example";
 pub const FIND_FILE_TOPIC: &str = "find_file";
 pub const ANNOUNCE_FILE_TOPIC: &str = "announce_file";
+pub const ANNOUNCE_FILES_TOPIC: &str = "announce_files";
+pub const ANNOUNCE_SHARD_CONFIG_TOPIC: &str = "announce_shard_config";
 
-pub const CORE_TOPICS: [GossipKind; 2] = [GossipKind::FindFile, GossipKind::AnnounceFile];
+pub const CORE_TOPICS: [GossipKind; 4] = [
+    GossipKind::FindFile,
+    GossipKind::AnnounceFile,
+    GossipKind::AnnounceFiles,
+    GossipKind::AnnounceShardConfig,
+];
 
 /// A gossipsub topic which encapsulates the type of messages that should be sent and received over
 /// the pubsub protocol and the way the messages should be encoded.
@@ -19,6 +28,8 @@ pub const CORE_TOPICS: [GossipKind; 2] = [GossipKind::FindFile, GossipKind::Anno
 pub struct GossipTopic {
     /// The encoding of the topic.
     encoding: GossipEncoding,
+    /// The namespace of the network that the topic belongs to.
+    namespace: String,
     /// The kind of topic.
     kind: GossipKind,
 }
@@ -31,6 +42,8 @@ pub enum GossipKind {
     Example,
     FindFile,
     AnnounceFile,
+    AnnounceFiles,
+    AnnounceShardConfig,
 }
 
 /// The known encoding types for gossipsub messages.
@@ -47,8 +60,12 @@ impl Default for GossipEncoding {
 }
 
 impl GossipTopic {
-    pub fn new(kind: GossipKind, encoding: GossipEncoding) -> Self {
-        GossipTopic { encoding, kind }
+    pub fn new(kind: GossipKind, encoding: GossipEncoding, namespace: &str) -> Self {
+        GossipTopic {
+            encoding,
+            namespace: namespace.to_string(),
+            kind,
+        }
     }
 
     /// Returns the encoding type for the gossipsub topic.
@@ -56,14 +73,21 @@ impl GossipTopic {
         &self.encoding
     }
 
+    /// Returns the namespace of the network that the gossipsub topic belongs to.
+    pub fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
     /// Returns the kind of message expected on the gossipsub topic.
     pub fn kind(&self) -> &GossipKind {
         &self.kind
     }
 
-    pub fn decode(topic: &str) -> Result<Self, String> {
+    /// Decodes the topic of the specified namespace, and topics of any other namespaces
+    /// are rejected.
+    pub fn decode(topic: &str, namespace: &str) -> Result<Self, String> {
         let topic_parts: Vec<&str> = topic.split('/').collect();
-        if topic_parts.len() == 4 && topic_parts[1] == TOPIC_PREFIX {
+        if topic_parts.len() == 4 && topic_parts[1] == namespace {
             let encoding = match topic_parts[3] {
                 SSZ_SNAPPY_ENCODING_POSTFIX => GossipEncoding::SSZSnappy,
                 _ => return Err(format!("Unknown encoding: {}", topic)),
@@ -73,10 +97,16 @@ impl GossipTopic {
                 EXAMPLE_TOPIC => GossipKind::Example,
                 FIND_FILE_TOPIC => GossipKind::FindFile,
                 ANNOUNCE_FILE_TOPIC => GossipKind::AnnounceFile,
+                ANNOUNCE_FILES_TOPIC => GossipKind::AnnounceFiles,
+                ANNOUNCE_SHARD_CONFIG_TOPIC => GossipKind::AnnounceShardConfig,
                 _ => return Err(format!("Unknown topic: {}", topic)),
             };
 
-            return Ok(GossipTopic { encoding, kind });
+            return Ok(GossipTopic {
+                encoding,
+                namespace: namespace.to_string(),
+                kind,
+            });
         }
 
         Err(format!("Unknown topic: {}", topic))
@@ -99,9 +129,11 @@ impl From<GossipTopic> for String {
             GossipKind::Example => EXAMPLE_TOPIC,
             GossipKind::FindFile => FIND_FILE_TOPIC,
             GossipKind::AnnounceFile => ANNOUNCE_FILE_TOPIC,
+            GossipKind::AnnounceFiles => ANNOUNCE_FILES_TOPIC,
+            GossipKind::AnnounceShardConfig => ANNOUNCE_SHARD_CONFIG_TOPIC,
         };
 
-        format!("/{}/{}/{}", TOPIC_PREFIX, kind, encoding)
+        format!("/{}/{}/{}", topic.namespace, kind, encoding)
     }
 }
 
@@ -115,9 +147,11 @@ impl std::fmt::Display for GossipTopic {
             GossipKind::Example => EXAMPLE_TOPIC,
             GossipKind::FindFile => FIND_FILE_TOPIC,
             GossipKind::AnnounceFile => ANNOUNCE_FILE_TOPIC,
+            GossipKind::AnnounceFiles => ANNOUNCE_FILES_TOPIC,
+            GossipKind::AnnounceShardConfig => ANNOUNCE_SHARD_CONFIG_TOPIC,
         };
 
-        write!(f, "/{}/{}/{}", TOPIC_PREFIX, kind, encoding)
+        write!(f, "/{}/{}/{}", self.namespace, kind, encoding)
     }
 }
 
@@ -136,7 +170,10 @@ mod tests {
         for encoding in [GossipEncoding::SSZSnappy].iter() {
             {
                 let kind = &Example;
-                topics.push(GossipTopic::new(kind.clone(), encoding.clone()).into());
+                topics.push(
+                    GossipTopic::new(kind.clone(), encoding.clone(), DEFAULT_TOPIC_NAMESPACE)
+                        .into(),
+                );
             }
         }
         topics
@@ -149,30 +186,47 @@ mod tests {
     #[test]
     fn test_decode() {
         for topic in topics().iter() {
-            assert!(GossipTopic::decode(topic.as_str()).is_ok());
+            assert!(GossipTopic::decode(topic.as_str(), DEFAULT_TOPIC_NAMESPACE).is_ok());
         }
     }
 
     #[test]
     fn test_decode_malicious() {
+        let decode = |topic: &str| GossipTopic::decode(topic, DEFAULT_TOPIC_NAMESPACE);
+
         let bad_prefix_str = create_topic(BAD_PREFIX, EXAMPLE_TOPIC, SSZ_SNAPPY_ENCODING_POSTFIX);
-        assert!(GossipTopic::decode(bad_prefix_str.as_str()).is_err());
+        assert!(decode(bad_prefix_str.as_str()).is_err());
 
-        let bad_kind_str = create_topic(TOPIC_PREFIX, BAD_KIND, SSZ_SNAPPY_ENCODING_POSTFIX);
-        assert!(GossipTopic::decode(bad_kind_str.as_str()).is_err());
+        let bad_kind_str = create_topic(
+            DEFAULT_TOPIC_NAMESPACE,
+            BAD_KIND,
+            SSZ_SNAPPY_ENCODING_POSTFIX,
+        );
+        assert!(decode(bad_kind_str.as_str()).is_err());
 
-        let bad_encoding_str = create_topic(TOPIC_PREFIX, EXAMPLE_TOPIC, BAD_ENCODING);
-        assert!(GossipTopic::decode(bad_encoding_str.as_str()).is_err());
+        let bad_encoding_str = create_topic(DEFAULT_TOPIC_NAMESPACE, EXAMPLE_TOPIC, BAD_ENCODING);
+        assert!(decode(bad_encoding_str.as_str()).is_err());
 
         // Extra parts
         assert!(
-            GossipTopic::decode("/eth2/beacon_block/ssz_snappy/yolo").is_err(),
+            decode("/ionian/find_file/ssz_snappy/yolo").is_err(),
             "should have exactly 4 parts"
         );
         // Empty string
-        assert!(GossipTopic::decode("").is_err());
+        assert!(decode("").is_err());
         // Empty parts
-        assert!(GossipTopic::decode("////").is_err());
+        assert!(decode("////").is_err());
+    }
+
+    #[test]
+    fn test_decode_namespace() {
+        let topic = GossipTopic::new(FindFile, GossipEncoding::SSZSnappy, "testnet");
+        let topic_str: String = topic.clone().into();
+        assert_eq!(topic_str, "/testnet/find_file/ssz_snappy");
+
+        assert_eq!(GossipTopic::decode(&topic_str, "testnet"), Ok(topic));
+        assert!(GossipTopic::decode(&topic_str, "mainnet").is_err());
+        assert!(GossipTopic::decode(&topic_str, DEFAULT_TOPIC_NAMESPACE).is_err());
     }
 
     #[test]
@@ -23,10 +23,20 @@ fn test_status_rpc() {
         let (mut sender, mut receiver) = common::build_node_pair(Arc::downgrade(&rt)).await;
 
         // Dummy STATUS RPC message
-        let rpc_request = Request::Status(StatusMessage { data: 2 });
+        let rpc_request = Request::Status(StatusMessage {
+            network_id: 1,
+            flow_address: Default::default(),
+            next_tx_seq: 2,
+            flow_root: Default::default(),
+        });
 
         // Dummy STATUS RPC message
-        let rpc_response = Response::Status(StatusMessage { data: 3 });
+        let rpc_response = Response::Status(StatusMessage {
+            network_id: 1,
+            flow_address: Default::default(),
+            next_tx_seq: 3,
+            flow_root: Default::default(),
+        });
 
         // build the sender future
         let sender_future = async {
@@ -117,6 +127,10 @@ fn test_data_by_hash_chunked_rpc() {
         // DataByHash Response
         let data = IonianData {
             hash: Hash256::from_low_u64_be(0),
+            tx_id: Default::default(),
+            size: 0,
+            finalized: false,
+            data: VariableList::empty(),
         };
         let rpc_response = Response::DataByHash(Some(Box::new(data)));
 
@@ -231,6 +245,10 @@ fn test_data_by_hash_chunked_rpc_terminates_correctly() {
         // DataByHash Response
         let data = IonianData {
             hash: Hash256::from_low_u64_be(0),
+            tx_id: Default::default(),
+            size: 0,
+            finalized: false,
+            data: VariableList::empty(),
         };
         let rpc_response = Response::DataByHash(Some(Box::new(data)));
 
@@ -4,6 +4,8 @@ extern crate tracing;
 mod peer_manager;
 mod service;
 
+use ethereum_types::H160;
+use network::types::ShardConfig;
 use network::Multiaddr;
 
 pub use crate::service::RouterService;
@@ -16,6 +18,21 @@ pub struct Config {
     pub max_idle_incoming_peers: usize,
     pub max_idle_outgoing_peers: usize,
     pub libp2p_nodes: Vec<Multiaddr>,
+
+    /// Local files finalized within the batching window are announced in a single
+    /// `AnnounceFiles` message, which includes at most `announce_files_batch_size` files.
+    pub announce_files_batch_interval_ms: u64,
+    pub announce_files_batch_size: usize,
+
+    /// Peers with different network id or flow contract address are disconnected
+    /// in the status handshake.
+    pub network_id: u64,
+    pub flow_address: H160,
+
+    /// The flow entries stored by this node long-term, which is announced to peers
+    /// periodically if not empty.
+    pub shard_config: ShardConfig,
+    pub announce_shard_config_interval_secs: u64,
 }
 
 impl Default for Config {
@@ -26,6 +43,12 @@ impl Default for Config {
             max_idle_incoming_peers: 12,
             max_idle_outgoing_peers: 20,
             libp2p_nodes: vec![],
+            announce_files_batch_interval_ms: 1000,
+            announce_files_batch_size: 64,
+            network_id: 1,
+            flow_address: H160::zero(),
+            shard_config: Default::default(),
+            announce_shard_config_interval_secs: 600,
         }
     }
 }
@@ -4,13 +4,17 @@ use file_location_cache::FileLocationCache;
 use futures::{channel::mpsc::Sender, prelude::*};
 use miner::MinerMessage;
 use network::{
-    rpc::StatusMessage,
-    types::{AnnounceFile, FindFile, SignedAnnounceFile},
-    BehaviourEvent, Keypair, Libp2pEvent, MessageAcceptance, MessageId, NetworkGlobals,
-    NetworkMessage, PeerId, PeerRequestId, PublicKey, PubsubMessage, Request, RequestId, Response,
-    Service as LibP2PService, Swarm,
+    rpc::{GoodbyeReason, RPCError, RPCResponseErrorCode, StatusMessage},
+    types::{
+        AnnounceFile, AnnounceFiles, AnnounceShardConfig, FindFile, SignedAnnounceFile,
+        SignedAnnounceFiles, SignedAnnounceShardConfig, MAX_ANNOUNCE_FILES,
+    },
+    BehaviourEvent, EnrExt, Keypair, Libp2pEvent, MessageAcceptance, MessageId, Multiaddr,
+    NetworkGlobals, NetworkMessage, PeerId, PeerRequestId, PublicKey, PubsubMessage, ReportSource,
+    Request, RequestId, Response, Service as LibP2PService, Swarm, SyncInfo, SyncStatus,
 };
 use shared_types::{timestamp_now, TxID};
+use std::cmp::Ordering;
 use std::time::Duration;
 use std::{ops::Neg, sync::Arc};
 use storage::log_store::Store as LogStore;
@@ -18,7 +22,7 @@ use storage_async::Store;
 use sync::{SyncMessage, SyncSender};
 use task_executor::ShutdownReason;
 use tokio::sync::{broadcast, mpsc, RwLock};
-use tokio::time::interval;
+use tokio::time::{interval, interval_at, Instant};
 
 pub fn peer_id_to_public_key(peer_id: &PeerId) -> Result<PublicKey, String> {
     // A libp2p peer id byte representation should be 2 length bytes + 4 protobuf bytes + compressed pk bytes
@@ -40,9 +44,30 @@ fn duration_since(timestamp: u32) -> chrono::Duration {
     now.signed_duration_since(timestamp)
 }
 
+/// Returns the sync status of a peer by comparing its status with the local one.
+fn peer_sync_status(status: &StatusMessage, local_status: &StatusMessage) -> SyncStatus {
+    if status.network_id != local_status.network_id
+        || status.flow_address != local_status.flow_address
+    {
+        return SyncStatus::IrrelevantPeer;
+    }
+
+    let info = SyncInfo {
+        next_tx_seq: status.next_tx_seq,
+        flow_root: status.flow_root,
+    };
+
+    match status.next_tx_seq.cmp(&local_status.next_tx_seq) {
+        Ordering::Less => SyncStatus::Behind { info },
+        Ordering::Equal => SyncStatus::Synced { info },
+        Ordering::Greater => SyncStatus::Advanced { info },
+    }
+}
+
 lazy_static::lazy_static! {
     pub static ref FIND_FILE_TIMEOUT: chrono::Duration = chrono::Duration::minutes(2);
     pub static ref ANNOUNCE_FILE_TIMEOUT: chrono::Duration = chrono::Duration::minutes(2);
+    pub static ref ANNOUNCE_SHARD_CONFIG_TIMEOUT: chrono::Duration = chrono::Duration::minutes(2);
     pub static ref TOLERABLE_DRIFT: chrono::Duration = chrono::Duration::seconds(5);
 }
 
@@ -80,6 +105,9 @@ pub struct RouterService {
 
     /// All connected peers.
     peers: PeerManager,
+
+    /// Local files to announce in batch.
+    pending_announcements: Vec<TxID>,
 }
 
 impl RouterService {
@@ -112,6 +140,7 @@ impl RouterService {
             file_location_cache,
             local_keypair,
             peers: PeerManager::new(config),
+            pending_announcements: vec![],
         };
 
         // spawn service
@@ -126,6 +155,14 @@ impl RouterService {
     async fn main(mut self, mut shutdown_sender: Sender<ShutdownReason>) {
         let mut heartbeat = interval(Duration::from_secs(self.config.heartbeat_interval_secs));
 
+        // announce shard config after listen address available
+        let period = Duration::from_secs(self.config.announce_shard_config_interval_secs);
+        let mut announce_shard_config = interval_at(Instant::now() + period, period);
+
+        let mut announce_files = interval(Duration::from_millis(
+            self.config.announce_files_batch_interval_ms,
+        ));
+
         loop {
             tokio::select! {
                 // handle a message sent to the network
@@ -136,6 +173,12 @@ impl RouterService {
 
                 // heartbeat
                 _ = heartbeat.tick() => self.on_heartbeat(),
+
+                // announce local files in batch
+                _ = announce_files.tick() => self.flush_pending_announcements(),
+
+                // announce the flow entries stored by this node
+                _ = announce_shard_config.tick() => self.on_announce_shard_config_tick(),
             }
         }
     }
@@ -169,10 +212,10 @@ impl RouterService {
         match ev {
             Libp2pEvent::Behaviour(event) => match event {
                 BehaviourEvent::PeerConnectedOutgoing(peer_id) => {
-                    self.on_peer_connected(peer_id, true);
+                    self.on_peer_connected(peer_id, true).await;
                 }
                 BehaviourEvent::PeerConnectedIncoming(peer_id) => {
-                    self.on_peer_connected(peer_id, false);
+                    self.on_peer_connected(peer_id, false).await;
                 }
                 BehaviourEvent::PeerBanned(_) | BehaviourEvent::PeerUnbanned(_) => {
                     // No action required for these events.
@@ -185,20 +228,20 @@ impl RouterService {
                     id,
                     request,
                 } => {
-                    self.on_rpc_request(peer_id, id, request);
+                    self.on_rpc_request(peer_id, id, request).await;
                 }
                 BehaviourEvent::ResponseReceived {
                     peer_id,
                     id,
                     response,
                 } => {
-                    self.on_rpc_response(peer_id, id, response);
+                    self.on_rpc_response(peer_id, id, response).await;
                 }
-                BehaviourEvent::RPCFailed { id, peer_id } => {
-                    self.on_rpc_error(peer_id, id);
+                BehaviourEvent::RPCFailed { id, peer_id, error } => {
+                    self.on_rpc_error(peer_id, id, error);
                 }
                 BehaviourEvent::StatusPeer(peer_id) => {
-                    self.send_status(peer_id);
+                    self.send_status(peer_id).await;
                 }
                 BehaviourEvent::PubsubMessage {
                     id,
@@ -317,19 +360,40 @@ impl RouterService {
                     };
                 }
             }
+            NetworkMessage::AddPeer { enr } => {
+                let addresses = enr.multiaddr_p2p_tcp();
+                self.libp2p.add_enr(*enr);
+
+                for address in addresses {
+                    self.dial_address(address);
+                }
+            }
+            NetworkMessage::DialAddress { address } => self.dial_address(address),
+            NetworkMessage::BanPeer { peer_id } => self.libp2p.ban_peer(&peer_id),
+            NetworkMessage::UnbanPeer { peer_id } => self.libp2p.unban_peer(&peer_id),
+            NetworkMessage::AddTrustedPeer { peer_id } => self.libp2p.add_trusted_peer(&peer_id),
             NetworkMessage::AnnounceLocalFile { tx_id } => {
-                if let Some(msg) = self.construct_announce_file_message(tx_id) {
-                    self.publish(msg);
+                self.pending_announcements.push(tx_id);
+
+                if self.pending_announcements.len() >= self.announce_files_batch_size() {
+                    self.flush_pending_announcements();
                 }
             }
         }
     }
 
-    fn on_peer_connected(&mut self, peer_id: PeerId, outgoing: bool) {
+    fn dial_address(&mut self, address: Multiaddr) {
+        match Swarm::dial(&mut self.libp2p.swarm, address.clone()) {
+            Ok(()) => debug!(%address, "Dialing libp2p peer"),
+            Err(err) => info!(%address, error = ?err, "Failed to dial peer"),
+        }
+    }
+
+    async fn on_peer_connected(&mut self, peer_id: PeerId, outgoing: bool) {
         self.peers.add(peer_id, outgoing);
 
         if outgoing {
-            self.send_status(peer_id);
+            self.send_status(peer_id).await;
             self.send_to_sync(SyncMessage::PeerConnected { peer_id });
         }
     }
@@ -339,7 +403,12 @@ impl RouterService {
         self.send_to_sync(SyncMessage::PeerDisconnected { peer_id });
     }
 
-    fn on_rpc_request(&mut self, peer_id: PeerId, request_id: PeerRequestId, request: Request) {
+    async fn on_rpc_request(
+        &mut self,
+        peer_id: PeerId,
+        request_id: PeerRequestId,
+        request: Request,
+    ) {
         if !self.network_globals.peers.read().is_connected(&peer_id) {
             debug!(%peer_id, ?request, "Dropping request of disconnected peer");
             return;
@@ -349,7 +418,7 @@ impl RouterService {
 
         match request {
             Request::Status(status) => {
-                self.on_status_request(peer_id, request_id, status);
+                self.on_status_request(peer_id, request_id, status).await;
             }
             Request::GetChunks(request) => {
                 self.send_to_sync(SyncMessage::RequestChunks {
@@ -358,18 +427,34 @@ impl RouterService {
                     request,
                 });
             }
-            Request::DataByHash(_) => {
-                // ignore
+            Request::GetFileAvailability(request) => {
+                self.send_to_sync(SyncMessage::RequestFileAvailability {
+                    peer_id,
+                    request_id,
+                    request,
+                });
+            }
+            Request::DataByHash(request) => {
+                self.send_to_sync(SyncMessage::RequestDataByHash {
+                    peer_id,
+                    request_id,
+                    request,
+                });
             }
         }
     }
 
-    fn on_rpc_response(&mut self, peer_id: PeerId, request_id: RequestId, response: Response) {
+    async fn on_rpc_response(
+        &mut self,
+        peer_id: PeerId,
+        request_id: RequestId,
+        response: Response,
+    ) {
         self.peers.update(&peer_id);
 
         match response {
             Response::Status(status_message) => {
-                self.on_status_response(peer_id, status_message);
+                self.on_status_response(peer_id, status_message).await;
             }
             Response::Chunks(response) => {
                 let request_id = match request_id {
@@ -383,13 +468,19 @@ impl RouterService {
                     response,
                 });
             }
-            Response::DataByHash(_) => {
-                // ignore
+            Response::FileAvailability(response) => {
+                self.send_to_sync(SyncMessage::FileAvailabilityResponse { peer_id, response });
+            }
+            Response::DataByHash(Some(response)) => {
+                self.send_to_sync(SyncMessage::DataByHashResponse { peer_id, response });
+            }
+            Response::DataByHash(None) => {
+                // end of the responses, which are handled as they come
             }
         }
     }
 
-    fn on_rpc_error(&mut self, peer_id: PeerId, request_id: RequestId) {
+    fn on_rpc_error(&mut self, peer_id: PeerId, request_id: RequestId, error: RPCError) {
         self.peers.update(&peer_id);
 
         // Check if the failed RPC belongs to sync
@@ -397,12 +488,34 @@ impl RouterService {
             self.send_to_sync(SyncMessage::RpcError {
                 peer_id,
                 request_id,
+                error,
             });
         }
     }
 
-    fn send_status(&mut self, peer_id: PeerId) {
-        let status_message = StatusMessage { data: 123 }; // dummy status message
+    async fn local_status(&self) -> Option<StatusMessage> {
+        let next_tx_seq = self.store.next_tx_seq();
+        let context = self.store.get_context();
+
+        match tokio::try_join!(next_tx_seq, context) {
+            Ok((next_tx_seq, (flow_root, _))) => Some(StatusMessage {
+                network_id: self.config.network_id,
+                flow_address: self.config.flow_address,
+                next_tx_seq,
+                flow_root,
+            }),
+            Err(err) => {
+                error!(%err, "Failed to get local status");
+                None
+            }
+        }
+    }
+
+    async fn send_status(&mut self, peer_id: PeerId) {
+        let status_message = match self.local_status().await {
+            Some(status) => status,
+            None => return,
+        };
         debug!(%peer_id, ?status_message, "Sending Status request");
 
         self.send_to_network(NetworkMessage::SendRequest {
@@ -412,7 +525,7 @@ impl RouterService {
         })
     }
 
-    fn on_status_request(
+    async fn on_status_request(
         &mut self,
         peer_id: PeerId,
         request_id: PeerRequestId,
@@ -420,18 +533,61 @@ impl RouterService {
     ) {
         debug!(%peer_id, ?status, "Received Status request");
 
-        let status_message = StatusMessage { data: 456 }; // dummy status message
-        debug!(%peer_id, ?status_message, "Sending Status response");
+        let local_status = match self.local_status().await {
+            Some(status) => status,
+            None => {
+                self.send_to_network(NetworkMessage::SendErrorResponse {
+                    peer_id,
+                    error: RPCResponseErrorCode::ServerError,
+                    reason: "Failed to get local status".into(),
+                    id: request_id,
+                });
+                return;
+            }
+        };
+        debug!(%peer_id, status_message = ?local_status, "Sending Status response");
 
         self.send_to_network(NetworkMessage::SendResponse {
             peer_id,
             id: request_id,
-            response: Response::Status(status_message),
+            response: Response::Status(local_status.clone()),
         });
+
+        self.on_status(peer_id, status, &local_status);
     }
 
-    pub fn on_status_response(&mut self, peer_id: PeerId, status: StatusMessage) {
+    pub async fn on_status_response(&mut self, peer_id: PeerId, status: StatusMessage) {
         debug!(%peer_id, ?status, "Received Status response");
+
+        if let Some(local_status) = self.local_status().await {
+            self.on_status(peer_id, status, &local_status);
+        }
+    }
+
+    /// Records the sync status of the peer for the network and sync services, and
+    /// disconnects the peer if in another network.
+    fn on_status(&mut self, peer_id: PeerId, status: StatusMessage, local_status: &StatusMessage) {
+        let sync_status = peer_sync_status(&status, local_status);
+
+        self.network_globals
+            .peers
+            .write()
+            .update_sync_status(&peer_id, sync_status.clone());
+
+        self.send_to_sync(SyncMessage::PeerSyncStatus {
+            peer_id,
+            status: sync_status.clone(),
+        });
+
+        if sync_status == SyncStatus::IrrelevantPeer {
+            info!(%peer_id, ?status, "Disconnect peer in irrelevant network");
+
+            self.send_to_network(NetworkMessage::GoodbyePeer {
+                peer_id,
+                reason: GoodbyeReason::IrrelevantNetwork,
+                source: ReportSource::RPC,
+            });
+        }
     }
 
     async fn on_pubsub_message(
@@ -447,6 +603,10 @@ impl RouterService {
             PubsubMessage::ExampleMessage(_) => MessageAcceptance::Ignore,
             PubsubMessage::FindFile(msg) => self.on_find_file(msg).await,
             PubsubMessage::AnnounceFile(msg) => self.on_announce_file(propagation_source, msg),
+            PubsubMessage::AnnounceFiles(msg) => self.on_announce_files(propagation_source, msg),
+            PubsubMessage::AnnounceShardConfig(msg) => {
+                self.on_announce_shard_config(propagation_source, msg)
+            }
         };
 
         self.libp2p
@@ -488,6 +648,113 @@ impl RouterService {
         Some(PubsubMessage::AnnounceFile(signed))
     }
 
+    fn construct_announce_files_message(&self, tx_ids: Vec<TxID>) -> Option<PubsubMessage> {
+        let peer_id = *self.network_globals.peer_id.read();
+
+        let addr = match self.network_globals.listen_multiaddrs.read().first() {
+            Some(addr) => addr.clone(),
+            None => {
+                error!("No listen address available");
+                return None;
+            }
+        };
+
+        let timestamp = timestamp_now();
+
+        let msg = AnnounceFiles {
+            tx_ids,
+            peer_id: peer_id.into(),
+            at: addr.into(),
+            timestamp,
+        };
+
+        let mut signed = match msg.into_signed(&self.local_keypair) {
+            Ok(signed) => signed,
+            Err(e) => {
+                error!(%e, "Failed to sign AnnounceFiles message");
+                return None;
+            }
+        };
+
+        signed.resend_timestamp = timestamp;
+
+        Some(PubsubMessage::AnnounceFiles(signed))
+    }
+
+    fn announce_files_batch_size(&self) -> usize {
+        self.config
+            .announce_files_batch_size
+            .clamp(1, MAX_ANNOUNCE_FILES)
+    }
+
+    /// Announces the pending local files, which are announced in `AnnounceFiles`
+    /// messages unless there is only one file.
+    fn flush_pending_announcements(&mut self) {
+        if self.pending_announcements.is_empty() {
+            return;
+        }
+
+        let batch_size = self.announce_files_batch_size();
+        let pending = std::mem::take(&mut self.pending_announcements);
+
+        for tx_ids in pending.chunks(batch_size) {
+            let msg = if tx_ids.len() == 1 {
+                self.construct_announce_file_message(tx_ids[0])
+            } else {
+                debug!(count = tx_ids.len(), "Announce local files in batch");
+                self.construct_announce_files_message(tx_ids.to_vec())
+            };
+
+            if let Some(msg) = msg {
+                self.publish(msg);
+            }
+        }
+    }
+
+    fn construct_announce_shard_config_message(&self) -> Option<PubsubMessage> {
+        let peer_id = *self.network_globals.peer_id.read();
+
+        let addr = match self.network_globals.listen_multiaddrs.read().first() {
+            Some(addr) => addr.clone(),
+            None => {
+                error!("No listen address available");
+                return None;
+            }
+        };
+
+        let timestamp = timestamp_now();
+
+        let msg = AnnounceShardConfig {
+            config: self.config.shard_config.clone(),
+            peer_id: peer_id.into(),
+            at: addr.into(),
+            timestamp,
+        };
+
+        let mut signed = match msg.into_signed(&self.local_keypair) {
+            Ok(signed) => signed,
+            Err(e) => {
+                error!(%e, "Failed to sign AnnounceShardConfig message");
+                return None;
+            }
+        };
+
+        signed.resend_timestamp = timestamp;
+
+        Some(PubsubMessage::AnnounceShardConfig(signed))
+    }
+
+    fn on_announce_shard_config_tick(&mut self) {
+        if self.config.shard_config.is_empty() {
+            return;
+        }
+
+        if let Some(msg) = self.construct_announce_shard_config_message() {
+            debug!(config = ?self.config.shard_config, "Announce shard config");
+            self.publish(msg);
+        }
+    }
+
     async fn on_find_file(&mut self, msg: FindFile) -> MessageAcceptance {
         let FindFile { tx_id, timestamp } = msg;
 
@@ -517,15 +784,36 @@ impl RouterService {
         }
 
         // try from cache
-        if let Some(mut msg) = self.file_location_cache.get_one(tx_id) {
+        if let Some(msg) = self.file_location_cache.get_one(tx_id) {
             debug!(?tx_id, "Found file in cache, responding to FindFile query");
 
-            msg.resend_timestamp = timestamp_now();
-            self.publish(PubsubMessage::AnnounceFile(msg));
+            self.publish(msg.into_pubsub_message(timestamp_now()));
 
             return MessageAcceptance::Ignore;
         }
 
+        // try from peers that store the flow entries of file long-term
+        if let Ok(Some(tx)) = self.store.get_tx_by_seq_number(tx_id.seq).await {
+            if tx.id() == tx_id {
+                let end_index = tx.start_entry_index + tx.num_entries() as u64;
+                let shard_configs = self
+                    .file_location_cache
+                    .get_shard_configs(tx.start_entry_index, end_index);
+
+                if let Some(mut msg) = shard_configs.into_iter().next() {
+                    debug!(
+                        ?tx_id,
+                        "Found shard config in cache, responding to FindFile query"
+                    );
+
+                    msg.resend_timestamp = timestamp_now();
+                    self.publish(PubsubMessage::AnnounceShardConfig(msg));
+
+                    return MessageAcceptance::Ignore;
+                }
+            }
+        }
+
         // propagate FindFile query to other nodes
         MessageAcceptance::Accept
     }
@@ -575,6 +863,113 @@ impl RouterService {
         MessageAcceptance::Accept
     }
 
+    fn on_announce_files(
+        &mut self,
+        propagation_source: PeerId,
+        msg: SignedAnnounceFiles,
+    ) -> MessageAcceptance {
+        // verify message signature
+        let pk = match peer_id_to_public_key(&msg.peer_id) {
+            Ok(pk) => pk,
+            Err(e) => {
+                error!(
+                    "Failed to convert peer id {:?} to public key: {:?}",
+                    msg.peer_id, e
+                );
+                return MessageAcceptance::Reject;
+            }
+        };
+
+        if !msg.verify_signature(&pk) {
+            warn!(
+                "Received message with invalid signature from peer {:?}",
+                propagation_source
+            );
+            return MessageAcceptance::Reject;
+        }
+
+        if !msg.is_valid() {
+            warn!(
+                count = msg.tx_ids.len(),
+                "Received invalid AnnounceFiles message from peer {:?}", propagation_source
+            );
+            return MessageAcceptance::Reject;
+        }
+
+        // propagate gossip to peers
+        let d = duration_since(msg.resend_timestamp);
+        if d < TOLERABLE_DRIFT.neg() || d > *ANNOUNCE_FILE_TIMEOUT {
+            debug!(%msg.resend_timestamp, "Invalid resend timestamp, ignoring AnnounceFiles message");
+            return MessageAcceptance::Ignore;
+        }
+
+        // notify sync layer for each file
+        let peer_id: PeerId = msg.peer_id.clone().into();
+        let addr: Multiaddr = msg.at.clone().into();
+        for tx_id in msg.tx_ids.iter() {
+            self.send_to_sync(SyncMessage::AnnounceFileGossip {
+                tx_id: *tx_id,
+                peer_id,
+                addr: addr.clone(),
+            });
+        }
+
+        // insert message to cache
+        self.file_location_cache.insert_batch(msg);
+
+        MessageAcceptance::Accept
+    }
+
+    fn on_announce_shard_config(
+        &mut self,
+        propagation_source: PeerId,
+        msg: SignedAnnounceShardConfig,
+    ) -> MessageAcceptance {
+        // verify message signature
+        let pk = match peer_id_to_public_key(&msg.peer_id) {
+            Ok(pk) => pk,
+            Err(e) => {
+                error!(
+                    "Failed to convert peer id {:?} to public key: {:?}",
+                    msg.peer_id, e
+                );
+                return MessageAcceptance::Reject;
+            }
+        };
+
+        if !msg.verify_signature(&pk) {
+            warn!(
+                "Received message with invalid signature from peer {:?}",
+                propagation_source
+            );
+            return MessageAcceptance::Reject;
+        }
+
+        if !msg.config.is_valid() {
+            warn!(config = ?msg.config, "Received invalid shard config from peer {:?}", propagation_source);
+            return MessageAcceptance::Reject;
+        }
+
+        // propagate gossip to peers
+        let d = duration_since(msg.resend_timestamp);
+        if d < TOLERABLE_DRIFT.neg() || d > *ANNOUNCE_SHARD_CONFIG_TIMEOUT {
+            debug!(%msg.resend_timestamp, "Invalid resend timestamp, ignoring AnnounceShardConfig message");
+            return MessageAcceptance::Ignore;
+        }
+
+        // notify sync layer
+        self.send_to_sync(SyncMessage::AnnounceShardConfigGossip {
+            peer_id: msg.peer_id.clone().into(),
+            addr: msg.at.clone().into(),
+            config: msg.config.clone(),
+        });
+
+        // insert message to cache
+        self.file_location_cache.insert_shard_config(msg);
+
+        MessageAcceptance::Accept
+    }
+
     fn on_heartbeat(&mut self) {
         let expired_peers = self.peers.expired_peers();
 
@@ -596,3 +991,538 @@ impl Drop for RouterService {
         info!("Router service shutdown");
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethereum_types::{H160, H256};
+    use futures::FutureExt;
+    use network::discovery::ConnectionId;
+    use network::rpc::methods::{BitList, FileAvailability, IonianData};
+    use network::rpc::SubstreamId;
+    use network::{Context as NetworkContext, NetworkConfig, SyncId};
+    use std::sync::Weak;
+    use storage::log_store::log_manager::LogConfig;
+    use storage::LogManager;
+    use sync::{SyncRequest, SyncResponse};
+    use task_executor::test_utils::TestRuntime;
+    use task_executor::TaskExecutor;
+    use tokio::runtime::Runtime;
+    use unused_port::unused_tcp_port;
+
+    struct TestRouter {
+        router: RouterService,
+        sync_recv: channel::Receiver<SyncMessage, SyncRequest, SyncResponse>,
+        _runtime: TestRuntime,
+    }
+
+    impl TestRouter {
+        async fn new(config: Config) -> Self {
+            let runtime = TestRuntime::default();
+            let executor = runtime.task_executor.clone();
+            Self::with_store_executor(config, runtime, executor).await
+        }
+
+        /// Creates a router whose store fails all operations.
+        async fn with_failed_store(config: Config) -> Self {
+            let (_, exit) = exit_future::signal();
+            let (shutdown_tx, _) = futures::channel::mpsc::channel(1);
+            let executor = TaskExecutor::new(Weak::<Runtime>::new(), exit, shutdown_tx);
+            Self::with_store_executor(config, TestRuntime::default(), executor).await
+        }
+
+        async fn with_store_executor(
+            config: Config,
+            runtime: TestRuntime,
+            store_executor: TaskExecutor,
+        ) -> Self {
+            let port = unused_tcp_port().unwrap();
+            let mut network_config = NetworkConfig::default();
+            network_config.libp2p_port = port;
+            network_config.discovery_port = port;
+            network_config.disable_discovery = true;
+            network_config.network_dir = tempfile::tempdir().unwrap().into_path();
+
+            let (network_globals, local_keypair, libp2p) = LibP2PService::new(
+                runtime.task_executor.clone(),
+                NetworkContext {
+                    config: &network_config,
+                },
+            )
+            .await
+            .unwrap();
+
+            let (network_send, network_recv) = mpsc::unbounded_channel();
+            let (sync_send, sync_recv) = channel::Channel::unbounded();
+            let store = LogManager::memorydb(LogConfig::default()).unwrap();
+
+            let router = RouterService {
+                config: config.clone(),
+                libp2p,
+                network_globals,
+                network_recv,
+                network_send,
+                sync_send,
+                miner_send: None,
+                store: Store::new(Arc::new(RwLock::new(store)), store_executor),
+                file_location_cache: Default::default(),
+                local_keypair,
+                peers: PeerManager::new(config),
+                pending_announcements: vec![],
+            };
+
+            Self {
+                router,
+                sync_recv,
+                _runtime: runtime,
+            }
+        }
+
+        fn network_msg(&mut self) -> Option<NetworkMessage> {
+            self.router.network_recv.try_recv().ok()
+        }
+
+        fn sync_msg(&mut self) -> Option<SyncMessage> {
+            match self.sync_recv.recv().now_or_never() {
+                Some(Some(channel::Message::Notification(msg))) => Some(msg),
+                _ => None,
+            }
+        }
+
+        /// Creates a router that is able to sign announcements of local files.
+        async fn with_listen_addr(config: Config) -> Self {
+            let test = Self::new(config).await;
+            test.router
+                .network_globals
+                .listen_multiaddrs
+                .write()
+                .push("/ip4/127.0.0.1/tcp/10000".parse().unwrap());
+            test
+        }
+
+        async fn announce_local_file(&mut self, tx_id: TxID) {
+            let (mut shutdown_sender, _) = futures::channel::mpsc::channel(1);
+            self.router
+                .on_network_msg(
+                    NetworkMessage::AnnounceLocalFile { tx_id },
+                    &mut shutdown_sender,
+                )
+                .await;
+        }
+
+        fn published_msg(&mut self) -> Option<PubsubMessage> {
+            match self.network_msg() {
+                Some(NetworkMessage::Publish { mut messages }) => {
+                    assert_eq!(messages.len(), 1);
+                    messages.pop()
+                }
+                None => None,
+                msg => panic!("Unexpected network message: {:?}", msg),
+            }
+        }
+    }
+
+    fn tx_id(seq: u64) -> TxID {
+        TxID {
+            seq,
+            hash: H256::random(),
+        }
+    }
+
+    fn status_message(config: &Config, next_tx_seq: u64) -> StatusMessage {
+        StatusMessage {
+            network_id: config.network_id,
+            flow_address: config.flow_address,
+            next_tx_seq,
+            flow_root: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_peer_sync_status() {
+        let config = Config::default();
+        let local_status = status_message(&config, 10);
+
+        let status = status_message(&config, 5);
+        assert!(peer_sync_status(&status, &local_status).is_behind());
+        let status = status_message(&config, 10);
+        assert!(peer_sync_status(&status, &local_status).is_synced());
+        let status = status_message(&config, 15);
+        assert!(peer_sync_status(&status, &local_status).is_advanced());
+
+        let status = StatusMessage {
+            network_id: config.network_id + 1,
+            ..status_message(&config, 10)
+        };
+        assert_eq!(
+            peer_sync_status(&status, &local_status),
+            SyncStatus::IrrelevantPeer
+        );
+
+        let status = StatusMessage {
+            flow_address: H160::repeat_byte(1),
+            ..status_message(&config, 10)
+        };
+        assert_eq!(
+            peer_sync_status(&status, &local_status),
+            SyncStatus::IrrelevantPeer
+        );
+    }
+
+    #[tokio::test]
+    async fn test_status_request() {
+        let config = Config::default();
+        let mut test = TestRouter::new(config.clone()).await;
+        let peer_id = PeerId::random();
+        let request_id = (ConnectionId::new(0), SubstreamId(0));
+
+        test.router
+            .on_status_request(peer_id, request_id, status_message(&config, 3))
+            .await;
+
+        match test.network_msg() {
+            Some(NetworkMessage::SendResponse {
+                peer_id: to_peer_id,
+                response: Response::Status(status),
+                id,
+            }) => {
+                assert_eq!(to_peer_id, peer_id);
+                assert_eq!(id, request_id);
+                assert_eq!(status.network_id, config.network_id);
+                assert_eq!(status.next_tx_seq, 0);
+            }
+            msg => panic!("Unexpected network message: {:?}", msg),
+        }
+        assert!(test.network_msg().is_none());
+
+        match test.sync_msg() {
+            Some(SyncMessage::PeerSyncStatus {
+                peer_id: status_peer_id,
+                status: SyncStatus::Advanced { info },
+            }) => {
+                assert_eq!(status_peer_id, peer_id);
+                assert_eq!(info.next_tx_seq, 3);
+            }
+            msg => panic!("Unexpected sync message: {:?}", msg),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_status_request_irrelevant_network() {
+        let config = Config::default();
+        let mut test = TestRouter::new(config.clone()).await;
+        let peer_id = PeerId::random();
+        let request_id = (ConnectionId::new(0), SubstreamId(0));
+
+        let status = StatusMessage {
+            network_id: config.network_id + 1,
+            ..status_message(&config, 0)
+        };
+        test.router
+            .on_status_request(peer_id, request_id, status)
+            .await;
+
+        // respond with the local status, and then disconnect
+        assert!(matches!(
+            test.network_msg(),
+            Some(NetworkMessage::SendResponse {
+                response: Response::Status(_),
+                ..
+            })
+        ));
+        assert!(matches!(
+            test.network_msg(),
+            Some(NetworkMessage::GoodbyePeer {
+                peer_id: goodbye_peer_id,
+                reason: GoodbyeReason::IrrelevantNetwork,
+                source: ReportSource::RPC,
+            }) if goodbye_peer_id == peer_id
+        ));
+
+        assert!(matches!(
+            test.sync_msg(),
+            Some(SyncMessage::PeerSyncStatus {
+                status: SyncStatus::IrrelevantPeer,
+                ..
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_status_request_store_error() {
+        let config = Config::default();
+        let mut test = TestRouter::with_failed_store(config.clone()).await;
+        let peer_id = PeerId::random();
+        let request_id = (ConnectionId::new(0), SubstreamId(0));
+
+        test.router
+            .on_status_request(peer_id, request_id, status_message(&config, 0))
+            .await;
+
+        match test.network_msg() {
+            Some(NetworkMessage::SendErrorResponse {
+                peer_id: to_peer_id,
+                error,
+                id,
+                ..
+            }) => {
+                assert_eq!(to_peer_id, peer_id);
+                assert_eq!(id, request_id);
+                assert_eq!(error, RPCResponseErrorCode::ServerError);
+            }
+            msg => panic!("Unexpected network message: {:?}", msg),
+        }
+        assert!(test.sync_msg().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_status_response() {
+        let config = Config::default();
+        let mut test = TestRouter::new(config.clone()).await;
+        let peer_id = PeerId::random();
+
+        test.router
+            .on_status_response(peer_id, status_message(&config, 0))
+            .await;
+
+        assert!(test.network_msg().is_none());
+        assert!(matches!(
+            test.sync_msg(),
+            Some(SyncMessage::PeerSyncStatus {
+                status: SyncStatus::Synced { .. },
+                ..
+            })
+        ));
+
+        let status = StatusMessage {
+            flow_address: H160::repeat_byte(1),
+            ..status_message(&config, 0)
+        };
+        test.router.on_status_response(peer_id, status).await;
+
+        assert!(matches!(
+            test.network_msg(),
+            Some(NetworkMessage::GoodbyePeer {
+                reason: GoodbyeReason::IrrelevantNetwork,
+                ..
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_file_availability_response() {
+        let mut test = TestRouter::new(Config::default()).await;
+        let peer_id = PeerId::random();
+        let tx_id = TxID::default();
+        let availability = FileAvailability {
+            tx_id,
+            finalized: true,
+            segments: BitList::with_capacity(1).unwrap(),
+        };
+
+        test.router
+            .on_rpc_response(
+                peer_id,
+                RequestId::Sync(SyncId::FileAvailability { tx_id }),
+                Response::FileAvailability(availability.clone()),
+            )
+            .await;
+
+        match test.sync_msg() {
+            Some(SyncMessage::FileAvailabilityResponse {
+                peer_id: from_peer_id,
+                response,
+            }) => {
+                assert_eq!(from_peer_id, peer_id);
+                assert_eq!(response, availability);
+            }
+            msg => panic!("Unexpected sync message: {:?}", msg),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_data_by_hash_response() {
+        let mut test = TestRouter::new(Config::default()).await;
+        let peer_id = PeerId::random();
+        let data = IonianData {
+            hash: H256::random(),
+            tx_id: TxID::default(),
+            size: 0,
+            finalized: true,
+            data: vec![].into(),
+        };
+
+        test.router
+            .on_rpc_response(
+                peer_id,
+                RequestId::Sync(SyncId::DataByHash),
+                Response::DataByHash(Some(Box::new(data.clone()))),
+            )
+            .await;
+
+        match test.sync_msg() {
+            Some(SyncMessage::DataByHashResponse {
+                peer_id: from_peer_id,
+                response,
+            }) => {
+                assert_eq!(from_peer_id, peer_id);
+                assert_eq!(*response, data);
+            }
+            msg => panic!("Unexpected sync message: {:?}", msg),
+        }
+
+        // the end of responses
+        test.router
+            .on_rpc_response(
+                peer_id,
+                RequestId::Sync(SyncId::DataByHash),
+                Response::DataByHash(None),
+            )
+            .await;
+        assert!(test.sync_msg().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_announce_local_files_in_window() {
+        let config = Config {
+            announce_files_batch_size: 3,
+            ..Default::default()
+        };
+        let mut test = TestRouter::with_listen_addr(config).await;
+
+        // nothing to announce
+        test.router.flush_pending_announcements();
+        assert!(test.published_msg().is_none());
+
+        // announce a single file at the end of batching window
+        let tx1 = tx_id(1);
+        test.announce_local_file(tx1).await;
+        assert!(test.published_msg().is_none());
+
+        test.router.flush_pending_announcements();
+        match test.published_msg() {
+            Some(PubsubMessage::AnnounceFile(msg)) => assert_eq!(msg.tx_id, tx1),
+            msg => panic!("Unexpected pubsub message: {:?}", msg),
+        }
+        assert!(test.published_msg().is_none());
+
+        // announce files in batch at the end of batching window
+        let tx2 = tx_id(2);
+        let tx3 = tx_id(3);
+        test.announce_local_file(tx2).await;
+        test.announce_local_file(tx3).await;
+        assert!(test.published_msg().is_none());
+
+        test.router.flush_pending_announcements();
+        match test.published_msg() {
+            Some(PubsubMessage::AnnounceFiles(msg)) => assert_eq!(msg.tx_ids, vec![tx2, tx3]),
+            msg => panic!("Unexpected pubsub message: {:?}", msg),
+        }
+        assert!(test.published_msg().is_none());
+
+        // flushed already
+        test.router.flush_pending_announcements();
+        assert!(test.published_msg().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_announce_local_files_in_batch_size() {
+        let config = Config {
+            announce_files_batch_size: 3,
+            ..Default::default()
+        };
+        let mut test = TestRouter::with_listen_addr(config).await;
+
+        // announce once the batch is full, without waiting for the batching window
+        let tx_ids: Vec<TxID> = (0..4).map(tx_id).collect();
+        for tx_id in tx_ids.iter().take(2) {
+            test.announce_local_file(*tx_id).await;
+            assert!(test.published_msg().is_none());
+        }
+
+        test.announce_local_file(tx_ids[2]).await;
+        match test.published_msg() {
+            Some(PubsubMessage::AnnounceFiles(msg)) => assert_eq!(msg.tx_ids, tx_ids[..3].to_vec()),
+            msg => panic!("Unexpected pubsub message: {:?}", msg),
+        }
+        assert!(test.published_msg().is_none());
+
+        test.announce_local_file(tx_ids[3]).await;
+        assert!(test.published_msg().is_none());
+        test.router.flush_pending_announcements();
+        assert!(matches!(
+            test.published_msg(),
+            Some(PubsubMessage::AnnounceFile(_))
+        ));
+
+        // split pending files into batches of at most `announce_files_batch_size`
+        test.router.pending_announcements = (0..7).map(tx_id).collect();
+        test.router.flush_pending_announcements();
+        let mut batches = vec![];
+        while let Some(msg) = test.published_msg() {
+            match msg {
+                PubsubMessage::AnnounceFiles(msg) => batches.push(msg.tx_ids.len()),
+                PubsubMessage::AnnounceFile(_) => batches.push(1),
+                msg => panic!("Unexpected pubsub message: {:?}", msg),
+            }
+        }
+        assert_eq!(batches, vec![3, 3, 1]);
+    }
+
+    #[tokio::test]
+    async fn test_announce_files_gossip() {
+        let config = Config {
+            announce_files_batch_size: 3,
+            ..Default::default()
+        };
+        let mut announcer = TestRouter::with_listen_addr(config.clone()).await;
+        let mut test = TestRouter::new(config).await;
+
+        let tx_ids: Vec<TxID> = (0..3).map(tx_id).collect();
+        let msg = match announcer
+            .router
+            .construct_announce_files_message(tx_ids.clone())
+        {
+            Some(PubsubMessage::AnnounceFiles(msg)) => msg,
+            msg => panic!("Unexpected pubsub message: {:?}", msg),
+        };
+        let announcer_id = *announcer.router.network_globals.peer_id.read();
+        let propagation_source = PeerId::random();
+
+        // reject message with invalid signature
+        let mut invalid = msg.clone();
+        invalid.inner.tx_ids.pop();
+        assert!(matches!(
+            test.router.on_announce_files(propagation_source, invalid),
+            MessageAcceptance::Reject
+        ));
+        assert!(test.sync_msg().is_none());
+        assert!(test.router.file_location_cache.get_one(tx_ids[0]).is_none());
+
+        assert!(matches!(
+            test.router.on_announce_files(propagation_source, msg),
+            MessageAcceptance::Accept
+        ));
+
+        // notify sync layer and cache the announcement for each file
+        for tx_id in tx_ids.iter() {
+            match test.sync_msg() {
+                Some(SyncMessage::AnnounceFileGossip {
+                    tx_id: announced,
+                    peer_id,
+                    ..
+                }) => {
+                    assert_eq!(announced, *tx_id);
+                    assert_eq!(peer_id, announcer_id);
+                }
+                msg => panic!("Unexpected sync message: {:?}", msg),
+            }
+
+            let announcements = test.router.file_location_cache.get_all(*tx_id);
+            assert_eq!(announcements.len(), 1);
+            assert_eq!(announcements[0].peer_id(), announcer_id);
+        }
+        assert!(test.sync_msg().is_none());
+        assert!(test.router.file_location_cache.get_one(tx_id(3)).is_none());
+    }
+}
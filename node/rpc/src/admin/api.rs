@@ -1,8 +1,10 @@
-use crate::types::NetworkInfo;
+use crate::types::{NetworkInfo, PeerInfo};
 use jsonrpsee::core::RpcResult;
 use jsonrpsee::proc_macros::rpc;
+use log_entry_sync::LogSyncStatus;
+use shared_types::DataRoot;
 use std::collections::HashMap;
-use sync::FileSyncInfo;
+use sync::{BandwidthLimits, DataByHashInfo, FileSyncInfo, FlowRangeSyncInfo};
 
 #[rpc(server, client, namespace = "admin")]
 pub trait Rpc {
@@ -18,6 +20,48 @@ pub trait Rpc {
     #[method(name = "getSyncInfo")]
     async fn get_sync_info(&self, tx_seq: Option<u64>) -> RpcResult<HashMap<u64, FileSyncInfo>>;
 
+    #[method(name = "syncFlowRange")]
+    async fn sync_flow_range(&self, start_index: u64, end_index: u64) -> RpcResult<()>;
+
+    #[method(name = "getFlowRangeSyncInfo")]
+    async fn get_flow_range_sync_info(&self) -> RpcResult<Option<FlowRangeSyncInfo>>;
+
+    #[method(name = "findDataByHash")]
+    async fn find_data_by_hash(&self, roots: Vec<DataRoot>) -> RpcResult<()>;
+
+    #[method(name = "getDataByHash")]
+    async fn get_data_by_hash(&self, root: DataRoot) -> RpcResult<Option<DataByHashInfo>>;
+
+    #[method(name = "getBandwidthLimits")]
+    async fn get_bandwidth_limits(&self) -> RpcResult<BandwidthLimits>;
+
+    #[method(name = "setBandwidthLimits")]
+    async fn set_bandwidth_limits(&self, limits: BandwidthLimits) -> RpcResult<BandwidthLimits>;
+
     #[method(name = "getNetworkInfo")]
     async fn get_network_info(&self) -> RpcResult<NetworkInfo>;
+
+    #[method(name = "listPeers")]
+    async fn list_peers(&self) -> RpcResult<Vec<PeerInfo>>;
+
+    #[method(name = "addPeer")]
+    async fn add_peer(&self, enr: String) -> RpcResult<()>;
+
+    #[method(name = "dialPeer")]
+    async fn dial_peer(&self, address: String) -> RpcResult<()>;
+
+    #[method(name = "banPeer")]
+    async fn ban_peer(&self, peer_id: String) -> RpcResult<()>;
+
+    #[method(name = "unbanPeer")]
+    async fn unban_peer(&self, peer_id: String) -> RpcResult<()>;
+
+    #[method(name = "addTrustedPeer")]
+    async fn add_trusted_peer(&self, peer: String) -> RpcResult<()>;
+
+    #[method(name = "getLogSyncStatus")]
+    async fn get_log_sync_status(&self) -> RpcResult<LogSyncStatus>;
+
+    #[method(name = "resyncLogFrom")]
+    async fn resync_log_from(&self, block_number: u64) -> RpcResult<u64>;
 }
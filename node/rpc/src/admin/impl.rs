@@ -1,11 +1,18 @@
 use super::api::RpcServer;
-use crate::types::NetworkInfo;
+use crate::types::{NetworkInfo, PeerInfo};
 use crate::{error, Context};
 use futures::prelude::*;
 use jsonrpsee::core::async_trait;
 use jsonrpsee::core::RpcResult;
+use log_entry_sync::{LogSyncRequest, LogSyncResponse, LogSyncStatus};
+use network::multiaddr::Protocol;
+use network::{Enr, Multiaddr, NetworkMessage, PeerConnectionStatus, PeerId};
+use shared_types::DataRoot;
 use std::collections::HashMap;
-use sync::{FileSyncInfo, SyncRequest, SyncResponse};
+use std::str::FromStr;
+use sync::{
+    BandwidthLimits, DataByHashInfo, FileSyncInfo, FlowRangeSyncInfo, SyncRequest, SyncResponse,
+};
 use task_executor::ShutdownReason;
 
 pub struct RpcServerImpl {
@@ -79,6 +86,111 @@ impl RpcServer for RpcServerImpl {
         }
     }
 
+    #[tracing::instrument(skip(self), err)]
+    async fn sync_flow_range(&self, start_index: u64, end_index: u64) -> RpcResult<()> {
+        info!("admin_syncFlowRange({start_index}, {end_index})");
+
+        let response = self
+            .ctx
+            .request_sync(SyncRequest::SyncFlowRange {
+                start_index,
+                end_index,
+            })
+            .await?;
+
+        match response {
+            SyncResponse::SyncFlowRange { err } => {
+                if err.is_empty() {
+                    Ok(())
+                } else {
+                    Err(error::internal_error(err))
+                }
+            }
+            _ => Err(error::internal_error("unexpected response type")),
+        }
+    }
+
+    #[tracing::instrument(skip(self), err)]
+    async fn get_flow_range_sync_info(&self) -> RpcResult<Option<FlowRangeSyncInfo>> {
+        info!("admin_getFlowRangeSyncInfo()");
+
+        let response = self
+            .ctx
+            .request_sync(SyncRequest::FlowRangeSyncInfo)
+            .await?;
+
+        match response {
+            SyncResponse::FlowRangeSyncInfo { info } => Ok(info),
+            _ => Err(error::internal_error("unexpected response type")),
+        }
+    }
+
+    #[tracing::instrument(skip(self), err)]
+    async fn find_data_by_hash(&self, roots: Vec<DataRoot>) -> RpcResult<()> {
+        info!(?roots, "admin_findDataByHash()");
+
+        let response = self
+            .ctx
+            .request_sync(SyncRequest::FindDataByHash { roots })
+            .await?;
+
+        match response {
+            SyncResponse::FindDataByHash { err } => {
+                if err.is_empty() {
+                    Ok(())
+                } else {
+                    Err(error::internal_error(err))
+                }
+            }
+            _ => Err(error::internal_error("unexpected response type")),
+        }
+    }
+
+    #[tracing::instrument(skip(self), err)]
+    async fn get_data_by_hash(&self, root: DataRoot) -> RpcResult<Option<DataByHashInfo>> {
+        info!(?root, "admin_getDataByHash()");
+
+        let response = self
+            .ctx
+            .request_sync(SyncRequest::DataByHashInfo { root })
+            .await?;
+
+        match response {
+            SyncResponse::DataByHashInfo { info } => Ok(info),
+            _ => Err(error::internal_error("unexpected response type")),
+        }
+    }
+
+    #[tracing::instrument(skip(self), err)]
+    async fn get_bandwidth_limits(&self) -> RpcResult<BandwidthLimits> {
+        info!("admin_getBandwidthLimits()");
+
+        let response = self
+            .ctx
+            .request_sync(SyncRequest::GetBandwidthLimits)
+            .await?;
+
+        match response {
+            SyncResponse::BandwidthLimits { limits } => Ok(limits),
+            _ => Err(error::internal_error("unexpected response type")),
+        }
+    }
+
+    #[tracing::instrument(skip(self), err)]
+    async fn set_bandwidth_limits(&self, limits: BandwidthLimits) -> RpcResult<BandwidthLimits> {
+        info!(?limits, "admin_setBandwidthLimits()");
+
+        let response = self
+            .ctx
+            .request_sync(SyncRequest::SetBandwidthLimits { limits })
+            .await?;
+
+        match response {
+            SyncResponse::BandwidthLimits { limits } => Ok(limits),
+            _ => Err(error::internal_error("unexpected response type")),
+        }
+    }
+
     #[tracing::instrument(skip(self), err)]
     async fn get_network_info(&self) -> RpcResult<NetworkInfo> {
         info!("admin_getNetworkInfo()");
@@ -98,4 +210,155 @@ impl RpcServer for RpcServerImpl {
             connected_incoming_peers: connected_peers - connected_outgoing_peers,
         })
     }
+
+    #[tracing::instrument(skip(self), err)]
+    async fn list_peers(&self) -> RpcResult<Vec<PeerInfo>> {
+        info!("admin_listPeers()");
+
+        let db = self.ctx.network_globals.peers.read();
+
+        Ok(db
+            .peers()
+            .map(|(peer_id, info)| {
+                let score = db.score(peer_id);
+
+                PeerInfo {
+                    peer_id: peer_id.to_base58(),
+                    listening_addresses: info
+                        .listening_addresses()
+                        .iter()
+                        .map(|addr| addr.to_string())
+                        .collect(),
+                    seen_addresses: info.seen_addresses().map(|addr| addr.to_string()).collect(),
+                    direction: info
+                        .connection_direction()
+                        .map(|direction| direction.as_ref().to_string()),
+                    score: score.is_finite().then_some(score),
+                    client: info.client().to_string(),
+                    connection_status: connection_status(info.connection_status()).into(),
+                    trusted: info.is_trusted(),
+                }
+            })
+            .collect())
+    }
+
+    #[tracing::instrument(skip(self), err)]
+    async fn add_peer(&self, enr: String) -> RpcResult<()> {
+        info!("admin_addPeer({enr})");
+
+        let enr = Enr::from_str(&enr).map_err(|e| error::invalid_params("enr", e))?;
+
+        self.ctx
+            .send_network(NetworkMessage::AddPeer { enr: Box::new(enr) })
+    }
+
+    #[tracing::instrument(skip(self), err)]
+    async fn dial_peer(&self, address: String) -> RpcResult<()> {
+        info!("admin_dialPeer({address})");
+
+        let address = parse_address("address", &address)?;
+
+        self.ctx
+            .send_network(NetworkMessage::DialAddress { address })
+    }
+
+    #[tracing::instrument(skip(self), err)]
+    async fn ban_peer(&self, peer_id: String) -> RpcResult<()> {
+        info!("admin_banPeer({peer_id})");
+
+        let peer_id = parse_peer_id(&peer_id)?;
+        if peer_id == self.ctx.network_globals.local_peer_id() {
+            return Err(error::invalid_params(
+                "peer_id",
+                "cannot ban the local peer",
+            ));
+        }
+
+        self.ctx.send_network(NetworkMessage::BanPeer { peer_id })
+    }
+
+    #[tracing::instrument(skip(self), err)]
+    async fn unban_peer(&self, peer_id: String) -> RpcResult<()> {
+        info!("admin_unbanPeer({peer_id})");
+
+        let peer_id = parse_peer_id(&peer_id)?;
+
+        self.ctx.send_network(NetworkMessage::UnbanPeer { peer_id })
+    }
+
+    #[tracing::instrument(skip(self), err)]
+    async fn add_trusted_peer(&self, peer: String) -> RpcResult<()> {
+        info!("admin_addTrustedPeer({peer})");
+
+        // either a peer id, or an address with peer id to dial
+        let (peer_id, address) = match PeerId::from_str(&peer) {
+            Ok(peer_id) => (peer_id, None),
+            Err(_) => {
+                let address = parse_address("peer", &peer)?;
+                let peer_id = address_peer_id(&address)
+                    .ok_or_else(|| error::invalid_params("peer", "peer id not found in address"))?;
+                (peer_id, Some(address))
+            }
+        };
+
+        self.ctx
+            .send_network(NetworkMessage::AddTrustedPeer { peer_id })?;
+
+        match address {
+            Some(address) => self
+                .ctx
+                .send_network(NetworkMessage::DialAddress { address }),
+            None => Ok(()),
+        }
+    }
+
+    #[tracing::instrument(skip(self), err)]
+    async fn get_log_sync_status(&self) -> RpcResult<LogSyncStatus> {
+        info!("admin_getLogSyncStatus()");
+
+        Ok(self.ctx.log_sync_status.read().await.clone())
+    }
+
+    #[tracing::instrument(skip(self), err)]
+    async fn resync_log_from(&self, block_number: u64) -> RpcResult<u64> {
+        info!("admin_resyncLogFrom({block_number})");
+
+        let response = self
+            .ctx
+            .request_log_sync(LogSyncRequest::ResyncFrom { block_number })
+            .await?;
+
+        match response {
+            LogSyncResponse::ResyncFrom { result } => result.map_err(error::internal_error),
+        }
+    }
+}
+
+fn parse_peer_id(peer_id: &str) -> RpcResult<PeerId> {
+    PeerId::from_str(peer_id).map_err(|e| error::invalid_params("peer_id", e.to_string()))
+}
+
+fn parse_address(param: &str, address: &str) -> RpcResult<Multiaddr> {
+    address
+        .parse::<Multiaddr>()
+        .map_err(|e| error::invalid_params(param, e.to_string()))
+}
+
+/// Returns the peer id in the `/p2p/` component of the address, if any.
+fn address_peer_id(address: &Multiaddr) -> Option<PeerId> {
+    address.iter().find_map(|protocol| match protocol {
+        Protocol::P2p(hash) => PeerId::from_multihash(hash).ok(),
+        _ => None,
+    })
+}
+
+fn connection_status(status: &PeerConnectionStatus) -> &'static str {
+    match status {
+        PeerConnectionStatus::Connected { .. } => "connected",
+        PeerConnectionStatus::Disconnecting { .. } => "disconnecting",
+        PeerConnectionStatus::Disconnected { .. } => "disconnected",
+        PeerConnectionStatus::Banned { .. } => "banned",
+        PeerConnectionStatus::Dialing { .. } => "dialing",
+        PeerConnectionStatus::Unknown => "unknown",
+    }
 }
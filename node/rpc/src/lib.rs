@@ -18,6 +18,7 @@ use ionian::RpcServer as IonianRpcServer;
 use ionian_miner::MinerMessage;
 use jsonrpsee::core::RpcResult;
 use jsonrpsee::http_server::{HttpServerBuilder, HttpServerHandle};
+use log_entry_sync::{LogSyncRequest, LogSyncResponse, LogSyncSender, LogSyncStatus};
 use network::NetworkGlobals;
 use network::NetworkMessage;
 use std::error::Error;
@@ -27,6 +28,7 @@ use sync::{SyncRequest, SyncResponse, SyncSender};
 use task_executor::ShutdownReason;
 use tokio::sync::broadcast;
 use tokio::sync::mpsc::UnboundedSender;
+use tokio::sync::RwLock;
 
 pub use config::Config as RPCConfig;
 
@@ -39,6 +41,8 @@ pub struct Context {
     pub network_globals: Arc<NetworkGlobals>,
     pub network_send: UnboundedSender<NetworkMessage>,
     pub sync_send: SyncSender,
+    pub log_sync_status: Arc<RwLock<LogSyncStatus>>,
+    pub log_sync_send: LogSyncSender,
     pub chunk_pool: Arc<MemoryChunkPool>,
     pub log_store: Store,
     pub shutdown_sender: Sender<ShutdownReason>,
@@ -58,6 +62,13 @@ impl Context {
             .await
             .map_err(|e| error::internal_error(format!("Failed to send sync request: {:?}", e)))
     }
+
+    pub async fn request_log_sync(&self, request: LogSyncRequest) -> RpcResult<LogSyncResponse> {
+        self.log_sync_send
+            .request(request)
+            .await
+            .map_err(|e| error::internal_error(format!("Failed to send log sync request: {:?}", e)))
+    }
 }
 
 pub async fn run_server(ctx: Context) -> Result<HttpServerHandle, Box<dyn Error>> {
@@ -32,6 +32,21 @@ pub struct NetworkInfo {
     pub connected_incoming_peers: usize,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PeerInfo {
+    pub peer_id: String,
+    pub listening_addresses: Vec<String>,
+    pub seen_addresses: Vec<String>,
+    /// Direction of the last (or current) connection, which is `incoming` or `outgoing`.
+    pub direction: Option<String>,
+    /// None for trusted peers, whose score is infinite.
+    pub score: Option<f64>,
+    pub client: String,
+    pub connection_status: String,
+    pub trusted: bool,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct FileInfo {
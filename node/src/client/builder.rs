@@ -1,7 +1,8 @@
 use super::{Client, RuntimeContext};
 use chunk_pool::Config as ChunkPoolConfig;
 use file_location_cache::FileLocationCache;
-use log_entry_sync::{LogSyncConfig, LogSyncEvent, LogSyncManager};
+use ionian_spec::SECTORS_PER_MAX_MINING_RANGE;
+use log_entry_sync::{LogSyncConfig, LogSyncEvent, LogSyncManager, LogSyncSender, LogSyncStatus};
 use miner::{MineService, MinerConfig, MinerMessage};
 use network::{
     self, Keypair, NetworkConfig, NetworkGlobals, NetworkMessage, RequestId,
@@ -13,7 +14,7 @@ use std::sync::Arc;
 use storage::log_store::log_manager::LogConfig;
 use storage::log_store::Store;
 use storage::{LogManager, StorageConfig};
-use sync::{SyncSender, SyncService};
+use sync::{Config as SyncConfig, SyncRequest, SyncResponse, SyncSender, SyncService};
 use tokio::sync::{broadcast, mpsc, RwLock};
 
 macro_rules! require {
@@ -47,6 +48,10 @@ struct MinerComponents {
 
 struct LogSyncComponents {
     send: broadcast::Sender<LogSyncEvent>,
+    // note: this will be owned by the sync service
+    recv: Option<broadcast::Receiver<LogSyncEvent>>,
+    status: Arc<RwLock<LogSyncStatus>>,
+    request_send: LogSyncSender,
 }
 
 /// Builds a `Client` instance.
@@ -138,14 +143,18 @@ impl ClientBuilder {
         Ok(self)
     }
 
-    pub async fn with_sync(mut self) -> Result<Self, String> {
+    pub async fn with_sync(mut self, config: SyncConfig) -> Result<Self, String> {
         let executor = require!("sync", self, runtime_context).clone().executor;
         let store = require!("sync", self, store).clone();
         let file_location_cache = require!("sync", self, file_location_cache).clone();
         let network_send = require!("sync", self, network).send.clone();
-        let event_recv = require!("sync", self, log_sync).send.subscribe();
+        let event_recv = match self.log_sync.as_mut().and_then(|x| x.recv.take()) {
+            Some(recv) => recv,
+            None => require!("sync", self, log_sync).send.subscribe(),
+        };
 
-        let send = SyncService::spawn(
+        let send = SyncService::spawn_with_config(
+            config,
             executor,
             network_send,
             store,
@@ -165,7 +174,13 @@ impl ClientBuilder {
             let network_send = require!("miner", self, network).send.clone();
             let store = self.store.as_ref().unwrap().clone();
 
-            let send = MineService::spawn(executor, network_send, config, store).await?;
+            let send = MineService::spawn(executor.clone(), network_send, config, store).await?;
+
+            // sync the data in mining range once changed
+            if let Some(sync) = self.sync.as_ref() {
+                spawn_mine_range_sync(&executor, send.subscribe(), sync.send.clone());
+            }
+
             self.miner = Some(MinerComponents { send });
         }
 
@@ -220,14 +235,17 @@ impl ClientBuilder {
 
         let (chunk_pool, chunk_pool_handler) =
             chunk_pool::unbounded(chunk_pool_config, async_store.clone(), network_send.clone());
+        let log_sync_event_recv = require!("rpc", self, log_sync).send.subscribe();
 
         let ctx = rpc::Context {
             config: rpc_config,
             network_globals: require!("rpc", self, network).globals.clone(),
             network_send,
             sync_send: require!("rpc", self, sync).send.clone(),
+            log_sync_status: require!("rpc", self, log_sync).status.clone(),
+            log_sync_send: require!("rpc", self, log_sync).request_send.clone(),
             log_store: async_store,
-            chunk_pool,
+            chunk_pool: chunk_pool.clone(),
             shutdown_sender: executor.shutdown_sender(),
             mine_service_sender: mine_send,
         };
@@ -238,6 +256,10 @@ impl ClientBuilder {
 
         executor.spawn(rpc_handle, "rpc");
         executor.spawn(chunk_pool_handler.run(), "chunk_pool_handler");
+        executor.spawn(
+            chunk_pool::monitor_reorg(chunk_pool, log_sync_event_recv),
+            "chunk_pool_reorg",
+        );
 
         Ok(self)
     }
@@ -245,10 +267,15 @@ impl ClientBuilder {
     pub async fn with_log_sync(mut self, config: LogSyncConfig) -> Result<Self, String> {
         let executor = require!("log_sync", self, runtime_context).clone().executor;
         let store = require!("log_sync", self, store).clone();
-        let send = LogSyncManager::spawn(config, executor, store)
+        let (send, recv, status, request_send) = LogSyncManager::spawn(config, executor, store)
             .await
             .map_err(|e| e.to_string())?;
-        self.log_sync = Some(LogSyncComponents { send });
+        self.log_sync = Some(LogSyncComponents {
+            send,
+            recv: Some(recv),
+            status,
+            request_send,
+        });
         Ok(self)
     }
 
@@ -262,3 +289,45 @@ impl ClientBuilder {
         })
     }
 }
+
+/// Requests to sync the flow entries in mining range when the start position of miner changes.
+fn spawn_mine_range_sync(
+    executor: &task_executor::TaskExecutor,
+    mut miner_recv: broadcast::Receiver<MinerMessage>,
+    sync_send: SyncSender,
+) {
+    let fut = async move {
+        let mut end_position = None;
+
+        loop {
+            let start_position = match miner_recv.recv().await {
+                Ok(MinerMessage::SetStartPosition(Some(position))) => position,
+                Ok(MinerMessage::SetEndPosition(position)) => {
+                    end_position = position;
+                    continue;
+                }
+                Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => return,
+            };
+
+            let max_end_position =
+                start_position.saturating_add(SECTORS_PER_MAX_MINING_RANGE as u64);
+            let end_index =
+                end_position.map_or(max_end_position, |end: u64| end.min(max_end_position));
+
+            match sync_send
+                .request(SyncRequest::SyncFlowRange {
+                    start_index: start_position,
+                    end_index,
+                })
+                .await
+            {
+                Ok(SyncResponse::SyncFlowRange { err }) if err.is_empty() => {}
+                Ok(resp) => warn!(?resp, "Failed to sync mining range"),
+                Err(err) => warn!(?err, "Failed to sync mining range"),
+            }
+        }
+    };
+
+    executor.spawn(fut, "mine_range_sync");
+}
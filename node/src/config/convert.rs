@@ -1,11 +1,13 @@
 #![allow(clippy::field_reassign_with_default)]
 
 use crate::IonianConfig;
-use ethereum_types::H256;
-use log_entry_sync::{CacheConfig, ContractAddress, LogSyncConfig};
+use ethereum_types::{H160, H256, U256};
+use log_entry_sync::{CacheConfig, ConfirmationStrategy, ContractAddress, LogSyncConfig};
 use miner::MinerConfig;
+use network::types::{FlowRange, ShardConfig};
 use network::NetworkConfig;
 use rpc::RPCConfig;
+use std::path::Path;
 use storage::StorageConfig;
 
 impl IonianConfig {
@@ -20,6 +22,7 @@ impl IonianConfig {
         network_config.network_dir = self.network_dir.clone().into();
         network_config.libp2p_port = self.network_libp2p_port;
         network_config.disable_discovery = self.network_disable_discovery;
+        network_config.enable_mdns = self.network_enable_mdns;
         network_config.discovery_port = self.network_libp2p_port;
         network_config.enr_tcp_port = Some(self.network_libp2p_port);
         network_config.enr_udp_port = Some(self.network_libp2p_port);
@@ -44,10 +47,31 @@ impl IonianConfig {
         network_config.enr_address = Some("127.0.0.1".parse::<std::net::IpAddr>().unwrap());
         network_config.target_peers = self.network_target_peers;
         network_config.private = self.network_private;
+        network_config.topic_namespace = self.topic_namespace()?;
 
         Ok(network_config)
     }
 
+    /// Returns the gossip topic namespace, which is the configured network name, or derived
+    /// from the network id and flow contract address by default.
+    fn topic_namespace(&self) -> Result<String, String> {
+        let namespace = if self.network_name.is_empty() {
+            let flow_address = self
+                .log_contract_address
+                .parse::<ContractAddress>()
+                .map_err(|e| format!("Unable to parse log_contract_address: {:?}", e))?;
+            format!("ionian-{}-{:x}", self.network_id, flow_address)
+        } else {
+            self.network_name.clone()
+        };
+
+        if namespace.contains('/') {
+            return Err(format!("Invalid network_name: {}", namespace));
+        }
+
+        Ok(namespace)
+    }
+
     pub fn storage_config(&self) -> Result<StorageConfig, String> {
         Ok(StorageConfig {
             db_dir: self.db_dir.clone().into(),
@@ -73,19 +97,34 @@ impl IonianConfig {
             .log_contract_address
             .parse::<ContractAddress>()
             .map_err(|e| format!("Unable to parse log_contract_address: {:?}", e))?;
+        let confirmation_strategy = self
+            .confirmation_strategy
+            .parse::<ConfirmationStrategy>()
+            .map_err(|e| format!("Unable to parse confirmation_strategy: {:?}", e))?;
         let cache_config = CacheConfig {
             // 100 MB.
             max_data_size: self.max_cache_data_size,
+            // Large reverted data are not cached on disk if the disk size is 0.
+            dir: (self.max_cache_disk_data_size > 0).then(|| match &self.cache_dir {
+                Some(dir) => dir.into(),
+                None => Path::new(&self.db_dir).join("reverted_data"),
+            }),
+            max_disk_data_size: self.max_cache_disk_data_size,
             // This should be enough if we have about one Ionian tx per block.
             tx_seq_ttl: self.cache_tx_seq_ttl,
         };
         Ok(LogSyncConfig::new(
-            self.blockchain_rpc_endpoint.clone(),
+            self.blockchain_rpc_endpoints(),
+            self.blockchain_rpc_quorum,
+            self.blockchain_ws_endpoint.clone(),
             contract_address,
             self.log_sync_start_block_number,
             self.confirmation_block_count,
+            confirmation_strategy,
             cache_config,
             self.log_page_size,
+            (!self.log_sync_submission_file.is_empty())
+                .then(|| self.log_sync_submission_file.clone().into()),
         ))
     }
 
@@ -120,12 +159,19 @@ impl IonianConfig {
         Ok(MinerConfig::new(
             miner_id,
             miner_key,
-            self.blockchain_rpc_endpoint.clone(),
+            self.blockchain_rpc_endpoints(),
             mine_address,
             flow_address,
         ))
     }
 
+    /// Return the primary blockchain endpoint followed by the backup ones.
+    fn blockchain_rpc_endpoints(&self) -> Vec<String> {
+        let mut endpoints = vec![self.blockchain_rpc_endpoint.clone()];
+        endpoints.extend(self.blockchain_rpc_backup_endpoints.iter().cloned());
+        endpoints
+    }
+
     pub fn chunk_pool_config(&self) -> chunk_pool::Config {
         chunk_pool::Config {
             write_window_size: self.chunk_pool_write_window_size,
@@ -135,9 +181,93 @@ impl IonianConfig {
         }
     }
 
+    pub fn sync_config(&self) -> Result<sync::Config, String> {
+        let mut sync_config = sync::Config::default();
+        sync_config.max_parallel_requests = self.sync_max_parallel_requests;
+        sync_config.bandwidth_limits = sync::BandwidthLimits {
+            max_upload_bytes_per_sec: self.sync_max_upload_bytes_per_sec,
+            max_upload_bytes_per_sec_per_peer: self.sync_max_upload_bytes_per_sec_per_peer,
+            max_download_bytes_per_sec: self.sync_max_download_bytes_per_sec,
+            max_download_bytes_per_sec_per_peer: self.sync_max_download_bytes_per_sec_per_peer,
+        };
+
+        let policy = &mut sync_config.auto_sync_policy;
+        policy.max_file_size = self.auto_sync_max_file_size;
+        policy.allowed_stream_ids = parse_stream_ids(&self.auto_sync_allowed_stream_ids)
+            .map_err(|e| format!("Unable to parse auto_sync_allowed_stream_ids: {}", e))?;
+        policy.denied_stream_ids = parse_stream_ids(&self.auto_sync_denied_stream_ids)
+            .map_err(|e| format!("Unable to parse auto_sync_denied_stream_ids: {}", e))?;
+        policy.allowed_submitters = self
+            .auto_sync_allowed_submitters
+            .iter()
+            .map(|addr| addr.parse::<H160>())
+            .collect::<Result<_, _>>()
+            .map_err(|e| format!("Unable to parse auto_sync_allowed_submitters: {:?}", e))?;
+        if self.auto_sync_flow_range_start > 0 || self.auto_sync_flow_range_end > 0 {
+            let end = match self.auto_sync_flow_range_end {
+                0 => u64::MAX,
+                end => end,
+            };
+            policy.flow_range = Some((self.auto_sync_flow_range_start, end));
+        }
+        if self.auto_sync_sample_percent > 100 {
+            return Err("auto_sync_sample_percent should not be greater than 100".into());
+        }
+        policy.sample_percent = self.auto_sync_sample_percent;
+
+        Ok(sync_config)
+    }
+
     pub fn router_config(&self, network_config: &NetworkConfig) -> Result<router::Config, String> {
         let mut router_config = router::Config::default();
         router_config.libp2p_nodes = network_config.libp2p_nodes.to_vec();
+        router_config.network_id = self.network_id;
+        router_config.flow_address = self
+            .log_contract_address
+            .parse::<ContractAddress>()
+            .map_err(|e| format!("Unable to parse log_contract_address: {:?}", e))?;
+        router_config.shard_config = self.shard_config()?;
         Ok(router_config)
     }
+
+    fn shard_config(&self) -> Result<ShardConfig, String> {
+        let flow_ranges = self
+            .shard_flow_ranges
+            .iter()
+            .map(|range| parse_flow_range(range))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let config = ShardConfig {
+            shard_id: self.shard_id,
+            num_shard: self.num_shard,
+            flow_ranges,
+        };
+
+        if !config.is_valid() {
+            return Err(format!("Invalid shard config: {:?}", config));
+        }
+
+        Ok(config)
+    }
+}
+
+fn parse_flow_range(range: &str) -> Result<FlowRange, String> {
+    let (start, end) = range
+        .split_once('-')
+        .ok_or_else(|| format!("Invalid flow range: {}", range))?;
+    let start = start
+        .trim()
+        .parse::<u64>()
+        .map_err(|e| format!("Invalid flow range {}: {:?}", range, e))?;
+    let end = end
+        .trim()
+        .parse::<u64>()
+        .map_err(|e| format!("Invalid flow range {}: {:?}", range, e))?;
+    Ok(FlowRange { start, end })
+}
+
+fn parse_stream_ids(ids: &[String]) -> Result<Vec<U256>, String> {
+    ids.iter()
+        .map(|id| U256::from_dec_str(id).map_err(|e| format!("{}: {:?}", id, e)))
+        .collect()
 }
@@ -14,16 +14,43 @@ build_config! {
     (network_libp2p_nodes, (Vec<String>), vec![])
     (network_private, (bool), false)
     (network_disable_discovery, (bool), false)
+    (network_enable_mdns, (bool), false)
+    (network_id, (u64), 1)
+    (network_name, (String), "".to_string())
+    (shard_id, (u64), 0)
+    (num_shard, (u64), 0) // 0 means no shard stored
+    (shard_flow_ranges, (Vec<String>), vec![]) // "start-end"
 
     // log sync
     (blockchain_rpc_endpoint, (String), "http://127.0.0.1:8545".to_string())
+    (blockchain_rpc_backup_endpoints, (Vec<String>), vec![])
+    (blockchain_rpc_quorum, (usize), 1)
+    (blockchain_ws_endpoint, (Option<String>), None)
     (log_contract_address, (String), "".to_string())
     (log_sync_start_block_number, (u64), 0)
     (confirmation_block_count, (u64), 12)
+    (confirmation_strategy, (String), "depth".to_string())
     (log_page_size, (u64), 1000)
+    (log_sync_submission_file, (String), "".to_string())
     (max_cache_data_size, (usize), 100 * 1024 * 1024) // 100 MB
+    (cache_dir, (Option<String>), None) // "<db_dir>/reverted_data" by default
+    (max_cache_disk_data_size, (usize), 10 * 1024 * 1024 * 1024) // 10 GB
     (cache_tx_seq_ttl, (usize), 500)
 
+    // sync
+    (sync_max_parallel_requests, (usize), 4)
+    (sync_max_upload_bytes_per_sec, (u64), 0)
+    (sync_max_upload_bytes_per_sec_per_peer, (u64), 0)
+    (sync_max_download_bytes_per_sec, (u64), 0)
+    (sync_max_download_bytes_per_sec_per_peer, (u64), 0)
+    (auto_sync_max_file_size, (u64), 0)
+    (auto_sync_allowed_stream_ids, (Vec<String>), vec![]) // decimal
+    (auto_sync_denied_stream_ids, (Vec<String>), vec![]) // decimal
+    (auto_sync_allowed_submitters, (Vec<String>), vec![])
+    (auto_sync_flow_range_start, (u64), 0)
+    (auto_sync_flow_range_end, (u64), 0) // 0 means unlimited
+    (auto_sync_sample_percent, (u8), 100)
+
     // rpc
     (rpc_enabled, (bool), true)
     (rpc_listen_address, (String), "127.0.0.1:5678".to_string())
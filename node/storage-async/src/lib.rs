@@ -4,7 +4,7 @@ extern crate tracing;
 use anyhow::bail;
 use shared_types::{Chunk, ChunkArray, ChunkArrayWithProof, DataRoot, Transaction};
 use std::sync::Arc;
-use storage::{error, error::Result, log_store::Store as LogStore, H256};
+use storage::{error, error::Result, log_store::Store as LogStore, H160, H256};
 use task_executor::TaskExecutor;
 use tokio::sync::{oneshot, RwLock};
 
@@ -38,12 +38,17 @@ impl Store {
     }
 
     delegate!(fn check_tx_completed(tx_seq: u64) -> Result<bool>);
+    delegate!(fn next_tx_seq() -> Result<u64>);
+    delegate!(fn get_context() -> Result<(DataRoot, u64)>);
     delegate!(fn get_chunk_by_tx_and_index(tx_seq: u64, index: usize) -> Result<Option<Chunk>>);
     delegate!(fn get_chunks_by_tx_and_index_range(tx_seq: u64, index_start: usize, index_end: usize) -> Result<Option<ChunkArray>>);
     delegate!(fn get_chunks_with_proof_by_tx_and_index_range(tx_seq: u64, index_start: usize, index_end: usize) -> Result<Option<ChunkArrayWithProof>>);
     delegate!(fn get_tx_by_seq_number(seq: u64) -> Result<Option<Transaction>>);
+    delegate!(fn get_tx_sender(tx_seq: u64) -> Result<Option<H160>>);
     delegate!(fn put_chunks(tx_seq: u64, chunks: ChunkArray) -> Result<()>);
     delegate!(fn put_chunks_with_tx_hash(tx_seq: u64, tx_hash: H256, chunks: ChunkArray) -> Result<bool>);
+    delegate!(fn get_available_chunks_by_tx(tx_seq: u64) -> Result<Vec<ChunkArray>>);
+    delegate!(fn get_available_chunk_ranges_by_tx(tx_seq: u64) -> Result<Vec<(u64, u64)>>);
     delegate!(fn get_chunk_by_flow_index(index: u64, length: u64) -> Result<Option<ChunkArray>>);
     delegate!(fn finalize_tx(tx_seq: u64) -> Result<()>);
     delegate!(fn finalize_tx_with_hash(tx_seq: u64, tx_hash: H256) -> Result<bool>);
@@ -54,6 +59,12 @@ impl Store {
             .await
     }
 
+    pub async fn get_tx_seq_list_by_data_root(&self, data_root: &DataRoot) -> Result<Vec<u64>> {
+        let root = *data_root;
+        self.spawn(move |store| store.get_tx_seq_list_by_data_root(&root))
+            .await
+    }
+
     pub async fn get_tx_by_data_root(&self, data_root: &DataRoot) -> Result<Option<Transaction>> {
         let root = *data_root;
         self.spawn(move |store| store.get_tx_by_data_root(&root))
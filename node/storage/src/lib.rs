@@ -7,7 +7,7 @@ pub mod log_store;
 pub use config::Config as StorageConfig;
 pub use log_store::log_manager::LogManager;
 
-pub use ethereum_types::H256;
+pub use ethereum_types::{H160, H256};
 
 pub trait IonianKeyValueDB: KeyValueDB {
     fn put(&self, col: u32, key: &[u8], value: &[u8]) -> std::io::Result<()> {
@@ -1,7 +1,9 @@
 use super::load_chunk::EntryBatch;
 use super::{MineLoadChunk, SealAnswer, SealTask};
 use crate::error::Error;
-use crate::log_store::log_manager::{bytes_to_entries, COL_ENTRY_BATCH, COL_ENTRY_BATCH_ROOT};
+use crate::log_store::log_manager::{
+    bytes_to_entries, COL_ENTRY_BATCH, COL_ENTRY_BATCH_RANGES, COL_ENTRY_BATCH_ROOT,
+};
 use crate::log_store::{FlowRead, FlowSeal, FlowWrite};
 use crate::{try_option, IonianKeyValueDB};
 use anyhow::{anyhow, bail, Result};
@@ -39,6 +41,10 @@ impl FlowStore {
     pub fn put_batch_root(&self, batch_index: u64, root: DataRoot, length: usize) -> Result<()> {
         self.db.put_batch_root(batch_index, root, length)
     }
+
+    pub fn batch_size(&self) -> usize {
+        self.config.batch_size
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -130,6 +136,37 @@ impl FlowRead for FlowStore {
         Ok(entry_list)
     }
 
+    fn get_available_entry_ranges(
+        &self,
+        index_start: u64,
+        index_end: u64,
+    ) -> Result<Vec<(u64, u64)>> {
+        let batch_size = self.config.batch_size as u64;
+        let mut ranges: Vec<(u64, u64)> = Vec::new();
+        if index_end <= index_start {
+            return Ok(ranges);
+        }
+        for batch_index in index_start / batch_size..(index_end + batch_size - 1) / batch_size {
+            let batch_start = batch_index * batch_size;
+            for (start, length) in self
+                .db
+                .get_entry_batch_ranges(batch_index)?
+                .unwrap_or_default()
+            {
+                let range_start = cmp::max(batch_start + start, index_start);
+                let range_end = cmp::min(batch_start + start + length, index_end);
+                if range_start >= range_end {
+                    continue;
+                }
+                match ranges.last_mut() {
+                    Some(last) if last.1 == range_start => last.1 = range_end,
+                    _ => ranges.push((range_start, range_end)),
+                }
+            }
+        }
+        Ok(ranges)
+    }
+
     /// Return the list of all stored chunk roots.
     fn get_chunk_root_list(&self) -> Result<Vec<(usize, DataRoot)>> {
         let mut chunk_roots = Vec::new();
@@ -311,6 +348,11 @@ impl FlowDBStore {
                 &batch_index.to_be_bytes(),
                 &batch.as_ssz_bytes(),
             );
+            tx.put(
+                COL_ENTRY_BATCH_RANGES,
+                &batch_index.to_be_bytes(),
+                &entry_ranges(&batch).as_ssz_bytes(),
+            );
             if let Some(root) = batch.build_root(batch_index == 0)? {
                 tx.put(
                     COL_ENTRY_BATCH_ROOT,
@@ -342,6 +384,22 @@ impl FlowDBStore {
         Ok(Some(EntryBatch::from_ssz_bytes(&raw).map_err(Error::from)?))
     }
 
+    /// Return the available entry ranges `(start, length)` in a batch.
+    fn get_entry_batch_ranges(&self, batch_index: u64) -> Result<Option<Vec<(u64, u64)>>> {
+        match self
+            .kvdb
+            .get(COL_ENTRY_BATCH_RANGES, &batch_index.to_be_bytes())?
+        {
+            Some(raw) => Ok(Some(
+                <Vec<(u64, u64)>>::from_ssz_bytes(&raw).map_err(Error::from)?,
+            )),
+            // The batches stored before the ranges are tracked.
+            None => Ok(self
+                .get_entry_batch(batch_index)?
+                .map(|batch| entry_ranges(&batch))),
+        }
+    }
+
     pub fn put_batch_root(&self, batch_index: u64, root: DataRoot, length: usize) -> Result<()> {
         let root = if length == 1 {
             BatchRoot::Single(root)
@@ -380,8 +438,14 @@ impl FlowDBStore {
                         &start_batch_index.to_be_bytes(),
                         &first_batch.as_ssz_bytes(),
                     );
+                    tx.put(
+                        COL_ENTRY_BATCH_RANGES,
+                        &start_batch_index.to_be_bytes(),
+                        &entry_ranges(&first_batch).as_ssz_bytes(),
+                    );
                 } else {
                     tx.delete(COL_ENTRY_BATCH, &start_batch_index.to_be_bytes());
+                    tx.delete(COL_ENTRY_BATCH_RANGES, &start_batch_index.to_be_bytes());
                 }
             }
 
@@ -398,6 +462,7 @@ impl FlowDBStore {
         };
         for batch_index in start_batch_index..=end {
             tx.delete(COL_ENTRY_BATCH, &batch_index.to_be_bytes());
+            tx.delete(COL_ENTRY_BATCH_RANGES, &batch_index.to_be_bytes());
             tx.delete(COL_ENTRY_BATCH_ROOT, &batch_index.to_be_bytes());
         }
         self.kvdb.write(tx)?;
@@ -405,6 +470,16 @@ impl FlowDBStore {
     }
 }
 
+/// The available entry ranges `(start, length)` of a batch, which are stored separately so that
+/// the availability of large files can be checked without reading the entries.
+fn entry_ranges(batch: &EntryBatch) -> Vec<(u64, u64)> {
+    batch
+        .available_range_entries()
+        .into_iter()
+        .map(|(start, length)| (start as u64, length as u64))
+        .collect()
+}
+
 #[derive(DeriveEncode, DeriveDecode)]
 #[ssz(enum_behaviour = "union")]
 pub enum BatchRoot {
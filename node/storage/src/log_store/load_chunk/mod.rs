@@ -149,6 +149,11 @@ impl EntryBatch {
             .collect()
     }
 
+    /// Return the available entry ranges `(start_entry, length_entry)` in this batch.
+    pub fn available_range_entries(&self) -> Vec<(usize, usize)> {
+        self.data.available_range_entries()
+    }
+
     fn truncate_seal(&mut self, truncated_sector: usize) -> Vec<u16> {
         let reverted_seal_index = (truncated_sector / SECTORS_PER_SEAL) as u16;
 
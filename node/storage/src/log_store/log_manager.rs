@@ -6,7 +6,7 @@ use crate::log_store::{
 use crate::{try_option, IonianKeyValueDB};
 use anyhow::{anyhow, bail, Result};
 use append_merkle::{Algorithm, AppendMerkleTree, MerkleTreeRead, Sha3Algorithm};
-use ethereum_types::H256;
+use ethereum_types::{H160, H256};
 use kvdb_rocksdb::{Database, DatabaseConfig};
 use merkle_light::merkle::{log2_pow2, MerkleTree};
 use merkle_tree::RawLeafSha3Algorithm;
@@ -16,6 +16,7 @@ use shared_types::{
     bytes_to_chunks, compute_padded_chunk_size, compute_segment_size, Chunk, ChunkArray,
     ChunkArrayWithProof, ChunkWithProof, DataRoot, FlowProof, FlowRangeProof, Transaction,
 };
+use std::cmp;
 use std::path::Path;
 use std::sync::Arc;
 use tracing::{debug, error, instrument, trace};
@@ -34,7 +35,10 @@ pub const COL_ENTRY_BATCH_ROOT: u32 = 3;
 pub const COL_TX_COMPLETED: u32 = 4;
 pub const COL_MISC: u32 = 5;
 pub const COL_SEAL_CONTEXT: u32 = 6;
-pub const COL_NUM: u32 = 7;
+pub const COL_LOG_SYNC_BLOCK: u32 = 7;
+pub const COL_ENTRY_BATCH_RANGES: u32 = 8;
+pub const COL_TX_SENDER: u32 = 9;
+pub const COL_NUM: u32 = 10;
 
 type Merkle = AppendMerkleTree<H256, Sha3Algorithm>;
 
@@ -198,8 +202,12 @@ impl LogStoreWrite for LogManager {
         }
     }
 
-    fn put_sync_progress(&self, progress: (u64, H256)) -> Result<()> {
-        self.tx_store.put_progress(progress)
+    fn put_sync_progress(&self, progress: (u64, H256), next_tx_seq: u64) -> Result<()> {
+        self.tx_store.put_progress(progress, next_tx_seq)
+    }
+
+    fn put_tx_sender(&self, tx_seq: u64, sender: H160) -> Result<()> {
+        self.tx_store.put_sender(tx_seq, sender)
     }
 
     /// Return the reverted Transactions in order.
@@ -275,6 +283,52 @@ impl LogStoreChunkRead for LogManager {
         todo!()
     }
 
+    fn get_available_chunks_by_tx(&self, tx_seq: u64) -> crate::error::Result<Vec<ChunkArray>> {
+        let tx = match self.get_tx_by_seq_number(tx_seq)? {
+            Some(tx) => tx,
+            None => return Ok(Vec::new()),
+        };
+        let start_flow_index = tx.start_entry_index;
+        let end_flow_index = tx.start_entry_index + tx.num_entries() as u64;
+        // `get_available_entries` only accepts the range at the batch boundaries.
+        let batch_size = self.flow_store.batch_size() as u64;
+        let batch_start = start_flow_index / batch_size * batch_size;
+        let batch_end = (end_flow_index + batch_size - 1) / batch_size * batch_size;
+        let mut tx_chunks = Vec::new();
+        for chunks in self
+            .flow_store
+            .get_available_entries(batch_start, batch_end)?
+        {
+            let chunks_end = chunks.start_index + (chunks.data.len() / ENTRY_SIZE) as u64;
+            if let Some(mut sub_array) = chunks.sub_array(
+                cmp::max(chunks.start_index, start_flow_index),
+                cmp::min(chunks_end, end_flow_index),
+            ) {
+                sub_array.start_index -= tx.start_entry_index;
+                tx_chunks.push(sub_array);
+            }
+        }
+        Ok(tx_chunks)
+    }
+
+    fn get_available_chunk_ranges_by_tx(
+        &self,
+        tx_seq: u64,
+    ) -> crate::error::Result<Vec<(u64, u64)>> {
+        let tx = match self.get_tx_by_seq_number(tx_seq)? {
+            Some(tx) => tx,
+            None => return Ok(Vec::new()),
+        };
+        let start_flow_index = tx.start_entry_index;
+        let end_flow_index = tx.start_entry_index + tx.num_entries() as u64;
+        Ok(self
+            .flow_store
+            .get_available_entry_ranges(start_flow_index, end_flow_index)?
+            .into_iter()
+            .map(|(start, end)| (start - start_flow_index, end - start_flow_index))
+            .collect())
+    }
+
     fn get_chunk_by_flow_index(
         &self,
         index: u64,
@@ -296,6 +350,10 @@ impl LogStoreRead for LogManager {
         self.tx_store.get_first_tx_seq_by_data_root(data_root)
     }
 
+    fn get_tx_seq_list_by_data_root(&self, data_root: &DataRoot) -> crate::error::Result<Vec<u64>> {
+        self.tx_store.get_tx_seq_list_by_data_root(data_root)
+    }
+
     fn get_chunk_with_proof_by_tx_and_index(
         &self,
         tx_seq: u64,
@@ -353,6 +411,14 @@ impl LogStoreRead for LogManager {
         self.tx_store.get_progress()
     }
 
+    fn get_synced_blocks(&self) -> Result<Vec<(u64, H256, u64)>> {
+        self.tx_store.get_synced_blocks()
+    }
+
+    fn get_tx_sender(&self, tx_seq: u64) -> Result<Option<H160>> {
+        self.tx_store.get_sender(tx_seq)
+    }
+
     fn next_tx_seq(&self) -> Result<u64> {
         self.tx_store.next_tx_seq()
     }
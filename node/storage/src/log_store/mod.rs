@@ -1,4 +1,4 @@
-use ethereum_types::H256;
+use ethereum_types::{H160, H256};
 use ionian_spec::{BYTES_PER_SEAL, SEALS_PER_LOAD};
 use shared_types::{
     Chunk, ChunkArray, ChunkArrayWithProof, ChunkWithProof, DataRoot, FlowRangeProof, Transaction,
@@ -25,6 +25,9 @@ pub trait LogStoreRead: LogStoreChunkRead {
     /// Get a transaction by the data root of its data.
     fn get_tx_seq_by_data_root(&self, data_root: &DataRoot) -> Result<Option<u64>>;
 
+    /// Get all the transactions of the data root in ascending order.
+    fn get_tx_seq_list_by_data_root(&self, data_root: &DataRoot) -> Result<Vec<u64>>;
+
     fn get_tx_by_data_root(&self, data_root: &DataRoot) -> Result<Option<Transaction>> {
         match self.get_tx_seq_by_data_root(data_root)? {
             Some(seq) => self.get_tx_by_seq_number(seq),
@@ -51,6 +54,15 @@ pub trait LogStoreRead: LogStoreChunkRead {
 
     fn get_sync_progress(&self) -> Result<Option<(u64, H256)>>;
 
+    /// Return the recently synced blocks as `(block_number, block_hash, next_tx_seq)` in
+    /// ascending order, where `next_tx_seq` is the first tx seq submitted after this block.
+    /// This is used to find the common ancestor if chain reorg happens during node restart.
+    fn get_synced_blocks(&self) -> Result<Vec<(u64, H256, u64)>>;
+
+    /// Get the account that submitted a transaction, which is not known for the transactions
+    /// replayed from other sources than the chain.
+    fn get_tx_sender(&self, tx_seq: u64) -> Result<Option<H160>>;
+
     fn validate_range_proof(&self, tx_seq: u64, data: &ChunkArrayWithProof) -> Result<bool>;
 
     fn get_proof_at_root(&self, root: &DataRoot, index: u64, length: u64)
@@ -88,6 +100,15 @@ pub trait LogStoreChunkRead {
 
     fn get_chunk_index_list(&self, tx_seq: u64) -> Result<Vec<usize>>;
 
+    /// Return the available chunks of a transaction that may not be completed.
+    /// The `ChunkArray` in the returned list are in order, and their `start_index` are the
+    /// offsets in the transaction.
+    fn get_available_chunks_by_tx(&self, tx_seq: u64) -> Result<Vec<ChunkArray>>;
+
+    /// Return the available chunk ranges `[start, end)` of a transaction as the offsets in the
+    /// transaction. Only the range metadata are read, so it's cheap for large files.
+    fn get_available_chunk_ranges_by_tx(&self, tx_seq: u64) -> Result<Vec<(u64, u64)>>;
+
     /// Accessing chunks by absolute flow index
     fn get_chunk_by_flow_index(&self, index: u64, length: u64) -> Result<Option<ChunkArray>>;
 }
@@ -106,7 +127,15 @@ pub trait LogStoreWrite: LogStoreChunkWrite {
     fn finalize_tx_with_hash(&mut self, tx_seq: u64, tx_hash: H256) -> Result<bool>;
 
     /// Store the progress of synced block number and its hash.
-    fn put_sync_progress(&self, progress: (u64, H256)) -> Result<()>;
+    /// `next_tx_seq` is the first tx seq submitted after this block.
+    ///
+    /// The synced blocks after this block are removed, so this can also be used to revert the
+    /// progress after chain reorg.
+    fn put_sync_progress(&self, progress: (u64, H256), next_tx_seq: u64) -> Result<()>;
+
+    /// Store the account that submitted a transaction. It's kept out of `Transaction`, so the
+    /// stored transactions and their hashes are not changed.
+    fn put_tx_sender(&self, tx_seq: u64, sender: H160) -> Result<()>;
 
     /// Revert the log state to a given tx seq.
     /// This is needed when transactions are reverted because of chain reorg.
@@ -172,6 +201,14 @@ pub trait FlowRead {
     /// For simplicity, `index_start` and `index_end` must be at the batch boundaries.
     fn get_available_entries(&self, index_start: u64, index_end: u64) -> Result<Vec<ChunkArray>>;
 
+    /// Return the available entry ranges `[start, end)` in the given range without reading the
+    /// entries. The ranges are in order and they will not overlap or be adjacent.
+    fn get_available_entry_ranges(
+        &self,
+        index_start: u64,
+        index_end: u64,
+    ) -> Result<Vec<(u64, u64)>>;
+
     fn get_chunk_root_list(&self) -> Result<Vec<(usize, DataRoot)>>;
 
     fn load_sealed_data(&self, chunk_index: u64) -> Result<Option<MineLoadChunk>>;
@@ -2,9 +2,12 @@ use crate::log_store::log_manager::{
     data_to_merkle_leaves, sub_merkle_tree, tx_subtree_root_list_padded, LogConfig, LogManager,
     PORA_CHUNK_SIZE,
 };
-use crate::log_store::{LogStoreChunkRead, LogStoreChunkWrite, LogStoreRead, LogStoreWrite};
+use crate::log_store::tx_store::LOG_SYNC_BLOCK_HISTORY;
+use crate::log_store::{
+    FlowWrite, LogStoreChunkRead, LogStoreChunkWrite, LogStoreInner, LogStoreRead, LogStoreWrite,
+};
 use append_merkle::{Algorithm, AppendMerkleTree, MerkleTreeRead, Sha3Algorithm};
-use ethereum_types::H256;
+use ethereum_types::{H160, H256};
 use rand::random;
 use shared_types::{compute_padded_chunk_size, ChunkArray, Transaction, CHUNK_SIZE};
 use std::cmp;
@@ -150,6 +153,136 @@ fn test_revert() {
     put_tx(&mut store, 1, 1, 2);
 }
 
+#[test]
+fn test_tx_sender() {
+    let mut store = create_store();
+    put_tx(&mut store, 1, 0, 1);
+    put_tx(&mut store, 1, 1, 2);
+    assert_eq!(store.get_tx_sender(0).unwrap(), None);
+
+    let sender = H160::from_low_u64_be(1);
+    store.put_tx_sender(0, sender).unwrap();
+    store.put_tx_sender(1, sender).unwrap();
+    assert_eq!(store.get_tx_sender(0).unwrap(), Some(sender));
+
+    // The sender is removed with the reverted transaction.
+    store.revert_to(0).unwrap();
+    assert_eq!(store.get_tx_sender(0).unwrap(), Some(sender));
+    assert_eq!(store.get_tx_sender(1).unwrap(), None);
+}
+
+#[test]
+fn test_sync_progress() {
+    let store = create_store();
+    assert_eq!(store.get_sync_progress().unwrap(), None);
+    for block_number in 0..3 {
+        let block_hash = H256::from_low_u64_be(block_number);
+        store
+            .put_sync_progress((block_number, block_hash), block_number + 1)
+            .unwrap();
+    }
+    assert_eq!(
+        store.get_sync_progress().unwrap(),
+        Some((2, H256::from_low_u64_be(2)))
+    );
+    assert_eq!(store.get_synced_blocks().unwrap().len(), 3);
+
+    // Revert the progress, and the blocks after it are removed.
+    store
+        .put_sync_progress((1, H256::from_low_u64_be(10)), 1)
+        .unwrap();
+    assert_eq!(
+        store.get_synced_blocks().unwrap(),
+        vec![
+            (0, H256::from_low_u64_be(0), 1),
+            (1, H256::from_low_u64_be(10), 1)
+        ]
+    );
+}
+
+#[test]
+fn test_sync_progress_history() {
+    let store = create_store();
+    let last_block_number = LOG_SYNC_BLOCK_HISTORY + 10;
+    for block_number in 0..=last_block_number {
+        store
+            .put_sync_progress((block_number, H256::from_low_u64_be(block_number)), 0)
+            .unwrap();
+    }
+
+    // Only the recent blocks are kept.
+    let synced_blocks = store.get_synced_blocks().unwrap();
+    assert_eq!(synced_blocks.len() as u64, LOG_SYNC_BLOCK_HISTORY + 1);
+    assert_eq!(
+        synced_blocks[0].0,
+        last_block_number - LOG_SYNC_BLOCK_HISTORY
+    );
+
+    // Jump forward beyond the history.
+    let block_number = last_block_number * 3;
+    store
+        .put_sync_progress((block_number, H256::from_low_u64_be(block_number)), 0)
+        .unwrap();
+    assert_eq!(
+        store.get_synced_blocks().unwrap(),
+        vec![(block_number, H256::from_low_u64_be(block_number), 0)]
+    );
+}
+
+#[test]
+fn test_available_chunks() {
+    let mut store = create_store();
+    let chunk_count = 8;
+    let mut data = vec![0u8; CHUNK_SIZE * chunk_count];
+    for i in 0..chunk_count {
+        data[i * CHUNK_SIZE] = random();
+    }
+    let tx = Transaction {
+        stream_ids: vec![],
+        size: data.len() as u64,
+        data_merkle_root: sub_merkle_tree(&data).unwrap().root().into(),
+        seq: 0,
+        data: vec![],
+        start_entry_index: 0,
+        merkle_nodes: tx_subtree_root_list_padded(&data),
+    };
+    store.put_tx(tx).unwrap();
+    assert!(store.get_available_chunks_by_tx(0).unwrap().is_empty());
+    assert!(store
+        .get_available_chunk_ranges_by_tx(0)
+        .unwrap()
+        .is_empty());
+
+    let chunk_array = ChunkArray {
+        data,
+        start_index: 0,
+    };
+    let partial = vec![
+        chunk_array.sub_array(0, 3).unwrap(),
+        chunk_array.sub_array(5, 8).unwrap(),
+    ];
+    for chunks in &partial {
+        store.put_chunks(0, chunks.clone()).unwrap();
+    }
+    assert_eq!(store.get_available_chunks_by_tx(0).unwrap(), partial);
+    assert!(store.get_available_chunks_by_tx(1).unwrap().is_empty());
+    assert_eq!(
+        store.get_available_chunk_ranges_by_tx(0).unwrap(),
+        vec![(0, 3), (5, 8)]
+    );
+    assert!(store
+        .get_available_chunk_ranges_by_tx(1)
+        .unwrap()
+        .is_empty());
+
+    // The ranges are updated after the data are reverted.
+    store.flow_mut().truncate(2).unwrap();
+    assert_eq!(
+        store.get_available_chunk_ranges_by_tx(0).unwrap(),
+        vec![(0, 2)]
+    );
+}
+
 fn create_store() -> LogManager {
     let config = LogConfig::default();
 
@@ -1,12 +1,12 @@
 use crate::error::Error;
 use crate::log_store::log_manager::{
-    data_to_merkle_leaves, sub_merkle_tree, COL_MISC, COL_TX, COL_TX_COMPLETED,
-    COL_TX_DATA_ROOT_INDEX, ENTRY_SIZE, PORA_CHUNK_SIZE,
+    data_to_merkle_leaves, sub_merkle_tree, COL_LOG_SYNC_BLOCK, COL_MISC, COL_TX, COL_TX_COMPLETED,
+    COL_TX_DATA_ROOT_INDEX, COL_TX_SENDER, ENTRY_SIZE, PORA_CHUNK_SIZE,
 };
 use crate::{try_option, IonianKeyValueDB, LogManager};
 use anyhow::{anyhow, Result};
 use append_merkle::{AppendMerkleTree, MerkleTreeRead, Sha3Algorithm};
-use ethereum_types::H256;
+use ethereum_types::{H160, H256};
 use merkle_light::merkle::log2_pow2;
 use shared_types::{DataRoot, Transaction};
 use ssz::{Decode, Encode};
@@ -15,6 +15,8 @@ use std::sync::Arc;
 use tracing::instrument;
 
 const LOG_SYNC_PROGRESS_KEY: &str = "log_sync_progress";
+/// The number of recent blocks whose hashes are kept to handle chain reorg.
+pub(crate) const LOG_SYNC_BLOCK_HISTORY: u64 = 1000;
 
 pub struct TransactionStore {
     kvdb: Arc<dyn IonianKeyValueDB>,
@@ -71,6 +73,7 @@ impl TransactionStore {
         let mut db_tx = self.kvdb.transaction();
         db_tx.delete(COL_TX, &seq.to_be_bytes());
         db_tx.delete(COL_TX_COMPLETED, &seq.to_be_bytes());
+        db_tx.delete(COL_TX_SENDER, &seq.to_be_bytes());
         // We only remove tx when the blockchain reorgs.
         // If a tx is reverted, all data after it will also be reverted, so we call remove
         // all indices after it.
@@ -89,6 +92,20 @@ impl TransactionStore {
         Ok(Some(tx))
     }
 
+    pub fn put_sender(&self, tx_seq: u64, sender: H160) -> Result<()> {
+        Ok(self
+            .kvdb
+            .put(COL_TX_SENDER, &tx_seq.to_be_bytes(), sender.as_bytes())?)
+    }
+
+    pub fn get_sender(&self, tx_seq: u64) -> Result<Option<H160>> {
+        let value = try_option!(self.kvdb.get(COL_TX_SENDER, &tx_seq.to_be_bytes())?);
+        if value.len() != H160::len_bytes() {
+            return Err(anyhow!("incorrect sender length: {}", value.len()));
+        }
+        Ok(Some(H160::from_slice(&value)))
+    }
+
     pub fn get_tx_seq_list_by_data_root(&self, data_root: &DataRoot) -> Result<Vec<u64>> {
         let value = match self
             .kvdb
@@ -130,12 +147,33 @@ impl TransactionStore {
     }
 
     #[instrument(skip(self))]
-    pub fn put_progress(&self, progress: (u64, H256)) -> Result<()> {
-        Ok(self.kvdb.put(
+    pub fn put_progress(&self, progress: (u64, H256), next_tx_seq: u64) -> Result<()> {
+        let (block_number, block_hash) = progress;
+        let mut db_tx = self.kvdb.transaction();
+        db_tx.put(
             COL_MISC,
             LOG_SYNC_PROGRESS_KEY.as_bytes(),
             &progress.as_ssz_bytes(),
-        )?)
+        );
+        // Remove the blocks that are too old, or reverted if the progress goes back. Only the
+        // blocks within the history of the previous progress are stored, so they are removed by
+        // key instead of iterating the column.
+        if let Some((prev_block_number, _)) = self.get_progress()? {
+            let prev_oldest_block_number = prev_block_number.saturating_sub(LOG_SYNC_BLOCK_HISTORY);
+            let oldest_block_number = block_number.saturating_sub(LOG_SYNC_BLOCK_HISTORY);
+            let stale_blocks =
+                prev_oldest_block_number..oldest_block_number.min(prev_block_number + 1);
+            let reverted_blocks = (block_number + 1)..=prev_block_number;
+            for stale_block_number in stale_blocks.chain(reverted_blocks) {
+                db_tx.delete(COL_LOG_SYNC_BLOCK, &stale_block_number.to_be_bytes());
+            }
+        }
+        db_tx.put(
+            COL_LOG_SYNC_BLOCK,
+            &block_number.to_be_bytes(),
+            &(block_hash, next_tx_seq).as_ssz_bytes(),
+        );
+        Ok(self.kvdb.write(db_tx)?)
     }
 
     #[instrument(skip(self))]
@@ -148,6 +186,17 @@ impl TransactionStore {
         ))
     }
 
+    #[instrument(skip(self))]
+    pub fn get_synced_blocks(&self) -> Result<Vec<(u64, H256, u64)>> {
+        let mut synced_blocks = Vec::new();
+        for (key, value) in self.kvdb.iter(COL_LOG_SYNC_BLOCK) {
+            let (block_hash, next_tx_seq) =
+                <(H256, u64)>::from_ssz_bytes(value.as_ref()).map_err(Error::from)?;
+            synced_blocks.push((decode_block_number(key.as_ref())?, block_hash, next_tx_seq));
+        }
+        Ok(synced_blocks)
+    }
+
     /// Build the merkle tree at `pora_chunk_index` with the data before (including) `tx_seq`.
     /// This first rebuild the tree with the tx root nodes lists by repeatedly checking previous
     /// until we reach the start of this chunk.
@@ -245,3 +294,9 @@ fn decode_tx_seq(data: &[u8]) -> Result<u64> {
         data.try_into().map_err(|e| anyhow!("{:?}", e))?,
     ))
 }
+
+fn decode_block_number(data: &[u8]) -> Result<u64> {
+    Ok(u64::from_be_bytes(
+        data.try_into().map_err(|e| anyhow!("{:?}", e))?,
+    ))
+}
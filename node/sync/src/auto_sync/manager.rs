@@ -1,3 +1,4 @@
+use super::policy::Policy;
 use super::sync_store::SyncStore;
 use crate::{controllers::SyncState, SyncRequest, SyncResponse, SyncSender};
 use anyhow::{bail, Result};
@@ -46,12 +47,15 @@ pub struct Manager {
     store: Store,
     sync_store: SyncStore,
 
+    /// Policy to decide which files to synchronize in sequence.
+    policy: Arc<Policy>,
+
     /// Used to interact with sync service for the current file in sync.
     sync_send: SyncSender,
 }
 
 impl Manager {
-    pub async fn new(store: Store, sync_send: SyncSender) -> Result<Self> {
+    pub async fn new(store: Store, sync_send: SyncSender, policy: Policy) -> Result<Self> {
         let sync_store = SyncStore::new(store.clone());
 
         let (next_tx_seq, max_tx_seq) = sync_store.get_tx_seq_range().await?;
@@ -64,6 +68,7 @@ impl Manager {
             reverted_tx_seq: Arc::new(AtomicU64::new(u64::MAX)),
             store,
             sync_store,
+            policy: Arc::new(policy),
             sync_send,
         })
     }
@@ -275,6 +280,17 @@ async fn sync_once(manager: &Manager) -> Result<bool> {
         return Ok(true);
     }
 
+    // skipped by policy, and could be synchronized on demand
+    if let Some(tx) = manager.store.get_tx_by_seq_number(next_tx_seq).await? {
+        let sender = manager.store.get_tx_sender(next_tx_seq).await?;
+        if let Some(reason) = manager.policy.skip_reason(&tx, sender) {
+            debug!(%next_tx_seq, %reason, "Skip to sync file by policy");
+            manager.sync_store.add_skipped_tx(next_tx_seq).await?;
+            assert!(manager.move_forward(false).await?);
+            return Ok(true);
+        }
+    }
+
     // try sync tx
     let no_peer_timeout = manager.sync_tx(next_tx_seq).await?;
 
@@ -346,9 +362,15 @@ mod tests {
 
     use channel::Channel;
 
-    use crate::{auto_sync::sync_store::SyncStore, test_util::tests::TestStoreRuntime};
+    use storage_async::Store;
+    use task_executor::test_utils::TestRuntime;
 
-    use super::Manager;
+    use crate::{
+        auto_sync::{policy::Policy, sync_store::SyncStore},
+        test_util::tests::{create_2_store, TestStoreRuntime},
+    };
+
+    use super::{sync_once, Manager};
 
     async fn new_manager(runtime: &TestStoreRuntime, next_tx_seq: u64, max_tx_seq: u64) -> Manager {
         let sync_store = SyncStore::new(runtime.store.clone());
@@ -358,7 +380,7 @@ mod tests {
         }
 
         let (sync_send, _) = Channel::unbounded();
-        Manager::new(runtime.store.clone(), sync_send)
+        Manager::new(runtime.store.clone(), sync_send, Policy::default())
             .await
             .unwrap()
     }
@@ -474,4 +496,27 @@ mod tests {
         assert_eq!(manager.next_tx_seq.load(Ordering::Relaxed), 6);
         assert_eq!(manager.max_tx_seq.load(Ordering::Relaxed), 5);
     }
+
+    #[tokio::test]
+    async fn test_manager_skip_by_policy() {
+        let runtime = TestRuntime::default();
+        let (store, _, _, _) = create_2_store(vec![1024]);
+        let store = Store::new(store, runtime.task_executor.clone());
+
+        let mut policy = Policy::default();
+        policy.max_file_size = 1024;
+
+        let (sync_send, _) = Channel::unbounded();
+        let manager = Manager::new(store, sync_send, policy).await.unwrap();
+
+        // tx 0 skipped and recorded
+        assert_eq!(sync_once(&manager).await.unwrap(), true);
+        assert_eq!(manager.next_tx_seq.load(Ordering::Relaxed), 1);
+        assert_eq!(manager.sync_store.add_skipped_tx(0).await.unwrap(), false);
+        assert_eq!(manager.sync_store.random_tx().await.unwrap(), None);
+
+        // tx 1 not available yet
+        assert_eq!(sync_once(&manager).await.unwrap(), false);
+        assert_eq!(manager.next_tx_seq.load(Ordering::Relaxed), 1);
+    }
 }
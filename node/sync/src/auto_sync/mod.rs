@@ -1,5 +1,7 @@
 mod manager;
+mod policy;
 mod sync_store;
 mod tx_store;
 
 pub use manager::Manager as AutoSyncManager;
+pub use policy::Policy as AutoSyncPolicy;
@@ -0,0 +1,229 @@
+use ethereum_types::{H160, U256};
+use rand::Rng;
+use shared_types::Transaction;
+
+/// Policy to decide which files to synchronize automatically.
+///
+/// Files skipped by policy are not synchronized in sequence, but could still be
+/// synchronized on demand, e.g. via `admin_startSyncFile`.
+#[derive(Clone, Debug)]
+pub struct Policy {
+    /// Maximum file size in bytes, 0 means unlimited.
+    pub max_file_size: u64,
+
+    /// Only sync files of these streams if not empty.
+    pub allowed_stream_ids: Vec<U256>,
+
+    /// Never sync files of these streams.
+    pub denied_stream_ids: Vec<U256>,
+
+    /// Only sync files submitted by these accounts if not empty.
+    pub allowed_submitters: Vec<H160>,
+
+    /// Only sync files that overlap the flow entry range `[start, end)`.
+    pub flow_range: Option<(u64, u64)>,
+
+    /// Percentage of files to sync by random sampling, in range `[0, 100]`.
+    pub sample_percent: u8,
+
+    /// Random seed for sampling, so that nodes sample different files, while the same
+    /// file is always sampled the same way.
+    sample_seed: u64,
+}
+
+impl Default for Policy {
+    fn default() -> Self {
+        Self {
+            max_file_size: 0,
+            allowed_stream_ids: vec![],
+            denied_stream_ids: vec![],
+            allowed_submitters: vec![],
+            flow_range: None,
+            sample_percent: 100,
+            sample_seed: rand::thread_rng().gen(),
+        }
+    }
+}
+
+impl Policy {
+    /// Returns the reason if the specified transaction should not be synchronized automatically.
+    ///
+    /// The `sender` is `None` if unknown, e.g. replayed from a submission file.
+    pub fn skip_reason(&self, tx: &Transaction, sender: Option<H160>) -> Option<&'static str> {
+        if self.max_file_size > 0 && tx.size > self.max_file_size {
+            return Some("file too large");
+        }
+
+        if !self.allowed_stream_ids.is_empty()
+            && !tx
+                .stream_ids
+                .iter()
+                .any(|id| self.allowed_stream_ids.contains(id))
+        {
+            return Some("stream not allowed");
+        }
+
+        if tx
+            .stream_ids
+            .iter()
+            .any(|id| self.denied_stream_ids.contains(id))
+        {
+            return Some("stream denied");
+        }
+
+        if !self.allowed_submitters.is_empty()
+            && !sender.map_or(false, |sender| self.allowed_submitters.contains(&sender))
+        {
+            return Some("submitter not allowed");
+        }
+
+        if let Some((start, end)) = self.flow_range {
+            let tx_end = tx.start_entry_index + tx.num_entries() as u64;
+            if tx.start_entry_index >= end || tx_end <= start {
+                return Some("out of flow range");
+            }
+        }
+
+        if self.sample_percent < 100 && !self.is_sampled(tx) {
+            return Some("not sampled");
+        }
+
+        None
+    }
+
+    fn is_sampled(&self, tx: &Transaction) -> bool {
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(&tx.data_merkle_root[..8]);
+        let value = u64::from_be_bytes(bytes) ^ self.sample_seed;
+
+        value % 100 < self.sample_percent as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Policy;
+    use ethereum_types::{H160, U256};
+    use shared_types::Transaction;
+
+    fn new_tx(size: u64, stream_ids: Vec<u64>) -> Transaction {
+        Transaction {
+            stream_ids: stream_ids.into_iter().map(U256::from).collect(),
+            data: vec![],
+            data_merkle_root: Default::default(),
+            merkle_nodes: vec![(3, Default::default())],
+            start_entry_index: 1024,
+            size,
+            seq: 0,
+        }
+    }
+
+    fn sender(id: u64) -> Option<H160> {
+        Some(H160::from_low_u64_be(id))
+    }
+
+    #[test]
+    fn test_policy_default() {
+        let policy = Policy::default();
+        assert_eq!(policy.skip_reason(&new_tx(1024, vec![]), sender(1)), None);
+        assert_eq!(
+            policy.skip_reason(&new_tx(u64::MAX, vec![1]), sender(2)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_policy_max_file_size() {
+        let policy = Policy {
+            max_file_size: 1024,
+            ..Default::default()
+        };
+        assert_eq!(policy.skip_reason(&new_tx(1024, vec![]), sender(1)), None);
+        assert_eq!(
+            policy.skip_reason(&new_tx(1025, vec![]), sender(1)),
+            Some("file too large")
+        );
+    }
+
+    #[test]
+    fn test_policy_stream_ids() {
+        let policy = Policy {
+            allowed_stream_ids: vec![U256::from(1), U256::from(2)],
+            denied_stream_ids: vec![U256::from(2)],
+            ..Default::default()
+        };
+        assert_eq!(policy.skip_reason(&new_tx(1024, vec![1]), sender(1)), None);
+        assert_eq!(
+            policy.skip_reason(&new_tx(1024, vec![]), sender(1)),
+            Some("stream not allowed")
+        );
+        assert_eq!(
+            policy.skip_reason(&new_tx(1024, vec![1, 2]), sender(1)),
+            Some("stream denied")
+        );
+    }
+
+    #[test]
+    fn test_policy_submitters() {
+        let policy = Policy {
+            allowed_submitters: vec![H160::from_low_u64_be(1)],
+            ..Default::default()
+        };
+        assert_eq!(policy.skip_reason(&new_tx(1024, vec![]), sender(1)), None);
+        assert_eq!(
+            policy.skip_reason(&new_tx(1024, vec![]), sender(2)),
+            Some("submitter not allowed")
+        );
+        assert_eq!(
+            policy.skip_reason(&new_tx(1024, vec![]), None),
+            Some("submitter not allowed")
+        );
+    }
+
+    #[test]
+    fn test_policy_flow_range() {
+        // the tx covers flow entries [1024, 1028)
+        let overlapped = Policy {
+            flow_range: Some((1027, 2048)),
+            ..Default::default()
+        };
+        assert_eq!(
+            overlapped.skip_reason(&new_tx(1024, vec![]), sender(1)),
+            None
+        );
+
+        for range in [(0, 1024), (1028, 2048)] {
+            let policy = Policy {
+                flow_range: Some(range),
+                ..Default::default()
+            };
+            assert_eq!(
+                policy.skip_reason(&new_tx(1024, vec![]), sender(1)),
+                Some("out of flow range")
+            );
+        }
+    }
+
+    #[test]
+    fn test_policy_sample_percent() {
+        let policy = Policy {
+            sample_percent: 0,
+            ..Default::default()
+        };
+        assert_eq!(
+            policy.skip_reason(&new_tx(1024, vec![]), sender(1)),
+            Some("not sampled")
+        );
+
+        // always the same result for the same file
+        let policy = Policy {
+            sample_percent: 50,
+            ..Default::default()
+        };
+        let tx = new_tx(1024, vec![]);
+        let reason = policy.skip_reason(&tx, sender(1));
+        for _ in 0..10 {
+            assert_eq!(policy.skip_reason(&tx, sender(1)), reason);
+        }
+    }
+}
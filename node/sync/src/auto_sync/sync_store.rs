@@ -17,6 +17,9 @@ pub struct SyncStore {
     /// Ready transactions to sync with high priority since announcement
     /// already received from other peers.
     ready_txs: TxStore,
+
+    /// Transactions skipped by policy, which could be synchronized on demand.
+    skipped_txs: TxStore,
 }
 
 impl SyncStore {
@@ -25,6 +28,7 @@ impl SyncStore {
             store,
             pending_txs: TxStore::new("pending"),
             ready_txs: TxStore::new("ready"),
+            skipped_txs: TxStore::new("skipped"),
         }
     }
 
@@ -68,6 +72,11 @@ impl SyncStore {
         self.pending_txs.add(store.deref(), None, tx_seq)
     }
 
+    pub async fn add_skipped_tx(&self, tx_seq: u64) -> Result<bool> {
+        let store = self.store.get_store().write().await;
+        self.skipped_txs.add(store.deref(), None, tx_seq)
+    }
+
     pub async fn upgrade_tx_to_ready(&self, tx_seq: u64) -> Result<bool> {
         let store = self.store.get_store().write().await;
 
@@ -169,6 +178,19 @@ mod tests {
         assert_eq!(store.add_pending_tx(3).await.unwrap(), false);
     }
 
+    #[tokio::test]
+    async fn test_add_skipped_tx() {
+        let runtime = TestStoreRuntime::default();
+        let store = SyncStore::new(runtime.store.clone());
+
+        // add skipped tx 3
+        assert_eq!(store.add_skipped_tx(3).await.unwrap(), true);
+        assert_eq!(store.add_skipped_tx(3).await.unwrap(), false);
+
+        // skipped tx is not pending to sync
+        assert_eq!(store.random_tx().await.unwrap(), None);
+    }
+
     #[tokio::test]
     async fn test_upgrade_tx() {
         let runtime = TestStoreRuntime::default();
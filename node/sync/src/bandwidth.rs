@@ -0,0 +1,162 @@
+use crate::controllers::MAX_CHUNKS_TO_REQUEST;
+use network::rpc::{Limiter, Quota};
+use network::PeerId;
+use serde::{Deserialize, Serialize};
+use shared_types::CHUNK_SIZE;
+use std::time::{Duration, Instant};
+
+/// Bandwidth limits of chunks served to and fetched from peers, in bytes per second.
+/// A limit of 0 means unlimited.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BandwidthLimits {
+    pub max_upload_bytes_per_sec: u64,
+    pub max_upload_bytes_per_sec_per_peer: u64,
+    pub max_download_bytes_per_sec: u64,
+    pub max_download_bytes_per_sec_per_peer: u64,
+}
+
+/// Limits the bandwidth of chunks requests with token buckets, where a token is a byte.
+///
+/// It uses the same GCRA limiters as the network `RPCRateLimiter` quotas. Every bucket allows
+/// a burst of at least one chunks request of `MAX_CHUNKS_TO_REQUEST` chunks, otherwise such
+/// requests could never be allowed.
+pub struct BandwidthLimiter {
+    limits: BandwidthLimits,
+    init_time: Instant,
+    upload: DirectionLimiter,
+    download: DirectionLimiter,
+}
+
+struct DirectionLimiter {
+    total: Option<Limiter<()>>,
+    per_peer: Option<Limiter<PeerId>>,
+}
+
+impl DirectionLimiter {
+    fn new(bytes_per_sec: u64, bytes_per_sec_per_peer: u64) -> Self {
+        Self {
+            total: new_limiter(bytes_per_sec),
+            per_peer: new_limiter(bytes_per_sec_per_peer),
+        }
+    }
+
+    fn allows(&mut self, time_since_start: Duration, peer_id: &PeerId, bytes: u64) -> bool {
+        if let Some(limiter) = self.per_peer.as_mut() {
+            if limiter.allows(time_since_start, peer_id, bytes).is_err() {
+                return false;
+            }
+        }
+
+        match self.total.as_mut() {
+            Some(limiter) => limiter.allows(time_since_start, &(), bytes).is_ok(),
+            None => true,
+        }
+    }
+}
+
+fn new_limiter<Key: std::hash::Hash + Eq + Clone>(bytes_per_sec: u64) -> Option<Limiter<Key>> {
+    if bytes_per_sec == 0 {
+        return None;
+    }
+
+    let burst = bytes_per_sec.max(MAX_CHUNKS_TO_REQUEST * CHUNK_SIZE as u64);
+    let period = Duration::from_secs_f64(burst as f64 / bytes_per_sec as f64);
+
+    // the quota is always valid since both burst and period are positive
+    Limiter::from_quota(Quota::n_every(burst, period)).ok()
+}
+
+impl BandwidthLimiter {
+    pub fn new(limits: BandwidthLimits) -> Self {
+        Self {
+            limits,
+            init_time: Instant::now(),
+            upload: DirectionLimiter::new(
+                limits.max_upload_bytes_per_sec,
+                limits.max_upload_bytes_per_sec_per_peer,
+            ),
+            download: DirectionLimiter::new(
+                limits.max_download_bytes_per_sec,
+                limits.max_download_bytes_per_sec_per_peer,
+            ),
+        }
+    }
+
+    pub fn limits(&self) -> BandwidthLimits {
+        self.limits
+    }
+
+    /// Returns whether to serve `bytes` of chunks to the peer now, and consumes the tokens if so.
+    pub fn allows_upload(&mut self, peer_id: &PeerId, bytes: u64) -> bool {
+        let time_since_start = self.init_time.elapsed();
+        self.upload.allows(time_since_start, peer_id, bytes)
+    }
+
+    /// Returns whether to request `bytes` of chunks from the peer now, and consumes the tokens
+    /// if so.
+    pub fn allows_download(&mut self, peer_id: &PeerId, bytes: u64) -> bool {
+        let time_since_start = self.init_time.elapsed();
+        self.download.allows(time_since_start, peer_id, bytes)
+    }
+}
+
+impl Default for BandwidthLimiter {
+    fn default() -> Self {
+        Self::new(Default::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libp2p::identity;
+
+    const MAX_REQUEST_BYTES: u64 = MAX_CHUNKS_TO_REQUEST * CHUNK_SIZE as u64;
+
+    #[test]
+    fn test_unlimited() {
+        let mut limiter = BandwidthLimiter::default();
+        let peer_id = identity::Keypair::generate_ed25519().public().to_peer_id();
+
+        for _ in 0..100 {
+            assert!(limiter.allows_upload(&peer_id, MAX_REQUEST_BYTES));
+            assert!(limiter.allows_download(&peer_id, MAX_REQUEST_BYTES));
+        }
+    }
+
+    #[test]
+    fn test_per_peer_limit() {
+        let mut limiter = BandwidthLimiter::new(BandwidthLimits {
+            max_upload_bytes_per_sec_per_peer: 1024,
+            ..Default::default()
+        });
+        let peer1 = identity::Keypair::generate_ed25519().public().to_peer_id();
+        let peer2 = identity::Keypair::generate_ed25519().public().to_peer_id();
+
+        // a burst of one full request is allowed, even if larger than the limit per second
+        assert!(limiter.allows_upload(&peer1, MAX_REQUEST_BYTES));
+        assert!(!limiter.allows_upload(&peer1, CHUNK_SIZE as u64));
+        assert!(limiter.allows_upload(&peer2, MAX_REQUEST_BYTES));
+
+        // download is not limited
+        assert!(limiter.allows_download(&peer1, MAX_REQUEST_BYTES));
+        assert!(limiter.allows_download(&peer1, MAX_REQUEST_BYTES));
+    }
+
+    #[test]
+    fn test_total_limit() {
+        let mut limiter = BandwidthLimiter::new(BandwidthLimits {
+            max_download_bytes_per_sec: MAX_REQUEST_BYTES,
+            ..Default::default()
+        });
+        let peer1 = identity::Keypair::generate_ed25519().public().to_peer_id();
+        let peer2 = identity::Keypair::generate_ed25519().public().to_peer_id();
+
+        assert!(limiter.allows_download(&peer1, MAX_REQUEST_BYTES / 2));
+        assert!(limiter.allows_download(&peer2, MAX_REQUEST_BYTES / 2));
+        assert!(!limiter.allows_download(&peer1, MAX_REQUEST_BYTES / 2));
+        assert!(!limiter.allows_download(&peer2, MAX_REQUEST_BYTES / 4));
+        assert!(limiter.allows_upload(&peer1, MAX_REQUEST_BYTES));
+    }
+}
@@ -1,13 +1,82 @@
-use network::{NetworkMessage, PeerAction, PeerId, PubsubMessage, ReportSource};
+use crate::bandwidth::{BandwidthLimiter, BandwidthLimits};
+use crate::controllers::PeerStats;
+use network::{
+    rpc::GetFileAvailabilityRequest, NetworkMessage, PeerAction, PeerId, PubsubMessage,
+    ReportSource, Request, SyncId as RequestId, SyncStatus,
+};
+use shared_types::TxID;
+use std::collections::HashMap;
+use std::sync::Mutex;
 use tokio::sync::mpsc;
 
 pub struct SyncNetworkContext {
     network_send: mpsc::UnboundedSender<NetworkMessage>,
+
+    /// Bandwidth limiter of chunks served and fetched, shared by all file sync controllers.
+    bandwidth: Mutex<BandwidthLimiter>,
+
+    /// Statistics of chunks requests to peers, shared by all file sync controllers, so that
+    /// the peers are selected with the knowledge of previous files.
+    peer_stats: Mutex<HashMap<PeerId, PeerStats>>,
+
+    /// Sync status of the connected peers in the status handshake.
+    peer_sync_status: Mutex<HashMap<PeerId, SyncStatus>>,
 }
 
 impl SyncNetworkContext {
     pub fn new(network_send: mpsc::UnboundedSender<NetworkMessage>) -> Self {
-        Self { network_send }
+        Self {
+            network_send,
+            bandwidth: Default::default(),
+            peer_stats: Default::default(),
+            peer_sync_status: Default::default(),
+        }
+    }
+
+    pub fn bandwidth_limits(&self) -> BandwidthLimits {
+        self.bandwidth.lock().unwrap().limits()
+    }
+
+    /// Replaces the bandwidth limits, which resets the consumed tokens.
+    pub fn set_bandwidth_limits(&self, limits: BandwidthLimits) {
+        *self.bandwidth.lock().unwrap() = BandwidthLimiter::new(limits);
+    }
+
+    pub fn allows_upload(&self, peer_id: &PeerId, bytes: u64) -> bool {
+        self.bandwidth.lock().unwrap().allows_upload(peer_id, bytes)
+    }
+
+    pub fn allows_download(&self, peer_id: &PeerId, bytes: u64) -> bool {
+        self.bandwidth
+            .lock()
+            .unwrap()
+            .allows_download(peer_id, bytes)
+    }
+
+    pub fn peer_stats(&self) -> HashMap<PeerId, PeerStats> {
+        self.peer_stats.lock().unwrap().clone()
+    }
+
+    pub fn update_peer_stats<F>(&self, peer_id: PeerId, f: F)
+    where
+        F: FnOnce(&mut PeerStats),
+    {
+        f(self.peer_stats.lock().unwrap().entry(peer_id).or_default())
+    }
+
+    pub fn peer_sync_status(&self) -> HashMap<PeerId, SyncStatus> {
+        self.peer_sync_status.lock().unwrap().clone()
+    }
+
+    pub fn update_peer_sync_status(&self, peer_id: PeerId, status: SyncStatus) {
+        self.peer_sync_status
+            .lock()
+            .unwrap()
+            .insert(peer_id, status);
+    }
+
+    pub fn remove_peer_sync_status(&self, peer_id: &PeerId) {
+        self.peer_sync_status.lock().unwrap().remove(peer_id);
     }
 
     /// Sends an arbitrary network message.
@@ -24,6 +93,15 @@ impl SyncNetworkContext {
         });
     }
 
+    pub fn request_file_availability(&self, peer_id: PeerId, tx_id: TxID) {
+        debug!(%peer_id, ?tx_id, "Request file availability");
+        self.send(NetworkMessage::SendRequest {
+            peer_id,
+            request_id: network::RequestId::Sync(RequestId::FileAvailability { tx_id }),
+            request: Request::GetFileAvailability(GetFileAvailabilityRequest { tx_id }),
+        })
+    }
+
     pub fn report_peer(&self, peer_id: PeerId, action: PeerAction, msg: &'static str) {
         debug!(%peer_id, ?action, %msg, "Report peer");
         self.send(NetworkMessage::ReportPeer {
@@ -1,8 +1,16 @@
+mod parallel;
 mod peers;
 mod serial;
 
+use libp2p::swarm::DialError;
+use network::{rpc::methods::FileAvailability, Multiaddr, PeerId};
 use serde::{Deserialize, Serialize};
+use shared_types::ChunkArrayWithProof;
+use std::collections::HashMap;
 
+pub use parallel::ParallelSyncController;
+pub use peers::PeerStats;
+pub(crate) use serial::MAX_CHUNKS_TO_REQUEST;
 pub use serial::{FailureReason, SerialSyncController, SyncState};
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -13,4 +21,129 @@ pub struct FileSyncInfo {
     pub num_chunks: u64,
     pub next_chunks: u64,
     pub state: String,
+    pub peer_stats: HashMap<String, PeerStats>,
+}
+
+/// Returns the chunk ranges of a file that are not in the `downloaded` ranges.
+pub(crate) fn missing_ranges(num_chunks: u64, downloaded: &[(u64, u64)]) -> Vec<(u64, u64)> {
+    let mut downloaded = downloaded.to_vec();
+    downloaded.sort_unstable();
+
+    let mut missing = vec![];
+    let mut next_chunk = 0;
+    for (from_chunk, to_chunk) in downloaded {
+        if from_chunk > next_chunk {
+            missing.push((next_chunk, std::cmp::min(from_chunk, num_chunks)));
+        }
+        next_chunk = std::cmp::max(next_chunk, to_chunk);
+        if next_chunk >= num_chunks {
+            break;
+        }
+    }
+
+    if next_chunk < num_chunks {
+        missing.push((next_chunk, num_chunks));
+    }
+
+    missing
+}
+
+/// A file sync controller, which downloads chunks from one peer at a time,
+/// or from several peers in parallel for large files.
+pub enum SyncController {
+    Serial(SerialSyncController),
+    Parallel(ParallelSyncController),
+}
+
+impl SyncController {
+    pub fn get_sync_info(&self) -> FileSyncInfo {
+        match self {
+            SyncController::Serial(c) => c.get_sync_info(),
+            SyncController::Parallel(c) => c.get_sync_info(),
+        }
+    }
+
+    pub fn get_status(&self) -> &SyncState {
+        match self {
+            SyncController::Serial(c) => c.get_status(),
+            SyncController::Parallel(c) => c.get_status(),
+        }
+    }
+
+    pub fn reset(&mut self) {
+        match self {
+            SyncController::Serial(c) => c.reset(),
+            SyncController::Parallel(c) => c.reset(),
+        }
+    }
+
+    pub async fn resume(&mut self, downloaded: &[(u64, u64)]) {
+        match self {
+            SyncController::Serial(c) => c.resume(downloaded).await,
+            SyncController::Parallel(c) => c.resume(downloaded).await,
+        }
+    }
+
+    pub fn on_peer_found(&mut self, peer_id: PeerId, addr: Multiaddr) -> bool {
+        match self {
+            SyncController::Serial(c) => c.on_peer_found(peer_id, addr),
+            SyncController::Parallel(c) => c.on_peer_found(peer_id, addr),
+        }
+    }
+
+    pub fn on_dail_failed(&mut self, peer_id: PeerId, err: &DialError) {
+        match self {
+            SyncController::Serial(c) => c.on_dail_failed(peer_id, err),
+            SyncController::Parallel(c) => c.on_dail_failed(peer_id, err),
+        }
+    }
+
+    pub fn on_peer_connected(&mut self, peer_id: PeerId) {
+        match self {
+            SyncController::Serial(c) => c.on_peer_connected(peer_id),
+            SyncController::Parallel(c) => c.on_peer_connected(peer_id),
+        }
+    }
+
+    pub fn on_peer_disconnected(&mut self, peer_id: PeerId) {
+        match self {
+            SyncController::Serial(c) => c.on_peer_disconnected(peer_id),
+            SyncController::Parallel(c) => c.on_peer_disconnected(peer_id),
+        }
+    }
+
+    pub fn on_file_availability(&mut self, peer_id: PeerId, availability: FileAvailability) {
+        match self {
+            SyncController::Serial(c) => c.on_file_availability(peer_id, availability),
+            SyncController::Parallel(c) => c.on_file_availability(peer_id, availability),
+        }
+    }
+
+    pub async fn on_response(&mut self, from_peer_id: PeerId, response: ChunkArrayWithProof) {
+        match self {
+            SyncController::Serial(c) => c.on_response(from_peer_id, response).await,
+            SyncController::Parallel(c) => c.on_response(from_peer_id, response).await,
+        }
+    }
+
+    pub fn on_request_failed(&mut self, peer_id: PeerId) {
+        match self {
+            SyncController::Serial(c) => c.on_request_failed(peer_id),
+            SyncController::Parallel(c) => c.on_request_failed(peer_id),
+        }
+    }
+
+    pub fn on_request_rate_limited(&mut self, peer_id: PeerId) {
+        match self {
+            SyncController::Serial(c) => c.on_request_rate_limited(peer_id),
+            SyncController::Parallel(c) => c.on_request_rate_limited(peer_id),
+        }
+    }
+
+    pub fn transition(&mut self) {
+        match self {
+            SyncController::Serial(c) => c.transition(),
+            SyncController::Parallel(c) => c.transition(),
+        }
+    }
 }
@@ -0,0 +1,1000 @@
+use crate::context::SyncNetworkContext;
+use crate::controllers::peers::{PeerState, SyncPeers};
+use crate::controllers::serial::{
+    FailureReason, SyncState, DOWNLOAD_TIMEOUT, MAX_CHUNKS_TO_REQUEST, MAX_REQUEST_FAILURES,
+    PEER_REQUEST_TIMEOUT, RATE_LIMITED_BACKOFF, WAIT_OUTGOING_CONNECTION_TIMEOUT,
+};
+use crate::controllers::{missing_ranges, FileSyncInfo};
+use file_location_cache::FileLocationCache;
+use libp2p::swarm::DialError;
+use network::{
+    multiaddr::Protocol, rpc::methods::FileAvailability, rpc::GetChunksRequest, types::FindFile,
+    Multiaddr, NetworkMessage, PeerAction, PeerId, PubsubMessage, SyncId as RequestId,
+};
+use shared_types::{timestamp_now, ChunkArrayWithProof, TxID, CHUNK_SIZE};
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+    time::Instant,
+};
+use storage_async::Store;
+
+/// A chunks request that is waiting for the response.
+#[derive(Clone, Copy, Debug)]
+struct InFlightRequest {
+    from_chunk: u64,
+    to_chunk: u64,
+    since: Instant,
+}
+
+/// Downloads a file from several peers at the same time.
+///
+/// The file is split into ranges of at most `MAX_CHUNKS_TO_REQUEST` chunks, and every connected
+/// peer is assigned one range at a time. Each range is validated and stored on its own, so ranges
+/// may complete out of order. Ranges of failed requests are assigned again.
+pub struct ParallelSyncController {
+    // only used for log purpose
+    tx_seq: u64,
+
+    /// The unique transaction ID.
+    tx_id: TxID,
+
+    since: Instant,
+
+    /// The size of the file to be synced.
+    num_chunks: u64,
+
+    /// The maximum number of in-flight requests, and thus peers to download from.
+    max_requests: usize,
+
+    /// The chunk ranges to request, as `from_chunk -> to_chunk`.
+    pending: BTreeMap<u64, u64>,
+
+    /// In-flight requests, at most one for each peer.
+    requests: HashMap<PeerId, InFlightRequest>,
+
+    /// The number of chunks downloaded and stored.
+    downloaded_chunks: u64,
+
+    /// Continuous RPC failures to request chunks of each peer.
+    failures: HashMap<PeerId, usize>,
+
+    /// Current state of this request.
+    state: SyncState,
+
+    /// Sync peer manager.
+    peers: SyncPeers,
+
+    /// A network context to contact the network service.
+    ctx: Arc<SyncNetworkContext>,
+
+    /// Log and transaction storage.
+    store: Store,
+
+    /// Cache for storing and serving gossip messages.
+    file_location_cache: Arc<FileLocationCache>,
+}
+
+impl ParallelSyncController {
+    pub fn new(
+        tx_id: TxID,
+        num_chunks: u64,
+        max_requests: usize,
+        ctx: Arc<SyncNetworkContext>,
+        store: Store,
+        file_location_cache: Arc<FileLocationCache>,
+    ) -> Self {
+        ParallelSyncController {
+            tx_seq: tx_id.seq,
+            tx_id,
+            since: Instant::now(),
+            num_chunks,
+            max_requests: max_requests.max(1),
+            pending: split_ranges(&[(0, num_chunks)]),
+            requests: Default::default(),
+            downloaded_chunks: 0,
+            failures: Default::default(),
+            state: SyncState::Idle,
+            peers: Default::default(),
+            ctx,
+            store,
+            file_location_cache,
+        }
+    }
+
+    pub fn get_sync_info(&self) -> FileSyncInfo {
+        FileSyncInfo {
+            elapsed_secs: self.since.elapsed().as_secs(),
+            peers: self.peers.count(&[PeerState::Connected]),
+            num_chunks: self.num_chunks,
+            next_chunks: self.downloaded_chunks,
+            state: format!("{:?}", self.state),
+            peer_stats: self.peers.all_stats(&self.ctx.peer_stats()),
+        }
+    }
+
+    pub fn get_status(&self) -> &SyncState {
+        &self.state
+    }
+
+    /// Resets the status to re-sync file when failed.
+    pub fn reset(&mut self) {
+        self.pending = split_ranges(&[(0, self.num_chunks)]);
+        self.requests.clear();
+        self.downloaded_chunks = 0;
+        self.failures.clear();
+        self.state = SyncState::Idle;
+        // remove disconnected peers
+        self.peers.transition();
+    }
+
+    /// Resumes to download the missing chunks only, given the chunk ranges
+    /// already stored, e.g. before node restarted.
+    pub async fn resume(&mut self, downloaded: &[(u64, u64)]) {
+        let missing = missing_ranges(self.num_chunks, downloaded);
+        let missing_chunks: u64 = missing.iter().map(|(from, to)| to - from).sum();
+
+        self.pending = split_ranges(&missing);
+        self.downloaded_chunks = self.num_chunks - missing_chunks;
+
+        if self.downloaded_chunks > 0 {
+            info!(%self.tx_seq, %self.downloaded_chunks, "Resume to sync file");
+        }
+
+        if self.pending.is_empty() {
+            self.finalize().await;
+        }
+    }
+
+    fn try_find_peers(&mut self) {
+        info!(%self.tx_seq, "Finding peers");
+
+        // try from cache
+        let mut found_new_peer = false;
+
+        for announcement in self.file_location_cache.get_all(self.tx_id) {
+            // make sure peer_id is part of the address
+            let peer_id = announcement.peer_id();
+            let mut addr = announcement.at();
+            addr.push(Protocol::P2p(peer_id.into()));
+
+            found_new_peer = self.on_peer_found(peer_id, addr) || found_new_peer;
+        }
+
+        if !found_new_peer {
+            self.ctx.publish(PubsubMessage::FindFile(FindFile {
+                tx_id: self.tx_id,
+                timestamp: timestamp_now(),
+            }));
+        }
+
+        if !matches!(self.state, SyncState::FindingPeers { .. }) {
+            self.state = SyncState::FindingPeers {
+                since: Instant::now(),
+            };
+        }
+    }
+
+    /// Dials a random found peer, and returns `false` if there is none.
+    fn try_connect(&mut self) -> bool {
+        let (peer_id, address) = match self.peers.random_peer(PeerState::Found) {
+            Some((peer_id, address)) => (peer_id, address),
+            None => return false,
+        };
+
+        info!(%peer_id, %address, "Attempting to connect to peer");
+        self.ctx.send(NetworkMessage::DialPeer { address, peer_id });
+
+        self.peers
+            .update_state(&peer_id, PeerState::Found, PeerState::Connecting);
+
+        true
+    }
+
+    /// Requests the first pending range from the peer, and returns `false` if there is none
+    /// or the bandwidth limits exceeded.
+    fn try_request(&mut self, peer_id: PeerId) -> bool {
+        let (from_chunk, to_chunk) = match self.pending.iter().next() {
+            Some((&from_chunk, &to_chunk)) => (from_chunk, to_chunk),
+            None => return false,
+        };
+
+        let bytes = (to_chunk - from_chunk) * CHUNK_SIZE as u64;
+        if !self.ctx.allows_download(&peer_id, bytes) {
+            debug!(%self.tx_seq, %peer_id, "Bandwidth limits exceeded to request chunks");
+            return false;
+        }
+
+        self.pending.remove(&from_chunk);
+
+        let request_id = network::RequestId::Sync(RequestId::ParallelSync { tx_id: self.tx_id });
+
+        let request = network::Request::GetChunks(GetChunksRequest {
+            tx_id: self.tx_id,
+            index_start: from_chunk,
+            index_end: to_chunk,
+        });
+
+        self.ctx.send(NetworkMessage::SendRequest {
+            peer_id,
+            request_id,
+            request,
+        });
+
+        self.requests.insert(
+            peer_id,
+            InFlightRequest {
+                from_chunk,
+                to_chunk,
+                since: Instant::now(),
+            },
+        );
+
+        true
+    }
+
+    /// Removes the in-flight request of the peer, and puts its range back to be requested again.
+    fn cancel_request(&mut self, peer_id: &PeerId) -> Option<InFlightRequest> {
+        let request = self.requests.remove(peer_id)?;
+        self.pending.insert(request.from_chunk, request.to_chunk);
+        Some(request)
+    }
+
+    fn ban_peer(&mut self, peer_id: PeerId, reason: &'static str) {
+        self.ctx.ban_peer(peer_id, reason);
+
+        self.peers
+            .update_state(&peer_id, PeerState::Connected, PeerState::Disconnecting);
+
+        self.cancel_request(&peer_id);
+        self.failures.remove(&peer_id);
+    }
+
+    pub fn on_peer_found(&mut self, peer_id: PeerId, addr: Multiaddr) -> bool {
+        if self.peers.add_new_peer(peer_id, addr.clone()) {
+            info!(%self.tx_seq, %peer_id, %addr, "Found new peer");
+            true
+        } else {
+            // e.g. multiple `AnnounceFile` messages propagated
+            debug!(%self.tx_seq, %peer_id, %addr, "Found an existing peer");
+            false
+        }
+    }
+
+    pub fn on_dail_failed(&mut self, peer_id: PeerId, err: &DialError) {
+        match err {
+            DialError::ConnectionLimit(_) => {
+                if let Some(true) =
+                    self.peers
+                        .update_state(&peer_id, PeerState::Connecting, PeerState::Found)
+                {
+                    info!(%self.tx_seq, %peer_id, "Failed to dail peer due to outgoing connection limitation");
+                    self.state = SyncState::AwaitingOutgoingConnection {
+                        since: Instant::now(),
+                    };
+                }
+            }
+            _ => {
+                if let Some(true) = self.peers.update_state(
+                    &peer_id,
+                    PeerState::Connecting,
+                    PeerState::Disconnected,
+                ) {
+                    info!(%self.tx_seq, %peer_id, "Failed to dail peer");
+                }
+            }
+        }
+    }
+
+    pub fn on_peer_connected(&mut self, peer_id: PeerId) {
+        if let Some(true) =
+            self.peers
+                .update_state(&peer_id, PeerState::Connecting, PeerState::Connected)
+        {
+            info!(%self.tx_seq, %peer_id, "Peer connected");
+
+            // the peers found by shard config may have only part of the file
+            let announced = self
+                .file_location_cache
+                .get_all(self.tx_id)
+                .iter()
+                .any(|announcement| announcement.peer_id() == peer_id);
+            if !announced {
+                self.ctx.request_file_availability(peer_id, self.tx_id);
+            }
+        }
+    }
+
+    pub fn on_file_availability(&mut self, peer_id: PeerId, availability: FileAvailability) {
+        if availability.tx_id == self.tx_id {
+            self.peers.update_availability(&peer_id, availability);
+        }
+    }
+
+    pub fn on_peer_disconnected(&mut self, peer_id: PeerId) {
+        match self
+            .peers
+            .update_state_force(&peer_id, PeerState::Disconnected)
+        {
+            Some(PeerState::Disconnecting) => info!(%self.tx_seq, %peer_id, "Peer disconnected"),
+            Some(old_state) => {
+                info!(%self.tx_seq, %peer_id, ?old_state, "Peer disconnected by remote");
+            }
+            None => {}
+        }
+
+        self.cancel_request(&peer_id);
+    }
+
+    /// Handle the case that got a response from unexpected peer,
+    /// e.g. a response for a timeout request.
+    fn handle_on_response_mismatch(&self, from_peer_id: PeerId) -> bool {
+        if self.requests.contains_key(&from_peer_id) {
+            return false;
+        }
+
+        warn!(%self.tx_seq, %from_peer_id, ?self.state, "Got response from unexpected peer");
+        self.ctx.report_peer(
+            from_peer_id,
+            PeerAction::LowToleranceError,
+            "Peer id mismatch",
+        );
+
+        true
+    }
+
+    pub async fn on_response(&mut self, from_peer_id: PeerId, response: ChunkArrayWithProof) {
+        if self.handle_on_response_mismatch(from_peer_id) {
+            return;
+        }
+
+        let InFlightRequest {
+            from_chunk,
+            to_chunk,
+            since,
+        } = match self.requests.remove(&from_peer_id) {
+            Some(request) => request,
+            None => return,
+        };
+
+        debug_assert!(from_chunk < to_chunk, "Invalid chunk boundaries");
+
+        // invalid chunk array size: ban and re-request
+        let data_len = response.chunks.data.len();
+        if data_len == 0 || data_len % CHUNK_SIZE > 0 {
+            warn!(%from_peer_id, %self.tx_seq, %data_len, "Invalid chunk response data length");
+            self.pending.insert(from_chunk, to_chunk);
+            self.on_proof_failure(from_peer_id);
+            self.ban_peer(from_peer_id, "Invalid chunk response data length");
+            return;
+        }
+
+        // invalid chunk range: ban and re-request
+        let start_index = response.chunks.start_index;
+        let end_index = start_index + (data_len / CHUNK_SIZE) as u64;
+        if start_index != from_chunk || end_index != to_chunk {
+            warn!(%self.tx_seq, "Invalid chunk response range, expected={from_chunk}..{to_chunk}, actual={start_index}..{end_index}");
+            self.pending.insert(from_chunk, to_chunk);
+            self.on_proof_failure(from_peer_id);
+            self.ban_peer(from_peer_id, "Invalid chunk response range");
+            return;
+        }
+
+        // validate Merkle proofs
+        let validation_result = self
+            .store
+            .get_store()
+            .read()
+            .await
+            .validate_range_proof(self.tx_seq, &response);
+
+        match validation_result {
+            Ok(true) => {}
+            Ok(false) => {
+                info!("Failed to validate chunks response due to no root found");
+                self.pending.insert(from_chunk, to_chunk);
+                return;
+            }
+            Err(err) => {
+                warn!(%err, "Failed to validate chunks response");
+                self.pending.insert(from_chunk, to_chunk);
+                self.on_proof_failure(from_peer_id);
+                self.ban_peer(from_peer_id, "Chunk array validation failed");
+                return;
+            }
+        }
+
+        self.failures.remove(&from_peer_id);
+        self.ctx.update_peer_stats(from_peer_id, |stats| {
+            stats.on_response(since.elapsed(), data_len)
+        });
+
+        // store in db
+        match self
+            .store
+            .put_chunks_with_tx_hash(self.tx_id.seq, self.tx_id.hash, response.chunks)
+            .await
+        {
+            Ok(true) => self.downloaded_chunks += to_chunk - from_chunk,
+            Ok(false) => {
+                warn!(?self.tx_id, "Transaction reverted while storing chunks");
+                self.state = SyncState::Failed {
+                    reason: FailureReason::TxReverted(self.tx_id),
+                };
+                return;
+            }
+            Err(err) => {
+                error!(%err, "Unexpected DB error while storing chunks");
+                self.state = SyncState::Failed {
+                    reason: FailureReason::DBError(err.to_string()),
+                };
+                return;
+            }
+        }
+
+        // wait for other ranges
+        if !self.pending.is_empty() || !self.requests.is_empty() {
+            return;
+        }
+
+        self.finalize().await;
+    }
+
+    /// Finalizes the transaction once all chunks downloaded.
+    async fn finalize(&mut self) {
+        match self
+            .store
+            .finalize_tx_with_hash(self.tx_id.seq, self.tx_id.hash)
+            .await
+        {
+            Ok(true) => self.state = SyncState::Completed,
+            Ok(false) => {
+                warn!(?self.tx_id, "Transaction reverted during finalize_tx");
+                self.state = SyncState::Failed {
+                    reason: FailureReason::TxReverted(self.tx_id),
+                };
+            }
+            Err(err) => {
+                error!(%err, "Unexpected error during finalize_tx");
+                self.state = SyncState::Failed {
+                    reason: FailureReason::DBError(err.to_string()),
+                };
+            }
+        }
+    }
+
+    pub fn on_request_failed(&mut self, peer_id: PeerId) {
+        if self.handle_on_response_mismatch(peer_id) {
+            return;
+        }
+
+        self.ctx
+            .update_peer_stats(peer_id, |stats| stats.rpc_errors += 1);
+
+        self.handle_response_failure(peer_id, "RPC Error");
+    }
+
+    /// Handles the request rejected due to the rate limits of peer, which is not a failure of
+    /// peer, so the range is assigned to other peers and the peer is requested again later.
+    pub fn on_request_rate_limited(&mut self, peer_id: PeerId) {
+        if self.handle_on_response_mismatch(peer_id) {
+            return;
+        }
+
+        info!(%peer_id, %self.tx_seq, "Chunks request rate limited");
+        self.cancel_request(&peer_id);
+        self.peers.backoff(&peer_id, RATE_LIMITED_BACKOFF);
+    }
+
+    fn on_proof_failure(&mut self, peer_id: PeerId) {
+        self.ctx
+            .update_peer_stats(peer_id, |stats| stats.proof_failures += 1);
+    }
+
+    fn handle_response_failure(&mut self, peer_id: PeerId, reason: &'static str) {
+        info!(%peer_id, %self.tx_seq, %reason, "Chunks request failed");
+
+        // the range will be assigned to any idle peer
+        self.cancel_request(&peer_id);
+
+        // ban peer on too many failures
+        self.ctx
+            .report_peer(peer_id, PeerAction::LowToleranceError, reason);
+
+        let failures = self.failures.entry(peer_id).or_default();
+        *failures += 1;
+
+        if *failures > MAX_REQUEST_FAILURES {
+            self.ban_peer(peer_id, reason);
+        }
+    }
+
+    pub fn transition(&mut self) {
+        use PeerState::*;
+
+        // update peer connection states
+        self.peers.transition();
+
+        if matches!(self.state, SyncState::Completed | SyncState::Failed { .. }) {
+            return;
+        }
+
+        // re-assign the ranges of timeout requests or disconnected peers
+        let mut disconnected = vec![];
+        let mut timeout = vec![];
+        for (peer_id, request) in self.requests.iter() {
+            if !matches!(self.peers.peer_state(peer_id), Some(Connected)) {
+                disconnected.push(*peer_id);
+            } else if request.since.elapsed() >= DOWNLOAD_TIMEOUT {
+                timeout.push(*peer_id);
+            }
+        }
+
+        for peer_id in disconnected {
+            self.cancel_request(&peer_id);
+        }
+
+        for peer_id in timeout {
+            self.ctx
+                .update_peer_stats(peer_id, |stats| stats.timeouts += 1);
+            self.handle_response_failure(peer_id, "RPC timeout");
+        }
+
+        // find peers only if none available, and broadcast the `FindFile`
+        // message again on timeout
+        if self.peers.count(&[Found, Connecting, Connected]) == 0 {
+            match self.state {
+                SyncState::FindingPeers { since } if since.elapsed() < PEER_REQUEST_TIMEOUT => {}
+                _ => self.try_find_peers(),
+            }
+
+            if self.peers.count(&[Found, Connecting, Connected]) == 0 {
+                return;
+            }
+        }
+
+        // connect more peers to download in parallel
+        let awaiting_outgoing_connection = matches!(
+            self.state,
+            SyncState::AwaitingOutgoingConnection { since }
+                if since.elapsed() < WAIT_OUTGOING_CONNECTION_TIMEOUT
+        );
+        if !awaiting_outgoing_connection {
+            while self.peers.count(&[Connecting, Connected]) < self.max_requests
+                && self.try_connect()
+            {}
+        }
+
+        // assign pending ranges to idle peers, and prefer the faster ones
+        // if there are more idle peers than pending ranges
+        let stats = self.ctx.peer_stats();
+        let sync_status = self.ctx.peer_sync_status();
+        while self.requests.len() < self.max_requests {
+            let chunks = match self.pending.iter().next() {
+                Some((&from_chunk, &to_chunk)) => (from_chunk, to_chunk),
+                None => break,
+            };
+
+            let requests = &self.requests;
+            let peer_id = match self.peers.select_peer_for_chunks(
+                Connected,
+                &stats,
+                &sync_status,
+                self.tx_seq,
+                chunks,
+                |peer_id| !requests.contains_key(peer_id),
+            ) {
+                Some(peer_id) => peer_id,
+                None => break,
+            };
+
+            if !self.try_request(peer_id) {
+                break;
+            }
+        }
+
+        // report the first in-flight request as the download progress
+        if let Some((&peer_id, request)) = self.requests.iter().min_by_key(|(_, r)| r.from_chunk) {
+            self.state = SyncState::Downloading {
+                peer_id,
+                from_chunk: request.from_chunk,
+                to_chunk: request.to_chunk,
+                since: request.since,
+            };
+        } else if !awaiting_outgoing_connection {
+            self.state = if self.peers.count(&[Connected]) > 0 {
+                SyncState::AwaitingDownload
+            } else if self.peers.count(&[Connecting]) > 0 {
+                SyncState::ConnectingPeers
+            } else {
+                SyncState::FoundPeers
+            };
+        }
+    }
+}
+
+/// Splits the chunk ranges into smaller ones to request.
+fn split_ranges(ranges: &[(u64, u64)]) -> BTreeMap<u64, u64> {
+    ranges
+        .iter()
+        .flat_map(|&(from, to)| {
+            (from..to)
+                .step_by(MAX_CHUNKS_TO_REQUEST as usize)
+                .map(move |from_chunk| {
+                    (
+                        from_chunk,
+                        std::cmp::min(from_chunk + MAX_CHUNKS_TO_REQUEST, to),
+                    )
+                })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::tests::{create_2_store, create_file_location_cache};
+    use libp2p::identity;
+    use network::rpc::methods::BitList;
+    use network::Request;
+    use storage::log_store::log_manager::LogManager;
+    use storage::log_store::{LogStoreChunkRead, LogStoreChunkWrite, LogStoreRead};
+    use task_executor::{test_utils::TestRuntime, TaskExecutor};
+    use tokio::sync::mpsc::{self, UnboundedReceiver};
+    use tokio::sync::RwLock;
+
+    #[test]
+    fn test_split_ranges() {
+        assert!(split_ranges(&[(0, 0)]).is_empty());
+        assert_eq!(
+            split_ranges(&[(0, 1)]).into_iter().collect::<Vec<_>>(),
+            [(0, 1)]
+        );
+        assert_eq!(
+            split_ranges(&[(0, 2 * MAX_CHUNKS_TO_REQUEST + 1)])
+                .into_iter()
+                .collect::<Vec<_>>(),
+            [
+                (0, MAX_CHUNKS_TO_REQUEST),
+                (MAX_CHUNKS_TO_REQUEST, 2 * MAX_CHUNKS_TO_REQUEST),
+                (2 * MAX_CHUNKS_TO_REQUEST, 2 * MAX_CHUNKS_TO_REQUEST + 1)
+            ]
+        );
+        assert_eq!(
+            split_ranges(&[(1, 3), (10, MAX_CHUNKS_TO_REQUEST + 11)])
+                .into_iter()
+                .collect::<Vec<_>>(),
+            [
+                (1, 3),
+                (10, MAX_CHUNKS_TO_REQUEST + 10),
+                (MAX_CHUNKS_TO_REQUEST + 10, MAX_CHUNKS_TO_REQUEST + 11)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_missing_ranges() {
+        assert_eq!(missing_ranges(10, &[]), [(0, 10)]);
+        assert!(missing_ranges(10, &[(0, 10)]).is_empty());
+        assert_eq!(
+            missing_ranges(10, &[(2, 4), (3, 5), (8, 12)]),
+            [(0, 2), (5, 8)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_resume() {
+        let chunk_count = 2 * MAX_CHUNKS_TO_REQUEST as usize;
+        let tx_seq = 0;
+        let (store, peer_store, txs, _) = create_2_store(vec![chunk_count]);
+
+        // the second range is already stored
+        let chunks = peer_store
+            .read()
+            .await
+            .get_chunks_by_tx_and_index_range(tx_seq, MAX_CHUNKS_TO_REQUEST as usize, chunk_count)
+            .unwrap()
+            .unwrap();
+        store.write().await.put_chunks(tx_seq, chunks).unwrap();
+
+        let runtime = TestRuntime::default();
+        let (mut controller, mut network_recv) = create_controller(
+            runtime.task_executor.clone(),
+            store,
+            txs[0].id(),
+            chunk_count,
+        );
+        controller
+            .resume(&[(MAX_CHUNKS_TO_REQUEST, chunk_count as u64)])
+            .await;
+        assert_eq!(
+            controller.get_sync_info().next_chunks,
+            MAX_CHUNKS_TO_REQUEST
+        );
+
+        add_connected_peer(&mut controller);
+        controller.transition();
+        let (_, from_chunk, to_chunk) = receive_chunk_request(&mut network_recv, &controller);
+        assert_eq!((from_chunk, to_chunk), (0, MAX_CHUNKS_TO_REQUEST));
+        assert!(controller.pending.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_request_in_parallel() {
+        let chunk_count = 3 * MAX_CHUNKS_TO_REQUEST as usize;
+        let (store, _, txs, _) = create_2_store(vec![chunk_count]);
+
+        let runtime = TestRuntime::default();
+        let (mut controller, mut network_recv) = create_controller(
+            runtime.task_executor.clone(),
+            store,
+            txs[0].id(),
+            chunk_count,
+        );
+        let peer1 = add_connected_peer(&mut controller);
+        let peer2 = add_connected_peer(&mut controller);
+
+        controller.transition();
+
+        let mut requests = vec![
+            receive_chunk_request(&mut network_recv, &controller),
+            receive_chunk_request(&mut network_recv, &controller),
+        ];
+        requests.sort_by_key(|(_, from_chunk, _)| *from_chunk);
+        assert_eq!(requests[0].1, 0);
+        assert_eq!(requests[1].1, MAX_CHUNKS_TO_REQUEST);
+        assert_ne!(requests[0].0, requests[1].0);
+        assert!([peer1, peer2].contains(&requests[0].0));
+        assert!([peer1, peer2].contains(&requests[1].0));
+        assert!(network_recv.try_recv().is_err());
+
+        assert_eq!(controller.requests.len(), 2);
+        assert_eq!(controller.pending.len(), 1);
+        assert!(matches!(
+            *controller.get_status(),
+            SyncState::Downloading { from_chunk: 0, .. }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_response_out_of_order() {
+        let chunk_count = 2 * MAX_CHUNKS_TO_REQUEST as usize - 1;
+        let tx_seq = 0;
+        let (store, peer_store, txs, _) = create_2_store(vec![chunk_count]);
+
+        let runtime = TestRuntime::default();
+        let (mut controller, mut network_recv) = create_controller(
+            runtime.task_executor.clone(),
+            store.clone(),
+            txs[0].id(),
+            chunk_count,
+        );
+        add_connected_peer(&mut controller);
+        add_connected_peer(&mut controller);
+
+        controller.transition();
+
+        let mut requests = vec![
+            receive_chunk_request(&mut network_recv, &controller),
+            receive_chunk_request(&mut network_recv, &controller),
+        ];
+        requests.sort_by_key(|(_, from_chunk, _)| *from_chunk);
+
+        // respond the last range first
+        for (peer_id, from_chunk, to_chunk) in requests.into_iter().rev() {
+            let chunks = peer_store
+                .read()
+                .await
+                .get_chunks_with_proof_by_tx_and_index_range(
+                    tx_seq,
+                    from_chunk as usize,
+                    to_chunk as usize,
+                )
+                .unwrap()
+                .unwrap();
+
+            assert!(!store.read().await.check_tx_completed(tx_seq).unwrap());
+            controller.on_response(peer_id, chunks).await;
+        }
+
+        assert_eq!(*controller.get_status(), SyncState::Completed);
+        assert_eq!(controller.get_sync_info().next_chunks, chunk_count as u64);
+        assert!(store.read().await.check_tx_completed(tx_seq).unwrap());
+        assert!(network_recv.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_failed_range_reassigned() {
+        let chunk_count = 2 * MAX_CHUNKS_TO_REQUEST as usize;
+        let (store, _, txs, _) = create_2_store(vec![chunk_count]);
+
+        let runtime = TestRuntime::default();
+        let (mut controller, mut network_recv) = create_controller(
+            runtime.task_executor.clone(),
+            store,
+            txs[0].id(),
+            chunk_count,
+        );
+        add_connected_peer(&mut controller);
+
+        controller.transition();
+        let (peer_id, from_chunk, _) = receive_chunk_request(&mut network_recv, &controller);
+        assert_eq!(from_chunk, 0);
+
+        // the failed range is requested again before the others
+        controller.on_request_failed(peer_id);
+        assert!(matches!(
+            network_recv.try_recv(),
+            Ok(NetworkMessage::ReportPeer { .. })
+        ));
+        assert_eq!(controller.pending.len(), 2);
+
+        controller.transition();
+        let (retry_peer_id, from_chunk, to_chunk) =
+            receive_chunk_request(&mut network_recv, &controller);
+        assert_eq!(retry_peer_id, peer_id);
+        assert_eq!((from_chunk, to_chunk), (0, MAX_CHUNKS_TO_REQUEST));
+
+        // the range is assigned to another peer once the peer disconnected
+        controller.on_peer_disconnected(peer_id);
+        assert_eq!(controller.pending.len(), 2);
+        let new_peer_id = add_connected_peer(&mut controller);
+
+        controller.transition();
+        let (retry_peer_id, from_chunk, _) = receive_chunk_request(&mut network_recv, &controller);
+        assert_eq!(retry_peer_id, new_peer_id);
+        assert_eq!(from_chunk, 0);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited_range_reassigned() {
+        let chunk_count = 2 * MAX_CHUNKS_TO_REQUEST as usize;
+        let (store, _, txs, _) = create_2_store(vec![chunk_count]);
+
+        let runtime = TestRuntime::default();
+        let (mut controller, mut network_recv) = create_controller(
+            runtime.task_executor.clone(),
+            store,
+            txs[0].id(),
+            chunk_count,
+        );
+        add_connected_peer(&mut controller);
+
+        controller.transition();
+        let (peer_id, from_chunk, _) = receive_chunk_request(&mut network_recv, &controller);
+        assert_eq!(from_chunk, 0);
+
+        // neither reported nor counted as failure, and the peer backs off
+        controller.on_request_rate_limited(peer_id);
+        assert!(network_recv.try_recv().is_err());
+        assert!(controller.failures.is_empty());
+        assert!(!controller.ctx.peer_stats().contains_key(&peer_id));
+        assert_eq!(controller.pending.len(), 2);
+
+        controller.transition();
+        assert!(network_recv.try_recv().is_err());
+        assert_eq!(*controller.get_status(), SyncState::AwaitingDownload);
+
+        // the range is assigned to another peer
+        let new_peer_id = add_connected_peer(&mut controller);
+        controller.transition();
+        let (retry_peer_id, from_chunk, _) = receive_chunk_request(&mut network_recv, &controller);
+        assert_eq!(retry_peer_id, new_peer_id);
+        assert_eq!(from_chunk, 0);
+    }
+
+    #[tokio::test]
+    async fn test_request_by_file_availability() {
+        let chunk_count = 123;
+        let (store, _, txs, _) = create_2_store(vec![chunk_count]);
+
+        let runtime = TestRuntime::default();
+        let (mut controller, mut network_recv) = create_controller(
+            runtime.task_executor.clone(),
+            store,
+            txs[0].id(),
+            chunk_count,
+        );
+        let tx_id = controller.tx_id;
+        let addr: Multiaddr = "/ip4/127.0.0.1/tcp/10000".parse().unwrap();
+
+        // request the file availability of peers that have not announced the file
+        let mut peers = vec![];
+        for _ in 0..2 {
+            let peer_id = identity::Keypair::generate_ed25519().public().to_peer_id();
+            controller.peers.add_new_peer(peer_id, addr.clone());
+            controller
+                .peers
+                .update_state_force(&peer_id, PeerState::Connecting);
+            controller.on_peer_connected(peer_id);
+
+            assert!(matches!(
+                network_recv.try_recv(),
+                Ok(NetworkMessage::SendRequest {
+                    peer_id: to_peer_id,
+                    request_id: network::RequestId::Sync(RequestId::FileAvailability { tx_id: request_tx_id }),
+                    request: Request::GetFileAvailability(request),
+                }) if to_peer_id == peer_id && request_tx_id == tx_id && request.tx_id == tx_id
+            ));
+            peers.push(peer_id);
+        }
+
+        let availability = |finalized| FileAvailability {
+            tx_id,
+            finalized,
+            segments: BitList::with_capacity(1).unwrap(),
+        };
+        controller.on_file_availability(peers[0], availability(false));
+        controller.on_file_availability(peers[1], availability(true));
+
+        // request from the peer that has the chunks
+        for _ in 0..10 {
+            controller.transition();
+            let (peer_id, from_chunk, _) = receive_chunk_request(&mut network_recv, &controller);
+            assert_eq!(peer_id, peers[1]);
+            assert_eq!(from_chunk, 0);
+            controller.cancel_request(&peer_id);
+        }
+    }
+
+    fn add_connected_peer(controller: &mut ParallelSyncController) -> PeerId {
+        let peer_id = identity::Keypair::generate_ed25519().public().to_peer_id();
+        let addr: Multiaddr = "/ip4/127.0.0.1/tcp/10000".parse().unwrap();
+
+        controller.peers.add_new_peer(peer_id, addr);
+        controller
+            .peers
+            .update_state_force(&peer_id, PeerState::Connected);
+
+        peer_id
+    }
+
+    fn receive_chunk_request(
+        network_recv: &mut UnboundedReceiver<NetworkMessage>,
+        controller: &ParallelSyncController,
+    ) -> (PeerId, u64, u64) {
+        match network_recv.try_recv() {
+            Ok(NetworkMessage::SendRequest {
+                peer_id,
+                request_id,
+                request: Request::GetChunks(request),
+            }) => {
+                assert!(matches!(
+                    request_id,
+                    network::RequestId::Sync(network::SyncId::ParallelSync { tx_id })
+                        if tx_id == controller.tx_id
+                ));
+                assert_eq!(request.tx_id, controller.tx_id);
+
+                (peer_id, request.index_start, request.index_end)
+            }
+            _ => {
+                panic!("Not expected message: NetworkMessage::SendRequest");
+            }
+        }
+    }
+
+    fn create_controller(
+        task_executor: TaskExecutor,
+        store: Arc<RwLock<LogManager>>,
+        tx_id: TxID,
+        num_chunks: usize,
+    ) -> (ParallelSyncController, UnboundedReceiver<NetworkMessage>) {
+        let (network_send, network_recv) = mpsc::unbounded_channel::<NetworkMessage>();
+        let ctx = Arc::new(SyncNetworkContext::new(network_send));
+
+        let peer_id = identity::Keypair::generate_ed25519().public().to_peer_id();
+        let file_location_cache = create_file_location_cache(peer_id, vec![]);
+
+        let controller = ParallelSyncController::new(
+            tx_id,
+            num_chunks as u64,
+            4,
+            ctx,
+            Store::new(store, task_executor),
+            file_location_cache,
+        );
+
+        (controller, network_recv)
+    }
+}
@@ -1,11 +1,68 @@
-use network::{Multiaddr, PeerId};
-use rand::seq::IteratorRandom;
+use network::{rpc::methods::FileAvailability, Multiaddr, PeerId, SyncStatus};
+use rand::seq::{IteratorRandom, SliceRandom};
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::time::{Duration, Instant};
 
 const PEER_CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
 const PEER_DISCONNECT_TIMEOUT: Duration = Duration::from_secs(5);
 
+/// The assumed throughput of peers not requested yet, so that they are likely to be tried.
+const DEFAULT_BYTES_PER_SEC: u64 = 1024 * 1024;
+/// The weight in percent of new samples in the smoothed RTT and throughput.
+const NEW_SAMPLE_WEIGHT_PERCENT: u64 = 30;
+/// A proof failure demotes a peer as much as this number of timeouts.
+const PROOF_FAILURE_PENALTY: usize = 4;
+/// The round trip time that halves the weight of a peer.
+const RTT_HALF_WEIGHT_MS: u64 = 1000;
+
+/// Statistics of chunks requests to a peer, which are used to select peers to download from.
+/// They are kept by the sync service for all files, see `SyncNetworkContext::peer_stats`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PeerStats {
+    /// Smoothed round trip time of chunks requests in milliseconds.
+    pub rtt_ms: Option<u64>,
+    /// Smoothed download throughput in bytes per second.
+    pub bytes_per_sec: Option<u64>,
+    pub bytes_downloaded: u64,
+    pub responses: usize,
+    pub proof_failures: usize,
+    pub timeouts: usize,
+    pub rpc_errors: usize,
+}
+
+impl PeerStats {
+    pub fn on_response(&mut self, elapsed: Duration, bytes: usize) {
+        let elapsed_ms = elapsed.as_millis().max(1) as u64;
+        let bytes_per_sec = bytes as u64 * 1000 / elapsed_ms;
+
+        self.rtt_ms = Some(smooth(self.rtt_ms, elapsed_ms));
+        self.bytes_per_sec = Some(smooth(self.bytes_per_sec, bytes_per_sec));
+        self.bytes_downloaded += bytes as u64;
+        self.responses += 1;
+    }
+
+    /// Returns the weight to select the peer, which is the throughput demoted by failures
+    /// and round trip time.
+    pub fn weight(&self) -> f64 {
+        let bytes_per_sec = self.bytes_per_sec.unwrap_or(DEFAULT_BYTES_PER_SEC).max(1);
+        let failures =
+            self.timeouts + self.rpc_errors + self.proof_failures * PROOF_FAILURE_PENALTY;
+        let rtt_factor = 1.0 + self.rtt_ms.unwrap_or(0) as f64 / RTT_HALF_WEIGHT_MS as f64;
+        bytes_per_sec as f64 / ((1 + failures) * (1 + failures)) as f64 / rtt_factor
+    }
+}
+
+fn smooth(old: Option<u64>, sample: u64) -> u64 {
+    match old {
+        Some(old) => {
+            (old * (100 - NEW_SAMPLE_WEIGHT_PERCENT) + sample * NEW_SAMPLE_WEIGHT_PERCENT) / 100
+        }
+        None => sample,
+    }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum PeerState {
     Found,
@@ -24,6 +81,12 @@ struct PeerInfo {
 
     /// Timestamp of the last state change.
     pub since: Instant,
+
+    /// Do not request the peer until then, e.g. rate limited by the peer.
+    pub backoff_until: Option<Instant>,
+
+    /// The file availability reported by the peer, if requested.
+    pub availability: Option<FileAvailability>,
 }
 
 impl PeerInfo {
@@ -50,6 +113,8 @@ impl SyncPeers {
                 addr,
                 state: PeerState::Found,
                 since: Instant::now(),
+                backoff_until: None,
+                availability: None,
             },
         );
 
@@ -91,6 +156,107 @@ impl SyncPeers {
             .choose(&mut rand::thread_rng())
     }
 
+    /// Selects a peer in the specified state that is accepted by `filter`, randomly but
+    /// weighted by the peer statistics, so that slow or failing peers are rarely selected.
+    /// The peers backing off are never selected.
+    pub fn select_peer<F>(
+        &self,
+        state: PeerState,
+        stats: &HashMap<PeerId, PeerStats>,
+        filter: F,
+    ) -> Option<PeerId>
+    where
+        F: Fn(&PeerId) -> bool,
+    {
+        let now = Instant::now();
+        let candidates: Vec<(PeerId, f64)> = self
+            .peers
+            .iter()
+            .filter(|(peer_id, info)| {
+                info.state == state
+                    && info.backoff_until.map_or(true, |until| until <= now)
+                    && filter(peer_id)
+            })
+            .map(|(peer_id, _)| {
+                let weight = stats.get(peer_id).cloned().unwrap_or_default().weight();
+                (*peer_id, weight)
+            })
+            .collect();
+
+        candidates
+            .choose_weighted(&mut rand::thread_rng(), |(_, weight)| *weight)
+            .ok()
+            .map(|(peer_id, _)| *peer_id)
+    }
+
+    /// Selects a peer like `select_peer` to request the chunks `[from_chunk, to_chunk)` of
+    /// transaction `tx_seq`, but prefers the peers that have synced the transaction in the
+    /// status handshake and have the chunks in the file availability if reported. As both
+    /// may be outdated, the other peers are selected if none of them is available, except
+    /// the peers in another network.
+    pub fn select_peer_for_chunks<F>(
+        &self,
+        state: PeerState,
+        stats: &HashMap<PeerId, PeerStats>,
+        sync_status: &HashMap<PeerId, SyncStatus>,
+        tx_seq: u64,
+        (from_chunk, to_chunk): (u64, u64),
+        filter: F,
+    ) -> Option<PeerId>
+    where
+        F: Fn(&PeerId) -> bool,
+    {
+        let has_tx = |peer_id: &PeerId| match sync_status.get(peer_id) {
+            Some(SyncStatus::Synced { info })
+            | Some(SyncStatus::Advanced { info })
+            | Some(SyncStatus::Behind { info }) => tx_seq < info.next_tx_seq,
+            Some(SyncStatus::IrrelevantPeer) => false,
+            Some(SyncStatus::Unknown) | None => true,
+        };
+
+        let has_chunks = |peer_id: &PeerId| match self.peers.get(peer_id) {
+            Some(PeerInfo {
+                availability: Some(availability),
+                ..
+            }) => availability.has_chunks(from_chunk, to_chunk),
+            _ => true,
+        };
+
+        self.select_peer(state, stats, |peer_id| {
+            filter(peer_id) && has_tx(peer_id) && has_chunks(peer_id)
+        })
+        .or_else(|| {
+            self.select_peer(state, stats, |peer_id| {
+                filter(peer_id)
+                    && !matches!(sync_status.get(peer_id), Some(SyncStatus::IrrelevantPeer))
+            })
+        })
+    }
+
+    pub fn update_availability(&mut self, peer_id: &PeerId, availability: FileAvailability) {
+        if let Some(info) = self.peers.get_mut(peer_id) {
+            info.availability = Some(availability);
+        }
+    }
+
+    /// Stops selecting the peer to request for the specified duration.
+    pub fn backoff(&mut self, peer_id: &PeerId, duration: Duration) {
+        if let Some(info) = self.peers.get_mut(peer_id) {
+            info.backoff_until = Some(Instant::now() + duration);
+        }
+    }
+
+    /// Returns the statistics of the peers in `stats` that are managed here.
+    pub fn all_stats(&self, stats: &HashMap<PeerId, PeerStats>) -> HashMap<String, PeerStats> {
+        self.peers
+            .keys()
+            .map(|peer_id| {
+                let peer_stats = stats.get(peer_id).cloned().unwrap_or_default();
+                (peer_id.to_string(), peer_stats)
+            })
+            .collect()
+    }
+
     pub fn count(&self, states: &[PeerState]) -> usize {
         self.peers
             .values()
@@ -132,6 +298,8 @@ impl SyncPeers {
 #[cfg(test)]
 mod tests {
     use libp2p::identity;
+    use network::rpc::methods::{BitList, CHUNKS_PER_SEGMENT};
+    use network::SyncInfo;
     use std::collections::HashSet;
 
     use super::*;
@@ -243,6 +411,256 @@ mod tests {
         }
     }
 
+    fn assert_weight(stats: &PeerStats, expected: f64) {
+        assert!(
+            (stats.weight() - expected).abs() < 1e-9,
+            "weight={}, expected={}",
+            stats.weight(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_peer_stats() {
+        let mut stats = PeerStats::default();
+        assert_eq!(stats.weight(), DEFAULT_BYTES_PER_SEC as f64);
+
+        stats.on_response(Duration::from_millis(500), 1000);
+        assert_eq!(stats.rtt_ms, Some(500));
+        assert_eq!(stats.bytes_per_sec, Some(2000));
+        assert_weight(&stats, 2000.0 / 1.5);
+
+        stats.on_response(Duration::from_millis(1000), 1000);
+        assert_eq!(stats.rtt_ms, Some(650));
+        assert_eq!(stats.bytes_per_sec, Some(1700));
+        assert_eq!(stats.bytes_downloaded, 2000);
+        assert_eq!(stats.responses, 2);
+
+        stats.timeouts += 1;
+        assert_weight(&stats, 425.0 / 1.65);
+        stats.proof_failures += 1;
+        assert_weight(&stats, 1700.0 / 36.0 / 1.65);
+    }
+
+    #[test]
+    fn test_peer_stats_rtt() {
+        let fast = PeerStats {
+            rtt_ms: Some(100),
+            bytes_per_sec: Some(2000),
+            ..Default::default()
+        };
+        let slow = PeerStats {
+            rtt_ms: Some(3000),
+            ..fast.clone()
+        };
+
+        // the same throughput, but demoted by the round trip time
+        assert_weight(&fast, 2000.0 / 1.1);
+        assert_weight(&slow, 2000.0 / 4.0);
+        assert!(fast.weight() > slow.weight());
+    }
+
+    #[test]
+    fn test_select_peer() {
+        let mut sync_peers: SyncPeers = Default::default();
+        let addr: Multiaddr = "/ip4/127.0.0.1/tcp/10000".parse().unwrap();
+
+        let mut stats = HashMap::new();
+        assert_eq!(
+            sync_peers.select_peer(PeerState::Connected, &stats, |_| true),
+            None
+        );
+
+        let fast_peer = identity::Keypair::generate_ed25519().public().to_peer_id();
+        sync_peers.add_new_peer(fast_peer, addr.clone());
+        sync_peers.update_state_force(&fast_peer, PeerState::Connected);
+
+        let failing_peer = identity::Keypair::generate_ed25519().public().to_peer_id();
+        sync_peers.add_new_peer(failing_peer, addr);
+        sync_peers.update_state_force(&failing_peer, PeerState::Connected);
+        stats.insert(
+            failing_peer,
+            PeerStats {
+                proof_failures: 100,
+                ..Default::default()
+            },
+        );
+
+        let mut fast_selected = 0;
+        for _ in 0..100 {
+            let selected = sync_peers.select_peer(PeerState::Connected, &stats, |_| true);
+            if selected == Some(fast_peer) {
+                fast_selected += 1;
+            }
+        }
+        assert!(fast_selected > 90);
+
+        let selected = sync_peers.select_peer(PeerState::Connected, &stats, |peer_id| {
+            *peer_id != fast_peer
+        });
+        assert_eq!(selected, Some(failing_peer));
+        assert_eq!(
+            sync_peers.select_peer(PeerState::Found, &stats, |_| true),
+            None
+        );
+
+        // only the stats of managed peers
+        let all_stats = sync_peers.all_stats(&stats);
+        assert_eq!(all_stats.len(), 2);
+        assert_eq!(all_stats[&failing_peer.to_string()].proof_failures, 100);
+        assert_eq!(all_stats[&fast_peer.to_string()], PeerStats::default());
+    }
+
+    #[test]
+    fn test_select_peer_backoff() {
+        let mut sync_peers: SyncPeers = Default::default();
+        let addr: Multiaddr = "/ip4/127.0.0.1/tcp/10000".parse().unwrap();
+
+        let peer_id = identity::Keypair::generate_ed25519().public().to_peer_id();
+        sync_peers.add_new_peer(peer_id, addr);
+        sync_peers.update_state_force(&peer_id, PeerState::Connected);
+
+        let stats = HashMap::new();
+        sync_peers.backoff(&peer_id, Duration::from_secs(60));
+        assert_eq!(
+            sync_peers.select_peer(PeerState::Connected, &stats, |_| true),
+            None
+        );
+        assert_eq!(sync_peers.count(&[PeerState::Connected]), 1);
+
+        sync_peers.backoff(&peer_id, Duration::ZERO);
+        assert_eq!(
+            sync_peers.select_peer(PeerState::Connected, &stats, |_| true),
+            Some(peer_id)
+        );
+    }
+
+    #[test]
+    fn test_select_peer_for_chunks() {
+        let mut sync_peers: SyncPeers = Default::default();
+        let addr: Multiaddr = "/ip4/127.0.0.1/tcp/10000".parse().unwrap();
+
+        let mut peers = vec![];
+        for _ in 0..3 {
+            let peer_id = identity::Keypair::generate_ed25519().public().to_peer_id();
+            sync_peers.add_new_peer(peer_id, addr.clone());
+            sync_peers.update_state_force(&peer_id, PeerState::Connected);
+            peers.push(peer_id);
+        }
+
+        let info = |next_tx_seq| SyncInfo {
+            next_tx_seq,
+            flow_root: Default::default(),
+        };
+        let stats = HashMap::new();
+        let mut sync_status = HashMap::new();
+        sync_status.insert(peers[0], SyncStatus::Behind { info: info(5) });
+        sync_status.insert(peers[1], SyncStatus::Advanced { info: info(20) });
+        sync_status.insert(peers[2], SyncStatus::IrrelevantPeer);
+
+        // prefer the peer that has synced the transaction
+        for _ in 0..10 {
+            let selected = sync_peers.select_peer_for_chunks(
+                PeerState::Connected,
+                &stats,
+                &sync_status,
+                10,
+                (0, 1),
+                |_| true,
+            );
+            assert_eq!(selected, Some(peers[1]));
+        }
+
+        // fall back to the peer behind, whose status may be outdated
+        let selected = sync_peers.select_peer_for_chunks(
+            PeerState::Connected,
+            &stats,
+            &sync_status,
+            10,
+            (0, 1),
+            |peer_id| *peer_id != peers[1],
+        );
+        assert_eq!(selected, Some(peers[0]));
+
+        // never select the peer in another network
+        let selected = sync_peers.select_peer_for_chunks(
+            PeerState::Connected,
+            &stats,
+            &sync_status,
+            10,
+            (0, 1),
+            |peer_id| *peer_id == peers[2],
+        );
+        assert_eq!(selected, None);
+
+        // unknown status
+        sync_status.clear();
+        let selected = sync_peers.select_peer_for_chunks(
+            PeerState::Connected,
+            &stats,
+            &sync_status,
+            10,
+            (0, 1),
+            |peer_id| *peer_id == peers[2],
+        );
+        assert_eq!(selected, Some(peers[2]));
+    }
+
+    #[test]
+    fn test_select_peer_for_chunks_availability() {
+        let mut sync_peers: SyncPeers = Default::default();
+        let addr: Multiaddr = "/ip4/127.0.0.1/tcp/10000".parse().unwrap();
+
+        let mut peers = vec![];
+        for _ in 0..2 {
+            let peer_id = identity::Keypair::generate_ed25519().public().to_peer_id();
+            sync_peers.add_new_peer(peer_id, addr.clone());
+            sync_peers.update_state_force(&peer_id, PeerState::Connected);
+            peers.push(peer_id);
+        }
+
+        // the first peer has only the first segment
+        let mut segments = BitList::with_capacity(2).unwrap();
+        segments.set(0, true).unwrap();
+        let availability = FileAvailability {
+            tx_id: Default::default(),
+            finalized: false,
+            segments,
+        };
+        sync_peers.update_availability(&peers[0], availability);
+
+        let stats = HashMap::new();
+        let sync_status = HashMap::new();
+        let select = |from_chunk, to_chunk| {
+            sync_peers.select_peer_for_chunks(
+                PeerState::Connected,
+                &stats,
+                &sync_status,
+                0,
+                (from_chunk, to_chunk),
+                |peer_id| *peer_id != peers[1],
+            )
+        };
+        assert_eq!(select(0, CHUNKS_PER_SEGMENT as u64), Some(peers[0]));
+
+        // prefer the peer without availability reported
+        let next_segment = (CHUNKS_PER_SEGMENT as u64, 2 * CHUNKS_PER_SEGMENT as u64);
+        for _ in 0..10 {
+            let selected = sync_peers.select_peer_for_chunks(
+                PeerState::Connected,
+                &stats,
+                &sync_status,
+                0,
+                next_segment,
+                |_| true,
+            );
+            assert_eq!(selected, Some(peers[1]));
+        }
+
+        // fall back to the peer without the chunks, whose availability may be outdated
+        assert_eq!(select(next_segment.0, next_segment.1), Some(peers[0]));
+    }
+
     #[test]
     fn test_transition() {
         let mut sync_peers: SyncPeers = Default::default();
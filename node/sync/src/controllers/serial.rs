@@ -1,11 +1,11 @@
 use crate::context::SyncNetworkContext;
 use crate::controllers::peers::{PeerState, SyncPeers};
-use crate::controllers::FileSyncInfo;
+use crate::controllers::{missing_ranges, FileSyncInfo};
 use file_location_cache::FileLocationCache;
 use libp2p::swarm::DialError;
 use network::{
-    multiaddr::Protocol, rpc::GetChunksRequest, types::FindFile, Multiaddr, NetworkMessage,
-    PeerAction, PeerId, PubsubMessage, SyncId as RequestId,
+    multiaddr::Protocol, rpc::methods::FileAvailability, rpc::GetChunksRequest, types::FindFile,
+    Multiaddr, NetworkMessage, PeerAction, PeerId, PubsubMessage, SyncId as RequestId,
 };
 use shared_types::{timestamp_now, ChunkArrayWithProof, TxID, CHUNK_SIZE};
 use std::{
@@ -14,11 +14,12 @@ use std::{
 };
 use storage_async::Store;
 
-const MAX_CHUNKS_TO_REQUEST: u64 = 2 * 1024;
-const MAX_REQUEST_FAILURES: usize = 3;
-const PEER_REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
-const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(5);
-const WAIT_OUTGOING_CONNECTION_TIMEOUT: Duration = Duration::from_secs(10);
+pub(crate) const MAX_CHUNKS_TO_REQUEST: u64 = 2 * 1024;
+pub(crate) const MAX_REQUEST_FAILURES: usize = 3;
+pub(crate) const PEER_REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+pub(crate) const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(5);
+pub(crate) const WAIT_OUTGOING_CONNECTION_TIMEOUT: Duration = Duration::from_secs(10);
+pub(crate) const RATE_LIMITED_BACKOFF: Duration = Duration::from_secs(1);
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum FailureReason {
@@ -114,6 +115,7 @@ impl SerialSyncController {
             num_chunks: self.num_chunks,
             next_chunks: self.next_chunk,
             state: format!("{:?}", self.state),
+            peer_stats: self.peers.all_stats(&self.ctx.peer_stats()),
         }
     }
 
@@ -130,6 +132,22 @@ impl SerialSyncController {
         self.peers.transition();
     }
 
+    /// Resumes to download from the first missing chunk, given the chunk ranges
+    /// already stored, e.g. before node restarted.
+    pub async fn resume(&mut self, downloaded: &[(u64, u64)]) {
+        self.next_chunk = missing_ranges(self.num_chunks, downloaded)
+            .first()
+            .map_or(self.num_chunks, |(from_chunk, _)| *from_chunk);
+
+        if self.next_chunk > 0 {
+            info!(%self.tx_seq, %self.next_chunk, "Resume to sync file");
+        }
+
+        if self.next_chunk >= self.num_chunks {
+            self.finalize().await;
+        }
+    }
+
     fn try_find_peers(&mut self) {
         info!(%self.tx_seq, "Finding peers");
 
@@ -138,8 +156,8 @@ impl SerialSyncController {
 
         for announcement in self.file_location_cache.get_all(self.tx_id) {
             // make sure peer_id is part of the address
-            let peer_id: PeerId = announcement.peer_id.clone().into();
-            let mut addr: Multiaddr = announcement.at.clone().into();
+            let peer_id = announcement.peer_id();
+            let mut addr = announcement.at();
             addr.push(Protocol::P2p(peer_id.into()));
 
             found_new_peer = self.on_peer_found(peer_id, addr) || found_new_peer;
@@ -182,9 +200,28 @@ impl SerialSyncController {
     }
 
     fn try_request_next(&mut self) {
-        // select a random peer
-        let peer_id = match self.peers.random_peer(PeerState::Connected) {
-            Some((peer_id, _)) => peer_id,
+        // request next chunk array
+        let from_chunk = self.next_chunk;
+        let to_chunk = std::cmp::min(from_chunk + MAX_CHUNKS_TO_REQUEST, self.num_chunks);
+
+        // select a peer weighted by statistics
+        let stats = self.ctx.peer_stats();
+        let sync_status = self.ctx.peer_sync_status();
+        let peer_id = match self.peers.select_peer_for_chunks(
+            PeerState::Connected,
+            &stats,
+            &sync_status,
+            self.tx_seq,
+            (from_chunk, to_chunk),
+            |_| true,
+        ) {
+            Some(peer_id) => peer_id,
+            // wait for the peers rate limited or to be disconnected for another network,
+            // and keep in `AwaitingDownload` state
+            None if self.peers.count(&[PeerState::Connected]) > 0 => {
+                debug!(%self.tx_seq, "No connected peers available to request chunks");
+                return;
+            }
             None => {
                 warn!(%self.tx_seq, "No peers available to request chunks");
                 self.state = SyncState::Idle;
@@ -192,9 +229,12 @@ impl SerialSyncController {
             }
         };
 
-        // request next chunk array
-        let from_chunk = self.next_chunk;
-        let to_chunk = std::cmp::min(from_chunk + MAX_CHUNKS_TO_REQUEST, self.num_chunks);
+        // wait for the bandwidth quota, and keep in `AwaitingDownload` state
+        let bytes = (to_chunk - from_chunk) * CHUNK_SIZE as u64;
+        if !self.ctx.allows_download(&peer_id, bytes) {
+            debug!(%self.tx_seq, %peer_id, "Bandwidth limits exceeded to request chunks");
+            return;
+        }
 
         let request_id = network::RequestId::Sync(RequestId::SerialSync { tx_id: self.tx_id });
 
@@ -268,6 +308,22 @@ impl SerialSyncController {
                 .update_state(&peer_id, PeerState::Connecting, PeerState::Connected)
         {
             info!(%self.tx_seq, %peer_id, "Peer connected");
+
+            // the peers found by shard config may have only part of the file
+            let announced = self
+                .file_location_cache
+                .get_all(self.tx_id)
+                .iter()
+                .any(|announcement| announcement.peer_id() == peer_id);
+            if !announced {
+                self.ctx.request_file_availability(peer_id, self.tx_id);
+            }
+        }
+    }
+
+    pub fn on_file_availability(&mut self, peer_id: PeerId, availability: FileAvailability) {
+        if availability.tx_id == self.tx_id {
+            self.peers.update_availability(&peer_id, availability);
         }
     }
 
@@ -321,13 +377,13 @@ impl SerialSyncController {
             return;
         }
 
-        let (from_chunk, to_chunk) = match self.state {
+        let (from_chunk, to_chunk, since) = match self.state {
             SyncState::Downloading {
                 peer_id: _peer_id,
                 from_chunk,
                 to_chunk,
-                ..
-            } => (from_chunk, to_chunk),
+                since,
+            } => (from_chunk, to_chunk, since),
             _ => return,
         };
 
@@ -337,6 +393,7 @@ impl SerialSyncController {
         let data_len = response.chunks.data.len();
         if data_len == 0 || data_len % CHUNK_SIZE > 0 {
             warn!(%from_peer_id, %self.tx_seq, %data_len, "Invalid chunk response data length");
+            self.on_proof_failure(from_peer_id);
             self.ban_peer(from_peer_id, "Invalid chunk response data length");
             self.state = SyncState::Idle;
             return;
@@ -347,6 +404,7 @@ impl SerialSyncController {
         let end_index = start_index + (data_len / CHUNK_SIZE) as u64;
         if start_index != from_chunk || end_index != to_chunk {
             warn!(%self.tx_seq, "Invalid chunk response range, expected={from_chunk}..{to_chunk}, actual={start_index}..{end_index}");
+            self.on_proof_failure(from_peer_id);
             self.ban_peer(from_peer_id, "Invalid chunk response range");
             self.state = SyncState::Idle;
             return;
@@ -369,6 +427,7 @@ impl SerialSyncController {
             }
             Err(err) => {
                 warn!(%err, "Failed to validate chunks response");
+                self.on_proof_failure(from_peer_id);
                 self.ban_peer(from_peer_id, "Chunk array validation failed");
                 self.state = SyncState::Idle;
                 return;
@@ -376,6 +435,9 @@ impl SerialSyncController {
         }
 
         self.failures = 0;
+        self.ctx.update_peer_stats(from_peer_id, |stats| {
+            stats.on_response(since.elapsed(), data_len)
+        });
 
         // store in db
         match self
@@ -406,7 +468,11 @@ impl SerialSyncController {
             return;
         }
 
-        // finalize tx if all chunks downloaded
+        self.finalize().await;
+    }
+
+    /// Finalizes the transaction once all chunks downloaded.
+    async fn finalize(&mut self) {
         match self
             .store
             .finalize_tx_with_hash(self.tx_id.seq, self.tx_id.hash)
@@ -433,9 +499,29 @@ impl SerialSyncController {
             return;
         }
 
+        self.ctx
+            .update_peer_stats(peer_id, |stats| stats.rpc_errors += 1);
+
         self.handle_response_failure(peer_id, "RPC Error");
     }
 
+    /// Handles the request rejected due to the rate limits of peer, which is not a failure of
+    /// peer, so just back off and request again later.
+    pub fn on_request_rate_limited(&mut self, peer_id: PeerId) {
+        if self.handle_on_response_mismatch(peer_id) {
+            return;
+        }
+
+        info!(%peer_id, %self.tx_seq, "Chunks request rate limited");
+        self.peers.backoff(&peer_id, RATE_LIMITED_BACKOFF);
+        self.state = SyncState::AwaitingDownload;
+    }
+
+    fn on_proof_failure(&mut self, peer_id: PeerId) {
+        self.ctx
+            .update_peer_stats(peer_id, |stats| stats.proof_failures += 1);
+    }
+
     fn handle_response_failure(&mut self, peer_id: PeerId, reason: &'static str) {
         info!(%peer_id, %self.tx_seq, %reason, "Chunks request failed");
 
@@ -518,6 +604,11 @@ impl SerialSyncController {
 
                 SyncState::AwaitingDownload => {
                     self.try_request_next();
+
+                    // bandwidth limits exceeded
+                    if self.state == SyncState::AwaitingDownload {
+                        return;
+                    }
                 }
 
                 SyncState::Downloading { peer_id, since, .. } => {
@@ -525,6 +616,8 @@ impl SerialSyncController {
                         // e.g. peer disconnected by remote node
                         self.state = SyncState::Idle;
                     } else if since.elapsed() >= DOWNLOAD_TIMEOUT {
+                        self.ctx
+                            .update_peer_stats(peer_id, |stats| stats.timeouts += 1);
                         self.handle_response_failure(peer_id, "RPC timeout");
                     } else {
                         return;
@@ -542,10 +635,11 @@ mod tests {
     use super::*;
     use crate::test_util::tests::{create_2_store, create_file_location_cache};
     use libp2p::identity;
-    use network::{ReportSource, Request};
+    use network::rpc::methods::BitList;
+    use network::{ReportSource, Request, SyncInfo, SyncStatus};
     use storage::log_store::log_manager::LogConfig;
     use storage::log_store::log_manager::LogManager;
-    use storage::log_store::LogStoreRead;
+    use storage::log_store::{LogStoreChunkRead, LogStoreChunkWrite, LogStoreRead};
     use storage::H256;
     use task_executor::{test_utils::TestRuntime, TaskExecutor};
     use tokio::sync::mpsc::{self, UnboundedReceiver};
@@ -720,6 +814,9 @@ mod tests {
                             network::SyncId::SerialSync { tx_id } => {
                                 assert_eq!(tx_id, controller.tx_id);
                             }
+                            _ => {
+                                panic!("Not expected message: network::SyncId::SerialSync");
+                            }
                         },
                         _ => {
                             panic!("Not expected message: network::RequestId::Sync");
@@ -738,6 +835,202 @@ mod tests {
         ));
     }
 
+    #[tokio::test]
+    async fn test_request_rate_limited() {
+        let runtime = TestRuntime::default();
+        let task_executor = runtime.task_executor.clone();
+        let (mut controller, mut network_recv) = create_default_controller(task_executor, None);
+        let addr: Multiaddr = "/ip4/127.0.0.1/tcp/10000".parse().unwrap();
+
+        let limited_peer_id = identity::Keypair::generate_ed25519().public().to_peer_id();
+        controller.peers.add_new_peer(limited_peer_id, addr.clone());
+        controller
+            .peers
+            .update_state_force(&limited_peer_id, PeerState::Connected);
+
+        controller.state = SyncState::AwaitingDownload;
+        controller.transition();
+        assert!(matches!(
+            network_recv.try_recv(),
+            Ok(NetworkMessage::SendRequest { peer_id, .. }) if peer_id == limited_peer_id
+        ));
+
+        // neither reported nor counted as failure
+        controller.on_request_rate_limited(limited_peer_id);
+        assert_eq!(controller.failures, 0);
+        assert!(!controller.ctx.peer_stats().contains_key(&limited_peer_id));
+        assert_eq!(*controller.get_status(), SyncState::AwaitingDownload);
+
+        // wait for the peer backing off
+        controller.transition();
+        assert_eq!(*controller.get_status(), SyncState::AwaitingDownload);
+        assert!(network_recv.try_recv().is_err());
+
+        // request from other peers
+        let new_peer_id = identity::Keypair::generate_ed25519().public().to_peer_id();
+        controller.peers.add_new_peer(new_peer_id, addr);
+        controller
+            .peers
+            .update_state_force(&new_peer_id, PeerState::Connected);
+        controller.transition();
+        assert!(matches!(
+            network_recv.try_recv(),
+            Ok(NetworkMessage::SendRequest { peer_id, .. }) if peer_id == new_peer_id
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_request_by_sync_status() {
+        let runtime = TestRuntime::default();
+        let task_executor = runtime.task_executor.clone();
+        let (mut controller, mut network_recv) = create_default_controller(task_executor, None);
+        let addr: Multiaddr = "/ip4/127.0.0.1/tcp/10000".parse().unwrap();
+
+        let behind_peer_id = identity::Keypair::generate_ed25519().public().to_peer_id();
+        let synced_peer_id = identity::Keypair::generate_ed25519().public().to_peer_id();
+        for peer_id in [behind_peer_id, synced_peer_id] {
+            controller.peers.add_new_peer(peer_id, addr.clone());
+            controller
+                .peers
+                .update_state_force(&peer_id, PeerState::Connected);
+        }
+
+        // the file is tx 0, which is not synced by the peer behind
+        let info = |next_tx_seq| SyncInfo {
+            next_tx_seq,
+            flow_root: H256::zero(),
+        };
+        controller
+            .ctx
+            .update_peer_sync_status(behind_peer_id, SyncStatus::Behind { info: info(0) });
+        controller
+            .ctx
+            .update_peer_sync_status(synced_peer_id, SyncStatus::Synced { info: info(1) });
+
+        for _ in 0..10 {
+            controller.state = SyncState::AwaitingDownload;
+            controller.transition();
+            assert!(matches!(
+                network_recv.try_recv(),
+                Ok(NetworkMessage::SendRequest { peer_id, .. }) if peer_id == synced_peer_id
+            ));
+        }
+
+        // the peer in another network is never requested
+        controller
+            .ctx
+            .update_peer_sync_status(behind_peer_id, SyncStatus::IrrelevantPeer);
+        controller.ctx.remove_peer_sync_status(&synced_peer_id);
+        controller
+            .peers
+            .update_state_force(&synced_peer_id, PeerState::Disconnected);
+        controller.state = SyncState::AwaitingDownload;
+        controller.transition();
+        assert!(network_recv.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_request_by_file_availability() {
+        let runtime = TestRuntime::default();
+        let task_executor = runtime.task_executor.clone();
+        let (mut controller, mut network_recv) = create_default_controller(task_executor, None);
+        let tx_id = controller.tx_id;
+        let addr: Multiaddr = "/ip4/127.0.0.1/tcp/10000".parse().unwrap();
+
+        // request the file availability of peers that have not announced the file
+        let mut peers = vec![];
+        for _ in 0..2 {
+            let peer_id = identity::Keypair::generate_ed25519().public().to_peer_id();
+            controller.peers.add_new_peer(peer_id, addr.clone());
+            controller
+                .peers
+                .update_state_force(&peer_id, PeerState::Connecting);
+            controller.on_peer_connected(peer_id);
+
+            assert!(matches!(
+                network_recv.try_recv(),
+                Ok(NetworkMessage::SendRequest {
+                    peer_id: to_peer_id,
+                    request_id: network::RequestId::Sync(RequestId::FileAvailability { .. }),
+                    request: Request::GetFileAvailability(request),
+                }) if to_peer_id == peer_id && request.tx_id == tx_id
+            ));
+            peers.push(peer_id);
+        }
+
+        let availability = |finalized| FileAvailability {
+            tx_id,
+            finalized,
+            segments: BitList::with_capacity(1).unwrap(),
+        };
+        controller.on_file_availability(peers[0], availability(false));
+        controller.on_file_availability(peers[1], availability(true));
+
+        // ignore the availability of other files
+        let mut segments = BitList::with_capacity(1).unwrap();
+        segments.set(0, true).unwrap();
+        let other_file = FileAvailability {
+            tx_id: TxID {
+                seq: 1,
+                hash: H256::random(),
+            },
+            finalized: false,
+            segments,
+        };
+        controller.on_file_availability(peers[0], other_file);
+
+        // request from the peer that has the chunks
+        for _ in 0..10 {
+            controller.state = SyncState::AwaitingDownload;
+            controller.transition();
+            assert!(matches!(
+                network_recv.try_recv(),
+                Ok(NetworkMessage::SendRequest { peer_id, .. }) if peer_id == peers[1]
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_peer_stats_shared() {
+        let runtime = TestRuntime::default();
+        let task_executor = runtime.task_executor.clone();
+        let (mut controller, _network_recv) =
+            create_default_controller(task_executor.clone(), None);
+
+        let peer_id = identity::Keypair::generate_ed25519().public().to_peer_id();
+        let addr: Multiaddr = "/ip4/127.0.0.1/tcp/10000".parse().unwrap();
+        controller.on_peer_found(peer_id, addr.clone());
+        controller
+            .peers
+            .update_state_force(&peer_id, PeerState::Connected);
+        controller.state = SyncState::Downloading {
+            peer_id,
+            from_chunk: 0,
+            to_chunk: 123,
+            since: Instant::now(),
+        };
+        controller.on_request_failed(peer_id);
+
+        // the stats are kept for other files synchronized later
+        let tx_id = TxID {
+            seq: 1,
+            hash: H256::random(),
+        };
+        let store = Arc::new(RwLock::new(
+            LogManager::memorydb(LogConfig::default()).unwrap(),
+        ));
+        let mut other = SerialSyncController::new(
+            tx_id,
+            123,
+            controller.ctx.clone(),
+            Store::new(store, task_executor),
+            create_file_location_cache(peer_id, vec![tx_id]),
+        );
+        other.on_peer_found(peer_id, addr);
+        let peer_stats = other.get_sync_info().peer_stats;
+        assert_eq!(peer_stats[&peer_id.to_string()].rpc_errors, 1);
+    }
+
     #[tokio::test]
     async fn test_ban_peer() {
         let runtime = TestRuntime::default();
@@ -1342,6 +1635,37 @@ mod tests {
         assert_eq!(network_recv.try_recv().is_err(), true);
     }
 
+    #[tokio::test]
+    async fn test_resume() {
+        let tx_seq = 0;
+        let chunk_count = 2048;
+        let (store, peer_store, txs, _) = create_2_store(vec![chunk_count]);
+
+        let runtime = TestRuntime::default();
+        let task_executor = runtime.task_executor.clone();
+        let (mut controller, mut network_recv) =
+            create_controller(task_executor, None, store.clone(), txs[0].id(), chunk_count);
+
+        controller.resume(&[(0, 1024), (1536, 2048)]).await;
+        assert_eq!(controller.next_chunk, 1024);
+        assert_eq!(*controller.get_status(), SyncState::Idle);
+
+        // finalize directly if all chunks stored
+        let chunks = peer_store
+            .read()
+            .await
+            .get_chunks_by_tx_and_index_range(tx_seq, 0, chunk_count)
+            .unwrap()
+            .unwrap();
+        store.write().await.put_chunks(tx_seq, chunks).unwrap();
+
+        controller.resume(&[(0, chunk_count as u64)]).await;
+        assert_eq!(controller.next_chunk, chunk_count as u64);
+        assert_eq!(*controller.get_status(), SyncState::Completed);
+        assert!(store.read().await.check_tx_completed(tx_seq).unwrap());
+        assert_eq!(network_recv.try_recv().is_err(), true);
+    }
+
     #[tokio::test]
     async fn test_handle_response_failure() {
         let init_peer_id = identity::Keypair::generate_ed25519().public().to_peer_id();
@@ -0,0 +1,141 @@
+use network::{rpc::IonianData, PeerId};
+use serde::{Deserialize, Serialize};
+use shared_types::DataRoot;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use storage::H256;
+
+/// Lookups are dropped after this duration, whether any peer responded or not.
+const LOOKUP_EXPIRATION: Duration = Duration::from_secs(600);
+
+/// The latest transaction of a data root reported by peers.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DataByHashInfo {
+    pub tx_seq: u64,
+    pub tx_hash: H256,
+    /// The file size in bytes.
+    pub size: u64,
+    /// Whether the file is finalized on the responding peer.
+    pub finalized: bool,
+    /// The file data if finalized and small enough, otherwise empty.
+    pub data: Vec<u8>,
+    pub peer_id: String,
+}
+
+struct Lookup {
+    since: Instant,
+    result: Option<DataByHashInfo>,
+}
+
+/// Resolves data roots to the latest transactions by `DataByHash` requests to peers,
+/// so that files could be found without the log entries synced from the chain.
+#[derive(Default)]
+pub struct DataByHashLookups {
+    lookups: HashMap<DataRoot, Lookup>,
+}
+
+impl DataByHashLookups {
+    /// Starts to look up the data roots, keeping the results of previous lookups.
+    pub fn start(&mut self, roots: &[DataRoot]) {
+        let now = Instant::now();
+
+        for root in roots {
+            self.lookups
+                .entry(*root)
+                .and_modify(|lookup| lookup.since = now)
+                .or_insert(Lookup {
+                    since: now,
+                    result: None,
+                });
+        }
+    }
+
+    /// Records the response of a peer if the data root is being looked up and the
+    /// transaction is newer than the known one, and returns whether recorded.
+    pub fn on_response(&mut self, peer_id: PeerId, data: IonianData) -> bool {
+        let lookup = match self.lookups.get_mut(&data.hash) {
+            Some(lookup) => lookup,
+            None => return false,
+        };
+
+        if matches!(&lookup.result, Some(result) if result.tx_seq >= data.tx_id.seq) {
+            return false;
+        }
+
+        lookup.result = Some(DataByHashInfo {
+            tx_seq: data.tx_id.seq,
+            tx_hash: data.tx_id.hash,
+            size: data.size,
+            finalized: data.finalized,
+            data: data.data.to_vec(),
+            peer_id: peer_id.to_string(),
+        });
+
+        true
+    }
+
+    /// Returns the latest transaction of a data root reported so far.
+    pub fn get(&self, root: &DataRoot) -> Option<DataByHashInfo> {
+        self.lookups.get(root)?.result.clone()
+    }
+
+    pub fn remove_expired(&mut self) {
+        self.lookups
+            .retain(|_, lookup| lookup.since.elapsed() < LOOKUP_EXPIRATION);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shared_types::TxID;
+
+    fn ionian_data(root: DataRoot, tx_seq: u64) -> IonianData {
+        IonianData {
+            hash: root,
+            tx_id: TxID {
+                seq: tx_seq,
+                hash: H256::random(),
+            },
+            size: 3,
+            finalized: true,
+            data: vec![1, 2, 3].into(),
+        }
+    }
+
+    #[test]
+    fn test_on_response() {
+        let mut lookups = DataByHashLookups::default();
+        let root = H256::random();
+        let peer_id = PeerId::random();
+
+        // not being looked up
+        assert!(!lookups.on_response(peer_id, ionian_data(root, 1)));
+        assert_eq!(lookups.get(&root), None);
+
+        lookups.start(&[root]);
+        assert_eq!(lookups.get(&root), None);
+
+        let data = ionian_data(root, 5);
+        assert!(lookups.on_response(peer_id, data.clone()));
+        let info = lookups.get(&root).unwrap();
+        assert_eq!(info.tx_seq, 5);
+        assert_eq!(info.tx_hash, data.tx_id.hash);
+        assert_eq!(info.data, vec![1, 2, 3]);
+        assert_eq!(info.peer_id, peer_id.to_string());
+
+        // keep the latest transaction
+        assert!(!lookups.on_response(PeerId::random(), ionian_data(root, 3)));
+        assert_eq!(lookups.get(&root).unwrap().tx_seq, 5);
+        assert!(lookups.on_response(PeerId::random(), ionian_data(root, 8)));
+        assert_eq!(lookups.get(&root).unwrap().tx_seq, 8);
+
+        // restart the lookup without dropping the result
+        lookups.start(&[root]);
+        assert_eq!(lookups.get(&root).unwrap().tx_seq, 8);
+
+        lookups.remove_expired();
+        assert_eq!(lookups.get(&root).unwrap().tx_seq, 8);
+    }
+}
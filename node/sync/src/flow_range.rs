@@ -0,0 +1,285 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use shared_types::{bytes_to_chunks, Transaction};
+use std::cmp;
+use std::time::Instant;
+use storage_async::Store;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FlowRangeSyncInfo {
+    pub start_index: u64,
+    pub end_index: u64,
+    pub elapsed_secs: u64,
+    /// The number of transactions that cover the range.
+    pub num_txs: u64,
+    pub completed_txs: u64,
+    /// The percentage of the data chunks in the range that are already stored.
+    pub coverage_percent: f64,
+}
+
+/// Syncs the files that cover a flow entry range, e.g. the mining range of a miner.
+///
+/// Transactions are checked in sequence, and the incompleted ones are synced as files,
+/// which only download the missing chunks. Transactions appended later are also synced
+/// if they fall into the range. A transaction is checked again until completed, so it's
+/// synced again if the previous sync failed.
+pub struct FlowRangeSync {
+    start_index: u64,
+    end_index: u64,
+    since: Instant,
+
+    /// The first transaction that covers the range.
+    first_tx_seq: u64,
+
+    /// The next transaction to check, where the previous ones are completed.
+    next_tx_seq: u64,
+}
+
+impl FlowRangeSync {
+    pub async fn new(store: &Store, start_index: u64, end_index: u64) -> Result<Self> {
+        let first_tx_seq = find_first_tx(store, start_index).await?;
+
+        Ok(Self {
+            start_index,
+            end_index,
+            since: Instant::now(),
+            first_tx_seq,
+            next_tx_seq: first_tx_seq,
+        })
+    }
+
+    pub fn range(&self) -> (u64, u64) {
+        (self.start_index, self.end_index)
+    }
+
+    /// Returns at most `max_txs` transactions to sync next, which are neither completed nor
+    /// in sync according to `is_syncing`.
+    pub async fn next_txs(
+        &mut self,
+        store: &Store,
+        max_txs: usize,
+        is_syncing: impl Fn(u64) -> bool,
+    ) -> Result<Vec<u64>> {
+        let mut txs = vec![];
+        let mut tx_seq = self.next_tx_seq;
+
+        while txs.len() < max_txs {
+            let tx = match self.next_tx(store, tx_seq).await? {
+                Some(tx) => tx,
+                None => break,
+            };
+            tx_seq += 1;
+
+            if store.check_tx_completed(tx.seq).await? {
+                // no need to check the leading completed transactions again
+                if self.next_tx_seq == tx.seq {
+                    self.next_tx_seq += 1;
+                }
+            } else if !is_syncing(tx.seq) {
+                txs.push(tx.seq);
+            }
+        }
+
+        Ok(txs)
+    }
+
+    /// Re-checks the transactions from `min_tx_seq`, which are reverted and may be replaced.
+    pub fn on_tx_reverted(&mut self, min_tx_seq: u64) {
+        self.first_tx_seq = self.first_tx_seq.min(min_tx_seq);
+        self.next_tx_seq = self.next_tx_seq.min(min_tx_seq);
+    }
+
+    pub async fn get_sync_info(&self, store: &Store) -> Result<FlowRangeSyncInfo> {
+        let mut num_txs = 0;
+        let mut completed_txs = 0;
+        let mut total_chunks = 0;
+        let mut available_chunks = 0;
+
+        let mut tx_seq = self.first_tx_seq;
+        while let Some(tx) = self.next_tx(store, tx_seq).await? {
+            tx_seq += 1;
+            num_txs += 1;
+
+            // the data chunks of the transaction in range
+            let tx_start = tx.start_entry_index;
+            let tx_end = tx_start + bytes_to_chunks(tx.size as usize) as u64;
+            total_chunks += overlap((tx_start, tx_end), self.range());
+
+            if store.check_tx_completed(tx.seq).await? {
+                completed_txs += 1;
+                available_chunks += overlap((tx_start, tx_end), self.range());
+                continue;
+            }
+
+            for (start, end) in store.get_available_chunk_ranges_by_tx(tx.seq).await? {
+                available_chunks += overlap((tx_start + start, tx_start + end), self.range());
+            }
+        }
+
+        let coverage_percent = match total_chunks {
+            0 => 100.0,
+            _ => available_chunks as f64 * 100.0 / total_chunks as f64,
+        };
+
+        Ok(FlowRangeSyncInfo {
+            start_index: self.start_index,
+            end_index: self.end_index,
+            elapsed_secs: self.since.elapsed().as_secs(),
+            num_txs,
+            completed_txs,
+            coverage_percent,
+        })
+    }
+
+    /// Returns the transaction if it exists and starts before the end of range.
+    async fn next_tx(&self, store: &Store, tx_seq: u64) -> Result<Option<Transaction>> {
+        Ok(store
+            .get_tx_by_seq_number(tx_seq)
+            .await?
+            .filter(|tx| tx.start_entry_index < self.end_index))
+    }
+}
+
+/// Returns the first transaction that ends after `start_index` in flow, or the next
+/// transaction seq if none.
+async fn find_first_tx(store: &Store, start_index: u64) -> Result<u64> {
+    let (mut low, mut high) = (0, store.next_tx_seq().await?);
+
+    while low < high {
+        let mid = low + (high - low) / 2;
+        let tx_end = match store.get_tx_by_seq_number(mid).await? {
+            Some(tx) => tx.start_entry_index + tx.num_entries() as u64,
+            None => break,
+        };
+
+        if tx_end > start_index {
+            high = mid;
+        } else {
+            low = mid + 1;
+        }
+    }
+
+    Ok(low)
+}
+
+fn overlap(a: (u64, u64), b: (u64, u64)) -> u64 {
+    cmp::min(a.1, b.1).saturating_sub(cmp::max(a.0, b.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::tests::create_2_store;
+    use storage::log_store::{LogStoreChunkRead, LogStoreChunkWrite, LogStoreWrite};
+    use task_executor::test_utils::TestRuntime;
+
+    #[tokio::test]
+    async fn test_flow_range_sync() {
+        let (store, peer_store, txs, _) = create_2_store(vec![1024, 2048, 1024, 512]);
+        let runtime = TestRuntime::default();
+        let async_store = Store::new(store.clone(), runtime.task_executor.clone());
+
+        // half of the second tx is stored, and the third tx is completed
+        let chunks = peer_store
+            .read()
+            .await
+            .get_chunks_by_tx_and_index_range(1, 0, 1024)
+            .unwrap()
+            .unwrap();
+        store.write().await.put_chunks(1, chunks).unwrap();
+        let chunks = peer_store
+            .read()
+            .await
+            .get_chunks_by_tx_and_index_range(2, 0, 1024)
+            .unwrap()
+            .unwrap();
+        store.write().await.put_chunks(2, chunks).unwrap();
+        store.write().await.finalize_tx(2).unwrap();
+
+        // the range from the middle of the second tx to the end of the third tx
+        let start_index = txs[1].start_entry_index + 1024;
+        let end_index = txs[2].start_entry_index + 1024;
+        let mut range_sync = FlowRangeSync::new(&async_store, start_index, end_index)
+            .await
+            .unwrap();
+        assert_eq!(range_sync.first_tx_seq, 1);
+
+        let info = range_sync.get_sync_info(&async_store).await.unwrap();
+        assert_eq!(info.num_txs, 2);
+        assert_eq!(info.completed_txs, 1);
+        assert_eq!(info.coverage_percent, 50.0);
+
+        assert_eq!(
+            range_sync
+                .next_txs(&async_store, 8, |_| false)
+                .await
+                .unwrap(),
+            vec![1]
+        );
+        assert!(range_sync
+            .next_txs(&async_store, 8, |tx_seq| tx_seq == 1)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_flow_range_sync_retry() {
+        let (store, peer_store, txs, _) = create_2_store(vec![1024, 1024, 1024]);
+        let runtime = TestRuntime::default();
+        let async_store = Store::new(store.clone(), runtime.task_executor.clone());
+
+        let start_index = txs[0].start_entry_index;
+        let end_index = txs[2].start_entry_index + 1024;
+        let mut range_sync = FlowRangeSync::new(&async_store, start_index, end_index)
+            .await
+            .unwrap();
+
+        // at most 2 txs at a time
+        assert_eq!(
+            range_sync
+                .next_txs(&async_store, 2, |_| false)
+                .await
+                .unwrap(),
+            vec![0, 1]
+        );
+        assert_eq!(
+            range_sync
+                .next_txs(&async_store, 2, |tx_seq| tx_seq < 2)
+                .await
+                .unwrap(),
+            vec![2]
+        );
+
+        // the first sync of tx 0 failed, so it's synced again
+        assert_eq!(
+            range_sync
+                .next_txs(&async_store, 2, |tx_seq| tx_seq > 0)
+                .await
+                .unwrap(),
+            vec![0]
+        );
+        assert_eq!(range_sync.next_tx_seq, 0);
+
+        // skip the completed txs from now on
+        for tx_seq in 0..2 {
+            let chunks = peer_store
+                .read()
+                .await
+                .get_chunks_by_tx_and_index_range(tx_seq, 0, 1024)
+                .unwrap()
+                .unwrap();
+            store.write().await.put_chunks(tx_seq, chunks).unwrap();
+            store.write().await.finalize_tx(tx_seq).unwrap();
+        }
+        assert_eq!(
+            range_sync
+                .next_txs(&async_store, 2, |_| false)
+                .await
+                .unwrap(),
+            vec![2]
+        );
+        assert_eq!(range_sync.next_tx_seq, 2);
+    }
+}
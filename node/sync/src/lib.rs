@@ -2,24 +2,39 @@
 extern crate tracing;
 
 mod auto_sync;
+mod bandwidth;
 mod context;
 mod controllers;
+mod data_by_hash;
+mod flow_range;
 mod service;
 mod test_util;
 
-pub use controllers::FileSyncInfo;
+pub use auto_sync::AutoSyncPolicy;
+pub use bandwidth::BandwidthLimits;
+pub use controllers::{FileSyncInfo, PeerStats};
+pub use data_by_hash::DataByHashInfo;
+pub use flow_range::FlowRangeSyncInfo;
 pub use service::{SyncMessage, SyncRequest, SyncResponse, SyncSender, SyncService};
 
 pub struct Config {
     pub auto_sync_disabled: bool,
+    /// Policy to decide which files to synchronize automatically.
+    pub auto_sync_policy: AutoSyncPolicy,
     pub max_sync_files: usize,
+    /// The maximum number of peers to download a large file from in parallel.
+    pub max_parallel_requests: usize,
+    pub bandwidth_limits: BandwidthLimits,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
             auto_sync_disabled: false,
+            auto_sync_policy: Default::default(),
             max_sync_files: 8,
+            max_parallel_requests: 4,
+            bandwidth_limits: Default::default(),
         }
     }
 }
@@ -1,18 +1,29 @@
 use crate::auto_sync::AutoSyncManager;
+use crate::bandwidth::BandwidthLimits;
 use crate::context::SyncNetworkContext;
-use crate::controllers::{FailureReason, FileSyncInfo, SerialSyncController, SyncState};
+use crate::controllers::{
+    FailureReason, FileSyncInfo, ParallelSyncController, SerialSyncController, SyncController,
+    SyncState, MAX_CHUNKS_TO_REQUEST,
+};
+use crate::data_by_hash::{DataByHashInfo, DataByHashLookups};
+use crate::flow_range::{FlowRangeSync, FlowRangeSyncInfo};
 use crate::Config;
 use anyhow::{bail, Result};
 use file_location_cache::FileLocationCache;
 use libp2p::swarm::DialError;
 use log_entry_sync::LogSyncEvent;
+use network::rpc::methods::{
+    BitList, FileAvailability, CHUNKS_PER_SEGMENT, MAX_DATA_LEN, MAX_REQUEST_BLOCKS, MAX_SEGMENTS,
+};
 use network::{
-    rpc::GetChunksRequest, rpc::RPCResponseErrorCode, Multiaddr, NetworkMessage, PeerAction,
-    PeerId, PeerRequestId, SyncId as RequestId,
+    multiaddr::Protocol, rpc::DataByHashRequest, rpc::GetChunksRequest,
+    rpc::GetFileAvailabilityRequest, rpc::IonianData, rpc::RPCError, rpc::RPCResponseErrorCode,
+    types::ShardConfig, Multiaddr, NetworkMessage, PeerAction, PeerId, PeerRequestId,
+    SyncId as RequestId, SyncStatus,
 };
-use shared_types::{bytes_to_chunks, ChunkArrayWithProof, TxID};
+use shared_types::{bytes_to_chunks, ChunkArrayWithProof, DataRoot, TxID, CHUNK_SIZE};
 use std::{
-    collections::{hash_map::Entry, HashMap},
+    collections::{hash_map::Entry, HashMap, HashSet},
     sync::Arc,
 };
 use storage::error::Result as StorageResult;
@@ -36,6 +47,10 @@ pub enum SyncMessage {
     PeerDisconnected {
         peer_id: PeerId,
     },
+    PeerSyncStatus {
+        peer_id: PeerId,
+        status: SyncStatus,
+    },
     RequestChunks {
         peer_id: PeerId,
         request_id: PeerRequestId,
@@ -46,15 +61,39 @@ pub enum SyncMessage {
         request_id: RequestId,
         response: ChunkArrayWithProof,
     },
+    RequestFileAvailability {
+        peer_id: PeerId,
+        request_id: PeerRequestId,
+        request: GetFileAvailabilityRequest,
+    },
+    FileAvailabilityResponse {
+        peer_id: PeerId,
+        response: FileAvailability,
+    },
+    RequestDataByHash {
+        peer_id: PeerId,
+        request_id: PeerRequestId,
+        request: DataByHashRequest,
+    },
+    DataByHashResponse {
+        peer_id: PeerId,
+        response: Box<IonianData>,
+    },
     RpcError {
         peer_id: PeerId,
         request_id: RequestId,
+        error: RPCError,
     },
     AnnounceFileGossip {
         tx_id: TxID,
         peer_id: PeerId,
         addr: Multiaddr,
     },
+    AnnounceShardConfigGossip {
+        peer_id: PeerId,
+        addr: Multiaddr,
+        config: ShardConfig,
+    },
 }
 
 #[derive(Debug)]
@@ -63,6 +102,12 @@ pub enum SyncRequest {
     SyncFile { tx_seq: u64 },
     FileSyncInfo { tx_seq: Option<u64> },
     TerminateFileSync { tx_seq: u64 },
+    GetBandwidthLimits,
+    SetBandwidthLimits { limits: BandwidthLimits },
+    SyncFlowRange { start_index: u64, end_index: u64 },
+    FlowRangeSyncInfo,
+    FindDataByHash { roots: Vec<DataRoot> },
+    DataByHashInfo { root: DataRoot },
 }
 
 #[derive(Debug)]
@@ -71,6 +116,11 @@ pub enum SyncResponse {
     SyncFile { err: String },
     FileSyncInfo { result: HashMap<u64, FileSyncInfo> },
     TerminateFileSync { count: usize },
+    BandwidthLimits { limits: BandwidthLimits },
+    SyncFlowRange { err: String },
+    FlowRangeSyncInfo { info: Option<FlowRangeSyncInfo> },
+    FindDataByHash { err: String },
+    DataByHashInfo { info: Option<DataByHashInfo> },
 }
 
 pub struct SyncService {
@@ -89,12 +139,18 @@ pub struct SyncService {
     file_location_cache: Arc<FileLocationCache>,
 
     /// A collection of file sync controllers.
-    controllers: HashMap<u64, SerialSyncController>,
+    controllers: HashMap<u64, SyncController>,
 
     /// Heartbeat interval for executing periodic tasks.
     heartbeat: tokio::time::Interval,
 
     manager: AutoSyncManager,
+
+    /// Syncs the files that cover a flow entry range, e.g. the mining range.
+    flow_range: Option<FlowRangeSync>,
+
+    /// Data roots being looked up from peers.
+    data_by_hash: DataByHashLookups,
 }
 
 impl SyncService {
@@ -131,20 +187,30 @@ impl SyncService {
 
         let store = Store::new(store, executor.clone());
 
-        let manager = AutoSyncManager::new(store.clone(), sync_send.clone()).await?;
+        let manager = AutoSyncManager::new(
+            store.clone(),
+            sync_send.clone(),
+            config.auto_sync_policy.clone(),
+        )
+        .await?;
         if !config.auto_sync_disabled {
             manager.spwn(&executor, event_recv);
         }
 
+        let ctx = SyncNetworkContext::new(network_send);
+        ctx.set_bandwidth_limits(config.bandwidth_limits);
+
         let mut sync = SyncService {
             config,
             msg_recv: sync_recv,
-            ctx: Arc::new(SyncNetworkContext::new(network_send)),
+            ctx: Arc::new(ctx),
             store,
             file_location_cache,
             controllers: Default::default(),
             heartbeat,
             manager,
+            flow_range: None,
+            data_by_hash: Default::default(),
         };
 
         debug!("Starting sync service");
@@ -165,7 +231,7 @@ impl SyncService {
                 }
 
                 // heartbeat
-                _ = self.heartbeat.tick() => self.on_heartbeat(),
+                _ = self.heartbeat.tick() => self.on_heartbeat().await,
             }
         }
     }
@@ -185,6 +251,10 @@ impl SyncService {
                 self.on_peer_disconnected(peer_id);
             }
 
+            SyncMessage::PeerSyncStatus { peer_id, status } => {
+                self.ctx.update_peer_sync_status(peer_id, status);
+            }
+
             SyncMessage::RequestChunks {
                 request_id,
                 peer_id,
@@ -202,11 +272,38 @@ impl SyncService {
                 self.on_chunks_response(peer_id, request_id, response).await;
             }
 
+            SyncMessage::RequestFileAvailability {
+                request_id,
+                peer_id,
+                request,
+            } => {
+                self.on_get_file_availability_request(peer_id, request_id, request)
+                    .await;
+            }
+
+            SyncMessage::FileAvailabilityResponse { peer_id, response } => {
+                self.on_file_availability_response(peer_id, response);
+            }
+
+            SyncMessage::RequestDataByHash {
+                request_id,
+                peer_id,
+                request,
+            } => {
+                self.on_data_by_hash_request(peer_id, request_id, request)
+                    .await;
+            }
+
             SyncMessage::RpcError {
                 peer_id,
                 request_id,
+                error,
             } => {
-                self.on_rpc_error(peer_id, request_id);
+                self.on_rpc_error(peer_id, request_id, error);
+            }
+
+            SyncMessage::DataByHashResponse { peer_id, response } => {
+                self.on_data_by_hash_response(peer_id, response);
             }
 
             SyncMessage::AnnounceFileGossip {
@@ -216,6 +313,15 @@ impl SyncService {
             } => {
                 self.on_announce_file_gossip(tx_id, peer_id, addr).await;
             }
+
+            SyncMessage::AnnounceShardConfigGossip {
+                peer_id,
+                addr,
+                config,
+            } => {
+                self.on_announce_shard_config_gossip(peer_id, addr, config)
+                    .await;
+            }
         }
     }
 
@@ -278,6 +384,58 @@ impl SyncService {
                 let count = self.on_terminate_file_sync(tx_seq);
                 let _ = sender.send(SyncResponse::TerminateFileSync { count });
             }
+
+            SyncRequest::GetBandwidthLimits => {
+                let limits = self.ctx.bandwidth_limits();
+                let _ = sender.send(SyncResponse::BandwidthLimits { limits });
+            }
+
+            SyncRequest::SetBandwidthLimits { limits } => {
+                info!(?limits, "Update bandwidth limits");
+                self.ctx.set_bandwidth_limits(limits);
+                let _ = sender.send(SyncResponse::BandwidthLimits { limits });
+            }
+
+            SyncRequest::SyncFlowRange {
+                start_index,
+                end_index,
+            } => {
+                let err = match self.on_sync_flow_range(start_index, end_index).await {
+                    Ok(()) => "".into(),
+                    Err(err) => err.to_string(),
+                };
+
+                let _ = sender.send(SyncResponse::SyncFlowRange { err });
+            }
+
+            SyncRequest::FlowRangeSyncInfo => {
+                let info = match self.flow_range.as_ref() {
+                    Some(range) => match range.get_sync_info(&self.store).await {
+                        Ok(info) => Some(info),
+                        Err(err) => {
+                            error!(%err, "Failed to get flow range sync info");
+                            None
+                        }
+                    },
+                    None => None,
+                };
+
+                let _ = sender.send(SyncResponse::FlowRangeSyncInfo { info });
+            }
+
+            SyncRequest::FindDataByHash { roots } => {
+                let err = match self.on_find_data_by_hash(roots) {
+                    Ok(()) => "".into(),
+                    Err(err) => err.to_string(),
+                };
+
+                let _ = sender.send(SyncResponse::FindDataByHash { err });
+            }
+
+            SyncRequest::DataByHashInfo { root } => {
+                let info = self.data_by_hash.get(&root);
+                let _ = sender.send(SyncResponse::DataByHashInfo { info });
+            }
         }
     }
 
@@ -302,6 +460,8 @@ impl SyncService {
     fn on_peer_disconnected(&mut self, peer_id: PeerId) {
         info!(%peer_id, "Peer disconnected");
 
+        self.ctx.remove_peer_sync_status(&peer_id);
+
         for controller in self.controllers.values_mut() {
             controller.on_peer_disconnected(peer_id);
             controller.transition();
@@ -383,6 +543,19 @@ impl SyncService {
             return Ok(());
         }
 
+        // reject if the bandwidth limits exceeded
+        let bytes = (request.index_end - request.index_start) * CHUNK_SIZE as u64;
+        if !self.ctx.allows_upload(&peer_id, bytes) {
+            debug!(%peer_id, %bytes, "Failed to handle chunks request due to bandwidth limits");
+            self.ctx.send(NetworkMessage::SendErrorResponse {
+                peer_id,
+                error: RPCResponseErrorCode::RateLimited,
+                reason: "Bandwidth limits exceeded".into(),
+                id: request_id,
+            });
+            return Ok(());
+        }
+
         let result = self
             .store
             .get_chunks_with_proof_by_tx_and_index_range(
@@ -415,6 +588,207 @@ impl SyncService {
         Ok(())
     }
 
+    async fn on_get_file_availability_request(
+        &mut self,
+        peer_id: PeerId,
+        request_id: PeerRequestId,
+        request: GetFileAvailabilityRequest,
+    ) {
+        debug!(?request, %peer_id, ?request_id, "Received GetFileAvailability request");
+
+        let (error, reason) = match self.get_file_availability(request.tx_id).await {
+            Ok(Ok(availability)) => {
+                self.ctx.send(NetworkMessage::SendResponse {
+                    peer_id,
+                    id: request_id,
+                    response: network::Response::FileAvailability(availability),
+                });
+                return;
+            }
+            Ok(Err(reason)) => (RPCResponseErrorCode::InvalidRequest, reason),
+            Err(err) => {
+                error!(%err, "Failed to handle file availability request due to db error");
+                (RPCResponseErrorCode::ServerError, "DB error")
+            }
+        };
+
+        self.ctx.send(NetworkMessage::SendErrorResponse {
+            peer_id,
+            id: request_id,
+            error,
+            reason: reason.into(),
+        });
+    }
+
+    /// Returns the available segments of the specified file, or the reason why it's an
+    /// invalid request.
+    async fn get_file_availability(
+        &self,
+        tx_id: TxID,
+    ) -> StorageResult<Result<FileAvailability, &'static str>> {
+        // tx may not be synced from blockchain yet, or reverted
+        let tx = match self.store.get_tx_by_seq_number(tx_id.seq).await? {
+            Some(tx) if tx.id() == tx_id => tx,
+            _ => return Ok(Err("Tx not found")),
+        };
+
+        let num_chunks = bytes_to_chunks(tx.size as usize);
+        let num_segments = (num_chunks + CHUNKS_PER_SEGMENT - 1) / CHUNKS_PER_SEGMENT;
+        if num_segments > MAX_SEGMENTS {
+            return Ok(Err("File too large"));
+        }
+
+        let mut segments = BitList::with_capacity(num_segments).expect("Valid capacity");
+
+        let finalized = self.store.check_tx_completed(tx_id.seq).await?;
+        if finalized {
+            for i in 0..num_segments {
+                segments.set(i, true).expect("Index in bounds");
+            }
+        } else {
+            let ranges = self
+                .store
+                .get_available_chunk_ranges_by_tx(tx_id.seq)
+                .await?;
+            for i in available_segments(num_chunks, &ranges) {
+                segments.set(i, true).expect("Index in bounds");
+            }
+        }
+
+        Ok(Ok(FileAvailability {
+            tx_id,
+            finalized,
+            segments,
+        }))
+    }
+
+    async fn on_data_by_hash_request(
+        &mut self,
+        peer_id: PeerId,
+        request_id: PeerRequestId,
+        request: DataByHashRequest,
+    ) {
+        debug!(%peer_id, ?request_id, hashes = %request.hashes.len(), "Received DataByHash request");
+
+        for data_root in request.hashes.iter() {
+            let data = match self.get_data_by_hash(data_root).await {
+                Ok(Some(data)) => data,
+                Ok(None) => continue,
+                Err(err) => {
+                    error!(%err, "Failed to handle data by hash request due to db error");
+                    self.ctx.send(NetworkMessage::SendErrorResponse {
+                        peer_id,
+                        id: request_id,
+                        error: RPCResponseErrorCode::ServerError,
+                        reason: "DB error".into(),
+                    });
+                    return;
+                }
+            };
+
+            self.ctx.send(NetworkMessage::SendResponse {
+                peer_id,
+                id: request_id,
+                response: network::Response::DataByHash(Some(Box::new(data))),
+            });
+        }
+
+        // terminate the stream
+        self.ctx.send(NetworkMessage::SendResponse {
+            peer_id,
+            id: request_id,
+            response: network::Response::DataByHash(None),
+        });
+    }
+
+    /// Requests the latest transactions of the data roots from all relevant peers.
+    fn on_find_data_by_hash(&mut self, roots: Vec<DataRoot>) -> Result<()> {
+        if roots.is_empty() || roots.len() as u64 > MAX_REQUEST_BLOCKS {
+            bail!("invalid number of data roots: {}", roots.len());
+        }
+
+        let peers: Vec<PeerId> = self
+            .ctx
+            .peer_sync_status()
+            .into_iter()
+            .filter(|(_, status)| *status != SyncStatus::IrrelevantPeer)
+            .map(|(peer_id, _)| peer_id)
+            .collect();
+        if peers.is_empty() {
+            bail!("no peers available");
+        }
+
+        info!(roots = %roots.len(), peers = %peers.len(), "Find data by hash");
+
+        for peer_id in peers {
+            self.ctx.send(NetworkMessage::SendRequest {
+                peer_id,
+                request_id: network::RequestId::Sync(RequestId::DataByHash),
+                request: network::Request::DataByHash(DataByHashRequest {
+                    hashes: roots.clone().into(),
+                }),
+            });
+        }
+
+        self.data_by_hash.start(&roots);
+
+        Ok(())
+    }
+
+    fn on_data_by_hash_response(&mut self, peer_id: PeerId, response: Box<IonianData>) {
+        debug!(%peer_id, hash = ?response.hash, tx_id = ?response.tx_id, "Received DataByHash response");
+
+        if !self.data_by_hash.on_response(peer_id, *response) {
+            debug!(%peer_id, "Ignore DataByHash response of unknown or older transaction");
+        }
+    }
+
+    /// Returns the latest transaction of the data root, where the finalized ones are preferred.
+    async fn get_data_by_hash(&self, data_root: &DataRoot) -> StorageResult<Option<IonianData>> {
+        let seq_list = self.store.get_tx_seq_list_by_data_root(data_root).await?;
+
+        let mut latest = None;
+        for &tx_seq in seq_list.iter().rev() {
+            if self.store.check_tx_completed(tx_seq).await? {
+                latest = Some((tx_seq, true));
+                break;
+            }
+        }
+
+        let (tx_seq, finalized) = match (latest, seq_list.last()) {
+            (Some(latest), _) => latest,
+            (None, Some(&tx_seq)) => (tx_seq, false),
+            (None, None) => return Ok(None),
+        };
+
+        let tx = match self.store.get_tx_by_seq_number(tx_seq).await? {
+            Some(tx) => tx,
+            None => return Ok(None),
+        };
+
+        // inline the data of small files
+        let mut data = vec![];
+        if finalized && tx.size > 0 && tx.size <= MAX_DATA_LEN {
+            let num_chunks = bytes_to_chunks(tx.size as usize);
+            if let Some(chunks) = self
+                .store
+                .get_chunks_by_tx_and_index_range(tx_seq, 0, num_chunks)
+                .await?
+            {
+                data = chunks.data;
+                data.truncate(tx.size as usize);
+            }
+        }
+
+        Ok(Some(IonianData {
+            hash: *data_root,
+            tx_id: tx.id(),
+            size: tx.size,
+            finalized,
+            data: data.into(),
+        }))
+    }
+
     async fn on_chunks_response(
         &mut self,
         peer_id: PeerId,
@@ -424,7 +798,11 @@ impl SyncService {
         info!(%response.chunks, %peer_id, ?request_id, "Received chunks response");
 
         let tx_seq = match request_id {
-            RequestId::SerialSync { tx_id } => tx_id.seq,
+            RequestId::SerialSync { tx_id } | RequestId::ParallelSync { tx_id } => tx_id.seq,
+            RequestId::FileAvailability { .. } | RequestId::DataByHash => {
+                warn!(%peer_id, ?request_id, "Received chunks response for other request");
+                return;
+            }
         };
 
         match self.controllers.get_mut(&tx_seq) {
@@ -438,16 +816,40 @@ impl SyncService {
         }
     }
 
-    fn on_rpc_error(&mut self, peer_id: PeerId, request_id: RequestId) {
-        info!(%peer_id, ?request_id, "Received RPC error");
+    fn on_file_availability_response(&mut self, peer_id: PeerId, response: FileAvailability) {
+        info!(
+            %peer_id,
+            tx_id = ?response.tx_id,
+            finalized = %response.finalized,
+            segments = %response.segments.num_set_bits(),
+            "Received file availability response",
+        );
+
+        if let Some(controller) = self.controllers.get_mut(&response.tx_id.seq) {
+            controller.on_file_availability(peer_id, response);
+            controller.transition();
+        }
+    }
+
+    fn on_rpc_error(&mut self, peer_id: PeerId, request_id: RequestId, error: RPCError) {
+        info!(%peer_id, ?request_id, %error, "Received RPC error");
 
         let tx_seq = match request_id {
-            RequestId::SerialSync { tx_id } => tx_id.seq,
+            RequestId::SerialSync { tx_id } | RequestId::ParallelSync { tx_id } => tx_id.seq,
+            // peers are still selected without the file availability
+            RequestId::FileAvailability { .. } => return,
+            // data roots are looked up from other peers as well
+            RequestId::DataByHash => return,
         };
 
         match self.controllers.get_mut(&tx_seq) {
             Some(controller) => {
-                controller.on_request_failed(peer_id);
+                match error {
+                    RPCError::ErrorResponse(RPCResponseErrorCode::RateLimited, _) => {
+                        controller.on_request_rate_limited(peer_id)
+                    }
+                    _ => controller.on_request_failed(peer_id),
+                }
                 controller.transition();
             }
             None => {
@@ -479,9 +881,11 @@ impl SyncService {
             self.controllers.remove(&tx_seq);
         }
 
+        let mut resume = false;
         let controller = match self.controllers.entry(tx_seq) {
             Entry::Occupied(entry) => entry.into_mut(),
             Entry::Vacant(entry) => {
+                resume = true;
                 let tx = match self.store.get_tx_by_seq_number(tx_seq).await? {
                     Some(tx) => tx,
                     None => bail!("transaction not found"),
@@ -500,19 +904,59 @@ impl SyncService {
                     bail!("File already exists");
                 }
 
-                entry.insert(SerialSyncController::new(
-                    tx.id(),
-                    num_chunks as u64,
-                    self.ctx.clone(),
-                    self.store.clone(),
-                    self.file_location_cache.clone(),
-                ))
+                // download large files from multiple peers in parallel
+                let num_chunks = num_chunks as u64;
+                let controller = if num_chunks > MAX_CHUNKS_TO_REQUEST
+                    && self.config.max_parallel_requests > 1
+                {
+                    SyncController::Parallel(ParallelSyncController::new(
+                        tx.id(),
+                        num_chunks,
+                        self.config.max_parallel_requests,
+                        self.ctx.clone(),
+                        self.store.clone(),
+                        self.file_location_cache.clone(),
+                    ))
+                } else {
+                    SyncController::Serial(SerialSyncController::new(
+                        tx.id(),
+                        num_chunks,
+                        self.ctx.clone(),
+                        self.store.clone(),
+                        self.file_location_cache.clone(),
+                    ))
+                };
+
+                let controller = entry.insert(controller);
+
+                // peers that store the flow entries of file long-term
+                let end_index = tx.start_entry_index + tx.num_entries() as u64;
+                for announcement in self
+                    .file_location_cache
+                    .get_shard_configs(tx.start_entry_index, end_index)
+                {
+                    // make sure peer_id is part of the address
+                    let peer_id: PeerId = announcement.peer_id.clone().into();
+                    let mut addr: Multiaddr = announcement.at.clone().into();
+                    addr.push(Protocol::P2p(peer_id.into()));
+
+                    controller.on_peer_found(peer_id, addr);
+                }
+
+                controller
             }
         };
 
         // trigger retry after failure
         if let SyncState::Failed { .. } = controller.get_status() {
             controller.reset();
+            resume = true;
+        }
+
+        // skip the chunks already stored, e.g. before node restarted
+        if resume {
+            let downloaded = self.store.get_available_chunk_ranges_by_tx(tx_seq).await?;
+            controller.resume(&downloaded).await;
         }
 
         if let Some((peer_id, addr)) = maybe_peer {
@@ -553,6 +997,41 @@ impl SyncService {
         }
     }
 
+    async fn on_announce_shard_config_gossip(
+        &mut self,
+        peer_id: PeerId,
+        addr: Multiaddr,
+        config: ShardConfig,
+    ) {
+        info!(%peer_id, %addr, ?config, "Received AnnounceShardConfig gossip");
+
+        // make sure peer_id is part of the address
+        let mut addr = addr;
+        addr.push(Protocol::P2p(peer_id.into()));
+
+        let tx_seqs = self.controllers.keys().cloned().collect::<Vec<_>>();
+        for tx_seq in tx_seqs {
+            let tx = match self.store.get_tx_by_seq_number(tx_seq).await {
+                Ok(Some(tx)) => tx,
+                Ok(None) => continue,
+                Err(err) => {
+                    error!(%tx_seq, %err, "Failed to get transaction");
+                    continue;
+                }
+            };
+
+            let end_index = tx.start_entry_index + tx.num_entries() as u64;
+            if !config.covers(tx.start_entry_index, end_index) {
+                continue;
+            }
+
+            if let Some(controller) = self.controllers.get_mut(&tx_seq) {
+                controller.on_peer_found(peer_id, addr.clone());
+                controller.transition();
+            }
+        }
+    }
+
     /// Terminate all file sync that `tx_seq` greater than `min_tx_seq`
     /// when confirmed transactions reverted.
     ///
@@ -571,10 +1050,63 @@ impl SyncService {
             self.controllers.remove(tx_seq);
         }
 
+        if let Some(range) = self.flow_range.as_mut() {
+            range.on_tx_reverted(min_tx_seq);
+        }
+
         reverted.len()
     }
 
-    fn on_heartbeat(&mut self) {
+    async fn on_sync_flow_range(&mut self, start_index: u64, end_index: u64) -> Result<()> {
+        if start_index >= end_index {
+            bail!("invalid flow range [{}, {})", start_index, end_index);
+        }
+
+        info!(%start_index, %end_index, "Start to sync flow range");
+
+        self.flow_range = Some(FlowRangeSync::new(&self.store, start_index, end_index).await?);
+        self.sync_flow_range().await;
+
+        Ok(())
+    }
+
+    /// Starts to sync the next files in the flow range, if any, and retries the failed ones.
+    async fn sync_flow_range(&mut self) {
+        let syncing = self
+            .controllers
+            .iter()
+            .filter(|(_, controller)| !matches!(controller.get_status(), SyncState::Failed { .. }))
+            .map(|(&tx_seq, _)| tx_seq)
+            .collect::<HashSet<_>>();
+        let max_txs = self.config.max_sync_files.saturating_sub(syncing.len());
+        if max_txs == 0 {
+            return;
+        }
+
+        let range = match self.flow_range.as_mut() {
+            Some(range) => range,
+            None => return,
+        };
+
+        let txs = match range
+            .next_txs(&self.store, max_txs, |tx_seq| syncing.contains(&tx_seq))
+            .await
+        {
+            Ok(txs) => txs,
+            Err(err) => {
+                warn!(%err, "Failed to find files to sync in flow range");
+                return;
+            }
+        };
+
+        for tx_seq in txs {
+            if let Err(err) = self.on_start_sync_file(tx_seq, None).await {
+                warn!(%tx_seq, %err, "Failed to sync file in flow range");
+            }
+        }
+    }
+
+    async fn on_heartbeat(&mut self) {
         let mut completed = vec![];
 
         for (&tx_seq, controller) in self.controllers.iter_mut() {
@@ -588,7 +1120,47 @@ impl SyncService {
         for tx_seq in completed {
             self.controllers.remove(&tx_seq);
         }
+
+        self.sync_flow_range().await;
+
+        self.data_by_hash.remove_expired();
+    }
+}
+
+/// Returns the indices of segments that are fully covered by the available chunk ranges, which
+/// are in order and do not overlap.
+fn available_segments(num_chunks: usize, available: &[(u64, u64)]) -> Vec<usize> {
+    // merge the adjacent ranges
+    let mut ranges: Vec<(usize, usize)> = vec![];
+    for &(start, end) in available {
+        let (start, end) = (start as usize, end as usize);
+        match ranges.last_mut() {
+            Some(last) if last.1 >= start => last.1 = std::cmp::max(last.1, end),
+            _ => ranges.push((start, end)),
+        }
+    }
+
+    let mut segments = vec![];
+    let mut ranges = ranges.into_iter().peekable();
+
+    for (index, start) in (0..num_chunks).step_by(CHUNKS_PER_SEGMENT).enumerate() {
+        let end = std::cmp::min(start + CHUNKS_PER_SEGMENT, num_chunks);
+
+        // skip the ranges that end before the segment
+        while matches!(ranges.peek(), Some(&(_, range_end)) if range_end <= start) {
+            ranges.next();
+        }
+
+        match ranges.peek() {
+            Some(&(range_start, range_end)) if range_start <= start && range_end >= end => {
+                segments.push(index)
+            }
+            Some(_) => {}
+            None => break,
+        }
     }
+
+    segments
 }
 
 #[cfg(test)]
@@ -598,7 +1170,7 @@ mod tests {
     use libp2p::identity;
     use network::discovery::ConnectionId;
     use network::rpc::SubstreamId;
-    use network::ReportSource;
+    use network::{ReportSource, SyncInfo};
     use shared_types::ChunkArray;
     use shared_types::Transaction;
     use std::thread;
@@ -606,7 +1178,7 @@ mod tests {
     use std::time::Instant;
     use storage::log_store::log_manager::LogConfig;
     use storage::log_store::log_manager::LogManager;
-    use storage::log_store::LogStoreRead;
+    use storage::log_store::{LogStoreChunkRead, LogStoreChunkWrite, LogStoreRead};
     use storage::H256;
     use task_executor::test_utils::TestRuntime;
     use tokio::sync::mpsc::UnboundedReceiver;
@@ -697,7 +1269,7 @@ mod tests {
         let (sync_send, sync_recv) = channel::Channel::unbounded();
 
         let heartbeat = tokio::time::interval(Duration::from_secs(HEARTBEAT_INTERVAL_SEC));
-        let manager = AutoSyncManager::new(store.clone(), sync_send)
+        let manager = AutoSyncManager::new(store.clone(), sync_send, Default::default())
             .await
             .unwrap();
 
@@ -710,6 +1282,8 @@ mod tests {
             controllers: Default::default(),
             heartbeat,
             manager,
+            flow_range: None,
+            data_by_hash: Default::default(),
         };
 
         sync.on_peer_connected(init_peer_id);
@@ -732,7 +1306,7 @@ mod tests {
         let (sync_send, sync_recv) = channel::Channel::unbounded();
 
         let heartbeat = tokio::time::interval(Duration::from_secs(HEARTBEAT_INTERVAL_SEC));
-        let manager = AutoSyncManager::new(store.clone(), sync_send)
+        let manager = AutoSyncManager::new(store.clone(), sync_send, Default::default())
             .await
             .unwrap();
 
@@ -745,6 +1319,8 @@ mod tests {
             controllers: Default::default(),
             heartbeat,
             manager,
+            flow_range: None,
+            data_by_hash: Default::default(),
         };
 
         sync.on_peer_disconnected(init_peer_id);
@@ -813,6 +1389,208 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_available_segments() {
+        let chunks = |start_index: u64, num_chunks: u64| (start_index, start_index + num_chunks);
+
+        assert!(available_segments(2500, &[]).is_empty());
+
+        // adjacent chunk arrays are merged, and the last segment is smaller
+        let available = [chunks(0, 512), chunks(512, 1024), chunks(2048, 452)];
+        assert_eq!(available_segments(2500, &available), vec![0, 2]);
+
+        // partially available segments
+        let available = [chunks(1, 1100), chunks(1500, 1000)];
+        assert_eq!(available_segments(2500, &available), vec![2]);
+    }
+
+    #[tokio::test]
+    async fn test_request_file_availability() {
+        let mut runtime = TestSyncRuntime::default();
+
+        // only the first segment is stored
+        let chunks = runtime
+            .peer_store
+            .read()
+            .await
+            .get_chunks_by_tx_and_index_range(0, 0, CHUNKS_PER_SEGMENT)
+            .unwrap()
+            .unwrap();
+        runtime.store.write().await.put_chunks(0, chunks).unwrap();
+
+        let sync_send = runtime.spawn_sync_service(false).await;
+
+        sync_send
+            .notify(SyncMessage::RequestFileAvailability {
+                request_id: (ConnectionId::new(0), SubstreamId(0)),
+                peer_id: runtime.init_peer_id,
+                request: GetFileAvailabilityRequest {
+                    tx_id: runtime.txs[0].id(),
+                },
+            })
+            .unwrap();
+
+        match runtime.network_recv.recv().await {
+            Some(NetworkMessage::SendResponse {
+                peer_id,
+                response: network::Response::FileAvailability(availability),
+                ..
+            }) => {
+                assert_eq!(peer_id, runtime.init_peer_id);
+                assert_eq!(availability.tx_id, runtime.txs[0].id());
+                assert!(!availability.finalized);
+                assert_eq!(availability.segments.len(), 2);
+                assert!(availability.has_chunks(0, CHUNKS_PER_SEGMENT as u64));
+                assert!(!availability.has_chunks(0, runtime.chunk_count as u64));
+            }
+            msg => panic!("Not expected message: {:?}", msg),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_request_data_by_hash() {
+        let mut runtime = TestSyncRuntime::default();
+        let sync_send = runtime.spawn_sync_service(true).await;
+
+        // the unknown data root is omitted in responses
+        let tx = runtime.txs[0].clone();
+        let request = DataByHashRequest {
+            hashes: vec![H256::random(), tx.data_merkle_root].into(),
+        };
+
+        sync_send
+            .notify(SyncMessage::RequestDataByHash {
+                request_id: (ConnectionId::new(0), SubstreamId(0)),
+                peer_id: runtime.init_peer_id,
+                request,
+            })
+            .unwrap();
+
+        match runtime.network_recv.recv().await {
+            Some(NetworkMessage::SendResponse {
+                response: network::Response::DataByHash(Some(data)),
+                ..
+            }) => {
+                assert_eq!(data.hash, tx.data_merkle_root);
+                assert_eq!(data.tx_id, tx.id());
+                assert_eq!(data.size, tx.size);
+                assert!(data.finalized);
+                // too large to inline
+                assert!(data.data.is_empty());
+            }
+            msg => panic!("Not expected message: {:?}", msg),
+        }
+
+        match runtime.network_recv.recv().await {
+            Some(NetworkMessage::SendResponse {
+                response: network::Response::DataByHash(None),
+                ..
+            }) => {}
+            msg => panic!("Not expected message: {:?}", msg),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_find_data_by_hash() {
+        let mut runtime = TestSyncRuntime::default();
+        let sync_send = runtime.spawn_sync_service(false).await;
+        let peer_sync_send = runtime.spawn_sync_service(true).await;
+        let tx = runtime.txs[0].clone();
+        let root = tx.data_merkle_root;
+
+        // no peers to request
+        assert!(matches!(
+            sync_send
+                .request(SyncRequest::FindDataByHash { roots: vec![root] })
+                .await
+                .unwrap(),
+            SyncResponse::FindDataByHash { err } if !err.is_empty()
+        ));
+
+        let info = SyncInfo {
+            next_tx_seq: 1,
+            flow_root: H256::zero(),
+        };
+        sync_send
+            .notify(SyncMessage::PeerSyncStatus {
+                peer_id: runtime.init_peer_id,
+                status: SyncStatus::Advanced { info },
+            })
+            .unwrap();
+
+        assert!(matches!(
+            sync_send
+                .request(SyncRequest::FindDataByHash { roots: vec![root] })
+                .await
+                .unwrap(),
+            SyncResponse::FindDataByHash { err } if err.is_empty()
+        ));
+
+        // forward the request to the peer
+        let request = match runtime.network_recv.recv().await {
+            Some(NetworkMessage::SendRequest {
+                peer_id,
+                request_id: network::RequestId::Sync(RequestId::DataByHash),
+                request: network::Request::DataByHash(request),
+            }) => {
+                assert_eq!(peer_id, runtime.init_peer_id);
+                request
+            }
+            msg => panic!("Not expected message: {:?}", msg),
+        };
+        assert_eq!(request.hashes.to_vec(), vec![root]);
+
+        peer_sync_send
+            .notify(SyncMessage::RequestDataByHash {
+                request_id: (ConnectionId::new(0), SubstreamId(0)),
+                peer_id: runtime.init_peer_id,
+                request,
+            })
+            .unwrap();
+
+        // forward the response back
+        let response = match runtime.network_recv.recv().await {
+            Some(NetworkMessage::SendResponse {
+                response: network::Response::DataByHash(Some(data)),
+                ..
+            }) => data,
+            msg => panic!("Not expected message: {:?}", msg),
+        };
+
+        sync_send
+            .notify(SyncMessage::DataByHashResponse {
+                peer_id: runtime.init_peer_id,
+                response,
+            })
+            .unwrap();
+
+        match sync_send
+            .request(SyncRequest::DataByHashInfo { root })
+            .await
+            .unwrap()
+        {
+            SyncResponse::DataByHashInfo { info: Some(info) } => {
+                assert_eq!(info.tx_seq, tx.seq);
+                assert_eq!(info.tx_hash, tx.hash());
+                assert_eq!(info.size, tx.size);
+                assert!(info.finalized);
+                assert_eq!(info.peer_id, runtime.init_peer_id.to_string());
+            }
+            msg => panic!("Not expected response: {:?}", msg),
+        }
+
+        // unknown data root
+        assert!(matches!(
+            sync_send
+                .request(SyncRequest::DataByHashInfo {
+                    root: H256::random()
+                })
+                .await
+                .unwrap(),
+            SyncResponse::DataByHashInfo { info: None }
+        ));
+    }
+
     #[tokio::test]
     async fn test_request_chunks_invalid_indices() {
         let mut runtime = TestSyncRuntime::default();
@@ -1330,6 +2108,7 @@ mod tests {
                     tx_id: runtime.txs[0].id(),
                 },
                 peer_id: runtime.init_peer_id,
+                error: RPCError::StreamTimeout,
             })
             .unwrap();
 
@@ -0,0 +1,44 @@
+#!/usr/bin/env python3
+
+from test_framework.test_framework import TestFramework
+from utility.submission import create_submission, submit_data
+from utility.utils import assert_equal, wait_until
+
+
+class LogResyncTest(TestFramework):
+    def run_test(self):
+        client = self.nodes[0]
+
+        wait_until(
+            lambda: client.admin_get_log_sync_status()["syncedBlockNumber"] is not None
+        )
+        block_number = client.admin_get_log_sync_status()["syncedBlockNumber"] + 1
+
+        chunk_data = b"\x03" * 256 * 3
+        submissions, data_root = create_submission(chunk_data)
+        self.contract.submit(submissions)
+        wait_until(lambda: self.contract.num_submissions() == 1)
+        wait_until(lambda: client.ionian_get_file_info(data_root) is not None)
+
+        segment = submit_data(client, chunk_data)
+        self.log.info("segment: %s", segment)
+        wait_until(lambda: client.ionian_get_file_info(data_root)["finalized"])
+
+        # Blocks that are not synced yet cannot be resynced.
+        failed = False
+        try:
+            client.admin_resync_log_from(block_number + 1000000)
+        except Exception as e:
+            self.log.info("resync error: %s", e)
+            failed = True
+        assert failed
+
+        # The transaction is reverted and synced again, and its data are restored.
+        assert_equal(client.admin_resync_log_from(block_number), 0)
+        wait_until(lambda: client.ionian_get_file_info(data_root) is not None)
+        wait_until(lambda: client.ionian_get_file_info(data_root)["finalized"])
+        assert_equal(client.admin_get_log_sync_status()["nextTxSeq"], 1)
+
+
+if __name__ == "__main__":
+    LogResyncTest().main()
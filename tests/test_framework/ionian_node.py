@@ -96,6 +96,33 @@ class IonianNode(TestNode):
     def admin_get_sync_status(self, tx_seq):
         return self.rpc.admin_getSyncStatus([tx_seq])
 
+    def admin_get_sync_info(self, tx_seq=None):
+        return self.rpc.admin_getSyncInfo([tx_seq])
+
+    def admin_sync_flow_range(self, start_index, end_index):
+        return self.rpc.admin_syncFlowRange([start_index, end_index])
+
+    def admin_get_flow_range_sync_info(self):
+        return self.rpc.admin_getFlowRangeSyncInfo()
+
+    def admin_find_data_by_hash(self, roots):
+        return self.rpc.admin_findDataByHash([roots])
+
+    def admin_get_data_by_hash(self, root):
+        return self.rpc.admin_getDataByHash([root])
+
+    def admin_get_bandwidth_limits(self):
+        return self.rpc.admin_getBandwidthLimits()
+
+    def admin_set_bandwidth_limits(self, limits):
+        return self.rpc.admin_setBandwidthLimits([limits])
+
+    def admin_get_log_sync_status(self):
+        return self.rpc.admin_getLogSyncStatus()
+
+    def admin_resync_log_from(self, block_number):
+        return self.rpc.admin_resyncLogFrom([block_number])
+
     def sycn_status_is_completed_or_unknown(self, tx_seq):
         status = self.rpc.admin_getSyncStatus([tx_seq])
         return status == "Completed" or status == "unknown"